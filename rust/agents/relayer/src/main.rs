@@ -7,14 +7,22 @@
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
-use eyre::Result;
+use std::env;
 
-use hyperlane_base::agent_main;
+use eyre::{bail, ensure, eyre, Context, Result};
+use itertools::Itertools;
+
+use hyperlane_base::{
+    agent_main, create_chain_metrics, metrics::create_agent_metrics, settings::Settings, BaseAgent,
+    LoadableFromSettings,
+};
+use hyperlane_core::H256;
 
 use crate::relayer::Relayer;
 
 mod merkle_tree;
 mod msg;
+mod optimistic_watcher;
 mod processor;
 mod prover;
 mod relayer;
@@ -22,5 +30,175 @@ mod settings;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
-    agent_main::<Relayer>().await
+    let (command, settings_args) = parse_command(env::args().skip(1).collect())?;
+    match command {
+        Some(Command::Diagnose { message_id }) => diagnose(&message_id, settings_args).await,
+        Some(Command::IsmConfig {
+            origin,
+            destination,
+            recipient,
+        }) => print_ism_config(&origin, &destination, &recipient, settings_args).await,
+        None => agent_main::<Relayer>().await,
+    }
+}
+
+/// Program argument to diagnose why a message was not delivered instead of
+/// running the relayer, followed by the message id.
+const DIAGNOSE_ARG: &str = "--diagnose";
+
+/// Program argument to print the ISM configuration a recipient is subject
+/// to instead of running the relayer, followed by
+/// `<origin>:<destination>:<recipient>`.
+const ISM_CONFIG_ARG: &str = "--ismConfig";
+
+/// A command run instead of the relayer
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Diagnose {
+        message_id: String,
+    },
+    IsmConfig {
+        origin: String,
+        destination: String,
+        recipient: String,
+    },
+}
+
+/// Splits the program arguments into the command to run instead of the
+/// relayer, if any, and the remaining arguments, which are settings.
+/// Commands take a single value, passed as `--name value` or `--name=value`.
+fn parse_command(args: Vec<String>) -> Result<(Option<Command>, Vec<String>)> {
+    let mut command = None;
+    let mut settings_args = Vec::with_capacity(args.len());
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some((name, value)) = [DIAGNOSE_ARG, ISM_CONFIG_ARG].into_iter().find_map(|name| {
+            if arg == name {
+                Some((name, args.next()))
+            } else {
+                arg.strip_prefix(name)
+                    .and_then(|value| value.strip_prefix('='))
+                    .map(|value| (name, Some(value.to_owned())))
+            }
+        }) else {
+            settings_args.push(arg);
+            continue;
+        };
+        ensure!(command.is_none(), "Only one command can be run at a time");
+        let value = value.ok_or_else(|| eyre!("Missing value of {name}"))?;
+        command = Some(match name {
+            DIAGNOSE_ARG => Command::Diagnose { message_id: value },
+            _ => {
+                let Some((origin, destination, recipient)) =
+                    value.split(':').collect_tuple::<(_, _, _)>()
+                else {
+                    bail!("Expected {name} <origin>:<destination>:<recipient>, got {value}");
+                };
+                Command::IsmConfig {
+                    origin: origin.to_owned(),
+                    destination: destination.to_owned(),
+                    recipient: recipient.to_owned(),
+                }
+            }
+        });
+    }
+    Ok((command, settings_args))
+}
+
+/// Loads the relayer with the same settings and database as when running it.
+/// The database can only be opened while the relayer is stopped.
+async fn load_relayer(settings_args: Vec<String>) -> Result<Relayer> {
+    let settings = <Relayer as BaseAgent>::Settings::load_with_args(settings_args)?;
+    let core_settings: &Settings = settings.as_ref();
+    let metrics = core_settings.metrics(Relayer::AGENT_NAME)?;
+    let agent_metrics = create_agent_metrics(&metrics)?;
+    let chain_metrics = create_chain_metrics(&metrics)?;
+    Relayer::from_settings(settings, metrics, agent_metrics, chain_metrics).await
+}
+
+/// Prints a diagnosis of why a message was not delivered. Use the relayer's
+/// `/diagnose` endpoint while it is running.
+async fn diagnose(message_id: &str, settings_args: Vec<String>) -> Result<()> {
+    let message_id: H256 = message_id
+        .parse()
+        .with_context(|| format!("Invalid message id {message_id}"))?;
+    let relayer = load_relayer(settings_args).await?;
+
+    let diagnosis = relayer.diagnoser().diagnose(message_id).await?;
+    println!("{}", serde_json::to_string_pretty(&diagnosis)?);
+    Ok(())
+}
+
+/// Prints the tree of ISMs which verify messages from `origin` to
+/// `recipient` on `destination`.
+async fn print_ism_config(
+    origin: &str,
+    destination: &str,
+    recipient: &str,
+    settings_args: Vec<String>,
+) -> Result<()> {
+    let recipient: H256 = recipient
+        .parse()
+        .with_context(|| format!("Invalid recipient {recipient}"))?;
+    let relayer = load_relayer(settings_args).await?;
+
+    let ism_config = relayer
+        .ism_config_tree(origin, destination, recipient)
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&ism_config)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_commands_out_of_settings_arguments() {
+        let recipient = format!("0x{}", "11".repeat(32));
+        let (command, settings_args) = parse_command(args(&[
+            "--db",
+            "/tmp/db",
+            "--ismConfig",
+            &format!("test1:test2:{recipient}"),
+            "--relayChains=test1,test2",
+        ]))
+        .unwrap();
+        assert_eq!(
+            command,
+            Some(Command::IsmConfig {
+                origin: "test1".to_owned(),
+                destination: "test2".to_owned(),
+                recipient,
+            })
+        );
+        assert_eq!(
+            settings_args,
+            args(&["--db", "/tmp/db", "--relayChains=test1,test2"])
+        );
+
+        let (command, settings_args) = parse_command(args(&["--diagnose=0x01"])).unwrap();
+        assert_eq!(
+            command,
+            Some(Command::Diagnose {
+                message_id: "0x01".to_owned()
+            })
+        );
+        assert!(settings_args.is_empty());
+
+        let (command, settings_args) = parse_command(args(&["--db", "/tmp/db"])).unwrap();
+        assert_eq!(command, None);
+        assert_eq!(settings_args, args(&["--db", "/tmp/db"]));
+    }
+
+    #[test]
+    fn rejects_malformed_commands() {
+        assert!(parse_command(args(&["--ismConfig", "test1", "test2", "0x01"])).is_err());
+        assert!(parse_command(args(&["--diagnose"])).is_err());
+        assert!(parse_command(args(&["--diagnose=0x01", "--diagnose=0x02"])).is_err());
+    }
 }
@@ -1,12 +1,12 @@
-use std::fmt::Display;
+use std::{fmt::Display, num::NonZeroU64};
 
 use eyre::{Context, Result};
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, info, instrument, warn};
 
-use hyperlane_base::db::DbError;
+use hyperlane_base::db::{DbError, HyperlaneRocksDB};
 use hyperlane_core::{
     accumulator::{incremental::IncrementalMerkle, merkle::Proof},
-    ChainCommunicationError, H256,
+    ChainCommunicationError, MerkleTreeHook, H256,
 };
 
 use crate::prover::{Prover, ProverError};
@@ -70,6 +70,63 @@ impl MerkleTreeBuilder {
         }
     }
 
+    /// Resumes from the latest merkle tree snapshot in `db` that is
+    /// consistent with the tree of `merkle_tree_hook`, or starts from an empty
+    /// tree if there is none.
+    pub async fn from_snapshot_or_new(
+        db: &HyperlaneRocksDB,
+        merkle_tree_hook: &dyn MerkleTreeHook,
+        lag: Option<NonZeroU64>,
+    ) -> Self {
+        match Self::from_snapshot(db, merkle_tree_hook, lag).await {
+            Ok(Some(builder)) => {
+                info!(
+                    count = builder.count(),
+                    "Resuming merkle tree from snapshot"
+                );
+                builder
+            }
+            Ok(None) => Self::new(),
+            Err(err) => {
+                warn!(
+                    ?err,
+                    "Failed to resume merkle tree from snapshot, rebuilding it"
+                );
+                Self::new()
+            }
+        }
+    }
+
+    async fn from_snapshot(
+        db: &HyperlaneRocksDB,
+        merkle_tree_hook: &dyn MerkleTreeHook,
+        lag: Option<NonZeroU64>,
+    ) -> Result<Option<Self>> {
+        let Some(incremental) = db
+            .retrieve_verified_merkle_tree_snapshot(merkle_tree_hook, lag)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let Some(leaves) = db.retrieve_merkle_tree_snapshot_leaves(incremental.count() as u32)?
+        else {
+            return Ok(None);
+        };
+        let prover = Prover::from(leaves);
+        if prover.root() != incremental.root() {
+            db.delete_latest_merkle_tree_snapshot()?;
+            return Err(MerkleTreeBuilderError::MismatchedRoots {
+                prover_root: prover.root(),
+                incremental_root: incremental.root(),
+            }
+            .into());
+        }
+        Ok(Some(Self {
+            prover,
+            incremental,
+        }))
+    }
+
     #[instrument(err, skip(self), level="debug", fields(prover_latest_index=self.count()-1))]
     pub fn get_proof(
         &self,
@@ -85,6 +142,10 @@ impl MerkleTreeBuilder {
         self.prover.count() as u32
     }
 
+    pub fn incremental(&self) -> &IncrementalMerkle {
+        &self.incremental
+    }
+
     pub async fn ingest_message_id(&mut self, message_id: H256) -> Result<()> {
         const CTX: &str = "When ingesting message id";
         debug!(?message_id, "Ingesting leaf");
@@ -11,7 +11,7 @@ use hyperlane_base::db::HyperlaneRocksDB;
 use hyperlane_core::{HyperlaneDomain, MerkleTreeInsertion};
 use prometheus::IntGauge;
 use tokio::sync::RwLock;
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::processor::ProcessorExt;
 
@@ -23,7 +23,6 @@ pub struct MerkleTreeProcessor {
     db: HyperlaneRocksDB,
     metrics: MerkleTreeProcessorMetrics,
     prover_sync: Arc<RwLock<MerkleTreeBuilder>>,
-    #[new(default)]
     leaf_index: u32,
 }
 
@@ -49,14 +48,22 @@ impl ProcessorExt for MerkleTreeProcessor {
     async fn tick(&mut self) -> Result<()> {
         if let Some(insertion) = self.next_unprocessed_leaf()? {
             // Feed the message to the prover sync
-            self.prover_sync
-                .write()
-                .await
-                .ingest_message_id(insertion.message_id())
-                .await?;
+            let tree = {
+                let mut prover_sync = self.prover_sync.write().await;
+                prover_sync
+                    .ingest_message_id(insertion.message_id())
+                    .await?;
+                *prover_sync.incremental()
+            };
 
             // Increase the leaf index to move on to the next leaf
             self.leaf_index += 1;
+
+            // Periodically snapshot the tree so restarts don't need to replay
+            // every insertion
+            if let Err(err) = self.db.store_merkle_tree_snapshot(&tree) {
+                warn!(?err, "Failed to store merkle tree snapshot");
+            }
         } else {
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
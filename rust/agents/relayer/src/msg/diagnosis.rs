@@ -0,0 +1,598 @@
+//! Explains why a message was not delivered, by walking the same checks as
+//! the `MessageProcessor` and `PendingMessage::prepare` without submitting
+//! anything.
+//!
+//! Served on the relayer's server under `/diagnose`
+//! - `GET /:message_id` - the diagnosis of a message
+
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use derive_new::new;
+use eyre::{Context, Result};
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::{HyperlaneChain, HyperlaneMessage, H256, U256};
+use serde::Serialize;
+use serde_json::json;
+use tracing::warn;
+
+use super::{
+    metadata::{IsmConfig, IsmReader, IsmTreeWalker, MessageMetadataBuilder},
+    pending_message::MessageContext,
+};
+use crate::settings::reload::ReloadableSettingsHandle;
+
+/// The diagnosis of a message
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnosis {
+    pub message_id: H256,
+    /// The first reason found for the message not being delivered
+    #[serde(flatten)]
+    pub status: DeliveryStatus,
+    pub message: Option<MessageDiagnosis>,
+    pub gas: Option<GasDiagnosis>,
+    /// The recipient's ISM, with the ISMs it depends on as submodules
+    pub ism: Option<IsmConfig>,
+}
+
+/// Why a message was not delivered
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum DeliveryStatus {
+    /// The message was not indexed from any origin this relayer relays from
+    NotIndexed,
+    /// The message does not match the whitelist
+    NotWhitelisted,
+    /// The message matches the blacklist
+    Blacklisted,
+    /// The message's destination is not relayed to
+    DestinationNotRelayed,
+    /// The message was delivered
+    Delivered,
+    /// The message is marked as processed in the database, but was not
+    /// delivered, so it won't be retried
+    MarkedProcessed,
+    /// The recipient is not a contract, so the message is dropped
+    RecipientNotContract,
+    /// Metadata could not be built for the ISM, see the ISM tree for details
+    NoMetadata,
+    /// Estimating the gas for the process transaction failed, usually because
+    /// it reverts
+    EstimationReverted { error: String },
+    /// The gas payment does not meet the gas payment enforcement policy
+    GasPaymentNotMet,
+    /// The estimated gas exceeds the transaction gas limit
+    GasLimitExceeded { limit: U256 },
+    /// The message is deliverable, and will be delivered once the relayer
+    /// retries it
+    Deliverable,
+    /// Diagnosing the message failed
+    Error { error: String },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageDiagnosis {
+    pub nonce: u32,
+    pub origin: u32,
+    pub destination: u32,
+    pub sender: H256,
+    pub recipient: H256,
+    /// The index of the message in the origin's merkle tree, if the merkle
+    /// tree insertion was indexed
+    pub leaf_index: Option<u32>,
+    /// Whether the message is marked as processed in the database
+    pub processed: bool,
+    /// The number of times delivery was retried
+    pub retries: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasDiagnosis {
+    /// Native tokens paid on the origin
+    pub paid_tokens: U256,
+    /// Destination gas paid for on the origin
+    pub paid_gas: U256,
+    /// Destination tokens spent on attempts to deliver the message
+    pub spent_tokens: U256,
+    /// Destination gas spent on attempts to deliver the message
+    pub spent_gas: U256,
+    /// Estimated gas to deliver the message
+    pub estimated_gas: Option<U256>,
+    /// Whether the gas payment meets the gas payment enforcement policy for
+    /// the estimated gas
+    pub requirement_met: Option<bool>,
+}
+
+/// Diagnoses why messages were not delivered
+#[derive(new)]
+pub struct MessageDiagnoser {
+    /// Whitelist and blacklist, which may be reloaded
+    settings: ReloadableSettingsHandle,
+    /// Databases of the origins relayed from, by domain id
+    origin_dbs: HashMap<u32, HyperlaneRocksDB>,
+    /// Context of each origin and destination pair relayed between, by their
+    /// domain ids
+    msg_ctxs: HashMap<(u32, u32), Arc<MessageContext>>,
+    /// Reads ISMs instead of the metadata builder of the message's context
+    #[new(default)]
+    ism_reader: Option<Arc<dyn IsmReader>>,
+}
+
+impl MessageDiagnoser {
+    #[cfg(test)]
+    fn with_ism_reader(mut self, ism_reader: Arc<dyn IsmReader>) -> Self {
+        self.ism_reader = Some(ism_reader);
+        self
+    }
+
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/:message_id", get(diagnose))
+            .with_state(self)
+    }
+
+    /// Diagnoses why a message was not delivered
+    pub async fn diagnose(&self, message_id: H256) -> Result<Diagnosis> {
+        let mut diagnosis = Diagnosis {
+            message_id,
+            status: DeliveryStatus::NotIndexed,
+            message: None,
+            gas: None,
+            ism: None,
+        };
+        let Some((message, origin_db)) = self.find_message(message_id)? else {
+            return Ok(diagnosis);
+        };
+
+        diagnosis.message = Some(MessageDiagnosis {
+            nonce: message.nonce,
+            origin: message.origin,
+            destination: message.destination,
+            sender: message.sender,
+            recipient: message.recipient,
+            leaf_index: origin_db.retrieve_merkle_leaf_index_by_message_id(&message_id)?,
+            processed: origin_db
+                .retrieve_processed_by_nonce(&message.nonce)?
+                .unwrap_or(false),
+            retries: origin_db
+                .retrieve_pending_message_retry_count_by_message_id(&message_id)?
+                .unwrap_or(0),
+        });
+        diagnosis.status = match self.walk(&message, &mut diagnosis).await {
+            Ok(status) => status,
+            Err(err) => DeliveryStatus::Error {
+                error: format!("{err:#}"),
+            },
+        };
+        Ok(diagnosis)
+    }
+
+    fn find_message(
+        &self,
+        message_id: H256,
+    ) -> Result<Option<(HyperlaneMessage, &HyperlaneRocksDB)>> {
+        for db in self.origin_dbs.values() {
+            if let Some(message) = db.retrieve_message_by_id(&message_id)? {
+                return Ok(Some((message, db)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Mirrors `MessageProcessor::tick` and `PendingMessage::prepare`,
+    /// returning the first reason found for the message not being delivered.
+    async fn walk(
+        &self,
+        message: &HyperlaneMessage,
+        diagnosis: &mut Diagnosis,
+    ) -> Result<DeliveryStatus> {
+        let settings = self.settings.get();
+        if !settings.whitelist.msg_matches(message, true) {
+            return Ok(DeliveryStatus::NotWhitelisted);
+        }
+        if settings.blacklist.msg_matches(message, false) {
+            return Ok(DeliveryStatus::Blacklisted);
+        }
+        let ctx = match self.msg_ctxs.get(&(message.origin, message.destination)) {
+            Some(ctx) if message.origin != message.destination => ctx,
+            _ => return Ok(DeliveryStatus::DestinationNotRelayed),
+        };
+
+        let (payment, expenditure) = ctx
+            .origin_gas_payment_enforcer
+            .current_payment_and_expenditure(message)?;
+        let gas = diagnosis.gas.insert(GasDiagnosis {
+            paid_tokens: payment.payment,
+            paid_gas: payment.gas_amount,
+            spent_tokens: expenditure.tokens_used,
+            spent_gas: expenditure.gas_used,
+            estimated_gas: None,
+            requirement_met: None,
+        });
+
+        let mailbox = &ctx.destination_mailbox;
+        if mailbox
+            .delivered(message.id())
+            .await
+            .context("When checking message delivery status")?
+        {
+            return Ok(DeliveryStatus::Delivered);
+        }
+        if diagnosis.message.as_ref().map_or(false, |m| m.processed) {
+            return Ok(DeliveryStatus::MarkedProcessed);
+        }
+        if !mailbox
+            .provider()
+            .is_contract(&message.recipient)
+            .await
+            .context("When checking if the recipient is a contract")?
+        {
+            return Ok(DeliveryStatus::RecipientNotContract);
+        }
+
+        let ism_address = mailbox
+            .recipient_ism(message.recipient)
+            .await
+            .context("When fetching the recipient ISM")?;
+        let builder;
+        let reader: &dyn IsmReader = match &self.ism_reader {
+            Some(reader) => reader.as_ref(),
+            None => {
+                builder =
+                    MessageMetadataBuilder::new(ism_address, message, ctx.metadata_builder.clone())
+                        .await?;
+                &builder
+            }
+        };
+        let leaf_index = diagnosis.message.as_ref().and_then(|m| m.leaf_index);
+        let ism = IsmTreeWalker::new(reader, message)
+            .with_leaf_index(leaf_index)
+            .verifying()
+            .walk(ism_address)
+            .await;
+        let metadata = ism.metadata.clone();
+        diagnosis.ism = Some(ism);
+        let Some(metadata) = metadata else {
+            return Ok(DeliveryStatus::NoMetadata);
+        };
+
+        let tx_cost_estimate = match mailbox.process_estimate_costs(message, &metadata).await {
+            Ok(tx_cost_estimate) => tx_cost_estimate,
+            Err(err) => {
+                return Ok(DeliveryStatus::EstimationReverted {
+                    error: err.to_string(),
+                })
+            }
+        };
+        gas.estimated_gas = Some(tx_cost_estimate.gas_limit);
+        let requirement_met = ctx
+            .origin_gas_payment_enforcer
+            .message_meets_gas_payment_requirement(message, &tx_cost_estimate)
+            .await?
+            .is_some();
+        gas.requirement_met = Some(requirement_met);
+        if !requirement_met {
+            return Ok(DeliveryStatus::GasPaymentNotMet);
+        }
+        if let Some(limit) = ctx.transaction_gas_limit {
+            if tx_cost_estimate.gas_limit > limit {
+                return Ok(DeliveryStatus::GasLimitExceeded { limit });
+            }
+        }
+        Ok(DeliveryStatus::Deliverable)
+    }
+}
+
+async fn diagnose(
+    State(diagnoser): State<Arc<MessageDiagnoser>>,
+    Path(message_id): Path<String>,
+) -> Response {
+    let Ok(message_id) = H256::from_str(&message_id) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Invalid message id `{message_id}`") })),
+        )
+            .into_response();
+    };
+    match diagnoser.diagnose(message_id).await {
+        Ok(diagnosis) => Json(diagnosis).into_response(),
+        Err(err) => {
+            warn!(error=?err, ?message_id, "Failed to diagnose message");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("{err:#}") })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_trait::async_trait;
+    use hyperlane_base::db::{test_utils, DB};
+    use hyperlane_core::{
+        BlockInfo, ChainInfo, ChainResult, HyperlaneDomain, HyperlaneProvider, TxCostEstimate,
+        TxnInfo, H512,
+    };
+    use hyperlane_test::mocks::MockMailboxContract;
+
+    use super::*;
+    use crate::{
+        msg::{
+            gas_payment::GasPaymentEnforcer,
+            metadata::{IsmVerification, MockIsm, MockIsmReader},
+            processor::test::{dummy_domain, dummy_metadata_builder, dummy_submission_metrics},
+        },
+        settings::{
+            reload::ReloadableSettings, GasPaymentEnforcementConf, GasPaymentEnforcementPolicy,
+        },
+    };
+
+    const ISM: H256 = H256::repeat_byte(1);
+    const VALIDATOR: H256 = H256::repeat_byte(0xa);
+
+    /// Answers whether an address is a contract, which is all the diagnosis
+    /// asks the destination's provider
+    #[derive(Debug, Clone)]
+    struct ContractProvider(HyperlaneDomain);
+
+    impl HyperlaneChain for ContractProvider {
+        fn domain(&self) -> &HyperlaneDomain {
+            &self.0
+        }
+
+        fn provider(&self) -> Box<dyn HyperlaneProvider> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneProvider for ContractProvider {
+        async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
+            unimplemented!()
+        }
+
+        async fn get_block_by_height(&self, _height: u64) -> ChainResult<BlockInfo> {
+            unimplemented!()
+        }
+
+        async fn get_txn_by_hash(&self, _hash: &H512) -> ChainResult<TxnInfo> {
+            unimplemented!()
+        }
+
+        async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
+            Ok(true)
+        }
+
+        async fn get_balance(&self, _address: String) -> ChainResult<U256> {
+            unimplemented!()
+        }
+
+        async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
+            unimplemented!()
+        }
+
+        async fn get_chain_id(&self) -> ChainResult<Option<U256>> {
+            unimplemented!()
+        }
+    }
+
+    /// A destination mailbox on which the message is not delivered yet, and
+    /// whose recipients are verified by a multisig ISM
+    fn dummy_mailbox(destination: &HyperlaneDomain) -> MockMailboxContract {
+        let mut mailbox = MockMailboxContract::new();
+        mailbox.expect__delivered().returning(|_| Ok(false));
+        let destination = destination.clone();
+        mailbox
+            .expect__provider()
+            .returning(move || Box::new(ContractProvider(destination.clone())));
+        mailbox.expect__recipient_ism().returning(|_| Ok(ISM));
+        mailbox.expect_process_estimate_costs().returning(|_, _| {
+            Ok(TxCostEstimate {
+                gas_limit: 100_000.into(),
+                ..Default::default()
+            })
+        });
+        mailbox
+    }
+
+    /// A multisig ISM, which verifies the message if `signed` is set
+    fn dummy_ism_reader(signed: bool) -> MockIsmReader {
+        MockIsmReader {
+            isms: HashMap::from([(
+                ISM,
+                MockIsm::Multisig {
+                    validators: vec![VALIDATOR],
+                    threshold: 1,
+                },
+            )]),
+            latest_indices: if signed {
+                HashMap::from([(VALIDATOR, 10)])
+            } else {
+                HashMap::new()
+            },
+            max_depth: 5,
+        }
+    }
+
+    /// Stores a message from `origin` to `destination` and diagnoses it
+    async fn diagnose(
+        db: HyperlaneRocksDB,
+        settings: ReloadableSettings,
+        gas_payment_policy: GasPaymentEnforcementPolicy,
+        mailbox: MockMailboxContract,
+        ism_reader: MockIsmReader,
+    ) -> Diagnosis {
+        let (origin, destination) = (dummy_domain(0, "origin"), dummy_domain(1, "destination"));
+        let message = HyperlaneMessage {
+            origin: origin.id(),
+            destination: destination.id(),
+            ..Default::default()
+        };
+        db.store_message(&message, 0).unwrap();
+        db.store_merkle_leaf_index_by_message_id(&message.id(), &0)
+            .unwrap();
+
+        let ctx = Arc::new(MessageContext {
+            destination_mailbox: Arc::new(mailbox),
+            origin_db: db.clone(),
+            metadata_builder: Arc::new(dummy_metadata_builder(&origin, &destination, &db)),
+            origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new(
+                [GasPaymentEnforcementConf {
+                    policy: gas_payment_policy,
+                    matching_list: Default::default(),
+                }],
+                db.clone(),
+            )),
+            transaction_gas_limit: None,
+            metrics: dummy_submission_metrics(),
+            notifier: Default::default(),
+        });
+        MessageDiagnoser::new(
+            ReloadableSettingsHandle::new(settings),
+            HashMap::from([(origin.id(), db)]),
+            HashMap::from([((origin.id(), destination.id()), ctx)]),
+        )
+        .with_ism_reader(Arc::new(ism_reader))
+        .diagnose(message.id())
+        .await
+        .unwrap()
+    }
+
+    fn origin_db(db: DB) -> HyperlaneRocksDB {
+        HyperlaneRocksDB::new(&dummy_domain(0, "origin"), db)
+    }
+
+    #[tokio::test]
+    async fn diagnoses_filtered_messages() {
+        test_utils::run_test_db(|db| async move {
+            let db = origin_db(db);
+            let destination = dummy_domain(1, "destination");
+            let settings = ReloadableSettings {
+                whitelist: serde_json::from_str(r#"[{"origindomain": 5}]"#).unwrap(),
+                ..Default::default()
+            };
+            let diagnosis = diagnose(
+                db.clone(),
+                settings,
+                GasPaymentEnforcementPolicy::None,
+                dummy_mailbox(&destination),
+                dummy_ism_reader(true),
+            )
+            .await;
+            assert!(matches!(diagnosis.status, DeliveryStatus::NotWhitelisted));
+
+            let settings = ReloadableSettings {
+                blacklist: serde_json::from_str(r#"[{"origindomain": 0}]"#).unwrap(),
+                ..Default::default()
+            };
+            let diagnosis = diagnose(
+                db,
+                settings,
+                GasPaymentEnforcementPolicy::None,
+                dummy_mailbox(&destination),
+                dummy_ism_reader(true),
+            )
+            .await;
+            assert!(matches!(diagnosis.status, DeliveryStatus::Blacklisted));
+            // The message is still described
+            assert_eq!(diagnosis.message.unwrap().leaf_index, Some(0));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn diagnoses_delivered_message() {
+        test_utils::run_test_db(|db| async move {
+            let mut mailbox = MockMailboxContract::new();
+            mailbox.expect__delivered().returning(|_| Ok(true));
+            let diagnosis = diagnose(
+                origin_db(db),
+                Default::default(),
+                GasPaymentEnforcementPolicy::None,
+                mailbox,
+                dummy_ism_reader(true),
+            )
+            .await;
+            assert!(matches!(diagnosis.status, DeliveryStatus::Delivered));
+            assert!(diagnosis.ism.is_none());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn diagnoses_missing_metadata() {
+        test_utils::run_test_db(|db| async move {
+            let diagnosis = diagnose(
+                origin_db(db),
+                Default::default(),
+                GasPaymentEnforcementPolicy::None,
+                dummy_mailbox(&dummy_domain(1, "destination")),
+                dummy_ism_reader(false),
+            )
+            .await;
+            assert!(matches!(diagnosis.status, DeliveryStatus::NoMetadata));
+            let ism = diagnosis.ism.unwrap();
+            assert!(matches!(
+                ism.verification,
+                Some(IsmVerification::NoMetadata)
+            ));
+            let validators = ism.validators.unwrap();
+            assert_eq!(validators[0].signed_message, Some(false));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn diagnoses_unmet_gas_payment() {
+        test_utils::run_test_db(|db| async move {
+            let diagnosis = diagnose(
+                origin_db(db),
+                Default::default(),
+                GasPaymentEnforcementPolicy::Minimum {
+                    payment: U256::one(),
+                },
+                dummy_mailbox(&dummy_domain(1, "destination")),
+                dummy_ism_reader(true),
+            )
+            .await;
+            assert!(matches!(diagnosis.status, DeliveryStatus::GasPaymentNotMet));
+            let gas = diagnosis.gas.unwrap();
+            assert_eq!(gas.estimated_gas, Some(100_000.into()));
+            assert_eq!(gas.requirement_met, Some(false));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn diagnoses_deliverable_message() {
+        test_utils::run_test_db(|db| async move {
+            let diagnosis = diagnose(
+                origin_db(db),
+                Default::default(),
+                GasPaymentEnforcementPolicy::None,
+                dummy_mailbox(&dummy_domain(1, "destination")),
+                dummy_ism_reader(true),
+            )
+            .await;
+            assert!(matches!(diagnosis.status, DeliveryStatus::Deliverable));
+            let ism = diagnosis.ism.unwrap();
+            assert!(matches!(
+                ism.verification,
+                Some(IsmVerification::Verified { .. })
+            ));
+            assert_eq!(ism.validators.unwrap()[0].signed_message, Some(true));
+        })
+        .await;
+    }
+}
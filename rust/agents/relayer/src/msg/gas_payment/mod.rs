@@ -1,4 +1,7 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    sync::{Arc, RwLock},
+};
 
 use async_trait::async_trait;
 use eyre::Result;
@@ -39,10 +42,13 @@ pub struct GasPaymentEnforcer {
     /// use a wild-card white list to ensure all messages fall into one
     /// policy or another. If a message matches multiple policies'
     /// whitelists, then whichever is first in the list will be used.
-    policies: Vec<(Box<dyn GasPaymentPolicy>, MatchingList)>,
+    /// The policies are swapped when the relayer settings are reloaded.
+    policies: RwLock<Arc<GasPaymentPolicies>>,
     db: HyperlaneRocksDB,
 }
 
+type GasPaymentPolicies = Vec<(Box<dyn GasPaymentPolicy>, MatchingList)>;
+
 impl GasPaymentEnforcer {
     /// Note that `policy_configs` should not be empty. In the settings,
     /// a default of vec![GasPaymentEnforcementConf::default()] is used.
@@ -50,7 +56,25 @@ impl GasPaymentEnforcer {
         policy_configs: impl IntoIterator<Item = GasPaymentEnforcementConf>,
         db: HyperlaneRocksDB,
     ) -> Self {
-        let policies = policy_configs
+        Self {
+            policies: RwLock::new(Arc::new(Self::build_policies(policy_configs))),
+            db,
+        }
+    }
+
+    /// Replaces the policies, e.g. after the relayer settings were reloaded.
+    pub fn set_policies(
+        &self,
+        policy_configs: impl IntoIterator<Item = GasPaymentEnforcementConf>,
+    ) {
+        *self.policies.write().expect("poisoned lock") =
+            Arc::new(Self::build_policies(policy_configs));
+    }
+
+    fn build_policies(
+        policy_configs: impl IntoIterator<Item = GasPaymentEnforcementConf>,
+    ) -> GasPaymentPolicies {
+        policy_configs
             .into_iter()
             .map(|cfg| {
                 let p: Box<dyn GasPaymentPolicy> = match cfg.policy {
@@ -65,9 +89,7 @@ impl GasPaymentEnforcer {
                 };
                 (p, cfg.matching_list)
             })
-            .collect();
-
-        Self { policies, db }
+            .collect()
     }
 }
 
@@ -79,17 +101,11 @@ impl GasPaymentEnforcer {
         message: &HyperlaneMessage,
         tx_cost_estimate: &TxCostEstimate,
     ) -> Result<Option<U256>> {
-        let msg_id = message.id();
-        let gas_payment_key = GasPaymentKey {
-            message_id: msg_id,
-            destination: message.destination,
-        };
-        let current_payment = self
-            .db
-            .retrieve_gas_payment_by_gas_payment_key(gas_payment_key)?;
-        let current_expenditure = self.db.retrieve_gas_expenditure_by_message_id(msg_id)?;
+        let (current_payment, current_expenditure) =
+            self.current_payment_and_expenditure(message)?;
 
-        for (policy, whitelist) in &self.policies {
+        let policies = self.policies.read().expect("poisoned lock").clone();
+        for (policy, whitelist) in policies.iter() {
             if !whitelist.msg_matches(message, true) {
                 trace!(
                     msg=%message,
@@ -131,6 +147,24 @@ impl GasPaymentEnforcer {
         Ok(None)
     }
 
+    /// Returns the total gas payment for a message to its destination, and
+    /// the gas spent on attempts to deliver it.
+    pub fn current_payment_and_expenditure(
+        &self,
+        message: &HyperlaneMessage,
+    ) -> Result<(InterchainGasPayment, InterchainGasExpenditure)> {
+        let msg_id = message.id();
+        let gas_payment_key = GasPaymentKey {
+            message_id: msg_id,
+            destination: message.destination,
+        };
+        let current_payment = self
+            .db
+            .retrieve_gas_payment_by_gas_payment_key(gas_payment_key)?;
+        let current_expenditure = self.db.retrieve_gas_expenditure_by_message_id(msg_id)?;
+        Ok((current_payment, current_expenditure))
+    }
+
     pub fn record_tx_outcome(&self, message: &HyperlaneMessage, outcome: TxOutcome) -> Result<()> {
         self.db.process_gas_expenditure(InterchainGasExpenditure {
             message_id: message.id(),
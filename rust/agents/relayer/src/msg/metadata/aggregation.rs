@@ -125,7 +125,15 @@ impl MetadataBuilder for AggregationIsmMetadataBuilder {
     ) -> eyre::Result<Option<Vec<u8>>> {
         const CTX: &str = "When fetching AggregationIsm metadata";
         let ism = self.build_aggregation_ism(ism_address).await.context(CTX)?;
-        let (ism_addresses, threshold) = ism.modules_and_threshold(message).await.context(CTX)?;
+        let (ism_addresses, threshold) = self
+            .ism_cache()
+            .modules_and_threshold(
+                ism_address,
+                message.origin,
+                ism.modules_and_threshold(message),
+            )
+            .await
+            .context(CTX)?;
         let threshold = threshold as usize;
 
         let sub_modules_and_metas = join_all(
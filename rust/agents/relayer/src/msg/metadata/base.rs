@@ -10,11 +10,14 @@ use std::{
 use crate::{
     merkle_tree::builder::MerkleTreeBuilder,
     msg::metadata::{
-        multisig::{MerkleRootMultisigMetadataBuilder, MessageIdMultisigMetadataBuilder},
-        AggregationIsmMetadataBuilder, CcipReadIsmMetadataBuilder, NullMetadataBuilder,
-        RoutingIsmMetadataBuilder,
+        multisig::{
+            BlsMultisigMetadataBuilder, MerkleRootMultisigMetadataBuilder,
+            MessageIdMultisigMetadataBuilder, WeightedMessageIdMultisigMetadataBuilder,
+        },
+        AggregationIsmMetadataBuilder, CcipReadClient, CcipReadIsmMetadataBuilder, IsmCache,
+        NullMetadataBuilder, OptimisticIsmMetadataBuilder, RoutingIsmMetadataBuilder,
     },
-    settings::matching_list::MatchingList,
+    settings::{matching_list::MatchingList, reload::ReloadableSettingsHandle},
 };
 use async_trait::async_trait;
 use derive_new::new;
@@ -25,9 +28,9 @@ use hyperlane_base::{
     CheckpointSyncer, CoreMetrics, MultisigCheckpointSyncer,
 };
 use hyperlane_core::{
-    accumulator::merkle::Proof, AggregationIsm, CcipReadIsm, Checkpoint, HyperlaneDomain,
-    HyperlaneMessage, InterchainSecurityModule, Mailbox, ModuleType, MultisigIsm, RoutingIsm,
-    ValidatorAnnounce, H160, H256,
+    accumulator::merkle::Proof, AggregationIsm, BlsMultisigIsm, CcipReadIsm, Checkpoint,
+    HyperlaneDomain, HyperlaneMessage, InterchainSecurityModule, Mailbox, ModuleType, MultisigIsm,
+    OptimisticIsm, RoutingIsm, ValidatorAnnounce, WeightedMultisigIsm, H160, H256,
 };
 
 use tokio::sync::RwLock;
@@ -39,6 +42,8 @@ pub enum MetadataBuilderError {
     UnsupportedModuleType(ModuleType),
     #[error("Exceeded max depth when building metadata ({0})")]
     MaxDepthExceeded(u32),
+    #[error("Optimistic ISMs are only supported as the recipient's ISM, not nested in other ISMs")]
+    NestedOptimisticIsm,
 }
 
 pub struct IsmWithMetadataAndType {
@@ -106,17 +111,15 @@ impl DefaultIsmCache {
 #[derive(Debug)]
 pub struct IsmAwareAppContextClassifier {
     default_ism: DefaultIsmCache,
-    app_context_classifier: AppContextClassifier,
+    /// Holds the app matching lists, which may be reloaded
+    settings: ReloadableSettingsHandle,
 }
 
 impl IsmAwareAppContextClassifier {
-    pub fn new(
-        destination_mailbox: Arc<dyn Mailbox>,
-        app_matching_lists: Vec<(MatchingList, String)>,
-    ) -> Self {
+    pub fn new(destination_mailbox: Arc<dyn Mailbox>, settings: ReloadableSettingsHandle) -> Self {
         Self {
             default_ism: DefaultIsmCache::new(destination_mailbox),
-            app_context_classifier: AppContextClassifier::new(app_matching_lists),
+            settings,
         }
     }
 
@@ -125,7 +128,9 @@ impl IsmAwareAppContextClassifier {
         message: &HyperlaneMessage,
         root_ism: H256,
     ) -> Result<Option<String>> {
-        if let Some(app_context) = self.app_context_classifier.get_app_context(message).await? {
+        let app_context_classifier =
+            AppContextClassifier::new(self.settings.get().metric_app_contexts.clone());
+        if let Some(app_context) = app_context_classifier.get_app_context(message).await? {
             return Ok(Some(app_context));
         }
 
@@ -214,6 +219,16 @@ impl MessageMetadataBuilder {
         })
     }
 
+    /// A builder for messages whose app context is unknown, e.g. when
+    /// inspecting ISMs outside of processing a message
+    pub fn without_app_context(base: Arc<BaseMetadataBuilder>) -> Self {
+        Self {
+            base,
+            depth: 0,
+            app_context: None,
+        }
+    }
+
     fn clone_with_incremented_depth(&self) -> Result<MessageMetadataBuilder> {
         let mut cloned = self.clone();
         cloned.depth += 1;
@@ -235,8 +250,9 @@ impl MessageMetadataBuilder {
             .await
             .context("When building ISM")?;
 
-        let module_type = ism
-            .module_type()
+        let module_type = self
+            .ism_cache
+            .module_type(ism_address, ism.module_type())
             .await
             .context("When fetching module type")?;
         let cloned = self.clone_with_incremented_depth()?;
@@ -252,6 +268,16 @@ impl MessageMetadataBuilder {
             ModuleType::Aggregation => Box::new(AggregationIsmMetadataBuilder::new(cloned)),
             ModuleType::Null => Box::new(NullMetadataBuilder::new()),
             ModuleType::CcipRead => Box::new(CcipReadIsmMetadataBuilder::new(cloned)),
+            ModuleType::BlsMultisig => Box::new(BlsMultisigMetadataBuilder::new(cloned)),
+            ModuleType::WeightedMessageIdMultisig => {
+                Box::new(WeightedMessageIdMultisigMetadataBuilder::new(cloned))
+            }
+            // Messages are only pre-verified with the recipient's ISM, so a
+            // nested optimistic ISM would never verify them
+            ModuleType::Optimistic if self.depth > 0 => {
+                return Err(MetadataBuilderError::NestedOptimisticIsm.into())
+            }
+            ModuleType::Optimistic => Box::new(OptimisticIsmMetadataBuilder::new(cloned)),
             _ => return Err(MetadataBuilderError::UnsupportedModuleType(module_type).into()),
         };
         let meta = metadata_builder
@@ -279,6 +305,12 @@ pub struct BaseMetadataBuilder {
     db: HyperlaneRocksDB,
     max_depth: u32,
     app_context_classifier: IsmAwareAppContextClassifier,
+    /// Module types and configuration of the destination's ISMs, shared
+    /// between the builders of all origins
+    ism_cache: Arc<IsmCache>,
+    /// Fetches metadata from the gateways of CCIP read ISMs, shared between
+    /// all builders
+    ccip_read_client: Arc<CcipReadClient>,
 }
 
 impl Debug for BaseMetadataBuilder {
@@ -300,6 +332,18 @@ impl BaseMetadataBuilder {
         &self.destination_chain_setup.domain
     }
 
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    pub fn ism_cache(&self) -> &IsmCache {
+        &self.ism_cache
+    }
+
+    pub fn ccip_read_client(&self) -> &CcipReadClient {
+        &self.ccip_read_client
+    }
+
     pub async fn get_proof(&self, leaf_index: u32, checkpoint: Checkpoint) -> Result<Proof> {
         const CTX: &str = "When fetching message proof";
         let proof = self
@@ -348,6 +392,27 @@ impl BaseMetadataBuilder {
             .await
     }
 
+    pub async fn build_optimistic_ism(&self, address: H256) -> Result<Box<dyn OptimisticIsm>> {
+        self.destination_chain_setup
+            .build_optimistic_ism(address, &self.metrics)
+            .await
+    }
+
+    pub async fn build_bls_multisig_ism(&self, address: H256) -> Result<Box<dyn BlsMultisigIsm>> {
+        self.destination_chain_setup
+            .build_bls_multisig_ism(address, &self.metrics)
+            .await
+    }
+
+    pub async fn build_weighted_multisig_ism(
+        &self,
+        address: H256,
+    ) -> Result<Box<dyn WeightedMultisigIsm>> {
+        self.destination_chain_setup
+            .build_weighted_multisig_ism(address, &self.metrics)
+            .await
+    }
+
     pub async fn build_aggregation_ism(&self, address: H256) -> Result<Box<dyn AggregationIsm>> {
         self.destination_chain_setup
             .build_aggregation_ism(address, &self.metrics)
@@ -360,15 +425,24 @@ impl BaseMetadataBuilder {
             .await
     }
 
+    /// Fetches the storage locations each validator announced on the origin,
+    /// from oldest to most recent.
+    pub async fn announced_storage_locations(
+        &self,
+        validators: &[H256],
+    ) -> Result<Vec<Vec<String>>> {
+        Ok(self
+            .origin_validator_announce
+            .get_announced_storage_locations(validators)
+            .await?)
+    }
+
     pub async fn build_checkpoint_syncer(
         &self,
         validators: &[H256],
         app_context: Option<String>,
     ) -> Result<MultisigCheckpointSyncer> {
-        let storage_locations = self
-            .origin_validator_announce
-            .get_announced_storage_locations(validators)
-            .await?;
+        let storage_locations = self.announced_storage_locations(validators).await?;
 
         // Only use the most recently announced location for now.
         let mut checkpoint_syncers: HashMap<H160, Arc<dyn CheckpointSyncer>> = HashMap::new();
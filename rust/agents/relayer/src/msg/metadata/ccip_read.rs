@@ -1,17 +1,31 @@
+use std::sync::Mutex;
+
 use async_trait::async_trait;
 use derive_more::Deref;
 use derive_new::new;
-use ethers::{abi::AbiDecode, core::utils::hex::decode as hex_decode};
-use eyre::Context;
-use hyperlane_core::{utils::bytes_to_hex, HyperlaneMessage, RawHyperlaneMessage, H256};
+use ethers::{abi::AbiDecode, core::utils::hex::decode as hex_decode, types::Address};
+use eyre::{bail, Context};
+use hyperlane_core::{
+    utils::bytes_to_hex, CcipReadIsm, HyperlaneMessage, RawHyperlaneMessage, H256,
+};
 use hyperlane_ethereum::OffchainLookup;
+use prometheus::IntCounterVec;
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{redirect, Client, Url};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use tracing::{info, instrument};
+use serde_json::{json, Value};
+use tokio::time::{sleep, timeout};
+use tracing::{debug, info, instrument};
+
+use super::{base::MessageMetadataBuilder, ism_cache::TtlCache, MetadataBuilder};
+use crate::settings::CcipReadConf;
+
+/// The maximum number of redirects followed per request to a gateway
+const MAX_REDIRECTS: usize = 5;
 
-use super::{base::MessageMetadataBuilder, MetadataBuilder};
+/// The host label of requests to gateways which aren't on the allow list, to
+/// bound the cardinality of the metric
+const OTHER_HOST_LABEL: &str = "other";
 
 #[derive(Serialize, Deserialize)]
 struct OffchainResponse {
@@ -34,64 +48,486 @@ impl MetadataBuilder for CcipReadIsmMetadataBuilder {
         const CTX: &str = "When fetching CcipRead metadata";
         let ism = self.build_ccip_read_ism(ism_address).await.context(CTX)?;
 
-        let response = ism
-            .get_offchain_verify_info(RawHyperlaneMessage::from(message).to_vec())
-            .await;
-        let info: OffchainLookup = match response {
-            Ok(_) => {
-                info!("incorrectly configured getOffchainVerifyInfo, expected revert");
-                return Ok(None);
+        let Some(info) = offchain_lookup(ism.as_ref(), message).await? else {
+            return Ok(None);
+        };
+        Ok(self.ccip_read_client().fetch(&info).await)
+    }
+}
+
+/// A request to one of the gateways of an `OffchainLookup`
+#[derive(Debug)]
+struct GatewayRequest {
+    url: Url,
+    host: String,
+    /// The body of gateways which don't take the call data in their url
+    body: Option<Value>,
+}
+
+/// Fetches metadata from the offchain gateways of CCIP read ISMs, retrying
+/// across the urls of an `OffchainLookup` and caching the responses.
+///
+/// The urls come from the ISM, so only the first `max_urls` of them are tried,
+/// each lookup is bounded by `lookup_timeout`, and redirects are only followed
+/// to allowed hosts.
+#[derive(Debug)]
+pub struct CcipReadClient {
+    conf: CcipReadConf,
+    http: Client,
+    /// Fetched metadata by the sender and call data of the lookup
+    responses: Mutex<TtlCache<(Address, Vec<u8>), Vec<u8>>>,
+    /// Requests by allowed gateway host and result
+    requests: IntCounterVec,
+}
+
+impl CcipReadClient {
+    pub fn new(conf: CcipReadConf, requests: IntCounterVec) -> eyre::Result<Self> {
+        let (allowed_hosts, denied_hosts) = (conf.allowed_hosts.clone(), conf.denied_hosts.clone());
+        let redirect_policy = redirect::Policy::custom(move |attempt| {
+            let allowed = attempt.url().host_str().map_or(false, |host| {
+                is_host_allowed(&allowed_hosts, &denied_hosts, &host.to_lowercase())
+            });
+            if !allowed {
+                attempt.error("redirect to a host which is not allowed")
+            } else if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
             }
-            Err(raw_error) => {
-                let matching_regex = Regex::new(r"0x[[:xdigit:]]+")?;
-                if let Some(matching) = &matching_regex.captures(&raw_error.to_string()) {
-                    OffchainLookup::decode(hex_decode(&matching[0][2..])?)?
-                } else {
-                    info!("unable to parse custom error out of revert");
-                    return Ok(None);
+        });
+        let http = Client::builder()
+            .timeout(conf.timeout)
+            .redirect(redirect_policy)
+            .build()?;
+        Ok(Self {
+            responses: Mutex::new(TtlCache::new(conf.cache_ttl, conf.cache_capacity)),
+            conf,
+            http,
+            requests,
+        })
+    }
+
+    /// Fetches the metadata of an `OffchainLookup` from the first of its
+    /// allowed gateways which responds, or `None` if none did.
+    pub async fn fetch(&self, info: &OffchainLookup) -> Option<Vec<u8>> {
+        let key = (info.sender, info.call_data.to_vec());
+        let cached = self.responses.lock().unwrap().get(&key);
+        if cached.is_some() {
+            return cached;
+        }
+
+        // Need to explicitly convert the sender H160 the hex because the `ToString` implementation
+        // for `H160` truncates the output. (e.g. `0xc66a…7b6f` instead of returning
+        // the full address)
+        let sender = bytes_to_hex(info.sender.as_bytes());
+        let data = info.call_data.to_string();
+        if info.urls.len() > self.conf.max_urls {
+            info!(
+                urls = info.urls.len(),
+                max_urls = self.conf.max_urls,
+                "Only trying the first CCIP read gateways of the lookup"
+            );
+        }
+        let requests = info
+            .urls
+            .iter()
+            .take(self.conf.max_urls)
+            .filter_map(|url| self.gateway_request(url, &sender, &data))
+            .collect::<Vec<_>>();
+        if requests.is_empty() {
+            info!(urls=?info.urls, "No allowed CCIP read gateways");
+            return None;
+        }
+
+        let metadata = timeout(self.conf.lookup_timeout, self.fetch_from(&requests))
+            .await
+            .unwrap_or_else(|_| {
+                info!(
+                    timeout=?self.conf.lookup_timeout,
+                    "Timed out fetching metadata from CCIP read gateways"
+                );
+                None
+            })?;
+        self.responses.lock().unwrap().insert(key, metadata.clone());
+        Some(metadata)
+    }
+
+    /// Tries the gateways in order until one responds, retrying all of them
+    /// with backoff.
+    async fn fetch_from(&self, requests: &[GatewayRequest]) -> Option<Vec<u8>> {
+        for attempt in 0..=self.conf.retries {
+            if attempt > 0 {
+                sleep(self.conf.retry_backoff * 2u32.saturating_pow(attempt - 1)).await;
+            }
+            for request in requests {
+                match self.send(request).await {
+                    Ok(metadata) => {
+                        self.record(&request.host, "success");
+                        return Some(metadata);
+                    }
+                    Err(err) => {
+                        self.record(&request.host, "failure");
+                        debug!(
+                            error=%err,
+                            url=%request.url,
+                            attempt,
+                            "Failed to fetch metadata from CCIP read gateway"
+                        );
+                    }
                 }
             }
+        }
+
+        // Endpoints down
+        None
+    }
+
+    /// Interpolates the url of a gateway, returning `None` if it is invalid
+    /// or its host is not allowed.
+    fn gateway_request(&self, url: &str, sender: &str, data: &str) -> Option<GatewayRequest> {
+        let interpolated_url = url.replace("{sender}", sender).replace("{data}", data);
+        let parsed_url = match Url::parse(&interpolated_url) {
+            Ok(parsed_url) => parsed_url,
+            Err(err) => {
+                debug!(error=%err, url, "Invalid CCIP read gateway url");
+                return None;
+            }
         };
+        let host = parsed_url.host_str()?.to_lowercase();
+        if !self.is_host_allowed(&host) {
+            self.record(&host, "denied");
+            debug!(url, "CCIP read gateway host is not allowed");
+            return None;
+        }
+        let body = (!url.contains("{data}")).then(|| {
+            json!({
+                "sender": sender,
+                "data": data
+            })
+        });
+        Some(GatewayRequest {
+            url: parsed_url,
+            host,
+            body,
+        })
+    }
 
-        for url in info.urls.iter() {
-            // Need to explicitly convert the sender H160 the hex because the `ToString` implementation
-            // for `H160` truncates the output. (e.g. `0xc66a…7b6f` instead of returning
-            // the full address)
-            let sender_as_bytes = &bytes_to_hex(info.sender.as_bytes());
-            let data_as_bytes = &info.call_data.to_string();
-            let interpolated_url = url
-                .replace("{sender}", sender_as_bytes)
-                .replace("{data}", data_as_bytes);
-            let res = if !url.contains("{data}") {
-                let body = json!({
-                    "sender": sender_as_bytes,
-                    "data": data_as_bytes
-                });
-                Client::new()
-                    .post(interpolated_url)
-                    .header("Content-Type", "application/json")
-                    .json(&body)
-                    .send()
-                    .await?
-            } else {
-                reqwest::get(interpolated_url).await?
-            };
+    fn is_host_allowed(&self, host: &str) -> bool {
+        is_host_allowed(&self.conf.allowed_hosts, &self.conf.denied_hosts, host)
+    }
 
-            let json: Result<OffchainResponse, reqwest::Error> = res.json().await;
+    async fn send(&self, request: &GatewayRequest) -> eyre::Result<Vec<u8>> {
+        let builder = match &request.body {
+            Some(body) => self.http.post(request.url.clone()).json(body),
+            None => self.http.get(request.url.clone()),
+        };
+        let mut response = builder.send().await?.error_for_status()?;
 
-            match json {
-                Ok(result) => {
-                    // remove leading 0x which hex_decode doesn't like
-                    let metadata = hex_decode(&result.data[2..])?;
-                    return Ok(Some(metadata));
-                }
-                Err(_err) => {
-                    // try the next URL
-                }
+        let max_size = self.conf.max_response_size;
+        if response
+            .content_length()
+            .map_or(false, |length| length > max_size as u64)
+        {
+            bail!("Response is larger than {max_size} bytes");
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() > max_size {
+                bail!("Response is larger than {max_size} bytes");
             }
         }
 
-        // No metadata endpoints or endpoints down
-        Ok(None)
+        let response: OffchainResponse = serde_json::from_slice(&body)?;
+        // remove leading 0x which hex_decode doesn't like
+        let data = response.data.strip_prefix("0x").unwrap_or(&response.data);
+        Ok(hex_decode(data)?)
+    }
+
+    /// Records a request by the allow list entry its host matches, or as
+    /// `other` if it matches none.
+    fn record(&self, host: &str, result: &str) {
+        let label = self
+            .conf
+            .allowed_hosts
+            .iter()
+            .find(|pattern| host_matches(host, pattern))
+            .map_or(OTHER_HOST_LABEL, String::as_str);
+        self.requests.with_label_values(&[label, result]).inc();
+    }
+}
+
+/// Whether `host` is `pattern` or one of its subdomains
+fn host_matches(host: &str, pattern: &str) -> bool {
+    host == pattern
+        || host
+            .strip_suffix(pattern)
+            .map_or(false, |subdomain| subdomain.ends_with('.'))
+}
+
+fn is_host_allowed(allowed_hosts: &[String], denied_hosts: &[String], host: &str) -> bool {
+    !denied_hosts
+        .iter()
+        .any(|pattern| host_matches(host, pattern))
+        && (allowed_hosts.is_empty()
+            || allowed_hosts
+                .iter()
+                .any(|pattern| host_matches(host, pattern)))
+}
+
+/// Fetches the `OffchainLookup` the ISM reverts with, which holds the urls
+/// to fetch the metadata of a message from.
+pub(super) async fn offchain_lookup(
+    ism: &dyn CcipReadIsm,
+    message: &HyperlaneMessage,
+) -> eyre::Result<Option<OffchainLookup>> {
+    let response = ism
+        .get_offchain_verify_info(RawHyperlaneMessage::from(message).to_vec())
+        .await;
+    match response {
+        Ok(_) => {
+            info!("incorrectly configured getOffchainVerifyInfo, expected revert");
+            Ok(None)
+        }
+        Err(raw_error) => {
+            let matching_regex = Regex::new(r"0x[[:xdigit:]]+")?;
+            if let Some(matching) = &matching_regex.captures(&raw_error.to_string()) {
+                Ok(Some(OffchainLookup::decode(hex_decode(
+                    &matching[0][2..],
+                )?)?))
+            } else {
+                info!("unable to parse custom error out of revert");
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use axum::{
+        extract::{Path, Query, State},
+        http::StatusCode,
+        response::Redirect,
+        routing::{get, post},
+        Json, Router,
+    };
+    use ethers::types::Bytes;
+    use prometheus::Opts;
+
+    use super::*;
+
+    /// A stand-in for a CCIP read gateway, counting the requests it serves
+    async fn spawn_gateway(requests: Arc<AtomicUsize>) -> SocketAddr {
+        async fn metadata(
+            State(requests): State<Arc<AtomicUsize>>,
+            Path((_sender, data)): Path<(String, String)>,
+        ) -> Json<Value> {
+            requests.fetch_add(1, Ordering::SeqCst);
+            Json(json!({ "data": data }))
+        }
+        async fn post_metadata(
+            State(requests): State<Arc<AtomicUsize>>,
+            Json(body): Json<Value>,
+        ) -> Json<Value> {
+            requests.fetch_add(1, Ordering::SeqCst);
+            Json(json!({ "data": body["data"] }))
+        }
+        async fn unavailable(State(requests): State<Arc<AtomicUsize>>) -> StatusCode {
+            requests.fetch_add(1, Ordering::SeqCst);
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        async fn large() -> String {
+            format!(r#"{{ "data": "0x{}" }}"#, "00".repeat(1024))
+        }
+        async fn slow() -> Json<Value> {
+            sleep(Duration::from_secs(5)).await;
+            Json(json!({ "data": "0x" }))
+        }
+        async fn redirect(Query(query): Query<HashMap<String, String>>) -> Redirect {
+            Redirect::temporary(&query["to"])
+        }
+
+        let router = Router::new()
+            .route("/metadata/:sender/:data", get(metadata))
+            .route("/metadata", post(post_metadata))
+            .route("/unavailable/:sender/:data", get(unavailable))
+            .route("/large/:sender/:data", get(large))
+            .route("/slow/:sender/:data", get(slow))
+            .route("/redirect", get(redirect))
+            .with_state(requests);
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(router.into_make_service());
+        let address = server.local_addr();
+        tokio::spawn(server);
+        address
+    }
+
+    fn client(conf: CcipReadConf) -> CcipReadClient {
+        CcipReadClient::new(
+            conf,
+            IntCounterVec::new(
+                Opts::new("ccip_read_requests", "help string"),
+                &["host", "result"],
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn test_conf() -> CcipReadConf {
+        CcipReadConf {
+            allowed_hosts: vec!["127.0.0.1".to_owned()],
+            retry_backoff: Duration::from_millis(10),
+            timeout: Duration::from_millis(500),
+            max_response_size: 1024,
+            ..Default::default()
+        }
+    }
+
+    fn lookup(urls: &[String]) -> OffchainLookup {
+        OffchainLookup {
+            sender: Address::repeat_byte(1),
+            urls: urls.to_vec(),
+            call_data: Bytes::from(vec![0xab, 0xcd]),
+            callback_function: [0; 4],
+            extra_data: Bytes::default(),
+        }
+    }
+
+    fn requests(client: &CcipReadClient, host: &str, result: &str) -> u64 {
+        client.requests.with_label_values(&[host, result]).get()
+    }
+
+    #[tokio::test]
+    async fn fetches_from_the_first_available_gateway() {
+        let served = Arc::new(AtomicUsize::new(0));
+        let address = spawn_gateway(served.clone()).await;
+        let client = client(test_conf());
+        let info = lookup(&[
+            format!("http://{address}/unavailable/{{sender}}/{{data}}"),
+            format!("http://{address}/large/{{sender}}/{{data}}"),
+            format!("http://{address}/slow/{{sender}}/{{data}}"),
+            format!("http://{address}/metadata"),
+        ]);
+
+        assert_eq!(client.fetch(&info).await, Some(vec![0xab, 0xcd]));
+        assert_eq!(requests(&client, "127.0.0.1", "failure"), 3);
+        assert_eq!(requests(&client, "127.0.0.1", "success"), 1);
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+
+        // The response is cached by call data
+        assert_eq!(client.fetch(&info).await, Some(vec![0xab, 0xcd]));
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_all_gateways_with_backoff() {
+        let served = Arc::new(AtomicUsize::new(0));
+        let address = spawn_gateway(served.clone()).await;
+        let client = client(CcipReadConf {
+            retries: 2,
+            ..test_conf()
+        });
+        let info = lookup(&[
+            format!("http://{address}/unavailable/{{sender}}/{{data}}"),
+            format!("http://{address}/unavailable/{{sender}}/{{data}}"),
+        ]);
+
+        assert_eq!(client.fetch(&info).await, None);
+        assert_eq!(served.load(Ordering::SeqCst), 6);
+        assert_eq!(requests(&client, "127.0.0.1", "failure"), 6);
+    }
+
+    #[tokio::test]
+    async fn skips_hosts_which_are_not_allowed() {
+        let served = Arc::new(AtomicUsize::new(0));
+        let address = spawn_gateway(served.clone()).await;
+        let port = address.port();
+        let client = client(CcipReadConf {
+            allowed_hosts: vec!["example.com".to_owned(), "127.0.0.1".to_owned()],
+            denied_hosts: vec!["evil.example.com".to_owned()],
+            ..test_conf()
+        });
+        let info = lookup(&[
+            format!("http://gateway.evil.example.com:{port}/metadata"),
+            format!("http://localhost:{port}/metadata"),
+            "not a url".to_owned(),
+        ]);
+
+        assert_eq!(client.fetch(&info).await, None);
+        assert_eq!(served.load(Ordering::SeqCst), 0);
+        // Hosts are labelled by the allow list entry they match
+        assert_eq!(requests(&client, "example.com", "denied"), 1);
+        assert_eq!(requests(&client, OTHER_HOST_LABEL, "denied"), 1);
+
+        assert!(client.is_host_allowed("example.com"));
+        assert!(client.is_host_allowed("gateway.example.com"));
+        assert!(!client.is_host_allowed("notexample.com"));
+
+        let info = lookup(&[format!("http://127.0.0.1:{port}/metadata")]);
+        assert_eq!(client.fetch(&info).await, Some(vec![0xab, 0xcd]));
+    }
+
+    #[tokio::test]
+    async fn only_follows_redirects_to_allowed_hosts() {
+        let served = Arc::new(AtomicUsize::new(0));
+        let address = spawn_gateway(served.clone()).await;
+        let port = address.port();
+        let client = client(CcipReadConf {
+            denied_hosts: vec!["localhost".to_owned()],
+            ..test_conf()
+        });
+        let redirect = |host: &str| {
+            format!(
+                "http://{address}/redirect?to=http://{host}:{port}/metadata/{{sender}}/{{data}}"
+            )
+        };
+
+        let info = lookup(&[redirect("localhost")]);
+        assert_eq!(client.fetch(&info).await, None);
+        assert_eq!(served.load(Ordering::SeqCst), 0);
+        assert_eq!(requests(&client, "127.0.0.1", "failure"), 3);
+
+        let info = lookup(&[redirect("127.0.0.1")]);
+        assert_eq!(client.fetch(&info).await, Some(vec![0xab, 0xcd]));
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn bounds_the_gateways_tried_per_lookup() {
+        let served = Arc::new(AtomicUsize::new(0));
+        let address = spawn_gateway(served.clone()).await;
+        let bounded = client(CcipReadConf {
+            retries: 0,
+            max_urls: 2,
+            ..test_conf()
+        });
+        let info = lookup(&[
+            format!("http://{address}/unavailable/{{sender}}/{{data}}"),
+            format!("http://{address}/unavailable/{{sender}}/{{data}}"),
+            format!("http://{address}/metadata"),
+        ]);
+        assert_eq!(bounded.fetch(&info).await, None);
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+
+        let client = client(CcipReadConf {
+            lookup_timeout: Duration::from_millis(200),
+            ..test_conf()
+        });
+        let info = lookup(&[format!("http://{address}/slow/{{sender}}/{{data}}")]);
+        let start = Instant::now();
+        assert_eq!(client.fetch(&info).await, None);
+        assert!(start.elapsed() < Duration::from_millis(500));
     }
 }
@@ -0,0 +1,475 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use eyre::Result;
+use hyperlane_core::{
+    BlsPublicKey, ChainResult, HyperlaneDomain, ModuleType, WeightedValidator, H256, U256,
+};
+use prometheus::IntCounterVec;
+use tracing::debug;
+
+use crate::settings::IsmCacheConf;
+
+/// A map whose entries expire after a time to live, and which evicts the
+/// oldest entry once it holds `capacity` entries.
+#[derive(Debug)]
+pub(super) struct TtlCache<K, V> {
+    entries: HashMap<K, (V, Instant)>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> TtlCache<K, V> {
+    pub(super) fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            capacity,
+        }
+    }
+
+    pub(super) fn get(&mut self, key: &K) -> Option<V> {
+        match self.entries.get(key) {
+            Some((value, inserted_at)) if inserted_at.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(super) fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let ttl = self.ttl;
+            self.entries
+                .retain(|_, (_, inserted_at)| inserted_at.elapsed() < ttl);
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, inserted_at))| *inserted_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (value, Instant::now()));
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(&K) -> bool) {
+        self.entries.retain(|key, _| f(key));
+    }
+}
+
+/// The weighted validators and threshold weight of a weighted multisig ISM
+type WeightedValidatorSet = (Vec<WeightedValidator>, U256);
+
+/// Caches the module types and configuration of the ISMs on a destination,
+/// so they aren't fetched for every message and every retry.
+///
+/// The configuration an ISM returns for a message may depend on the message,
+/// so it is cached per origin. When a message fails verification, the entries
+/// of the ISMs on its path are invalidated, in case one was reconfigured.
+#[derive(Debug)]
+pub struct IsmCache {
+    module_types: Mutex<TtlCache<H256, ModuleType>>,
+    validators_and_thresholds: Mutex<TtlCache<(H256, u32), (Vec<H256>, u8)>>,
+    validators_and_threshold_weights: Mutex<TtlCache<(H256, u32), WeightedValidatorSet>>,
+    bls_public_keys: Mutex<TtlCache<(H256, u32), Vec<BlsPublicKey>>>,
+    routes: Mutex<TtlCache<(H256, u32), H256>>,
+    modules_and_thresholds: Mutex<TtlCache<(H256, u32), (Vec<H256>, u8)>>,
+    destination: String,
+    /// Lookups by destination, kind of lookup and whether they hit the cache
+    lookups: IntCounterVec,
+}
+
+impl IsmCache {
+    pub fn new(conf: &IsmCacheConf, destination: &HyperlaneDomain, lookups: IntCounterVec) -> Self {
+        Self {
+            module_types: Mutex::new(TtlCache::new(conf.ttl, conf.capacity)),
+            validators_and_thresholds: Mutex::new(TtlCache::new(conf.ttl, conf.capacity)),
+            validators_and_threshold_weights: Mutex::new(TtlCache::new(conf.ttl, conf.capacity)),
+            bls_public_keys: Mutex::new(TtlCache::new(conf.ttl, conf.capacity)),
+            routes: Mutex::new(TtlCache::new(conf.ttl, conf.capacity)),
+            modules_and_thresholds: Mutex::new(TtlCache::new(conf.ttl, conf.capacity)),
+            destination: destination.name().to_owned(),
+            lookups,
+        }
+    }
+
+    /// Returns the module type of an ISM, calling `fetch` on a cache miss.
+    pub async fn module_type(
+        &self,
+        ism_address: H256,
+        fetch: impl Future<Output = ChainResult<ModuleType>>,
+    ) -> Result<ModuleType> {
+        self.get_or_fetch(&self.module_types, "module_type", ism_address, fetch)
+            .await
+    }
+
+    /// Returns the validators and threshold of a multisig ISM for messages
+    /// from `origin`, calling `fetch` on a cache miss.
+    pub async fn validators_and_threshold(
+        &self,
+        ism_address: H256,
+        origin: u32,
+        fetch: impl Future<Output = ChainResult<(Vec<H256>, u8)>>,
+    ) -> Result<(Vec<H256>, u8)> {
+        self.get_or_fetch(
+            &self.validators_and_thresholds,
+            "validators_and_threshold",
+            (ism_address, origin),
+            fetch,
+        )
+        .await
+    }
+
+    /// Returns the weighted validators and threshold weight of a weighted
+    /// multisig ISM for messages from `origin`, calling `fetch` on a cache miss.
+    pub async fn validators_and_threshold_weight(
+        &self,
+        ism_address: H256,
+        origin: u32,
+        fetch: impl Future<Output = ChainResult<(Vec<WeightedValidator>, U256)>>,
+    ) -> Result<(Vec<WeightedValidator>, U256)> {
+        self.get_or_fetch(
+            &self.validators_and_threshold_weights,
+            "validators_and_threshold_weight",
+            (ism_address, origin),
+            fetch,
+        )
+        .await
+    }
+
+    /// Returns the registered public keys of the validators of a BLS multisig
+    /// ISM for messages from `origin`, calling `fetch` on a cache miss.
+    pub async fn bls_public_keys(
+        &self,
+        ism_address: H256,
+        origin: u32,
+        fetch: impl Future<Output = ChainResult<Vec<BlsPublicKey>>>,
+    ) -> Result<Vec<BlsPublicKey>> {
+        self.get_or_fetch(
+            &self.bls_public_keys,
+            "bls_public_keys",
+            (ism_address, origin),
+            fetch,
+        )
+        .await
+    }
+
+    /// Returns the ISM a routing ISM routes messages from `origin` to,
+    /// calling `fetch` on a cache miss.
+    pub async fn route(
+        &self,
+        ism_address: H256,
+        origin: u32,
+        fetch: impl Future<Output = ChainResult<H256>>,
+    ) -> Result<H256> {
+        self.get_or_fetch(&self.routes, "route", (ism_address, origin), fetch)
+            .await
+    }
+
+    /// Returns the modules and threshold of an aggregation ISM for messages
+    /// from `origin`, calling `fetch` on a cache miss.
+    pub async fn modules_and_threshold(
+        &self,
+        ism_address: H256,
+        origin: u32,
+        fetch: impl Future<Output = ChainResult<(Vec<H256>, u8)>>,
+    ) -> Result<(Vec<H256>, u8)> {
+        self.get_or_fetch(
+            &self.modules_and_thresholds,
+            "modules_and_threshold",
+            (ism_address, origin),
+            fetch,
+        )
+        .await
+    }
+
+    /// Drops the cached configuration of the ISMs a message from `origin`
+    /// passes through from `root`, as found by following the cached routes
+    /// and aggregation modules. Called when such a message fails verification.
+    pub fn invalidate(&self, root: H256, origin: u32) {
+        let path = self.cached_path(root, origin);
+        debug!(
+            destination = %self.destination,
+            origin, ?root, isms = path.len(), "Invalidating cached ISM configuration"
+        );
+        let off_path = |ism: &H256, o: u32| o != origin || !path.contains(ism);
+        self.module_types
+            .lock()
+            .unwrap()
+            .retain(|ism| !path.contains(ism));
+        self.validators_and_thresholds
+            .lock()
+            .unwrap()
+            .retain(|(ism, o)| off_path(ism, *o));
+        self.validators_and_threshold_weights
+            .lock()
+            .unwrap()
+            .retain(|(ism, o)| off_path(ism, *o));
+        self.bls_public_keys
+            .lock()
+            .unwrap()
+            .retain(|(ism, o)| off_path(ism, *o));
+        self.routes
+            .lock()
+            .unwrap()
+            .retain(|(ism, o)| off_path(ism, *o));
+        self.modules_and_thresholds
+            .lock()
+            .unwrap()
+            .retain(|(ism, o)| off_path(ism, *o));
+    }
+
+    /// The ISMs reachable from `root` through the cached routes and
+    /// aggregation modules for messages from `origin`
+    fn cached_path(&self, root: H256, origin: u32) -> HashSet<H256> {
+        let mut path = HashSet::new();
+        let mut pending = vec![root];
+        while let Some(ism) = pending.pop() {
+            if !path.insert(ism) {
+                continue;
+            }
+            if let Some(route) = self.routes.lock().unwrap().get(&(ism, origin)) {
+                pending.push(route);
+            }
+            if let Some((modules, _)) = self
+                .modules_and_thresholds
+                .lock()
+                .unwrap()
+                .get(&(ism, origin))
+            {
+                pending.extend(modules);
+            }
+        }
+        path
+    }
+
+    async fn get_or_fetch<K: Hash + Eq + Clone, V: Clone>(
+        &self,
+        cache: &Mutex<TtlCache<K, V>>,
+        kind: &str,
+        key: K,
+        fetch: impl Future<Output = ChainResult<V>>,
+    ) -> Result<V> {
+        // The lock is not held while fetching, so concurrent misses may
+        // fetch the same value.
+        let cached = cache.lock().unwrap().get(&key);
+        if let Some(value) = cached {
+            self.lookups
+                .with_label_values(&[&self.destination, kind, "hit"])
+                .inc();
+            return Ok(value);
+        }
+        self.lookups
+            .with_label_values(&[&self.destination, kind, "miss"])
+            .inc();
+
+        let value = fetch.await?;
+        cache.lock().unwrap().insert(key, value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use prometheus::Opts;
+
+    use super::*;
+
+    fn dummy_lookups() -> IntCounterVec {
+        IntCounterVec::new(
+            Opts::new("ism_cache_lookups", "help string"),
+            &["destination", "kind", "result"],
+        )
+        .unwrap()
+    }
+
+    fn ism_cache(ttl: Duration, capacity: usize) -> IsmCache {
+        IsmCache::new(
+            &IsmCacheConf { ttl, capacity },
+            &HyperlaneDomain::new_test_domain("test_ism_cache"),
+            dummy_lookups(),
+        )
+    }
+
+    async fn fetched<T>(value: T) -> ChainResult<T> {
+        Ok(value)
+    }
+
+    async fn not_fetched<T>() -> ChainResult<T> {
+        panic!("fetched a cached value")
+    }
+
+    fn lookups(cache: &IsmCache, kind: &str, result: &str) -> u64 {
+        cache
+            .lookups
+            .with_label_values(&[&cache.destination, kind, result])
+            .get()
+    }
+
+    #[tokio::test]
+    async fn caches_module_types() {
+        let cache = ism_cache(Duration::from_secs(60), 10);
+        let ism = H256::repeat_byte(1);
+
+        let module_type = cache
+            .module_type(ism, fetched(ModuleType::Routing))
+            .await
+            .unwrap();
+        assert_eq!(module_type, ModuleType::Routing);
+
+        let module_type = cache.module_type(ism, not_fetched()).await.unwrap();
+        assert_eq!(module_type, ModuleType::Routing);
+        assert_eq!(lookups(&cache, "module_type", "miss"), 1);
+        assert_eq!(lookups(&cache, "module_type", "hit"), 1);
+    }
+
+    #[tokio::test]
+    async fn caches_config_per_origin() {
+        let cache = ism_cache(Duration::from_secs(60), 10);
+        let ism = H256::repeat_byte(1);
+
+        cache
+            .route(ism, 1, fetched(H256::repeat_byte(2)))
+            .await
+            .unwrap();
+        let route = cache
+            .route(ism, 2, fetched(H256::repeat_byte(3)))
+            .await
+            .unwrap();
+        assert_eq!(route, H256::repeat_byte(3));
+        assert_eq!(lookups(&cache, "route", "miss"), 2);
+
+        // Invalidating a path of an origin keeps the routes for other origins
+        cache.invalidate(ism, 2);
+        let route = cache.route(ism, 1, not_fetched()).await.unwrap();
+        assert_eq!(route, H256::repeat_byte(2));
+        cache
+            .route(ism, 2, fetched(H256::repeat_byte(3)))
+            .await
+            .unwrap();
+        assert_eq!(lookups(&cache, "route", "miss"), 3);
+    }
+
+    #[tokio::test]
+    async fn invalidates_only_the_path_of_a_message() {
+        let cache = ism_cache(Duration::from_secs(60), 10);
+        let (routing, aggregation, multisig, other) = (
+            H256::repeat_byte(1),
+            H256::repeat_byte(2),
+            H256::repeat_byte(3),
+            H256::repeat_byte(4),
+        );
+        cache.route(routing, 1, fetched(aggregation)).await.unwrap();
+        cache
+            .modules_and_threshold(aggregation, 1, fetched((vec![multisig], 1)))
+            .await
+            .unwrap();
+        cache
+            .validators_and_threshold(multisig, 1, fetched((vec![H256::zero()], 1)))
+            .await
+            .unwrap();
+        cache
+            .validators_and_threshold(other, 1, fetched((vec![H256::zero()], 1)))
+            .await
+            .unwrap();
+        for ism in [routing, other] {
+            cache
+                .module_type(ism, fetched(ModuleType::Routing))
+                .await
+                .unwrap();
+        }
+
+        cache.invalidate(routing, 1);
+        cache.route(routing, 1, fetched(aggregation)).await.unwrap();
+        cache
+            .modules_and_threshold(aggregation, 1, fetched((vec![multisig], 1)))
+            .await
+            .unwrap();
+        cache
+            .validators_and_threshold(multisig, 1, fetched((vec![H256::zero()], 1)))
+            .await
+            .unwrap();
+        assert_eq!(lookups(&cache, "route", "miss"), 2);
+        assert_eq!(lookups(&cache, "modules_and_threshold", "miss"), 2);
+        assert_eq!(lookups(&cache, "validators_and_threshold", "miss"), 3);
+
+        // ISMs off the path stay cached
+        cache
+            .validators_and_threshold(other, 1, not_fetched())
+            .await
+            .unwrap();
+        cache.module_type(other, not_fetched()).await.unwrap();
+        cache
+            .module_type(routing, fetched(ModuleType::Routing))
+            .await
+            .unwrap();
+        assert_eq!(lookups(&cache, "module_type", "miss"), 3);
+    }
+
+    #[tokio::test]
+    async fn caches_and_invalidates_weighted_validators() {
+        let cache = ism_cache(Duration::from_secs(60), 10);
+        let ism = H256::repeat_byte(1);
+        let validators = vec![WeightedValidator {
+            address: H256::repeat_byte(2),
+            weight: U256::from(10),
+        }];
+
+        cache
+            .validators_and_threshold_weight(ism, 1, fetched((validators.clone(), U256::from(10))))
+            .await
+            .unwrap();
+        let (cached, threshold_weight) = cache
+            .validators_and_threshold_weight(ism, 1, not_fetched())
+            .await
+            .unwrap();
+        assert_eq!((cached, threshold_weight), (validators.clone(), 10.into()));
+
+        cache.invalidate(ism, 1);
+        cache
+            .validators_and_threshold_weight(ism, 1, fetched((validators, U256::from(10))))
+            .await
+            .unwrap();
+        assert_eq!(
+            lookups(&cache, "validators_and_threshold_weight", "miss"),
+            2
+        );
+        assert_eq!(lookups(&cache, "validators_and_threshold_weight", "hit"), 1);
+    }
+
+    #[tokio::test]
+    async fn expires_and_evicts_entries() {
+        let cache = ism_cache(Duration::ZERO, 10);
+        let ism = H256::repeat_byte(1);
+        for _ in 0..2 {
+            cache
+                .module_type(ism, fetched(ModuleType::Null))
+                .await
+                .unwrap();
+        }
+        assert_eq!(lookups(&cache, "module_type", "miss"), 2);
+
+        let mut ttl_cache = TtlCache::new(Duration::from_secs(60), 2);
+        for key in 1..=3 {
+            ttl_cache.insert(key, ());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(ttl_cache.entries.len(), 2);
+        assert!(ttl_cache.get(&1).is_none());
+        assert!(ttl_cache.get(&3).is_some());
+    }
+}
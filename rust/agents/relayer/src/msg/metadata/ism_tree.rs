@@ -0,0 +1,651 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use eyre::{Context, Result};
+use futures_util::future::{BoxFuture, FutureExt};
+use hyperlane_core::{HyperlaneMessage, Mailbox, ModuleType, H256, U256};
+use serde::Serialize;
+use tracing::warn;
+
+use super::{
+    base::{BaseMetadataBuilder, MessageMetadataBuilder, MetadataBuilder},
+    ccip_read::offchain_lookup,
+};
+
+/// The configuration of an ISM, with the ISMs it depends on as submodules
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IsmConfig {
+    pub address: H256,
+    pub module_type: Option<ModuleType>,
+    /// The threshold of aggregation and multisig ISMs, which is a weight for
+    /// weighted multisig ISMs
+    pub threshold: Option<U256>,
+    /// The validators of multisig ISMs
+    pub validators: Option<Vec<ValidatorConfig>>,
+    /// The urls CCIP read ISMs fetch metadata from
+    pub urls: Option<Vec<String>>,
+    pub submodules: Vec<IsmConfig>,
+    /// Whether the ISM verifies the message with the metadata the relayer
+    /// builds, if it was checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<IsmVerification>,
+    /// Why the configuration of the ISM could not be fetched
+    pub error: Option<String>,
+    /// The metadata the relayer built for the ISM, if it was checked
+    #[serde(skip)]
+    pub metadata: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorConfig {
+    pub address: H256,
+    /// The weight of validators of weighted multisig ISMs
+    pub weight: Option<U256>,
+    /// The storage locations the validator announced on the origin, from
+    /// oldest to most recent
+    pub storage_locations: Vec<String>,
+    /// Whether a checkpoint syncer could be built from the storage locations
+    pub checkpoint_syncer: bool,
+    /// The index of the latest checkpoint the validator signed
+    pub latest_index: Option<u32>,
+    /// Whether the validator signed a checkpoint including the message, if
+    /// the message's index in the merkle tree is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signed_message: Option<bool>,
+}
+
+/// Whether an ISM verifies a message with the metadata the relayer builds
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum IsmVerification {
+    /// The ISM verifies the message, at a gas cost
+    Verified { gas: U256 },
+    /// The ISM does not verify the message with the metadata
+    VerifyFailed { error: Option<String> },
+    /// No metadata could be built for the ISM
+    NoMetadata,
+}
+
+/// Reads the configuration of the destination's ISMs, and whether they
+/// verify a message.
+#[async_trait]
+pub trait IsmReader: Send + Sync {
+    /// How deep ISMs may be nested
+    fn max_depth(&self) -> u32;
+
+    async fn module_type(&self, ism: H256) -> Result<ModuleType>;
+
+    /// The validators of a multisig ISM, with their weights if the ISM is
+    /// weighted, and its threshold
+    async fn validators_and_threshold(
+        &self,
+        ism: H256,
+        module_type: ModuleType,
+        message: &HyperlaneMessage,
+    ) -> Result<(Vec<(H256, Option<U256>)>, U256)>;
+
+    async fn route(&self, ism: H256, message: &HyperlaneMessage) -> Result<H256>;
+
+    async fn modules_and_threshold(
+        &self,
+        ism: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<(Vec<H256>, u8)>;
+
+    async fn optimistic_submodule(&self, ism: H256, message: &HyperlaneMessage) -> Result<H256>;
+
+    /// The urls a CCIP read ISM fetches metadata for the message from
+    async fn ccip_read_urls(
+        &self,
+        ism: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<Option<Vec<String>>>;
+
+    /// Fetches the announced storage locations of each validator and the
+    /// latest checkpoint it signed
+    async fn validators(
+        &self,
+        validators: Vec<(H256, Option<U256>)>,
+    ) -> Result<Vec<ValidatorConfig>>;
+
+    /// Builds metadata for the ISM and dry runs verifying the message with it
+    async fn verify(
+        &self,
+        ism: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<(IsmVerification, Option<Vec<u8>>)>;
+}
+
+#[async_trait]
+impl IsmReader for MessageMetadataBuilder {
+    fn max_depth(&self) -> u32 {
+        self.base.max_depth()
+    }
+
+    async fn module_type(&self, ism: H256) -> Result<ModuleType> {
+        let contract = self.build_ism(ism).await?;
+        self.ism_cache()
+            .module_type(ism, contract.module_type())
+            .await
+    }
+
+    async fn validators_and_threshold(
+        &self,
+        ism: H256,
+        module_type: ModuleType,
+        message: &HyperlaneMessage,
+    ) -> Result<(Vec<(H256, Option<U256>)>, U256)> {
+        if module_type == ModuleType::WeightedMessageIdMultisig {
+            let multisig_ism = self.build_weighted_multisig_ism(ism).await?;
+            let (validators, threshold_weight) = self
+                .ism_cache()
+                .validators_and_threshold_weight(
+                    ism,
+                    message.origin,
+                    multisig_ism.validators_and_threshold_weight(message),
+                )
+                .await?;
+            let validators = validators
+                .into_iter()
+                .map(|v| (v.address, Some(v.weight)))
+                .collect();
+            return Ok((validators, threshold_weight));
+        }
+        let multisig_ism = self.build_multisig_ism(ism).await?;
+        let (validators, threshold) = self
+            .ism_cache()
+            .validators_and_threshold(
+                ism,
+                message.origin,
+                multisig_ism.validators_and_threshold(message),
+            )
+            .await?;
+        let validators = validators.into_iter().map(|v| (v, None)).collect();
+        Ok((validators, threshold.into()))
+    }
+
+    async fn route(&self, ism: H256, message: &HyperlaneMessage) -> Result<H256> {
+        let routing_ism = self.build_routing_ism(ism).await?;
+        self.ism_cache()
+            .route(ism, message.origin, routing_ism.route(message))
+            .await
+    }
+
+    async fn modules_and_threshold(
+        &self,
+        ism: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<(Vec<H256>, u8)> {
+        let aggregation_ism = self.build_aggregation_ism(ism).await?;
+        self.ism_cache()
+            .modules_and_threshold(
+                ism,
+                message.origin,
+                aggregation_ism.modules_and_threshold(message),
+            )
+            .await
+    }
+
+    async fn optimistic_submodule(&self, ism: H256, message: &HyperlaneMessage) -> Result<H256> {
+        let optimistic_ism = self.build_optimistic_ism(ism).await?;
+        Ok(optimistic_ism.submodule(message).await?)
+    }
+
+    async fn ccip_read_urls(
+        &self,
+        ism: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<Option<Vec<String>>> {
+        let ccip_read_ism = self.build_ccip_read_ism(ism).await?;
+        Ok(offchain_lookup(ccip_read_ism.as_ref(), message)
+            .await?
+            .map(|info| info.urls))
+    }
+
+    async fn validators(
+        &self,
+        validators: Vec<(H256, Option<U256>)>,
+    ) -> Result<Vec<ValidatorConfig>> {
+        let addresses: Vec<H256> = validators.iter().map(|(address, _)| *address).collect();
+        let storage_locations = self.announced_storage_locations(&addresses).await?;
+        let checkpoint_syncer = self
+            .build_checkpoint_syncer(&addresses, self.app_context.clone())
+            .await?;
+
+        let mut configs = Vec::with_capacity(validators.len());
+        for ((address, weight), storage_locations) in validators.into_iter().zip(storage_locations)
+        {
+            let latest_index = checkpoint_syncer
+                .latest_index(address)
+                .await
+                .unwrap_or_else(|err| {
+                    warn!(error=%err, validator=?address, "Failed to fetch latest checkpoint index");
+                    None
+                });
+            configs.push(ValidatorConfig {
+                address,
+                weight,
+                storage_locations,
+                checkpoint_syncer: checkpoint_syncer.has_checkpoint_syncer(address),
+                latest_index,
+                signed_message: None,
+            });
+        }
+        Ok(configs)
+    }
+
+    async fn verify(
+        &self,
+        ism: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<(IsmVerification, Option<Vec<u8>>)> {
+        let Some(metadata) = self.build(ism, message).await? else {
+            return Ok((IsmVerification::NoMetadata, None));
+        };
+        let contract = self.build_ism(ism).await?;
+        let verification = match contract.dry_run_verify(message, &metadata).await {
+            Ok(Some(gas)) => IsmVerification::Verified { gas },
+            Ok(None) => IsmVerification::VerifyFailed { error: None },
+            Err(err) => IsmVerification::VerifyFailed {
+                error: Some(err.to_string()),
+            },
+        };
+        Ok((verification, Some(metadata)))
+    }
+}
+
+/// Resolves the ISM of `recipient` on the destination of `builder` and walks
+/// it into a tree of the configuration messages from the origin of `builder`
+/// are verified with.
+///
+/// ISMs may configure themselves per message, so they are queried with a
+/// message from the origin to the recipient with all other fields zeroed.
+/// ISMs which depend on other fields of a message, such as its sender, may
+/// verify real messages differently.
+pub async fn ism_config_tree(
+    builder: Arc<BaseMetadataBuilder>,
+    destination_mailbox: &dyn Mailbox,
+    recipient: H256,
+) -> Result<IsmConfig> {
+    let ism_address = destination_mailbox
+        .recipient_ism(recipient)
+        .await
+        .context("When fetching the recipient ISM")?;
+    let message = HyperlaneMessage {
+        origin: builder.origin_domain().id(),
+        destination: builder.destination_domain().id(),
+        recipient,
+        ..Default::default()
+    };
+    let reader = MessageMetadataBuilder::without_app_context(builder);
+    Ok(IsmTreeWalker::new(&reader, &message)
+        .walk(ism_address)
+        .await)
+}
+
+/// Walks the tree of ISMs which verify a message
+pub struct IsmTreeWalker<'a> {
+    reader: &'a dyn IsmReader,
+    message: &'a HyperlaneMessage,
+    /// The index of the message in the origin's merkle tree, to tell which
+    /// validators signed it
+    leaf_index: Option<u32>,
+    /// Whether to check that each ISM verifies the message
+    verify: bool,
+}
+
+impl<'a> IsmTreeWalker<'a> {
+    pub fn new(reader: &'a dyn IsmReader, message: &'a HyperlaneMessage) -> Self {
+        Self {
+            reader,
+            message,
+            leaf_index: None,
+            verify: false,
+        }
+    }
+
+    pub fn with_leaf_index(mut self, leaf_index: Option<u32>) -> Self {
+        self.leaf_index = leaf_index;
+        self
+    }
+
+    /// Also builds metadata for each ISM and checks it verifies the message
+    pub fn verifying(mut self) -> Self {
+        self.verify = true;
+        self
+    }
+
+    pub async fn walk(&'a self, address: H256) -> IsmConfig {
+        self.walk_at(address, 0).await
+    }
+
+    fn walk_at(&'a self, address: H256, depth: u32) -> BoxFuture<'a, IsmConfig> {
+        async move {
+            let mut config = IsmConfig {
+                address,
+                module_type: None,
+                threshold: None,
+                validators: None,
+                urls: None,
+                submodules: vec![],
+                verification: None,
+                error: None,
+                metadata: None,
+            };
+            if let Err(err) = self.inspect(&mut config, depth).await {
+                config.error = Some(format!("{err:#}"));
+            }
+            config
+        }
+        .boxed()
+    }
+
+    async fn inspect(&'a self, config: &mut IsmConfig, depth: u32) -> Result<()> {
+        let (reader, message, address) = (self.reader, self.message, config.address);
+        let module_type = reader.module_type(address).await?;
+        config.module_type = Some(module_type);
+
+        let submodules = match module_type {
+            ModuleType::MerkleRootMultisig
+            | ModuleType::MessageIdMultisig
+            | ModuleType::BlsMultisig
+            | ModuleType::WeightedMessageIdMultisig => {
+                let (validators, threshold) = reader
+                    .validators_and_threshold(address, module_type, message)
+                    .await?;
+                config.threshold = Some(threshold);
+                let mut validators = reader.validators(validators).await?;
+                if let Some(leaf_index) = self.leaf_index {
+                    for validator in &mut validators {
+                        validator.signed_message =
+                            Some(validator.latest_index.map_or(false, |i| i >= leaf_index));
+                    }
+                }
+                config.validators = Some(validators);
+                vec![]
+            }
+            ModuleType::Routing => vec![reader.route(address, message).await?],
+            ModuleType::Aggregation => {
+                let (modules, threshold) = reader.modules_and_threshold(address, message).await?;
+                config.threshold = Some(threshold.into());
+                modules
+            }
+            ModuleType::Optimistic => vec![reader.optimistic_submodule(address, message).await?],
+            ModuleType::CcipRead => {
+                config.urls = reader.ccip_read_urls(address, message).await?;
+                vec![]
+            }
+            _ => vec![],
+        };
+        if depth < reader.max_depth() {
+            for submodule in submodules {
+                config
+                    .submodules
+                    .push(self.walk_at(submodule, depth + 1).await);
+            }
+        }
+
+        if self.verify {
+            let (verification, metadata) = reader.verify(address, message).await?;
+            config.verification = Some(verification);
+            config.metadata = metadata;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::collections::HashMap;
+
+    use eyre::eyre;
+
+    use super::*;
+
+    /// An ISM of a `MockIsmReader`
+    #[derive(Debug, Clone)]
+    pub(crate) enum MockIsm {
+        Multisig {
+            validators: Vec<H256>,
+            threshold: u8,
+        },
+        Routing(H256),
+        Aggregation {
+            modules: Vec<H256>,
+            threshold: u8,
+        },
+        /// Fails to be read
+        Broken,
+    }
+
+    /// Reads mock ISMs. Multisig ISMs verify messages which all their
+    /// validators signed, and routing and aggregation ISMs verify messages
+    /// their submodules verify.
+    #[derive(Debug, Default)]
+    pub(crate) struct MockIsmReader {
+        pub isms: HashMap<H256, MockIsm>,
+        /// The latest index each validator signed
+        pub latest_indices: HashMap<H256, u32>,
+        pub max_depth: u32,
+    }
+
+    impl MockIsmReader {
+        fn ism(&self, ism: H256) -> Result<&MockIsm> {
+            match self.isms.get(&ism) {
+                Some(MockIsm::Broken) | None => Err(eyre!("Failed to read ISM {ism:?}")),
+                Some(mock) => Ok(mock),
+            }
+        }
+
+        fn verifies(&self, ism: H256, message: &HyperlaneMessage) -> bool {
+            match self.ism(ism) {
+                Ok(MockIsm::Multisig { validators, .. }) => validators.iter().all(|validator| {
+                    self.latest_indices
+                        .get(validator)
+                        .map_or(false, |&index| index >= message.nonce)
+                }),
+                Ok(MockIsm::Routing(route)) => self.verifies(*route, message),
+                Ok(MockIsm::Aggregation { modules, threshold }) => {
+                    modules
+                        .iter()
+                        .filter(|module| self.verifies(**module, message))
+                        .count()
+                        >= *threshold as usize
+                }
+                _ => false,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl IsmReader for MockIsmReader {
+        fn max_depth(&self) -> u32 {
+            self.max_depth
+        }
+
+        async fn module_type(&self, ism: H256) -> Result<ModuleType> {
+            Ok(match self.ism(ism)? {
+                MockIsm::Multisig { .. } => ModuleType::MessageIdMultisig,
+                MockIsm::Routing(_) => ModuleType::Routing,
+                MockIsm::Aggregation { .. } => ModuleType::Aggregation,
+                MockIsm::Broken => unreachable!(),
+            })
+        }
+
+        async fn validators_and_threshold(
+            &self,
+            ism: H256,
+            _module_type: ModuleType,
+            _message: &HyperlaneMessage,
+        ) -> Result<(Vec<(H256, Option<U256>)>, U256)> {
+            let MockIsm::Multisig {
+                validators,
+                threshold,
+            } = self.ism(ism)?
+            else {
+                return Err(eyre!("Not a multisig ISM"));
+            };
+            let validators = validators.iter().map(|v| (*v, None)).collect();
+            Ok((validators, (*threshold).into()))
+        }
+
+        async fn route(&self, ism: H256, _message: &HyperlaneMessage) -> Result<H256> {
+            let MockIsm::Routing(route) = self.ism(ism)? else {
+                return Err(eyre!("Not a routing ISM"));
+            };
+            Ok(*route)
+        }
+
+        async fn modules_and_threshold(
+            &self,
+            ism: H256,
+            _message: &HyperlaneMessage,
+        ) -> Result<(Vec<H256>, u8)> {
+            let MockIsm::Aggregation { modules, threshold } = self.ism(ism)? else {
+                return Err(eyre!("Not an aggregation ISM"));
+            };
+            Ok((modules.clone(), *threshold))
+        }
+
+        async fn optimistic_submodule(
+            &self,
+            _ism: H256,
+            _message: &HyperlaneMessage,
+        ) -> Result<H256> {
+            unimplemented!()
+        }
+
+        async fn ccip_read_urls(
+            &self,
+            _ism: H256,
+            _message: &HyperlaneMessage,
+        ) -> Result<Option<Vec<String>>> {
+            unimplemented!()
+        }
+
+        async fn validators(
+            &self,
+            validators: Vec<(H256, Option<U256>)>,
+        ) -> Result<Vec<ValidatorConfig>> {
+            Ok(validators
+                .into_iter()
+                .map(|(address, weight)| ValidatorConfig {
+                    address,
+                    weight,
+                    storage_locations: vec![],
+                    checkpoint_syncer: self.latest_indices.contains_key(&address),
+                    latest_index: self.latest_indices.get(&address).copied(),
+                    signed_message: None,
+                })
+                .collect())
+        }
+
+        async fn verify(
+            &self,
+            ism: H256,
+            message: &HyperlaneMessage,
+        ) -> Result<(IsmVerification, Option<Vec<u8>>)> {
+            Ok(if self.verifies(ism, message) {
+                (
+                    IsmVerification::Verified { gas: 100.into() },
+                    Some(ism.as_bytes().to_vec()),
+                )
+            } else {
+                (IsmVerification::NoMetadata, None)
+            })
+        }
+    }
+
+    fn address(byte: u8) -> H256 {
+        H256::repeat_byte(byte)
+    }
+
+    /// A routing ISM which routes to an aggregation of a multisig ISM and a
+    /// broken ISM
+    fn dummy_reader() -> MockIsmReader {
+        let (signer, laggard) = (address(0xa), address(0xb));
+        MockIsmReader {
+            isms: HashMap::from([
+                (address(1), MockIsm::Routing(address(2))),
+                (
+                    address(2),
+                    MockIsm::Aggregation {
+                        modules: vec![address(3), address(4)],
+                        threshold: 1,
+                    },
+                ),
+                (
+                    address(3),
+                    MockIsm::Multisig {
+                        validators: vec![signer, laggard],
+                        threshold: 1,
+                    },
+                ),
+                (address(4), MockIsm::Broken),
+            ]),
+            latest_indices: HashMap::from([(signer, 10), (laggard, 2)]),
+            max_depth: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn walks_ism_tree() {
+        let reader = dummy_reader();
+        let message = HyperlaneMessage {
+            nonce: 5,
+            ..Default::default()
+        };
+        let tree = IsmTreeWalker::new(&reader, &message)
+            .with_leaf_index(Some(5))
+            .walk(address(1))
+            .await;
+
+        assert_eq!(tree.module_type, Some(ModuleType::Routing));
+        assert!(tree.verification.is_none());
+        let aggregation = &tree.submodules[0];
+        assert_eq!(aggregation.module_type, Some(ModuleType::Aggregation));
+        assert_eq!(aggregation.threshold, Some(1.into()));
+        let [multisig, broken] = &aggregation.submodules[..] else {
+            panic!("Expected two submodules, got {:?}", aggregation.submodules);
+        };
+
+        assert_eq!(multisig.module_type, Some(ModuleType::MessageIdMultisig));
+        assert!(multisig.error.is_none());
+        let signed: Vec<_> = multisig
+            .validators
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|validator| (validator.latest_index, validator.signed_message))
+            .collect();
+        assert_eq!(signed, vec![(Some(10), Some(true)), (Some(2), Some(false))]);
+
+        // A broken ISM doesn't stop the rest of the tree from being walked
+        assert_eq!(broken.module_type, None);
+        assert!(broken.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn verifies_ism_tree() {
+        let mut reader = dummy_reader();
+        let message = HyperlaneMessage::default();
+        let tree = IsmTreeWalker::new(&reader, &message)
+            .verifying()
+            .walk(address(1))
+            .await;
+        assert!(matches!(
+            tree.verification,
+            Some(IsmVerification::Verified { .. })
+        ));
+        assert_eq!(tree.metadata, Some(address(1).as_bytes().to_vec()));
+        assert!(tree.submodules[0].submodules[1].verification.is_none());
+
+        // Nested ISMs past the max depth are not walked
+        reader.max_depth = 1;
+        let tree = IsmTreeWalker::new(&reader, &message).walk(address(1)).await;
+        assert_eq!(tree.submodules.len(), 1);
+        assert!(tree.submodules[0].submodules.is_empty());
+    }
+}
@@ -1,8 +1,11 @@
 mod aggregation;
 mod base;
 mod ccip_read;
+mod ism_cache;
+mod ism_tree;
 mod multisig;
 mod null_metadata;
+mod optimistic;
 mod routing;
 
 use aggregation::AggregationIsmMetadataBuilder;
@@ -10,6 +13,15 @@ pub(crate) use base::MetadataBuilder;
 pub(crate) use base::{
     AppContextClassifier, BaseMetadataBuilder, IsmAwareAppContextClassifier, MessageMetadataBuilder,
 };
+pub(crate) use ccip_read::CcipReadClient;
 use ccip_read::CcipReadIsmMetadataBuilder;
+pub(crate) use ism_cache::IsmCache;
+pub(crate) use ism_tree::{ism_config_tree, IsmConfig, IsmReader, IsmTreeWalker};
+#[cfg(test)]
+pub(crate) use ism_tree::{
+    test::{MockIsm, MockIsmReader},
+    IsmVerification,
+};
 use null_metadata::NullMetadataBuilder;
+use optimistic::OptimisticIsmMetadataBuilder;
 use routing::RoutingIsmMetadataBuilder;
@@ -105,8 +105,14 @@ impl<T: MultisigIsmMetadataBuilder> MetadataBuilder for T {
             .await
             .context(CTX)?;
 
-        let (validators, threshold) = multisig_ism
-            .validators_and_threshold(message)
+        let (validators, threshold) = self
+            .as_ref()
+            .ism_cache()
+            .validators_and_threshold(
+                ism_address,
+                message.origin,
+                multisig_ism.validators_and_threshold(message),
+            )
             .await
             .context(CTX)?;
 
@@ -0,0 +1,179 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use derive_more::{AsRef, Deref};
+use derive_new::new;
+
+use eyre::{Context, Result};
+use hyperlane_core::{unwrap_or_none_result, BlsAggregatedCheckpoint, HyperlaneMessage, H256};
+use tracing::{debug, info, warn};
+
+use crate::msg::metadata::{MessageMetadataBuilder, MetadataBuilder};
+
+/// Builds metadata for ISMs which verify a single aggregated BLS signature of
+/// a subset of the validator set, e.g. an EigenLayer operator set.
+///
+/// The ISM exposes its validator set and threshold like a multisig ISM, and
+/// the BLS public keys registered for its validators. Validators publish BLS
+/// signed checkpoints next to their ECDSA signed ones, whose signatures are
+/// checked against the registered keys before they're aggregated.
+#[derive(Debug, Clone, Deref, new, AsRef)]
+pub struct BlsMultisigMetadataBuilder(MessageMetadataBuilder);
+
+#[async_trait]
+impl MetadataBuilder for BlsMultisigMetadataBuilder {
+    async fn build(
+        &self,
+        ism_address: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<Option<Vec<u8>>> {
+        const CTX: &str = "When fetching BlsMultisigIsm metadata";
+        let multisig_ism = self
+            .as_ref()
+            .build_multisig_ism(ism_address)
+            .await
+            .context(CTX)?;
+
+        let (validators, threshold) = self
+            .as_ref()
+            .ism_cache()
+            .validators_and_threshold(
+                ism_address,
+                message.origin,
+                multisig_ism.validators_and_threshold(message),
+            )
+            .await
+            .context(CTX)?;
+
+        if validators.is_empty() {
+            info!("Could not fetch metadata: No validator set found for ISM");
+            return Ok(None);
+        }
+
+        let bls_multisig_ism = self
+            .as_ref()
+            .build_bls_multisig_ism(ism_address)
+            .await
+            .context(CTX)?;
+        let public_keys = self
+            .as_ref()
+            .ism_cache()
+            .bls_public_keys(
+                ism_address,
+                message.origin,
+                bls_multisig_ism.registered_public_keys(&validators),
+            )
+            .await
+            .context(CTX)?;
+
+        let message_id = message.id();
+        let leaf_index = unwrap_or_none_result!(
+            self.get_merkle_leaf_id_by_message_id(message_id)
+                .await
+                .context(CTX)?,
+            debug!(
+                ?message,
+                "No merkle leaf found for message id, must have not been enqueued in the tree"
+            )
+        );
+
+        let checkpoint_syncer = self
+            .as_ref()
+            .build_checkpoint_syncer(&validators, self.as_ref().app_context.clone())
+            .await
+            .context(CTX)?;
+
+        // Update the validator latest checkpoint metrics.
+        let _ = checkpoint_syncer
+            .get_validator_latest_checkpoints_and_update_metrics(
+                &validators,
+                self.origin_domain(),
+                self.destination_domain(),
+            )
+            .await;
+
+        let quorum_checkpoint = unwrap_or_none_result!(
+            checkpoint_syncer
+                .fetch_bls_checkpoint(&validators, &public_keys, threshold as usize, leaf_index)
+                .await
+                .context(CTX)?,
+            info!(
+                ?message, ?validators, threshold, ism=%multisig_ism.address(),
+                "Could not fetch metadata: Unable to reach BLS quorum"
+            )
+        );
+
+        if quorum_checkpoint.checkpoint.message_id != message_id {
+            warn!(
+                "Quorum checkpoint message id {} does not match message id {}",
+                quorum_checkpoint.checkpoint.message_id, message_id
+            );
+            return Ok(None);
+        }
+
+        debug!(
+            ?message,
+            ?quorum_checkpoint,
+            "Found BLS checkpoint with quorum"
+        );
+        Ok(Some(format_metadata(&quorum_checkpoint)))
+    }
+}
+
+/// Metadata layout:
+/// `merkle tree hook (32) || root (32) || index (4) || non-signer bitmap (32) || signature (64)`
+fn format_metadata(quorum_checkpoint: &BlsAggregatedCheckpoint) -> Vec<u8> {
+    let checkpoint = &quorum_checkpoint.checkpoint;
+    let mut non_signer_bitmap = [0u8; 32];
+    quorum_checkpoint
+        .non_signer_bitmap
+        .to_big_endian(&mut non_signer_bitmap);
+
+    [
+        checkpoint.merkle_tree_hook_address.as_bytes(),
+        checkpoint.root.as_bytes(),
+        &checkpoint.index.to_be_bytes(),
+        &non_signer_bitmap,
+        &quorum_checkpoint.signature.to_bytes(),
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{
+        BlsSecretKey, BlsSignature, Checkpoint, CheckpointWithMessageId, H256, U256,
+    };
+
+    use super::*;
+
+    #[test]
+    fn formats_metadata() {
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: H256::repeat_byte(1),
+                mailbox_domain: 1,
+                root: H256::repeat_byte(2),
+                index: 3,
+            },
+            message_id: H256::repeat_byte(4),
+        };
+        let key = BlsSecretKey::from_bytes(H256::from_low_u64_be(1)).unwrap();
+        let signed = vec![(1, key.sign_checkpoint(checkpoint))];
+        let quorum_checkpoint = BlsAggregatedCheckpoint::aggregate(3, &signed).unwrap();
+
+        let metadata = format_metadata(&quorum_checkpoint);
+        assert_eq!(metadata.len(), 32 + 32 + 4 + 32 + BlsSignature::LENGTH);
+        assert_eq!(&metadata[..32], H256::repeat_byte(1).as_bytes());
+        assert_eq!(&metadata[32..64], H256::repeat_byte(2).as_bytes());
+        assert_eq!(&metadata[64..68], &3u32.to_be_bytes());
+        assert_eq!(
+            U256::from_big_endian(&metadata[68..100]),
+            U256::from(0b101u64)
+        );
+        assert_eq!(
+            &metadata[100..],
+            &quorum_checkpoint.signature.to_bytes()[..]
+        );
+    }
+}
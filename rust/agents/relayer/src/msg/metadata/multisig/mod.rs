@@ -1,8 +1,12 @@
 mod base;
+mod bls_multisig;
 mod merkle_root_multisig;
 mod message_id_multisig;
+mod weighted_message_id_multisig;
 
 pub use base::{MetadataToken, MultisigIsmMetadataBuilder, MultisigMetadata};
 
+pub use bls_multisig::BlsMultisigMetadataBuilder;
 pub use merkle_root_multisig::MerkleRootMultisigMetadataBuilder;
 pub use message_id_multisig::MessageIdMultisigMetadataBuilder;
+pub use weighted_message_id_multisig::WeightedMessageIdMultisigMetadataBuilder;
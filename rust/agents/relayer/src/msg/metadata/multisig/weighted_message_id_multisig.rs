@@ -0,0 +1,123 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use derive_more::{AsRef, Deref};
+use derive_new::new;
+
+use eyre::{Context, Result};
+use hyperlane_core::{unwrap_or_none_result, HyperlaneMessage, MultisigSignedCheckpoint, H256};
+use tracing::{debug, info, warn};
+
+use crate::msg::metadata::{MessageMetadataBuilder, MetadataBuilder};
+
+/// Builds metadata for message ID multisig ISMs which weigh validators, e.g.
+/// by stake, and require the signers to reach a threshold weight.
+///
+/// The metadata has the same layout as for the `MessageIdMultisig` ISM, with
+/// the fewest signatures that reach the threshold weight.
+#[derive(Debug, Clone, Deref, new, AsRef)]
+pub struct WeightedMessageIdMultisigMetadataBuilder(MessageMetadataBuilder);
+
+#[async_trait]
+impl MetadataBuilder for WeightedMessageIdMultisigMetadataBuilder {
+    async fn build(
+        &self,
+        ism_address: H256,
+        message: &HyperlaneMessage,
+    ) -> Result<Option<Vec<u8>>> {
+        const CTX: &str = "When fetching WeightedMessageIdMultisig metadata";
+        let multisig_ism = self
+            .as_ref()
+            .build_weighted_multisig_ism(ism_address)
+            .await
+            .context(CTX)?;
+
+        let (validators, threshold_weight) = self
+            .as_ref()
+            .ism_cache()
+            .validators_and_threshold_weight(
+                ism_address,
+                message.origin,
+                multisig_ism.validators_and_threshold_weight(message),
+            )
+            .await
+            .context(CTX)?;
+
+        if validators.is_empty() {
+            info!("Could not fetch metadata: No validator set found for ISM");
+            return Ok(None);
+        }
+
+        let message_id = message.id();
+        let leaf_index = unwrap_or_none_result!(
+            self.get_merkle_leaf_id_by_message_id(message_id)
+                .await
+                .context(CTX)?,
+            debug!(
+                ?message,
+                "No merkle leaf found for message id, must have not been enqueued in the tree"
+            )
+        );
+
+        let addresses: Vec<H256> = validators.iter().map(|v| v.address).collect();
+        let checkpoint_syncer = self
+            .as_ref()
+            .build_checkpoint_syncer(&addresses, self.as_ref().app_context.clone())
+            .await
+            .context(CTX)?;
+
+        // Update the validator latest checkpoint metrics.
+        let _ = checkpoint_syncer
+            .get_validator_latest_checkpoints_and_update_metrics(
+                &addresses,
+                self.origin_domain(),
+                self.destination_domain(),
+            )
+            .await;
+
+        let quorum_checkpoint = unwrap_or_none_result!(
+            checkpoint_syncer
+                .fetch_weighted_checkpoint(&validators, threshold_weight, leaf_index)
+                .await
+                .context(CTX)?,
+            info!(
+                ?message, ?validators, %threshold_weight, ism=%multisig_ism.address(),
+                "Could not fetch metadata: Unable to reach threshold weight"
+            )
+        );
+
+        if quorum_checkpoint.checkpoint.message_id != message_id {
+            warn!(
+                "Quorum checkpoint message id {} does not match message id {}",
+                quorum_checkpoint.checkpoint.message_id, message_id
+            );
+            return Ok(None);
+        }
+
+        debug!(
+            ?message,
+            ?quorum_checkpoint,
+            "Found checkpoint with threshold weight"
+        );
+        Ok(Some(format_metadata(&quorum_checkpoint)))
+    }
+}
+
+/// Metadata layout:
+/// `merkle tree hook (32) || root (32) || index (4) || signatures (65 each)`
+fn format_metadata(quorum_checkpoint: &MultisigSignedCheckpoint) -> Vec<u8> {
+    let checkpoint = &quorum_checkpoint.checkpoint;
+    let signatures: Vec<u8> = quorum_checkpoint
+        .signatures
+        .iter()
+        .flat_map(|signature| signature.to_vec())
+        .collect();
+
+    [
+        checkpoint.merkle_tree_hook_address.as_bytes(),
+        checkpoint.root.as_bytes(),
+        &checkpoint.index.to_be_bytes(),
+        &signatures,
+    ]
+    .concat()
+}
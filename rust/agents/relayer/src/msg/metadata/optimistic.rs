@@ -0,0 +1,48 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use derive_more::Deref;
+use derive_new::new;
+use eyre::Context;
+use hyperlane_core::{HyperlaneMessage, H256};
+use tracing::{debug, instrument};
+
+use super::{MessageMetadataBuilder, MetadataBuilder};
+
+/// Builds metadata for optimistic ISMs, which verify messages with their own
+/// state once the fraud window after pre-verification has passed. The
+/// pre-verification itself is submitted by the pending message.
+#[derive(Clone, Debug, new, Deref)]
+pub struct OptimisticIsmMetadataBuilder {
+    base: MessageMetadataBuilder,
+}
+
+#[async_trait]
+impl MetadataBuilder for OptimisticIsmMetadataBuilder {
+    #[instrument(err, skip(self))]
+    async fn build(
+        &self,
+        ism_address: H256,
+        message: &HyperlaneMessage,
+    ) -> eyre::Result<Option<Vec<u8>>> {
+        const CTX: &str = "When fetching OptimisticIsm metadata";
+        let ism = self.build_optimistic_ism(ism_address).await.context(CTX)?;
+        let Some(pre_verified_at) = ism.pre_verified_at(message).await.context(CTX)? else {
+            debug!("Message has not been pre-verified yet");
+            return Ok(None);
+        };
+        let fraud_window = ism.fraud_window().await.context(CTX)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context(CTX)?
+            .as_secs();
+        if now < pre_verified_at.saturating_add(fraud_window) {
+            debug!(
+                pre_verified_at,
+                fraud_window, "Fraud window of the message has not passed yet"
+            );
+            return Ok(None);
+        }
+        Ok(Some(vec![]))
+    }
+}
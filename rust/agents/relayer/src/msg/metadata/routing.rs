@@ -22,7 +22,11 @@ impl MetadataBuilder for RoutingIsmMetadataBuilder {
     ) -> eyre::Result<Option<Vec<u8>>> {
         const CTX: &str = "When fetching RoutingIsm metadata";
         let ism = self.build_routing_ism(ism_address).await.context(CTX)?;
-        let module = ism.route(message).await.context(CTX)?;
+        let module = self
+            .ism_cache()
+            .route(ism_address, message.origin, ism.route(message))
+            .await
+            .context(CTX)?;
         self.base.build(module, message).await.context(CTX)
     }
 }
@@ -25,9 +25,12 @@
 //!   - FallbackProviderSubmitter (Serialized, but if some RPC provider sucks,
 //!   switch everyone to new one)
 
+pub(crate) mod diagnosis;
 pub(crate) mod gas_payment;
 pub(crate) mod metadata;
 pub(crate) mod pending_message;
 pub(crate) mod pending_operation;
 pub(crate) mod processor;
+pub(crate) mod rate_limit;
+pub(crate) mod scheduler;
 pub(crate) mod serial_submitter;
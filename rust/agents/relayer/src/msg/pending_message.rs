@@ -1,14 +1,21 @@
 use std::{
     fmt::{Debug, Formatter},
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use async_trait::async_trait;
 use derive_new::new;
 use eyre::Result;
-use hyperlane_base::{db::HyperlaneRocksDB, CoreMetrics};
-use hyperlane_core::{HyperlaneChain, HyperlaneDomain, HyperlaneMessage, Mailbox, U256};
+use hyperlane_base::{
+    db::HyperlaneRocksDB,
+    notifications::{LifecycleEvent, Notifier},
+    CoreMetrics,
+};
+use hyperlane_core::{
+    HyperlaneChain, HyperlaneDomain, HyperlaneMessage, Mailbox, ModuleType, OptimisticIsm,
+    TxCostEstimate, H256, H512, U256,
+};
 use prometheus::{IntCounter, IntGauge};
 use tracing::{debug, error, info, instrument, trace, warn};
 
@@ -43,6 +50,8 @@ pub struct MessageContext {
     /// destination.
     pub transaction_gas_limit: Option<U256>,
     pub metrics: MessageSubmissionMetrics,
+    /// Publishes the submission and delivery of messages.
+    pub notifier: Notifier,
 }
 
 /// A message that the submitter can and should try to submit.
@@ -65,8 +74,13 @@ pub struct PendingMessage {
 
 /// State for the next submission attempt generated by a prepare call.
 struct SubmissionData {
+    /// The ISM `metadata` was built for
+    ism_address: H256,
     metadata: Vec<u8>,
     gas_limit: U256,
+    /// If set, the message is pre-verified with this optimistic ISM instead
+    /// of being processed, and `metadata` is for the ISM's submodule.
+    pre_verify_with: Option<Box<dyn OptimisticIsm>>,
 }
 
 impl Debug for PendingMessage {
@@ -168,58 +182,54 @@ impl PendingOperation for PendingMessage {
             "getting the message metadata builder"
         );
 
-        let Some(metadata) = op_try!(
+        let ism_with_metadata = op_try!(
             message_metadata_builder
-                .build(ism_address, &self.message)
+                .build_ism_and_metadata(ism_address, &self.message)
                 .await,
             "building metadata"
-        ) else {
-            info!("Could not fetch metadata");
-            return self.on_reprepare();
+        );
+        let metadata = match ism_with_metadata.metadata {
+            Some(metadata) => metadata,
+            // Optimistic ISMs only verify a message once the fraud window after
+            // its pre-verification has passed, so it has to be pre-verified first.
+            None if ism_with_metadata.module_type == ModuleType::Optimistic => {
+                return match self
+                    .prepare_pre_verification(ism_address, &message_metadata_builder)
+                    .await
+                {
+                    Some(result) => result,
+                    // The fraud window passed since the metadata was built
+                    None => self.on_reprepare(),
+                };
+            }
+            None => return self.on_missing_metadata(ism_address),
         };
 
         // Estimate transaction costs for the process call. If there are issues, it's
         // likely that gas estimation has failed because the message is
         // reverting. This is defined behavior, so we just log the error and
         // move onto the next tick.
-        let tx_cost_estimate = op_try!(
-            self.ctx
-                .destination_mailbox
-                .process_estimate_costs(&self.message, &metadata)
-                .await,
-            "estimating costs for process call"
-        );
+        let tx_cost_estimate = self
+            .ctx
+            .destination_mailbox
+            .process_estimate_costs(&self.message, &metadata)
+            .await;
+        if tx_cost_estimate.is_err() {
+            self.invalidate_ism_cache_if_unverified(ism_address, &metadata)
+                .await;
+        }
+        let tx_cost_estimate = op_try!(tx_cost_estimate, "estimating costs for process call");
 
-        // If the gas payment requirement hasn't been met, move to the next tick.
-        let Some(gas_limit) = op_try!(
-            self.ctx
-                .origin_gas_payment_enforcer
-                .message_meets_gas_payment_requirement(&self.message, &tx_cost_estimate)
-                .await,
-            "checking if message meets gas payment requirement"
-        ) else {
-            warn!(?tx_cost_estimate, "Gas payment requirement not met yet");
-            return self.on_reprepare();
+        let gas_limit = match self.gas_limit(&tx_cost_estimate).await {
+            Ok(gas_limit) => gas_limit,
+            Err(result) => return result,
         };
 
-        // Go ahead and attempt processing of message to destination chain.
-        debug!(
-            ?gas_limit,
-            "Gas payment requirement met, ready to process message"
-        );
-
-        let gas_limit = tx_cost_estimate.gas_limit;
-
-        if let Some(max_limit) = self.ctx.transaction_gas_limit {
-            if gas_limit > max_limit {
-                info!("Message delivery estimated gas exceeds max gas limit");
-                return self.on_reprepare();
-            }
-        }
-
         self.submission_data = Some(Box::new(SubmissionData {
+            ism_address,
             metadata,
             gas_limit,
+            pre_verify_with: None,
         }));
         PendingOperationResult::Success
     }
@@ -241,16 +251,39 @@ impl PendingOperation for PendingMessage {
             .submission_data
             .take()
             .expect("Pending message must be prepared before it can be submitted");
+        let SubmissionData {
+            ism_address,
+            metadata,
+            gas_limit,
+            pre_verify_with,
+        } = *state;
+
+        self.ctx
+            .notifier
+            .notify(LifecycleEvent::SubmissionAttempted {
+                origin: self.message.origin,
+                destination: self.message.destination,
+                nonce: self.message.nonce,
+                message_id: self.message.id(),
+                attempt: self.num_retries,
+            })
+            .await;
+
+        if let Some(ism) = pre_verify_with {
+            return self.submit_pre_verification(ism, metadata, gas_limit).await;
+        }
 
         // We use the estimated gas limit from the prior call to
         // `process_estimate_costs` to avoid a second gas estimation.
-        let tx_outcome = op_try!(
-            self.ctx
-                .destination_mailbox
-                .process(&self.message, &state.metadata, Some(state.gas_limit))
-                .await,
-            "processing message"
-        );
+        let result = self
+            .ctx
+            .destination_mailbox
+            .process(&self.message, &metadata, Some(gas_limit))
+            .await;
+        if let Err(err) = &result {
+            self.notify_submission_failed(None, err.to_string()).await;
+        }
+        let tx_outcome = op_try!(result, "processing message");
 
         op_try!(critical: self.ctx.origin_gas_payment_enforcer.record_tx_outcome(&self.message, tx_outcome.clone()), "recording tx outcome");
         if tx_outcome.executed {
@@ -258,6 +291,16 @@ impl PendingOperation for PendingMessage {
                 txid=?tx_outcome.transaction_id,
                 "Message successfully processed by transaction"
             );
+            self.ctx
+                .notifier
+                .notify(LifecycleEvent::Delivered {
+                    origin: Some(self.message.origin),
+                    destination: self.message.destination,
+                    message_id: self.message.id(),
+                    transaction_id: tx_outcome.transaction_id,
+                    block_number: None,
+                })
+                .await;
             self.submitted = true;
             self.reset_attempts();
             self.next_attempt_after = Some(Instant::now() + CONFIRM_DELAY);
@@ -267,6 +310,10 @@ impl PendingOperation for PendingMessage {
                 txid=?tx_outcome.transaction_id,
                 "Transaction attempting to process message reverted"
             );
+            self.notify_submission_failed(Some(tx_outcome.transaction_id), "reverted".to_owned())
+                .await;
+            self.invalidate_ism_cache_if_unverified(ism_address, &metadata)
+                .await;
             self.on_reprepare()
         }
     }
@@ -300,6 +347,15 @@ impl PendingOperation for PendingMessage {
                 critical: self.record_message_process_success(),
                 "recording message process success"
             );
+            self.ctx
+                .notifier
+                .notify(LifecycleEvent::Confirmed {
+                    origin: self.message.origin,
+                    destination: self.message.destination,
+                    nonce: self.message.nonce,
+                    message_id: self.message.id(),
+                })
+                .await;
             PendingOperationResult::Success
         } else {
             self.reset_attempts();
@@ -344,6 +400,195 @@ impl PendingMessage {
         pm
     }
 
+    /// Defers attempting the message until at least `instant`, e.g. because
+    /// its route is rate limited.
+    pub fn defer_until(&mut self, instant: Instant) {
+        self.next_attempt_after = self.next_attempt_after.max(Some(instant));
+    }
+
+    /// Prepares pre-verifying the message with an optimistic ISM, which is
+    /// called once the ISM did not verify the message yet. Returns `None` once
+    /// the fraud window after the pre-verification has passed, so the
+    /// message can be processed.
+    async fn prepare_pre_verification(
+        &mut self,
+        ism_address: H256,
+        message_metadata_builder: &MessageMetadataBuilder,
+    ) -> Option<PendingOperationResult> {
+        make_op_try!(|| Some(self.on_reprepare()));
+
+        let ism = op_try!(
+            message_metadata_builder
+                .build_optimistic_ism(ism_address)
+                .await,
+            "building optimistic ISM"
+        );
+
+        if let Some(pre_verified_at) = op_try!(
+            ism.pre_verified_at(&self.message).await,
+            "checking if message was pre-verified"
+        ) {
+            let fraud_window = op_try!(ism.fraud_window().await, "fetching fraud window");
+            let now = op_try!(
+                SystemTime::now().duration_since(UNIX_EPOCH),
+                "getting the current time"
+            );
+            let remaining = Duration::from_secs(pre_verified_at.saturating_add(fraud_window))
+                .checked_sub(now)?;
+            debug!(
+                ?remaining,
+                "Message was pre-verified, waiting for the fraud window to pass"
+            );
+            self.next_attempt_after = Some(Instant::now() + remaining);
+            return Some(PendingOperationResult::NotReady);
+        }
+
+        let submodule = op_try!(
+            ism.submodule(&self.message).await,
+            "fetching optimistic ISM submodule"
+        );
+        let Some(metadata) = op_try!(
+            message_metadata_builder
+                .build(submodule, &self.message)
+                .await,
+            "building submodule metadata"
+        ) else {
+            info!("Could not fetch metadata to pre-verify message");
+            return Some(self.on_reprepare());
+        };
+
+        let tx_cost_estimate = op_try!(
+            ism.pre_verify_estimate_costs(&metadata, &self.message)
+                .await,
+            "estimating costs for pre-verify call"
+        );
+
+        let gas_limit = match self.gas_limit(&tx_cost_estimate).await {
+            Ok(gas_limit) => gas_limit,
+            Err(result) => return Some(result),
+        };
+
+        self.submission_data = Some(Box::new(SubmissionData {
+            ism_address: submodule,
+            metadata,
+            gas_limit,
+            pre_verify_with: Some(ism),
+        }));
+        Some(PendingOperationResult::Success)
+    }
+
+    /// Pre-verifies the message and schedules processing it once the fraud
+    /// window has passed.
+    async fn submit_pre_verification(
+        &mut self,
+        ism: Box<dyn OptimisticIsm>,
+        metadata: Vec<u8>,
+        gas_limit: U256,
+    ) -> PendingOperationResult {
+        make_op_try!(|| self.on_reprepare());
+
+        let result = ism
+            .pre_verify(&metadata, &self.message, Some(gas_limit))
+            .await;
+        if let Err(err) = &result {
+            self.notify_submission_failed(None, err.to_string()).await;
+        }
+        let tx_outcome = op_try!(result, "pre-verifying message");
+
+        op_try!(critical: self.ctx.origin_gas_payment_enforcer.record_tx_outcome(&self.message, tx_outcome.clone()), "recording tx outcome");
+        if !tx_outcome.executed {
+            info!(
+                txid=?tx_outcome.transaction_id,
+                "Transaction attempting to pre-verify message reverted"
+            );
+            self.notify_submission_failed(Some(tx_outcome.transaction_id), "reverted".to_owned())
+                .await;
+            return self.on_reprepare();
+        }
+
+        info!(
+            txid=?tx_outcome.transaction_id,
+            "Message successfully pre-verified by transaction"
+        );
+        // Back to the prepare queue, which processes the message once the
+        // fraud window has passed
+        let fraud_window = op_try!(ism.fraud_window().await, "fetching fraud window");
+        self.reset_attempts();
+        self.next_attempt_after = Some(Instant::now() + Duration::from_secs(fraud_window));
+        PendingOperationResult::Reprepare
+    }
+
+    /// Returns the gas limit to submit a transaction with the estimated costs
+    /// with, or the result to return if it should not be submitted.
+    async fn gas_limit(
+        &mut self,
+        tx_cost_estimate: &TxCostEstimate,
+    ) -> Result<U256, PendingOperationResult> {
+        make_op_try!(|| Err(self.on_reprepare()));
+
+        // If the gas payment requirement hasn't been met, move to the next tick.
+        let Some(gas_limit) = op_try!(
+            self.ctx
+                .origin_gas_payment_enforcer
+                .message_meets_gas_payment_requirement(&self.message, tx_cost_estimate)
+                .await,
+            "checking if message meets gas payment requirement"
+        ) else {
+            warn!(?tx_cost_estimate, "Gas payment requirement not met yet");
+            return Err(self.on_reprepare());
+        };
+
+        // Go ahead and attempt processing of message to destination chain.
+        debug!(
+            ?gas_limit,
+            "Gas payment requirement met, ready to process message"
+        );
+
+        let gas_limit = tx_cost_estimate.gas_limit;
+
+        if let Some(max_limit) = self.ctx.transaction_gas_limit {
+            if gas_limit > max_limit {
+                info!("Message delivery estimated gas exceeds max gas limit");
+                return Err(self.on_reprepare());
+            }
+        }
+        Ok(gas_limit)
+    }
+
+    /// Drops the cached configuration of the ISMs on the message's path if
+    /// the ISM doesn't verify the message with `metadata`, which may have
+    /// been built for a stale configuration. Failures the ISM isn't
+    /// responsible for, like the recipient reverting, keep the cache.
+    async fn invalidate_ism_cache_if_unverified(&self, ism_address: H256, metadata: &[u8]) {
+        let verified = match self.ctx.metadata_builder.build_ism(ism_address).await {
+            Ok(ism) => ism.dry_run_verify(&self.message, metadata).await,
+            Err(err) => {
+                warn!(error=?err, "Failed to build ISM to check verification");
+                return;
+            }
+        };
+        if let Ok(Some(_)) = verified {
+            return;
+        }
+        self.ctx
+            .metadata_builder
+            .ism_cache()
+            .invalidate(ism_address, self.message.origin);
+    }
+
+    /// Metadata can't be built for the message, e.g. because too few of the
+    /// cached validators signed it after the ISM's validator set changed, so
+    /// the cached configuration of the ISMs on its path is dropped before it
+    /// is prepared again.
+    fn on_missing_metadata(&mut self, ism_address: H256) -> PendingOperationResult {
+        info!("Could not fetch metadata");
+        self.ctx
+            .metadata_builder
+            .ism_cache()
+            .invalidate(ism_address, self.message.origin);
+        self.on_reprepare()
+    }
+
     fn on_reprepare(&mut self) -> PendingOperationResult {
         self.inc_attempts();
         self.submitted = false;
@@ -372,6 +617,21 @@ impl PendingMessage {
         Ok(())
     }
 
+    async fn notify_submission_failed(&self, transaction_id: Option<H512>, reason: String) {
+        self.ctx
+            .notifier
+            .notify(LifecycleEvent::SubmissionFailed {
+                origin: self.message.origin,
+                destination: self.message.destination,
+                nonce: self.message.nonce,
+                message_id: self.message.id(),
+                attempt: self.num_retries,
+                transaction_id,
+                reason,
+            })
+            .await;
+    }
+
     fn reset_attempts(&mut self) {
         self.set_retries(0);
         self.next_attempt_after = None;
@@ -455,3 +715,51 @@ impl MessageSubmissionMetrics {
             .set(std::cmp::max(self.last_known_nonce.get(), msg.nonce as i64));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::test_utils;
+
+    use super::*;
+    use crate::msg::processor::test::{dummy_domain, dummy_message_context};
+
+    #[tokio::test]
+    async fn invalidates_the_ism_cache_without_metadata() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            let ctx = Arc::new(dummy_message_context(
+                &origin_domain,
+                &destination_domain,
+                &db,
+            ));
+            let message = HyperlaneMessage {
+                origin: origin_domain.id(),
+                destination: destination_domain.id(),
+                ..Default::default()
+            };
+            let mut pending_message = PendingMessage::new(message, ctx.clone(), None);
+
+            let ism_cache = ctx.metadata_builder.ism_cache();
+            let ism = H256::repeat_byte(1);
+            let stale = (vec![H256::repeat_byte(2)], 1);
+            ism_cache
+                .validators_and_threshold(ism, origin_domain.id(), async { Ok(stale) })
+                .await
+                .unwrap();
+
+            assert!(matches!(
+                pending_message.on_missing_metadata(ism),
+                PendingOperationResult::Reprepare
+            ));
+            let current = (vec![H256::repeat_byte(3)], 2);
+            let validators_and_threshold = ism_cache
+                .validators_and_threshold(ism, origin_domain.id(), async { Ok(current.clone()) })
+                .await
+                .unwrap();
+            assert_eq!(validators_and_threshold, current);
+        })
+        .await
+    }
+}
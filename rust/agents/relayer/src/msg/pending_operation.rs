@@ -6,6 +6,7 @@ use hyperlane_core::HyperlaneDomain;
 
 #[allow(unused_imports)] // required for enum_dispatch
 use super::pending_message::PendingMessage;
+use crate::settings::PriorityClassConf;
 
 /// A pending operation that will be run by the submitter and cause a
 /// transaction to be sent.
@@ -76,6 +77,20 @@ pub enum DynPendingOperation {
     PendingMessage,
 }
 
+impl DynPendingOperation {
+    /// The index of the first priority class the operation is in, or the
+    /// number of classes if it's in none of them.
+    pub fn priority_class(&self, classes: &[PriorityClassConf]) -> usize {
+        use DynPendingOperation::*;
+        let class = match self {
+            PendingMessage(pm) => classes
+                .iter()
+                .position(|class| class.matching_list.msg_matches(&pm.message, false)),
+        };
+        class.unwrap_or(classes.len())
+    }
+}
+
 impl PartialOrd for DynPendingOperation {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -14,9 +14,9 @@ use prometheus::IntGauge;
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::{debug, trace};
 
-use super::{metadata::AppContextClassifier, pending_message::*};
+use super::{metadata::AppContextClassifier, pending_message::*, rate_limit::RateLimiter};
 use crate::msg::pending_operation::DynPendingOperation;
-use crate::{processor::ProcessorExt, settings::matching_list::MatchingList};
+use crate::{processor::ProcessorExt, settings::reload::ReloadableSettingsHandle};
 
 /// Finds unprocessed messages from an origin and submits then through a channel
 /// for to the appropriate destination.
@@ -24,25 +24,27 @@ use crate::{processor::ProcessorExt, settings::matching_list::MatchingList};
 #[derive(new)]
 pub struct MessageProcessor {
     db: HyperlaneRocksDB,
-    whitelist: Arc<MatchingList>,
-    blacklist: Arc<MatchingList>,
+    /// Whitelist, blacklist and app contexts, which may be reloaded
+    settings: ReloadableSettingsHandle,
     metrics: MessageProcessorMetrics,
     /// channel for each destination chain to send operations (i.e. message
     /// submissions) to
     send_channels: HashMap<u32, UnboundedSender<Box<DynPendingOperation>>>,
     /// Needed context to send a message for each destination chain
     destination_ctxs: HashMap<u32, Arc<MessageContext>>,
-    metric_app_contexts: Vec<(MatchingList, String)>,
+    /// Defers the messages of rate limited routes
+    rate_limiter: Arc<RateLimiter>,
     #[new(default)]
     message_nonce: u32,
 }
 
 impl Debug for MessageProcessor {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let settings = self.settings.get();
         write!(
             f,
             "MessageProcessor {{ whitelist: {:?}, blacklist: {:?}, message_nonce: {:?} }}",
-            self.whitelist, self.blacklist, self.message_nonce
+            settings.whitelist, settings.blacklist, self.message_nonce
         )
     }
 }
@@ -65,17 +67,18 @@ impl ProcessorExt for MessageProcessor {
         if let Some(msg) = self.try_get_unprocessed_message()? {
             debug!(?msg, "Processor working on message");
             let destination = msg.destination;
+            let settings = self.settings.get();
 
             // Skip if not whitelisted.
-            if !self.whitelist.msg_matches(&msg, true) {
-                debug!(?msg, whitelist=?self.whitelist, "Message not whitelisted, skipping");
+            if !settings.whitelist.msg_matches(&msg, true) {
+                debug!(?msg, whitelist=?settings.whitelist, "Message not whitelisted, skipping");
                 self.message_nonce += 1;
                 return Ok(());
             }
 
             // Skip if the message is blacklisted
-            if self.blacklist.msg_matches(&msg, false) {
-                debug!(?msg, blacklist=?self.blacklist, "Message blacklisted, skipping");
+            if settings.blacklist.msg_matches(&msg, false) {
+                debug!(?msg, blacklist=?settings.blacklist, "Message blacklisted, skipping");
                 self.message_nonce += 1;
                 return Ok(());
             }
@@ -97,15 +100,21 @@ impl ProcessorExt for MessageProcessor {
             debug!(%msg, "Sending message to submitter");
 
             let app_context_classifier =
-                AppContextClassifier::new(self.metric_app_contexts.clone());
+                AppContextClassifier::new(settings.metric_app_contexts.clone());
 
             let app_context = app_context_classifier.get_app_context(&msg).await?;
-            // Finally, build the submit arg and dispatch it to the submitter.
-            let pending_msg = PendingMessage::from_persisted_retries(
-                msg,
-                self.destination_ctxs[&destination].clone(),
-                app_context,
+            let destination_ctx = &self.destination_ctxs[&destination];
+            let throttled_until = self.rate_limiter.throttle(
+                &msg,
+                self.domain().name(),
+                destination_ctx.metadata_builder.destination_domain().name(),
             );
+            // Finally, build the submit arg and dispatch it to the submitter.
+            let mut pending_msg =
+                PendingMessage::from_persisted_retries(msg, destination_ctx.clone(), app_context);
+            if let Some(throttled_until) = throttled_until {
+                pending_msg.defer_until(throttled_until);
+            }
             self.send_channels[&destination].send(Box::new(pending_msg.into()))?;
             self.message_nonce += 1;
         } else {
@@ -184,17 +193,20 @@ impl MessageProcessorMetrics {
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use std::time::Instant;
 
     use crate::{
         merkle_tree::builder::MerkleTreeBuilder,
         msg::{
             gas_payment::GasPaymentEnforcer,
-            metadata::{BaseMetadataBuilder, IsmAwareAppContextClassifier},
+            metadata::{
+                BaseMetadataBuilder, CcipReadClient, IsmAwareAppContextClassifier, IsmCache,
+            },
             pending_operation::PendingOperation,
         },
         processor::Processor,
+        settings::RateLimitConf,
     };
 
     use super::*;
@@ -203,7 +215,7 @@ mod test {
         settings::{ChainConf, ChainConnectionConf, Settings},
     };
     use hyperlane_test::mocks::{MockMailboxContract, MockValidatorAnnounceContract};
-    use prometheus::{IntCounter, Registry};
+    use prometheus::{IntCounter, IntCounterVec, Opts, Registry};
     use tokio::{
         sync::{
             mpsc::{self, UnboundedReceiver},
@@ -226,7 +238,7 @@ mod test {
         }
     }
 
-    fn dummy_submission_metrics() -> MessageSubmissionMetrics {
+    pub(crate) fn dummy_submission_metrics() -> MessageSubmissionMetrics {
         MessageSubmissionMetrics {
             last_known_nonce: IntGauge::new("last_known_nonce_gauge", "help string").unwrap(),
             messages_processed: IntCounter::new("message_processed_gauge", "help string").unwrap(),
@@ -244,10 +256,11 @@ mod test {
             }),
             metrics_conf: Default::default(),
             index: Default::default(),
+            chain_id: None,
         }
     }
 
-    fn dummy_metadata_builder(
+    pub(crate) fn dummy_metadata_builder(
         origin_domain: &HyperlaneDomain,
         destination_domain: &HyperlaneDomain,
         db: &HyperlaneRocksDB,
@@ -272,38 +285,78 @@ mod test {
             Arc::new(core_metrics),
             db.clone(),
             5,
-            IsmAwareAppContextClassifier::new(Arc::new(MockMailboxContract::default()), vec![]),
+            IsmAwareAppContextClassifier::new(
+                Arc::new(MockMailboxContract::default()),
+                Default::default(),
+            ),
+            Arc::new(IsmCache::new(
+                &Default::default(),
+                destination_domain,
+                IntCounterVec::new(
+                    Opts::new("ism_cache_lookups", "help string"),
+                    &["destination", "kind", "result"],
+                )
+                .unwrap(),
+            )),
+            Arc::new(
+                CcipReadClient::new(
+                    Default::default(),
+                    IntCounterVec::new(
+                        Opts::new("ccip_read_requests", "help string"),
+                        &["host", "result"],
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+            ),
         )
     }
 
-    fn dummy_message_processor(
+    pub(crate) fn dummy_message_context(
         origin_domain: &HyperlaneDomain,
         destination_domain: &HyperlaneDomain,
         db: &HyperlaneRocksDB,
-    ) -> (
-        MessageProcessor,
-        UnboundedReceiver<Box<DynPendingOperation>>,
-    ) {
+    ) -> MessageContext {
         let base_metadata_builder = dummy_metadata_builder(origin_domain, destination_domain, db);
-        let message_context = Arc::new(MessageContext {
+        MessageContext {
             destination_mailbox: Arc::new(MockMailboxContract::default()),
             origin_db: db.clone(),
             metadata_builder: Arc::new(base_metadata_builder),
             origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
             transaction_gas_limit: Default::default(),
             metrics: dummy_submission_metrics(),
-        });
+            notifier: Default::default(),
+        }
+    }
+
+    fn dummy_message_processor(
+        origin_domain: &HyperlaneDomain,
+        destination_domain: &HyperlaneDomain,
+        db: &HyperlaneRocksDB,
+        rate_limits: Vec<RateLimitConf>,
+    ) -> (
+        MessageProcessor,
+        UnboundedReceiver<Box<DynPendingOperation>>,
+    ) {
+        let message_context =
+            Arc::new(dummy_message_context(origin_domain, destination_domain, db));
 
         let (send_channel, receive_channel) = mpsc::unbounded_channel::<Box<DynPendingOperation>>();
         (
             MessageProcessor::new(
                 db.clone(),
                 Default::default(),
-                Default::default(),
                 dummy_processor_metrics(origin_domain.id()),
                 HashMap::from([(destination_domain.id(), send_channel)]),
                 HashMap::from([(destination_domain.id(), message_context)]),
-                vec![],
+                Arc::new(RateLimiter::new(
+                    rate_limits,
+                    IntCounterVec::new(
+                        Opts::new("throttled_messages", "help string"),
+                        &["origin", "destination", "limit"],
+                    )
+                    .unwrap(),
+                )),
             ),
             receive_channel,
         )
@@ -330,7 +383,7 @@ mod test {
         }
     }
 
-    fn dummy_domain(domain_id: u32, name: &str) -> HyperlaneDomain {
+    pub(crate) fn dummy_domain(domain_id: u32, name: &str) -> HyperlaneDomain {
         let test_domain = HyperlaneDomain::new_test_domain(name);
         HyperlaneDomain::Unknown {
             domain_id,
@@ -364,9 +417,10 @@ mod test {
         destination_domain: &HyperlaneDomain,
         db: &HyperlaneRocksDB,
         num_operations: usize,
+        rate_limits: Vec<RateLimitConf>,
     ) -> Vec<Box<DynPendingOperation>> {
         let (message_processor, mut receive_channel) =
-            dummy_message_processor(origin_domain, destination_domain, db);
+            dummy_message_processor(origin_domain, destination_domain, db, rate_limits);
 
         let processor = Processor::new(Box::new(message_processor));
         let process_fut = processor.spawn();
@@ -404,6 +458,7 @@ mod test {
                 &destination_domain,
                 &db,
                 msg_retries.len(),
+                vec![],
             )
             .await;
 
@@ -420,6 +475,7 @@ mod test {
                 &destination_domain,
                 &db,
                 msg_retries.len(),
+                vec![],
             )
             .await;
 
@@ -441,4 +497,41 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_rate_limited_messages_are_deferred() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            persist_retried_messages(&[0, 0, 0], &db, &destination_domain);
+
+            let rate_limits = vec![RateLimitConf {
+                name: "all".to_owned(),
+                matching_list: Default::default(),
+                capacity: 1,
+                refill_rate: 1.0,
+            }];
+            let pending_messages = get_first_n_operations_from_processor(
+                &origin_domain,
+                &destination_domain,
+                &db,
+                3,
+                rate_limits,
+            )
+            .await;
+
+            // The first message takes the only token, and the others are
+            // spaced out at the refill rate rather than dropped
+            let deferrals: Vec<_> = pending_messages
+                .iter()
+                .map(|pm| {
+                    pm.next_attempt_after()
+                        .map(|instant| instant.duration_since(Instant::now()).as_secs_f32().round())
+                })
+                .collect();
+            assert_eq!(deferrals, vec![None, Some(1.0), Some(2.0)]);
+        })
+        .await;
+    }
 }
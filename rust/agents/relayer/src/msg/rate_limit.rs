@@ -0,0 +1,189 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hyperlane_core::HyperlaneMessage;
+use prometheus::IntCounterVec;
+use tracing::debug;
+
+use crate::settings::RateLimitConf;
+
+/// A bucket of tokens which is refilled at a constant rate, up to its
+/// capacity.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    /// Tokens added per second
+    refill_rate: f64,
+    /// The tokens left, which is negative once tokens were taken ahead of
+    /// being refilled
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, refill_rate: f64, now: Instant) -> Self {
+        Self {
+            capacity: capacity.into(),
+            refill_rate,
+            tokens: capacity.into(),
+            refilled_at: now,
+        }
+    }
+
+    /// Takes a token, even if there are none left, and returns how long
+    /// until the bucket is refilled with it.
+    fn take(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.refill_rate).min(self.capacity);
+        self.refilled_at = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_rate)
+        }
+    }
+}
+
+/// Throttles the messages matching each configured rate limit with a token
+/// bucket, so messages on one route can't starve the others. Shared between
+/// the message processors of all origins.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: Vec<(RateLimitConf, Mutex<TokenBucket>)>,
+    /// Throttled messages by origin, destination and rate limit
+    throttled: IntCounterVec,
+}
+
+impl RateLimiter {
+    pub fn new(limits: Vec<RateLimitConf>, throttled: IntCounterVec) -> Self {
+        let now = Instant::now();
+        Self {
+            limits: limits
+                .into_iter()
+                .map(|conf| {
+                    let bucket = TokenBucket::new(conf.capacity, conf.refill_rate, now);
+                    (conf, Mutex::new(bucket))
+                })
+                .collect(),
+            throttled,
+        }
+    }
+
+    /// Takes a token from the bucket of each rate limit the message matches.
+    /// Returns when the message may first be attempted if any of them ran
+    /// out of tokens.
+    ///
+    /// Tokens are taken even from buckets which ran out, so throttled
+    /// messages are spaced out at the refill rate rather than all attempted
+    /// as soon as a token is available.
+    pub fn throttle(
+        &self,
+        message: &HyperlaneMessage,
+        origin: &str,
+        destination: &str,
+    ) -> Option<Instant> {
+        let now = Instant::now();
+        let mut delay = Duration::ZERO;
+        for (conf, bucket) in &self.limits {
+            if !conf.matching_list.msg_matches(message, true) {
+                continue;
+            }
+            let wait = bucket.lock().unwrap().take(now);
+            if !wait.is_zero() {
+                debug!(
+                    message_id=?message.id(),
+                    limit=%conf.name,
+                    ?wait,
+                    "Message is rate limited, deferring it"
+                );
+                self.throttled
+                    .with_label_values(&[origin, destination, &conf.name])
+                    .inc();
+                delay = delay.max(wait);
+            }
+        }
+        (!delay.is_zero()).then(|| now + delay)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{H160, H256};
+    use prometheus::Opts;
+
+    use super::*;
+    use crate::settings::matching_list::MatchingList;
+
+    fn dummy_throttled() -> IntCounterVec {
+        IntCounterVec::new(
+            Opts::new("throttled_messages", "help string"),
+            &["origin", "destination", "limit"],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn token_bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, 2.0, start);
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        assert_eq!(bucket.take(start), Duration::from_millis(500));
+        assert_eq!(bucket.take(start), Duration::from_secs(1));
+
+        // Refilling pays back the tokens taken ahead first
+        assert_eq!(
+            bucket.take(start + Duration::from_secs(1)),
+            Duration::from_millis(500)
+        );
+        // The bucket never holds more than its capacity
+        assert_eq!(bucket.take(start + Duration::from_secs(60)), Duration::ZERO);
+        assert_eq!(bucket.take(start + Duration::from_secs(60)), Duration::ZERO);
+        assert_eq!(
+            bucket.take(start + Duration::from_secs(60)),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn throttles_only_matching_messages() {
+        let sender: H256 = "0x9d4454B023096f34B160D6B654540c56A1F81688"
+            .parse::<H160>()
+            .unwrap()
+            .into();
+        let matching_list: MatchingList = serde_json::from_str(
+            r#"[{"senderaddress": "0x9d4454B023096f34B160D6B654540c56A1F81688"}]"#,
+        )
+        .unwrap();
+        let limiter = RateLimiter::new(
+            vec![RateLimitConf {
+                name: "spammy_sender".to_owned(),
+                matching_list,
+                capacity: 1,
+                refill_rate: 1.0,
+            }],
+            dummy_throttled(),
+        );
+        let spam = HyperlaneMessage {
+            sender,
+            ..Default::default()
+        };
+        let other = HyperlaneMessage::default();
+
+        assert!(limiter.throttle(&spam, "origin", "destination").is_none());
+        let deferred_until = limiter.throttle(&spam, "origin", "destination").unwrap();
+        assert!(deferred_until > Instant::now() + Duration::from_millis(900));
+        assert!(limiter.throttle(&other, "origin", "destination").is_none());
+        assert!(limiter.throttle(&other, "origin", "destination").is_none());
+        assert_eq!(
+            limiter
+                .throttled
+                .with_label_values(&["origin", "destination", "spammy_sender"])
+                .get(),
+            1
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// Queues of one app context in a priority lane
+#[derive(Debug)]
+struct AppContextQueue<T> {
+    ops: BinaryHeap<Reverse<T>>,
+    /// Grows by the inverse of the app context's weight each time one of its
+    /// operations is served. The ready app context with the lowest pass is
+    /// served next.
+    pass: f64,
+}
+
+/// Operations of one priority class, scheduled fairly between app contexts
+#[derive(Debug)]
+struct PriorityLane<T> {
+    app_contexts: HashMap<String, AppContextQueue<T>>,
+    /// The pass of the app context served last. App contexts which had no
+    /// operations start from it, so they can't catch up on the time they
+    /// were idle by starving the others.
+    virtual_time: f64,
+}
+
+impl<T> Default for PriorityLane<T> {
+    fn default() -> Self {
+        Self {
+            app_contexts: HashMap::new(),
+            virtual_time: 0.,
+        }
+    }
+}
+
+impl<T: Ord> PriorityLane<T> {
+    fn push(&mut self, app_context: String, op: T) {
+        let virtual_time = self.virtual_time;
+        let queue = self
+            .app_contexts
+            .entry(app_context)
+            .or_insert_with(|| AppContextQueue {
+                ops: BinaryHeap::new(),
+                pass: virtual_time,
+            });
+        if queue.ops.is_empty() {
+            queue.pass = queue.pass.max(virtual_time);
+        }
+        queue.ops.push(Reverse(op));
+    }
+
+    /// The app context with the lowest pass whose next operation is ready
+    fn next_ready(&self, is_ready: &impl Fn(&T) -> bool) -> Option<&str> {
+        self.app_contexts
+            .iter()
+            .filter(|(_, queue)| queue.ops.peek().map_or(false, |op| is_ready(&op.0)))
+            .min_by(|(a_name, a), (b_name, b)| {
+                a.pass.total_cmp(&b.pass).then_with(|| a_name.cmp(b_name))
+            })
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Serves the next operation of an app context
+    fn serve(&mut self, app_context: &str, weight: u32) -> Option<T> {
+        let queue = self.app_contexts.get_mut(app_context)?;
+        let op = queue.ops.pop()?.0;
+        self.virtual_time = queue.pass;
+        queue.pass += 1. / f64::from(weight.max(1));
+        Some(op)
+    }
+
+    /// The app context whose next operation comes first in their order
+    fn earliest(&self) -> Option<(&str, &T)> {
+        self.app_contexts
+            .iter()
+            .filter_map(|(name, queue)| queue.ops.peek().map(|op| (name.as_str(), &op.0)))
+            .min_by(|(_, a), (_, b)| a.cmp(b))
+    }
+}
+
+/// Schedules operations in priority lanes, serving the ready operations of
+/// a lane before those of lower priority lanes. Within a lane, ready
+/// operations are served fairly between app contexts, in proportion to
+/// their weights. Within an app context, operations are served in their
+/// order.
+#[derive(Debug)]
+pub struct Scheduler<T> {
+    /// Lanes from highest to lowest priority
+    lanes: Vec<PriorityLane<T>>,
+}
+
+impl<T: Ord> Scheduler<T> {
+    pub fn new(num_lanes: usize) -> Self {
+        Self {
+            lanes: (0..num_lanes).map(|_| PriorityLane::default()).collect(),
+        }
+    }
+
+    /// Pushes an operation onto a lane, which is 0 for the highest priority
+    pub fn push(&mut self, lane: usize, app_context: String, op: T) {
+        let lane = lane.min(self.lanes.len() - 1);
+        self.lanes[lane].push(app_context, op);
+    }
+
+    /// Pops the next ready operation. If no operation is ready, pops the
+    /// operation which comes first in their order, which is expected to be
+    /// the first to become ready.
+    pub fn pop(
+        &mut self,
+        is_ready: impl Fn(&T) -> bool,
+        weight: impl Fn(&str) -> u32,
+    ) -> Option<T> {
+        let next_ready = self.lanes.iter().enumerate().find_map(|(lane, queues)| {
+            queues
+                .next_ready(&is_ready)
+                .map(|app_context| (lane, app_context.to_owned()))
+        });
+        if let Some((lane, app_context)) = next_ready {
+            return self.lanes[lane].serve(&app_context, weight(&app_context));
+        }
+
+        let (lane, app_context) = self
+            .lanes
+            .iter()
+            .enumerate()
+            .filter_map(|(lane, queues)| {
+                queues
+                    .earliest()
+                    .map(|(app_context, op)| (lane, app_context, op))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.cmp(b))
+            .map(|(lane, app_context, _)| (lane, app_context.to_owned()))?;
+        self.lanes[lane]
+            .app_contexts
+            .get_mut(&app_context)?
+            .ops
+            .pop()
+            .map(|op| op.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Operations ordered by id, which are ready if their id is even
+    fn pop_all(scheduler: &mut Scheduler<u32>, weight: impl Fn(&str) -> u32) -> Vec<u32> {
+        std::iter::from_fn(|| scheduler.pop(|op| op % 2 == 0, &weight)).collect()
+    }
+
+    #[test]
+    fn serves_ready_operations_of_higher_priority_lanes_first() {
+        let mut scheduler = Scheduler::new(2);
+        scheduler.push(1, "test".to_owned(), 2);
+        scheduler.push(1, "test".to_owned(), 4);
+        scheduler.push(0, "paying".to_owned(), 8);
+        scheduler.push(0, "pending".to_owned(), 3);
+        // Lanes past the last are the lowest priority lane
+        scheduler.push(5, "test".to_owned(), 6);
+
+        // Operations which are not ready don't block lower priority lanes,
+        // and are popped in order once no operation is ready
+        assert_eq!(pop_all(&mut scheduler, |_| 1), vec![8, 2, 4, 6, 3]);
+    }
+
+    #[test]
+    fn serves_app_contexts_fairly_by_weight() {
+        let mut scheduler = Scheduler::new(1);
+        for op in [2, 4, 6, 8, 10, 12] {
+            scheduler.push(0, "spammy".to_owned(), op);
+        }
+        for op in [102, 104, 106] {
+            scheduler.push(0, "heavy".to_owned(), op);
+        }
+        scheduler.push(0, "light".to_owned(), 202);
+
+        let weight = |app_context: &str| if app_context == "heavy" { 2 } else { 1 };
+        assert_eq!(
+            pop_all(&mut scheduler, weight),
+            vec![102, 202, 2, 104, 106, 4, 6, 8, 10, 12]
+        );
+    }
+
+    #[test]
+    fn idle_app_contexts_do_not_catch_up() {
+        let mut scheduler = Scheduler::new(1);
+        for op in [2, 4, 6] {
+            scheduler.push(0, "busy".to_owned(), op);
+        }
+        assert_eq!(scheduler.pop(|_| true, |_| 1), Some(2));
+        assert_eq!(scheduler.pop(|_| true, |_| 1), Some(4));
+
+        // A new app context starts from the pass of the last one served, so
+        // it is interleaved with the busy one rather than served until it
+        // caught up
+        for op in [102, 104] {
+            scheduler.push(0, "idle".to_owned(), op);
+        }
+        assert_eq!(pop_all(&mut scheduler, |_| 1), vec![102, 6, 104]);
+    }
+}
@@ -1,15 +1,13 @@
-use std::cmp::Reverse;
-use std::collections::BinaryHeap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use derive_new::new;
-use futures_util::future::try_join_all;
+use futures_util::future::{join_all, try_join_all};
 use prometheus::{IntCounter, IntGauge, IntGaugeVec};
 use tokio::spawn;
 use tokio::sync::{
     mpsc::{self},
-    Mutex,
+    Mutex, Notify, Semaphore,
 };
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
@@ -19,33 +17,77 @@ use hyperlane_base::CoreMetrics;
 use hyperlane_core::HyperlaneDomain;
 
 use super::pending_operation::*;
+use super::scheduler::Scheduler;
+use crate::settings::{PrepareConf, SchedulingConf};
 
 /// Queue of generic operations that can be submitted to a destination chain.
-/// Includes logic for maintaining queue metrics by the destination and `app_context` of an operation
-#[derive(Debug, Clone, new)]
+/// Operations are scheduled in lanes by their priority class, and fairly
+/// between the `app_context`s of a lane, see [`Scheduler`].
+/// Includes logic for maintaining queue metrics by the destination, `app_context`
+/// and priority class of an operation
+#[derive(Debug, Clone)]
 struct OpQueue {
     metrics: IntGaugeVec,
+    priority_metrics: IntGaugeVec,
     queue_metrics_label: String,
-    #[new(default)]
-    queue: Arc<Mutex<BinaryHeap<Reverse<Box<DynPendingOperation>>>>>,
+    scheduling: Arc<SchedulingConf>,
+    queue: Arc<Mutex<Scheduler<Box<DynPendingOperation>>>>,
 }
 
 impl OpQueue {
+    fn new(
+        metrics: &SerialSubmitterMetrics,
+        queue_metrics_label: String,
+        scheduling: Arc<SchedulingConf>,
+    ) -> Self {
+        // One lane per priority class, and one for operations in none of them
+        let queue = Scheduler::new(scheduling.priority_classes.len() + 1);
+        Self {
+            metrics: metrics.submitter_queue_length.clone(),
+            priority_metrics: metrics.submitter_priority_queue_length.clone(),
+            queue_metrics_label,
+            scheduling,
+            queue: Arc::new(Mutex::new(queue)),
+        }
+    }
+
     /// Push an element onto the queue and update metrics
     async fn push(&self, op: Box<DynPendingOperation>) {
-        // increment the metric before pushing onto the queue, because we lose ownership afterwards
+        // increment the metrics before pushing onto the queue, because we lose ownership afterwards
         self.get_operation_metric(&op).inc();
+        let priority_class = op.priority_class(&self.scheduling.priority_classes);
+        self.get_priority_metric(&op, priority_class).inc();
 
-        self.queue.lock().await.push(Reverse(op));
+        let (_, app_context) = op.get_operation_labels();
+        self.queue
+            .lock()
+            .await
+            .push(priority_class, app_context, op);
     }
 
     /// Pop an element from the queue and update metrics
-    async fn pop(&self) -> Option<Reverse<Box<DynPendingOperation>>> {
-        let op = self.queue.lock().await.pop();
+    async fn pop(&self) -> Option<Box<DynPendingOperation>> {
+        let now = Instant::now();
+        let weight = |app_context: &str| {
+            self.scheduling
+                .app_context_weights
+                .get(app_context)
+                .copied()
+                .unwrap_or(1)
+        };
+        let op = self.queue.lock().await.pop(
+            |op| {
+                op.next_attempt_after()
+                    .map_or(true, |next_attempt| next_attempt <= now)
+            },
+            weight,
+        );
         op.map(|op| {
             // even if the metric is decremented here, the operation may fail to process and be re-added to the queue.
             // in those cases, the queue length will decrease to zero until the operation is re-added.
-            self.get_operation_metric(&op.0).dec();
+            self.get_operation_metric(&op).dec();
+            let priority_class = op.priority_class(&self.scheduling.priority_classes);
+            self.get_priority_metric(&op, priority_class).dec();
             op
         })
     }
@@ -56,6 +98,76 @@ impl OpQueue {
         self.metrics
             .with_label_values(&[&destination, &self.queue_metrics_label, &app_context])
     }
+
+    /// Get the metric associated with the priority class of this operation
+    fn get_priority_metric(
+        &self,
+        operation: &DynPendingOperation,
+        priority_class: usize,
+    ) -> IntGauge {
+        let (destination, _) = operation.get_operation_labels();
+        let priority_class = self
+            .scheduling
+            .priority_classes
+            .get(priority_class)
+            .map_or(SchedulingConf::DEFAULT_PRIORITY_CLASS, |class| {
+                class.name.as_str()
+            });
+        self.priority_metrics.with_label_values(&[
+            &destination,
+            &self.queue_metrics_label,
+            priority_class,
+        ])
+    }
+}
+
+/// Queue of prepared operations waiting for the execution slot. Holds up to
+/// `lookahead` operations, which are submitted in the order of the
+/// [`Scheduler`] rather than in the order their preparation finished.
+#[derive(Debug, Clone)]
+struct SubmitQueue {
+    queue: OpQueue,
+    /// Permits for operations to wait in the queue
+    slots: Arc<Semaphore>,
+    /// Notified whenever an operation is pushed
+    pushed: Arc<Notify>,
+}
+
+impl SubmitQueue {
+    fn new(
+        metrics: &SerialSubmitterMetrics,
+        scheduling: Arc<SchedulingConf>,
+        lookahead: usize,
+    ) -> Self {
+        Self {
+            queue: OpQueue::new(metrics, "submit_queue".to_string(), scheduling),
+            slots: Arc::new(Semaphore::new(lookahead)),
+            pushed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Push a prepared operation, waiting until there is room for it
+    async fn push(&self, op: Box<DynPendingOperation>) {
+        // The permit is given back by `pop`
+        self.slots
+            .acquire()
+            .await
+            .expect("Submit queue semaphore is never closed")
+            .forget();
+        self.queue.push(op).await;
+        self.pushed.notify_one();
+    }
+
+    /// Pop the next operation to submit, waiting until there is one
+    async fn pop(&self) -> Box<DynPendingOperation> {
+        loop {
+            if let Some(op) = self.queue.pop().await {
+                self.slots.add_permits(1);
+                return op;
+            }
+            self.pushed.notified().await;
+        }
+    }
 }
 
 /// SerialSubmitter accepts operations over a channel. It is responsible for
@@ -105,6 +217,20 @@ impl OpQueue {
 /// eligible for submission, we should be working on it within reason. This
 /// must be balanced with the cost of making RPCs that will almost certainly
 /// fail and potentially block new messages from being sent immediately.
+///
+/// To that end, operations are prepared (e.g. their metadata is built from
+/// checkpoints in object storage) by a pool of workers ahead of time, so a
+/// number of prepared operations are waiting whenever the execution slot
+/// frees up. The waiting operations are submitted in order, but with more
+/// than one worker an operation may be submitted before an earlier one which
+/// is still being prepared.
+///
+/// 4. Operations of higher priority classes (e.g. paying customers) should be
+/// attempted before those of lower ones (e.g. test traffic), and no single
+/// application should be able to starve the others of the execution slot by
+/// sending many messages. So operations ready to be attempted are taken from
+/// the highest priority class first, and fairly between the `app_context`s
+/// within a class, in proportion to their configured weights.
 #[derive(Debug, new)]
 pub struct SerialSubmitter {
     /// Domain this submitter delivers to.
@@ -113,6 +239,10 @@ pub struct SerialSubmitter {
     rx: mpsc::UnboundedReceiver<Box<DynPendingOperation>>,
     /// Metrics for serial submitter.
     metrics: SerialSubmitterMetrics,
+    /// How operations are prepared ahead of submission.
+    prepare_conf: PrepareConf,
+    /// How operations are scheduled in the queues.
+    scheduling: SchedulingConf,
 }
 
 impl SerialSubmitter {
@@ -126,21 +256,18 @@ impl SerialSubmitter {
             domain,
             metrics,
             rx: rx_prepare,
+            prepare_conf,
+            scheduling,
         } = self;
-        let prepare_queue = OpQueue::new(
-            metrics.submitter_queue_length.clone(),
-            "prepare_queue".to_string(),
-        );
-        let confirm_queue = OpQueue::new(
-            metrics.submitter_queue_length.clone(),
-            "confirm_queue".to_string(),
-        );
+        let scheduling = Arc::new(scheduling);
+        let prepare_queue = OpQueue::new(&metrics, "prepare_queue".to_string(), scheduling.clone());
+        let confirm_queue = OpQueue::new(&metrics, "confirm_queue".to_string(), scheduling.clone());
 
-        // This is a channel because we want to only have a small number of messages
+        // This is bounded because we want to only have a small number of messages
         // sitting ready to go at a time and this acts as a synchronization tool
         // to slow down the preparation of messages when the submitter gets
         // behind.
-        let (tx_submit, rx_submit) = mpsc::channel(1);
+        let submit_queue = SubmitQueue::new(&metrics, scheduling, prepare_conf.lookahead.max(1));
 
         let tasks = [
             spawn(receive_task(
@@ -151,12 +278,13 @@ impl SerialSubmitter {
             spawn(prepare_task(
                 domain.clone(),
                 prepare_queue.clone(),
-                tx_submit,
+                submit_queue.clone(),
                 metrics.clone(),
+                prepare_conf.workers.max(1),
             )),
             spawn(submit_task(
                 domain.clone(),
-                rx_submit,
+                submit_queue,
                 prepare_queue.clone(),
                 confirm_queue.clone(),
                 metrics.clone(),
@@ -199,14 +327,34 @@ async fn receive_task(
 async fn prepare_task(
     domain: HyperlaneDomain,
     prepare_queue: OpQueue,
-    tx_submit: mpsc::Sender<Box<DynPendingOperation>>,
+    submit_queue: SubmitQueue,
+    metrics: SerialSubmitterMetrics,
+    workers: usize,
+) {
+    // Each worker prepares the next operation in the queue, so up to
+    // `workers` operations are prepared concurrently.
+    join_all((0..workers).map(|_| {
+        prepare_worker(
+            domain.clone(),
+            prepare_queue.clone(),
+            submit_queue.clone(),
+            metrics.clone(),
+        )
+    }))
+    .await;
+}
+
+async fn prepare_worker(
+    domain: HyperlaneDomain,
+    prepare_queue: OpQueue,
+    submit_queue: SubmitQueue,
     metrics: SerialSubmitterMetrics,
 ) {
     loop {
         // Pick the next message to try preparing.
         let next = prepare_queue.pop().await;
 
-        let Some(Reverse(mut op)) = next else {
+        let Some(mut op) = next else {
             // queue is empty so give some time before checking again to prevent burning CPU
             sleep(Duration::from_millis(200)).await;
             continue;
@@ -219,10 +367,8 @@ async fn prepare_task(
             PendingOperationResult::Success => {
                 debug!(?op, "Operation prepared");
                 metrics.ops_prepared.inc();
-                // this send will pause this task if the submitter is not ready to accept yet
-                if let Err(err) = tx_submit.send(op).await {
-                    tracing::error!(error=?err, "Failed to send prepared operation to submitter");
-                }
+                // this push will pause this worker if the submitter is not ready to accept yet
+                submit_queue.push(op).await;
             }
             PendingOperationResult::NotReady => {
                 // none of the operations are ready yet, so wait for a little bit
@@ -243,12 +389,13 @@ async fn prepare_task(
 #[instrument(skip_all, fields(%domain))]
 async fn submit_task(
     domain: HyperlaneDomain,
-    mut rx_submit: mpsc::Receiver<Box<DynPendingOperation>>,
+    submit_queue: SubmitQueue,
     prepare_queue: OpQueue,
     confirm_queue: OpQueue,
     metrics: SerialSubmitterMetrics,
 ) {
-    while let Some(mut op) = rx_submit.recv().await {
+    loop {
+        let mut op = submit_queue.pop().await;
         trace!(?op, "Submitting operation");
         debug_assert_eq!(*op.domain(), domain);
 
@@ -281,7 +428,7 @@ async fn confirm_task(
 ) {
     loop {
         // Pick the next message to try confirming.
-        let Some(Reverse(mut op)) = confirm_queue.pop().await else {
+        let Some(mut op) = confirm_queue.pop().await else {
             sleep(Duration::from_secs(5)).await;
             continue;
         };
@@ -313,6 +460,7 @@ async fn confirm_task(
 #[derive(Debug, Clone)]
 pub struct SerialSubmitterMetrics {
     submitter_queue_length: IntGaugeVec,
+    submitter_priority_queue_length: IntGaugeVec,
     ops_prepared: IntCounter,
     ops_submitted: IntCounter,
     ops_confirmed: IntCounter,
@@ -326,6 +474,7 @@ impl SerialSubmitterMetrics {
         let destination = destination.name();
         Self {
             submitter_queue_length: metrics.submitter_queue_length(),
+            submitter_priority_queue_length: metrics.submitter_priority_queue_length(),
             ops_prepared: metrics
                 .operations_processed_count()
                 .with_label_values(&["prepared", destination]),
@@ -347,3 +496,161 @@ impl SerialSubmitterMetrics {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU64;
+
+    use async_trait::async_trait;
+    use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
+    use hyperlane_core::{
+        ChainResult, HyperlaneChain, HyperlaneContract, HyperlaneMessage, HyperlaneProvider,
+        Mailbox, TxCostEstimate, TxOutcome, H256, U256,
+    };
+    use prometheus::Registry;
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::msg::{
+        gas_payment::GasPaymentEnforcer,
+        pending_message::{MessageContext, PendingMessage},
+        processor::test::{dummy_domain, dummy_metadata_builder, dummy_submission_metrics},
+    };
+
+    /// A destination mailbox on which every message is already delivered, but
+    /// which only answers for the `gated` message once `gate` is notified
+    #[derive(Debug)]
+    struct GatedMailbox {
+        domain: HyperlaneDomain,
+        gated: H256,
+        gate: Arc<Notify>,
+    }
+
+    impl HyperlaneChain for GatedMailbox {
+        fn domain(&self) -> &HyperlaneDomain {
+            &self.domain
+        }
+
+        fn provider(&self) -> Box<dyn HyperlaneProvider> {
+            unimplemented!()
+        }
+    }
+
+    impl HyperlaneContract for GatedMailbox {
+        fn address(&self) -> H256 {
+            H256::zero()
+        }
+    }
+
+    #[async_trait]
+    impl Mailbox for GatedMailbox {
+        async fn count(&self, _lag: Option<NonZeroU64>) -> ChainResult<u32> {
+            unimplemented!()
+        }
+
+        async fn delivered(&self, id: H256) -> ChainResult<bool> {
+            if id == self.gated {
+                self.gate.notified().await;
+            }
+            Ok(true)
+        }
+
+        async fn default_ism(&self) -> ChainResult<H256> {
+            unimplemented!()
+        }
+
+        async fn recipient_ism(&self, _recipient: H256) -> ChainResult<H256> {
+            unimplemented!()
+        }
+
+        async fn process(
+            &self,
+            _message: &HyperlaneMessage,
+            _metadata: &[u8],
+            _tx_gas_limit: Option<U256>,
+        ) -> ChainResult<TxOutcome> {
+            unimplemented!()
+        }
+
+        async fn process_estimate_costs(
+            &self,
+            _message: &HyperlaneMessage,
+            _metadata: &[u8],
+        ) -> ChainResult<TxCostEstimate> {
+            unimplemented!()
+        }
+
+        fn process_calldata(&self, _message: &HyperlaneMessage, _metadata: &[u8]) -> Vec<u8> {
+            unimplemented!()
+        }
+    }
+
+    /// Waits until `count` operations were submitted
+    async fn wait_for_submissions(metrics: &SerialSubmitterMetrics, count: u64) {
+        timeout(Duration::from_secs(5), async {
+            while metrics.ops_submitted.get() < count {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Operations were not submitted in time");
+    }
+
+    #[tokio::test]
+    async fn submits_prepared_operations_while_others_are_preparing() {
+        test_utils::run_test_db(|db| async move {
+            let (origin, destination) = (dummy_domain(0, "origin"), dummy_domain(1, "destination"));
+            let db = HyperlaneRocksDB::new(&origin, db);
+            let messages = (0..2)
+                .map(|nonce| HyperlaneMessage {
+                    nonce,
+                    origin: origin.id(),
+                    destination: destination.id(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+            let gate = Arc::new(Notify::new());
+            let ctx = Arc::new(MessageContext {
+                destination_mailbox: Arc::new(GatedMailbox {
+                    domain: destination.clone(),
+                    gated: messages[0].id(),
+                    gate: gate.clone(),
+                }),
+                origin_db: db.clone(),
+                metadata_builder: Arc::new(dummy_metadata_builder(&origin, &destination, &db)),
+                origin_gas_payment_enforcer: Arc::new(GasPaymentEnforcer::new([], db.clone())),
+                transaction_gas_limit: None,
+                metrics: dummy_submission_metrics(),
+                notifier: Default::default(),
+            });
+
+            let core_metrics = CoreMetrics::new("dummy_relayer", 37583, Registry::new()).unwrap();
+            let metrics = SerialSubmitterMetrics::new(&core_metrics, &destination);
+            let (tx, rx) = mpsc::unbounded_channel();
+            let submitter = SerialSubmitter::new(
+                destination.clone(),
+                rx,
+                metrics.clone(),
+                PrepareConf {
+                    workers: 2,
+                    lookahead: 1,
+                },
+                Default::default(),
+            )
+            .spawn();
+            for message in messages {
+                let op = PendingMessage::new(message, ctx.clone(), None);
+                tx.send(Box::new(op.into())).unwrap();
+            }
+
+            // The second message is submitted while the first is still being prepared
+            wait_for_submissions(&metrics, 1).await;
+            assert_eq!(metrics.ops_prepared.get(), 1);
+
+            gate.notify_one();
+            wait_for_submissions(&metrics, 2).await;
+            submitter.into_inner().abort();
+        })
+        .await;
+    }
+}
@@ -0,0 +1,566 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Formatter},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use eyre::Result;
+use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_core::{HyperlaneDomain, Mailbox, OptimisticIsm, PreVerification, H256};
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+
+use crate::processor::ProcessorExt;
+
+/// How long to wait between polls once there are no new pre-verifications.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Watches an optimistic ISM for pre-verified messages which were never
+/// dispatched on their origin, and flags the submodules which pre-verified
+/// them as fraudulent before the fraud window passes.
+///
+/// Flagging a submodule can't be undone, so a message is only considered
+/// undispatched once every message its origin's mailbox had dispatched by
+/// some time after it was pre-verified was indexed, and it is not among them.
+/// Messages are dispatched before they are pre-verified, so an index which
+/// lags behind the origin can't get an honest submodule flagged. The count of
+/// dispatched messages is sampled once per message rather than compared with
+/// the tip, which the index stays behind of on origins with steady traffic.
+pub struct OptimisticIsmWatcher {
+    ism: Arc<dyn OptimisticIsm>,
+    /// Databases of the origins which are indexed by this relayer. Only
+    /// pre-verifications of messages from these origins can be checked.
+    origin_dbs: HashMap<u32, HyperlaneRocksDB>,
+    /// Mailboxes of the origins, to check their databases are caught up with
+    origin_mailboxes: HashMap<u32, Arc<dyn Mailbox>>,
+    /// How long after a message was pre-verified to wait for it to be
+    /// indexed before flagging its submodule. Defaults to half the fraud
+    /// window.
+    grace_period: Option<Duration>,
+    /// The block to query pre-verifications from
+    from_block: Option<u64>,
+    /// By origin, the nonce below which all messages were indexed
+    indexed_nonces: HashMap<u32, u32>,
+    /// Pre-verified messages which were not found on their origin yet
+    suspects: HashMap<H256, Suspect>,
+}
+
+/// A pre-verified message which was not found on its origin yet
+#[derive(Debug)]
+struct Suspect {
+    pre_verification: PreVerification,
+    /// The number of messages the origin's mailbox had dispatched when it was
+    /// queried after the message was pre-verified, so the message is among
+    /// them if it was dispatched at all
+    dispatched_count: Option<u32>,
+}
+
+impl Debug for OptimisticIsmWatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "OptimisticIsmWatcher {{ ism: {:?}, origins: {:?}, from_block: {:?}, suspects: {} }}",
+            self.ism.address(),
+            self.origin_dbs.keys(),
+            self.from_block,
+            self.suspects.len()
+        )
+    }
+}
+
+impl OptimisticIsmWatcher {
+    pub fn new(
+        ism: Arc<dyn OptimisticIsm>,
+        origin_dbs: HashMap<u32, HyperlaneRocksDB>,
+        origin_mailboxes: HashMap<u32, Arc<dyn Mailbox>>,
+        grace_period: Option<Duration>,
+    ) -> Self {
+        Self {
+            ism,
+            origin_dbs,
+            origin_mailboxes,
+            grace_period,
+            from_block: None,
+            indexed_nonces: HashMap::new(),
+            suspects: HashMap::new(),
+        }
+    }
+
+    async fn grace_period(&mut self) -> Result<Duration> {
+        if let Some(grace_period) = self.grace_period {
+            return Ok(grace_period);
+        }
+        let grace_period = Duration::from_secs(self.ism.fraud_window().await? / 2);
+        self.grace_period = Some(grace_period);
+        Ok(grace_period)
+    }
+
+    /// Queries the number of messages dispatched by the origins of the
+    /// suspects which don't have it yet. Suspects are only collected once
+    /// they were pre-verified, so this count includes their message if it
+    /// was dispatched.
+    async fn sample_dispatched_counts(&mut self) {
+        let origins: HashSet<u32> = self
+            .suspects
+            .values()
+            .filter(|suspect| suspect.dispatched_count.is_none())
+            .map(|suspect| suspect.pre_verification.origin)
+            .collect();
+        for origin in origins {
+            let count = match self.origin_mailboxes[&origin].count(None).await {
+                Ok(count) => count,
+                Err(err) => {
+                    warn!(error=%err, origin, "Error querying the count of dispatched messages");
+                    continue;
+                }
+            };
+            self.suspects
+                .values_mut()
+                .filter(|suspect| {
+                    suspect.pre_verification.origin == origin && suspect.dispatched_count.is_none()
+                })
+                .for_each(|suspect| suspect.dispatched_count = Some(count));
+        }
+    }
+
+    /// Returns the nonce below which all messages of the origin were indexed.
+    fn indexed_nonce(&mut self, origin: u32) -> Result<u32> {
+        let db = &self.origin_dbs[&origin];
+        let indexed = self.indexed_nonces.entry(origin).or_default();
+        while db.retrieve_message_id_by_nonce(indexed)?.is_some() {
+            *indexed += 1;
+        }
+        Ok(*indexed)
+    }
+
+    /// Drops the suspects whose message was indexed on its origin, and
+    /// returns the submodules of those whose grace period has passed and
+    /// whose message was not dispatched.
+    async fn fraudulent_submodules(&mut self, grace_period: Duration) -> HashSet<H256> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let is_due = |pre_verification: &PreVerification| {
+            now >= pre_verification
+                .pre_verified_at
+                .saturating_add(grace_period.as_secs())
+        };
+
+        self.sample_dispatched_counts().await;
+
+        // How far the origins of the suspects which are due are indexed. This
+        // is checked before looking the messages up, so a message not found
+        // afterwards was not dispatched if its origin was indexed up to its
+        // dispatched count.
+        let due_origins: HashSet<u32> = self
+            .suspects
+            .values()
+            .filter(|suspect| is_due(&suspect.pre_verification))
+            .map(|suspect| suspect.pre_verification.origin)
+            .collect();
+        let mut indexed_nonces = HashMap::new();
+        for origin in due_origins {
+            match self.indexed_nonce(origin) {
+                Ok(indexed) => {
+                    indexed_nonces.insert(origin, indexed);
+                }
+                Err(err) => warn!(error=%err, origin, "Error checking how far origin is indexed"),
+            }
+        }
+
+        let mut fraudulent = HashSet::new();
+        self.suspects.retain(|message_id, suspect| {
+            let pre_verification = &suspect.pre_verification;
+            match self.origin_dbs[&pre_verification.origin].retrieve_message_by_id(message_id) {
+                Ok(Some(_)) => {
+                    debug!(?pre_verification, "Pre-verified message was dispatched");
+                    false
+                }
+                Ok(None) => {
+                    let indexed = indexed_nonces
+                        .get(&pre_verification.origin)
+                        .filter(|_| is_due(pre_verification));
+                    if let (Some(&indexed), Some(count)) = (indexed, suspect.dispatched_count) {
+                        if indexed >= count {
+                            fraudulent.insert(pre_verification.submodule);
+                        } else {
+                            debug!(
+                                ?pre_verification,
+                                indexed,
+                                count,
+                                "Origin is not indexed far enough yet, waiting before flagging submodule"
+                            );
+                        }
+                    }
+                    true
+                }
+                Err(err) => {
+                    warn!(error=%err, ?pre_verification, "Error looking up pre-verified message");
+                    true
+                }
+            }
+        });
+        fraudulent
+    }
+}
+
+#[async_trait]
+impl ProcessorExt for OptimisticIsmWatcher {
+    /// The domain the optimistic ISM is deployed on.
+    fn domain(&self) -> &HyperlaneDomain {
+        self.ism.domain()
+    }
+
+    /// One round of watching: collects new pre-verifications, and flags the
+    /// submodules which pre-verified messages that were not dispatched within
+    /// the grace period.
+    async fn tick(&mut self) -> Result<()> {
+        let grace_period = self.grace_period().await?;
+        let (pre_verifications, next_block) = self.ism.pre_verifications(self.from_block).await?;
+        self.from_block = Some(next_block);
+        let caught_up = pre_verifications.is_empty();
+
+        for pre_verification in pre_verifications {
+            if !self.origin_dbs.contains_key(&pre_verification.origin)
+                || !self.origin_mailboxes.contains_key(&pre_verification.origin)
+            {
+                debug!(
+                    ?pre_verification,
+                    "Skipping pre-verification of a message from an origin which is not indexed"
+                );
+                continue;
+            }
+            self.suspects
+                .entry(pre_verification.message_id)
+                .or_insert(Suspect {
+                    pre_verification,
+                    dispatched_count: None,
+                });
+        }
+
+        for submodule in self.fraudulent_submodules(grace_period).await {
+            if !self.ism.is_fraudulent(submodule).await? {
+                warn!(
+                    ism=?self.ism.address(),
+                    ?submodule,
+                    "Submodule pre-verified a message which was not dispatched, flagging it as fraudulent"
+                );
+                let outcome = self.ism.mark_fraudulent(submodule).await?;
+                if !outcome.executed {
+                    error!(
+                        ?outcome,
+                        ?submodule,
+                        "Transaction to flag submodule reverted"
+                    );
+                    continue;
+                }
+                info!(?outcome, ?submodule, "Flagged submodule as fraudulent");
+            }
+            self.suspects
+                .retain(|_, suspect| suspect.pre_verification.submodule != submodule);
+        }
+
+        if caught_up {
+            sleep(POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    };
+
+    use hyperlane_base::db::test_utils;
+    use hyperlane_core::{
+        ChainResult, FixedPointNumber, HyperlaneChain, HyperlaneContract, HyperlaneMessage,
+        HyperlaneProvider, TxCostEstimate, TxOutcome, H512, U256,
+    };
+    use hyperlane_test::mocks::MockMailboxContract;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct MockOptimisticIsm {
+        domain: HyperlaneDomain,
+        pre_verifications: Mutex<Vec<PreVerification>>,
+        marked: Mutex<Vec<H256>>,
+    }
+
+    impl HyperlaneContract for MockOptimisticIsm {
+        fn address(&self) -> H256 {
+            H256::zero()
+        }
+    }
+
+    impl HyperlaneChain for MockOptimisticIsm {
+        fn domain(&self) -> &HyperlaneDomain {
+            &self.domain
+        }
+
+        fn provider(&self) -> Box<dyn HyperlaneProvider> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait]
+    impl OptimisticIsm for MockOptimisticIsm {
+        async fn submodule(&self, _message: &HyperlaneMessage) -> ChainResult<H256> {
+            unimplemented!()
+        }
+
+        async fn fraud_window(&self) -> ChainResult<u64> {
+            Ok(0)
+        }
+
+        async fn pre_verified_at(&self, _message: &HyperlaneMessage) -> ChainResult<Option<u64>> {
+            unimplemented!()
+        }
+
+        async fn pre_verify(
+            &self,
+            _metadata: &[u8],
+            _message: &HyperlaneMessage,
+            _tx_gas_limit: Option<U256>,
+        ) -> ChainResult<TxOutcome> {
+            unimplemented!()
+        }
+
+        async fn pre_verify_estimate_costs(
+            &self,
+            _metadata: &[u8],
+            _message: &HyperlaneMessage,
+        ) -> ChainResult<TxCostEstimate> {
+            unimplemented!()
+        }
+
+        async fn is_fraudulent(&self, submodule: H256) -> ChainResult<bool> {
+            Ok(self.marked.lock().unwrap().contains(&submodule))
+        }
+
+        async fn mark_fraudulent(&self, submodule: H256) -> ChainResult<TxOutcome> {
+            self.marked.lock().unwrap().push(submodule);
+            Ok(TxOutcome {
+                transaction_id: H512::zero(),
+                executed: true,
+                gas_used: U256::zero(),
+                gas_price: FixedPointNumber::zero(),
+            })
+        }
+
+        async fn pre_verifications(
+            &self,
+            _from_block: Option<u64>,
+        ) -> ChainResult<(Vec<PreVerification>, u64)> {
+            Ok((
+                self.pre_verifications.lock().unwrap().drain(..).collect(),
+                1,
+            ))
+        }
+    }
+
+    fn mock_mailbox(count: u32) -> Arc<dyn Mailbox> {
+        mock_mailbox_with_tip(Arc::new(AtomicU32::new(count)))
+    }
+
+    /// A mailbox whose count at the tip is read from `tip`
+    fn mock_mailbox_with_tip(tip: Arc<AtomicU32>) -> Arc<dyn Mailbox> {
+        let mut mailbox = MockMailboxContract::new();
+        mailbox
+            .expect__count()
+            .returning(move |_| Ok(tip.load(Ordering::SeqCst)));
+        Arc::new(mailbox)
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[tokio::test]
+    async fn flags_submodules_of_undispatched_messages() {
+        test_utils::run_test_db(|db| async move {
+            let origin = HyperlaneDomain::new_test_domain("test_optimistic_watcher");
+            let origin_db = HyperlaneRocksDB::new(&origin, db);
+
+            let dispatched = HyperlaneMessage {
+                origin: origin.id(),
+                ..Default::default()
+            };
+            origin_db.store_message(&dispatched, 0).unwrap();
+            let forged = HyperlaneMessage {
+                origin: origin.id(),
+                nonce: 1,
+                ..Default::default()
+            };
+            let recently_forged = HyperlaneMessage {
+                origin: origin.id(),
+                nonce: 2,
+                ..Default::default()
+            };
+
+            let honest_submodule = H256::repeat_byte(1);
+            let fraudulent_submodule = H256::repeat_byte(2);
+            let recent_submodule = H256::repeat_byte(4);
+            let ism = Arc::new(MockOptimisticIsm {
+                domain: origin.clone(),
+                pre_verifications: Mutex::new(vec![
+                    PreVerification {
+                        message_id: dispatched.id(),
+                        origin: origin.id(),
+                        submodule: honest_submodule,
+                        pre_verified_at: 0,
+                    },
+                    PreVerification {
+                        message_id: forged.id(),
+                        origin: origin.id(),
+                        submodule: fraudulent_submodule,
+                        pre_verified_at: 0,
+                    },
+                    // The grace period is counted from when the message was
+                    // pre-verified on-chain
+                    PreVerification {
+                        message_id: recently_forged.id(),
+                        origin: origin.id(),
+                        submodule: recent_submodule,
+                        pre_verified_at: now(),
+                    },
+                    // Messages from origins which are not indexed can't be checked
+                    PreVerification {
+                        message_id: H256::repeat_byte(3),
+                        origin: origin.id() + 1,
+                        submodule: H256::repeat_byte(3),
+                        pre_verified_at: 0,
+                    },
+                ]),
+                marked: Mutex::new(vec![]),
+            });
+
+            let mut watcher = OptimisticIsmWatcher::new(
+                ism.clone(),
+                HashMap::from([(origin.id(), origin_db)]),
+                HashMap::from([(origin.id(), mock_mailbox(1))]),
+                Some(Duration::from_secs(60)),
+            );
+            watcher.tick().await.unwrap();
+
+            assert_eq!(*ism.marked.lock().unwrap(), vec![fraudulent_submodule]);
+            assert_eq!(
+                watcher.suspects.keys().collect::<Vec<_>>(),
+                vec![&recently_forged.id()]
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn waits_for_messages_indexed_late() {
+        test_utils::run_test_db(|db| async move {
+            let origin = HyperlaneDomain::new_test_domain("test_optimistic_watcher");
+            let origin_db = HyperlaneRocksDB::new(&origin, db);
+
+            let indexed = HyperlaneMessage {
+                origin: origin.id(),
+                ..Default::default()
+            };
+            origin_db.store_message(&indexed, 0).unwrap();
+            // Dispatched, but not indexed yet
+            let late = HyperlaneMessage {
+                origin: origin.id(),
+                nonce: 1,
+                ..Default::default()
+            };
+
+            let submodule = H256::repeat_byte(1);
+            let ism = Arc::new(MockOptimisticIsm {
+                domain: origin.clone(),
+                pre_verifications: Mutex::new(vec![PreVerification {
+                    message_id: late.id(),
+                    origin: origin.id(),
+                    submodule,
+                    pre_verified_at: 0,
+                }]),
+                marked: Mutex::new(vec![]),
+            });
+
+            let mut watcher = OptimisticIsmWatcher::new(
+                ism.clone(),
+                HashMap::from([(origin.id(), origin_db.clone())]),
+                HashMap::from([(origin.id(), mock_mailbox(2))]),
+                Some(Duration::ZERO),
+            );
+            // The grace period passed, but the origin is not fully indexed
+            watcher.tick().await.unwrap();
+            assert!(ism.marked.lock().unwrap().is_empty());
+            assert_eq!(watcher.suspects.len(), 1);
+
+            origin_db.store_message(&late, 0).unwrap();
+            assert!(watcher
+                .fraudulent_submodules(Duration::ZERO)
+                .await
+                .is_empty());
+            assert!(watcher.suspects.is_empty());
+            assert!(ism.marked.lock().unwrap().is_empty());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn flags_submodules_while_the_tip_is_ahead_of_the_index() {
+        test_utils::run_test_db(|db| async move {
+            let origin = HyperlaneDomain::new_test_domain("test_optimistic_watcher");
+            let origin_db = HyperlaneRocksDB::new(&origin, db);
+            let message = |nonce| HyperlaneMessage {
+                origin: origin.id(),
+                nonce,
+                ..Default::default()
+            };
+            origin_db.store_message(&message(0), 0).unwrap();
+            origin_db.store_message(&message(1), 0).unwrap();
+            let forged = HyperlaneMessage {
+                body: b"forged".to_vec(),
+                ..message(2)
+            };
+
+            let submodule = H256::repeat_byte(1);
+            let ism = Arc::new(MockOptimisticIsm {
+                domain: origin.clone(),
+                pre_verifications: Mutex::new(vec![PreVerification {
+                    message_id: forged.id(),
+                    origin: origin.id(),
+                    submodule,
+                    pre_verified_at: 0,
+                }]),
+                marked: Mutex::new(vec![]),
+            });
+
+            // The message with nonce 2 was dispatched, but the index stays
+            // one message behind the tip
+            let tip = Arc::new(AtomicU32::new(3));
+            let mut watcher = OptimisticIsmWatcher::new(
+                ism.clone(),
+                HashMap::from([(origin.id(), origin_db.clone())]),
+                HashMap::from([(origin.id(), mock_mailbox_with_tip(tip.clone()))]),
+                Some(Duration::ZERO),
+            );
+            watcher.tick().await.unwrap();
+            assert!(ism.marked.lock().unwrap().is_empty());
+
+            origin_db.store_message(&message(2), 0).unwrap();
+            tip.store(4, Ordering::SeqCst);
+            assert_eq!(
+                watcher.fraudulent_submodules(Duration::ZERO).await,
+                HashSet::from([submodule])
+            );
+        })
+        .await;
+    }
+}
@@ -1,22 +1,25 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::{Debug, Formatter},
+    num::NonZeroU64,
     sync::Arc,
 };
 
 use async_trait::async_trait;
 use derive_more::AsRef;
-use eyre::Result;
+use eyre::{bail, Result};
 use futures_util::future::try_join_all;
 use hyperlane_base::{
-    db::{HyperlaneRocksDB, DB},
+    db::{HyperlaneRocksDB, SnapshotImporter, DB},
     metrics::{AgentMetrics, MetricsUpdater},
+    notifications::NotifyingStore,
     settings::ChainConf,
     BaseAgent, ChainMetrics, ContractSyncMetrics, CoreMetrics, HyperlaneAgentCore,
     SequencedDataContractSync, WatermarkContractSync,
 };
 use hyperlane_core::{
-    HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, MerkleTreeInsertion, U256,
+    HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, Mailbox, MerkleTreeInsertion,
+    OptimisticIsm, H256, U256,
 };
 use tokio::{
     sync::{
@@ -31,14 +34,23 @@ use crate::processor::Processor;
 use crate::{
     merkle_tree::builder::MerkleTreeBuilder,
     msg::{
+        diagnosis::MessageDiagnoser,
         gas_payment::GasPaymentEnforcer,
-        metadata::{BaseMetadataBuilder, IsmAwareAppContextClassifier},
+        metadata::{
+            ism_config_tree, BaseMetadataBuilder, CcipReadClient, IsmAwareAppContextClassifier,
+            IsmCache, IsmConfig,
+        },
         pending_message::{MessageContext, MessageSubmissionMetrics},
         pending_operation::DynPendingOperation,
         processor::{MessageProcessor, MessageProcessorMetrics},
+        rate_limit::RateLimiter,
         serial_submitter::{SerialSubmitter, SerialSubmitterMetrics},
     },
-    settings::{matching_list::MatchingList, RelayerSettings},
+    optimistic_watcher::OptimisticIsmWatcher,
+    settings::{
+        reload::{ReloadableSettingsHandle, SettingsReloader},
+        PrepareConf, RelayerSettings, SchedulingConf,
+    },
 };
 use crate::{
     merkle_tree::processor::{MerkleTreeProcessor, MerkleTreeProcessorMetrics},
@@ -68,12 +80,23 @@ pub struct Relayer {
     merkle_tree_hook_syncs:
         HashMap<HyperlaneDomain, Arc<SequencedDataContractSync<MerkleTreeInsertion>>>,
     dbs: HashMap<HyperlaneDomain, HyperlaneRocksDB>,
-    whitelist: Arc<MatchingList>,
-    blacklist: Arc<MatchingList>,
+    /// Optimistic ISMs to watch for fraudulent pre-verifications
+    optimistic_isms: Vec<Arc<dyn OptimisticIsm>>,
+    /// Mailboxes of the origins, which the optimistic ISM watchers check the
+    /// databases against. Only built if there are optimistic ISMs to watch.
+    origin_mailboxes: HashMap<HyperlaneDomain, Arc<dyn Mailbox>>,
+    /// Whitelist, blacklist, gas payment enforcement policies and app contexts,
+    /// which are reloaded when the config changes
+    reloadable_settings: ReloadableSettingsHandle,
+    settings_reloader: SettingsReloader,
     transaction_gas_limit: Option<U256>,
     skip_transaction_gas_limit_for: HashSet<u32>,
     allow_local_checkpoint_syncers: bool,
-    metric_app_contexts: Vec<(MatchingList, String)>,
+    prepare_conf: PrepareConf,
+    scheduling: SchedulingConf,
+    /// Shared between the message processors of all origins, so limits on
+    /// destinations apply to messages from all origins
+    rate_limiter: Arc<RateLimiter>,
     core_metrics: Arc<CoreMetrics>,
     // TODO: decide whether to consolidate `agent_metrics` and `chain_metrics` into a single struct
     // or move them in `core_metrics`, like the validator metrics
@@ -83,13 +106,14 @@ pub struct Relayer {
 
 impl Debug for Relayer {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let reloadable_settings = self.reloadable_settings.get();
         write!(
             f,
             "Relayer {{ origin_chains: {:?}, destination_chains: {:?}, whitelist: {:?}, blacklist: {:?}, transaction_gas_limit: {:?}, skip_transaction_gas_limit_for: {:?}, allow_local_checkpoint_syncers: {:?} }}",
             self.origin_chains,
             self.destination_chains,
-            self.whitelist,
-            self.blacklist,
+            reloadable_settings.whitelist,
+            reloadable_settings.blacklist,
             self.transaction_gas_limit,
             self.skip_transaction_gas_limit_for,
             self.allow_local_checkpoint_syncers
@@ -121,6 +145,29 @@ impl BaseAgent for Relayer {
             .map(|origin| (origin.clone(), HyperlaneRocksDB::new(origin, db.clone())))
             .collect::<HashMap<_, _>>();
 
+        // Seed the databases with historical logs before indexing, so they
+        // don't have to be indexed over RPC. Logs of snapshots that can't be
+        // imported are still indexed over RPC, so this doesn't stop the relayer.
+        for (origin, db) in &dbs {
+            let chain_conf = settings.chain_setup(origin)?;
+            if let Some(snapshot) = &chain_conf.index.snapshot {
+                let import = async {
+                    SnapshotImporter::from_chain_conf(chain_conf, db.clone(), &core_metrics)
+                        .await?
+                        .import_path(snapshot)
+                        .await
+                };
+                if let Err(err) = import.await {
+                    warn!(
+                        domain = origin.name(),
+                        snapshot = %snapshot.display(),
+                        ?err,
+                        "Failed to import snapshot, indexing its logs over RPC instead"
+                    );
+                }
+            }
+        }
+
         let mailboxes = settings
             .build_mailboxes(settings.destination_chains.iter(), &core_metrics)
             .await?;
@@ -129,14 +176,23 @@ impl BaseAgent for Relayer {
             .await?;
 
         let contract_sync_metrics = Arc::new(ContractSyncMetrics::new(&core_metrics));
+        let notifier = settings
+            .notifications
+            .build(Self::AGENT_NAME, &core_metrics)
+            .await?;
 
+        // Indexed messages and gas payments are stored through the notifier, so
+        // they emit lifecycle events.
         let message_syncs = settings
             .build_message_indexers(
                 settings.origin_chains.iter(),
                 &core_metrics,
                 &contract_sync_metrics,
                 dbs.iter()
-                    .map(|(d, db)| (d.clone(), Arc::new(db.clone()) as _))
+                    .map(|(d, db)| {
+                        let store = NotifyingStore::new(db.clone(), notifier.clone(), d.clone());
+                        (d.clone(), Arc::new(store) as _)
+                    })
                     .collect(),
             )
             .await?;
@@ -146,7 +202,10 @@ impl BaseAgent for Relayer {
                 &core_metrics,
                 &contract_sync_metrics,
                 dbs.iter()
-                    .map(|(d, db)| (d.clone(), Arc::new(db.clone()) as _))
+                    .map(|(d, db)| {
+                        let store = NotifyingStore::new(db.clone(), notifier.clone(), d.clone());
+                        (d.clone(), Arc::new(store) as _)
+                    })
                     .collect(),
             )
             .await?;
@@ -161,31 +220,51 @@ impl BaseAgent for Relayer {
             )
             .await?;
 
-        let whitelist = Arc::new(settings.whitelist);
-        let blacklist = Arc::new(settings.blacklist);
+        // provers by origin chain, resumed from snapshots where possible
+        let mut prover_syncs = HashMap::new();
+        for origin in &settings.origin_chains {
+            let merkle_tree_hook = settings
+                .build_merkle_tree_hook(origin, &core_metrics)
+                .await?;
+            let lag = NonZeroU64::new(settings.chain_setup(origin)?.reorg_period as u64);
+            let builder = MerkleTreeBuilder::from_snapshot_or_new(
+                &dbs[origin],
+                merkle_tree_hook.as_ref(),
+                lag,
+            )
+            .await;
+            prover_syncs.insert(origin.clone(), Arc::new(RwLock::new(builder)));
+        }
+
+        let mut optimistic_isms: Vec<Arc<dyn OptimisticIsm>> =
+            Vec::with_capacity(settings.watch_optimistic_isms.len());
+        for (domain, address) in &settings.watch_optimistic_isms {
+            let ism = settings
+                .chain_setup(domain)?
+                .build_optimistic_ism(*address, &core_metrics)
+                .await?;
+            optimistic_isms.push(Arc::from(ism));
+        }
+        let origin_mailboxes = if optimistic_isms.is_empty() {
+            HashMap::new()
+        } else {
+            settings
+                .build_mailboxes(settings.origin_chains.iter(), &core_metrics)
+                .await?
+        };
+
+        let reloadable_settings = ReloadableSettingsHandle::new((&settings).into());
         let skip_transaction_gas_limit_for = settings.skip_transaction_gas_limit_for;
         let transaction_gas_limit = settings.transaction_gas_limit;
 
         info!(
-            %whitelist,
-            %blacklist,
+            whitelist=%settings.whitelist,
+            blacklist=%settings.blacklist,
             ?transaction_gas_limit,
             ?skip_transaction_gas_limit_for,
             "Whitelist configuration"
         );
 
-        // provers by origin chain
-        let prover_syncs = settings
-            .origin_chains
-            .iter()
-            .map(|origin| {
-                (
-                    origin.clone(),
-                    Arc::new(RwLock::new(MerkleTreeBuilder::new())),
-                )
-            })
-            .collect::<HashMap<_, _>>();
-
         info!(gas_enforcement_policies=?settings.gas_payment_enforcement, "Gas enforcement configuration");
 
         // need one of these per origin chain due to the database scoping even though
@@ -204,6 +283,30 @@ impl BaseAgent for Relayer {
             })
             .collect();
 
+        let ism_cache_lookups = core_metrics.new_int_counter(
+            "ism_cache_lookups",
+            "Lookups of ISM module types and configuration, by whether they hit the cache",
+            &["destination", "kind", "result"],
+        )?;
+
+        let rate_limiter = Arc::new(RateLimiter::new(
+            settings.rate_limits.clone(),
+            core_metrics.new_int_counter(
+                "throttled_messages",
+                "Messages deferred because their route is rate limited",
+                &["origin", "destination", "limit"],
+            )?,
+        ));
+
+        let ccip_read_client = Arc::new(CcipReadClient::new(
+            settings.ccip_read.clone(),
+            core_metrics.new_int_counter(
+                "ccip_read_requests",
+                "Requests to the gateways of CCIP read ISMs, by allowed host and result",
+                &["host", "result"],
+            )?,
+        )?);
+
         let mut msg_ctxs = HashMap::new();
         let mut destination_chains = HashMap::new();
         for destination in &settings.destination_chains {
@@ -215,6 +318,11 @@ impl BaseAgent for Relayer {
                 } else {
                     transaction_gas_limit
                 };
+            let ism_cache = Arc::new(IsmCache::new(
+                &settings.ism_cache,
+                destination,
+                ism_cache_lookups.clone(),
+            ));
 
             for origin in &settings.origin_chains {
                 let db = dbs.get(origin).unwrap().clone();
@@ -229,8 +337,10 @@ impl BaseAgent for Relayer {
                     5,
                     IsmAwareAppContextClassifier::new(
                         mailboxes[destination].clone(),
-                        settings.metric_app_contexts.clone(),
+                        reloadable_settings.clone(),
                     ),
+                    ism_cache.clone(),
+                    ccip_read_client.clone(),
                 );
 
                 msg_ctxs.insert(
@@ -245,6 +355,7 @@ impl BaseAgent for Relayer {
                         origin_gas_payment_enforcer: gas_payment_enforcers[origin].clone(),
                         transaction_gas_limit,
                         metrics: MessageSubmissionMetrics::new(&core_metrics, origin, destination),
+                        notifier: notifier.clone(),
                     }),
                 );
             }
@@ -252,6 +363,8 @@ impl BaseAgent for Relayer {
 
         Ok(Self {
             dbs,
+            optimistic_isms,
+            origin_mailboxes,
             origin_chains: settings.origin_chains,
             destination_chains,
             msg_ctxs,
@@ -260,12 +373,17 @@ impl BaseAgent for Relayer {
             interchain_gas_payment_syncs,
             prover_syncs,
             merkle_tree_hook_syncs,
-            whitelist,
-            blacklist,
+            settings_reloader: SettingsReloader::new(
+                reloadable_settings.clone(),
+                gas_payment_enforcers.into_values().collect(),
+            ),
+            reloadable_settings,
             transaction_gas_limit,
             skip_transaction_gas_limit_for,
             allow_local_checkpoint_syncers: settings.allow_local_checkpoint_syncers,
-            metric_app_contexts: settings.metric_app_contexts,
+            prepare_conf: settings.prepare,
+            scheduling: settings.scheduling,
+            rate_limiter,
             core_metrics,
             agent_metrics,
             chain_metrics,
@@ -282,9 +400,14 @@ impl BaseAgent for Relayer {
             .settings
             .server(self.core_metrics.clone())
             .expect("Failed to create server");
-        let server_task = server.run(vec![]).instrument(info_span!("Relayer server"));
+        let diagnoser = Arc::new(self.diagnoser());
+        let server_task = server
+            .run(vec![("/diagnose", diagnoser.router())])
+            .instrument(info_span!("Relayer server"));
         tasks.push(server_task);
 
+        tasks.push(self.settings_reloader.clone().spawn());
+
         // send channels by destination chain
         let mut send_channels = HashMap::with_capacity(self.destination_chains.len());
         for (dest_domain, dest_conf) in &self.destination_chains {
@@ -315,7 +438,11 @@ impl BaseAgent for Relayer {
         // each message process attempts to send messages from a chain
         for origin in &self.origin_chains {
             tasks.push(self.run_message_processor(origin, send_channels.clone()));
-            tasks.push(self.run_merkle_tree_processor(origin));
+            tasks.push(self.run_merkle_tree_processor(origin).await);
+        }
+
+        for ism in &self.optimistic_isms {
+            tasks.push(self.run_optimistic_ism_watcher(ism.clone()));
         }
 
         if let Err(err) = try_join_all(tasks).await {
@@ -328,8 +455,48 @@ impl BaseAgent for Relayer {
 }
 
 impl Relayer {
+    /// Diagnoses why messages relayed by this relayer were not delivered
+    pub fn diagnoser(&self) -> MessageDiagnoser {
+        MessageDiagnoser::new(
+            self.reloadable_settings.clone(),
+            self.dbs
+                .iter()
+                .map(|(origin, db)| (origin.id(), db.clone()))
+                .collect(),
+            self.msg_ctxs
+                .iter()
+                .map(|(key, ctx)| ((key.origin, key.destination), ctx.clone()))
+                .collect(),
+        )
+    }
+
+    /// Walks the configuration of the ISM which verifies messages from
+    /// `origin` to `recipient` on `destination`
+    pub async fn ism_config_tree(
+        &self,
+        origin: &str,
+        destination: &str,
+        recipient: H256,
+    ) -> Result<IsmConfig> {
+        let Some(ctx) = self.msg_ctxs.values().find(|ctx| {
+            ctx.metadata_builder.origin_domain().name() == origin
+                && ctx.metadata_builder.destination_domain().name() == destination
+        }) else {
+            bail!("Messages from {origin} to {destination} are not relayed");
+        };
+        ism_config_tree(
+            ctx.metadata_builder.clone(),
+            ctx.destination_mailbox.as_ref(),
+            recipient,
+        )
+        .await
+    }
+
     async fn run_message_sync(&self, origin: &HyperlaneDomain) -> Instrumented<JoinHandle<()>> {
-        let index_settings = self.as_ref().settings.chains[origin.name()].index_settings();
+        let chain_conf = &self.as_ref().settings.chains[origin.name()];
+        let index_settings = chain_conf.index_settings();
+        let chunk_size = index_settings.chunk_size;
+        let subscriber = chain_conf.build_message_log_subscriber().map(Arc::from);
         let contract_sync = self.message_syncs.get(origin).unwrap().clone();
         let cursor = contract_sync
             .forward_backward_message_sync_cursor(index_settings)
@@ -337,7 +504,7 @@ impl Relayer {
         tokio::spawn(async move {
             contract_sync
                 .clone()
-                .sync("dispatched_messages", cursor)
+                .sync_with_subscriber("dispatched_messages", cursor, subscriber, chunk_size)
                 .await
         })
         .instrument(info_span!("ContractSync"))
@@ -347,28 +514,48 @@ impl Relayer {
         &self,
         origin: &HyperlaneDomain,
     ) -> Instrumented<JoinHandle<()>> {
-        let index_settings = self.as_ref().settings.chains[origin.name()].index_settings();
+        let chain_conf = &self.as_ref().settings.chains[origin.name()];
+        let index_settings = chain_conf.index_settings();
+        let chunk_size = index_settings.chunk_size;
+        let subscriber = chain_conf
+            .build_interchain_gas_payment_log_subscriber()
+            .map(Arc::from);
         let contract_sync = self
             .interchain_gas_payment_syncs
             .get(origin)
             .unwrap()
             .clone();
         let cursor = contract_sync.rate_limited_cursor(index_settings).await;
-        tokio::spawn(async move { contract_sync.clone().sync("gas_payments", cursor).await })
-            .instrument(info_span!("ContractSync"))
+        tokio::spawn(async move {
+            contract_sync
+                .clone()
+                .sync_with_subscriber("gas_payments", cursor, subscriber, chunk_size)
+                .await
+        })
+        .instrument(info_span!("ContractSync"))
     }
 
     async fn run_merkle_tree_hook_syncs(
         &self,
         origin: &HyperlaneDomain,
     ) -> Instrumented<JoinHandle<()>> {
-        let index_settings = self.as_ref().settings.chains[origin.name()].index.clone();
+        let chain_conf = &self.as_ref().settings.chains[origin.name()];
+        let index_settings = chain_conf.index.clone();
+        let chunk_size = index_settings.chunk_size;
+        let subscriber = chain_conf
+            .build_merkle_tree_hook_log_subscriber()
+            .map(Arc::from);
         let contract_sync = self.merkle_tree_hook_syncs.get(origin).unwrap().clone();
         let cursor = contract_sync
             .forward_backward_message_sync_cursor(index_settings)
             .await;
-        tokio::spawn(async move { contract_sync.clone().sync("merkle_tree_hook", cursor).await })
-            .instrument(info_span!("ContractSync"))
+        tokio::spawn(async move {
+            contract_sync
+                .clone()
+                .sync_with_subscriber("merkle_tree_hook", cursor, subscriber, chunk_size)
+                .await
+        })
+        .instrument(info_span!("ContractSync"))
     }
 
     fn run_message_processor(
@@ -399,12 +586,11 @@ impl Relayer {
 
         let message_processor = MessageProcessor::new(
             self.dbs.get(origin).unwrap().clone(),
-            self.whitelist.clone(),
-            self.blacklist.clone(),
+            self.reloadable_settings.clone(),
             metrics,
             send_channels,
             destination_ctxs,
-            self.metric_app_contexts.clone(),
+            self.rate_limiter.clone(),
         );
 
         let span = info_span!("MessageProcessor", origin=%message_processor.domain());
@@ -413,12 +599,18 @@ impl Relayer {
         processor.spawn().instrument(span)
     }
 
-    fn run_merkle_tree_processor(&self, origin: &HyperlaneDomain) -> Instrumented<JoinHandle<()>> {
+    async fn run_merkle_tree_processor(
+        &self,
+        origin: &HyperlaneDomain,
+    ) -> Instrumented<JoinHandle<()>> {
         let metrics = MerkleTreeProcessorMetrics::new();
+        // Continue from the leaves the prover was restored with from a snapshot
+        let leaf_index = self.prover_syncs[origin].read().await.count();
         let merkle_tree_processor = MerkleTreeProcessor::new(
             self.dbs.get(origin).unwrap().clone(),
             metrics,
             self.prover_syncs[origin].clone(),
+            leaf_index,
         );
 
         let span = info_span!("MerkleTreeProcessor", origin=%merkle_tree_processor.domain());
@@ -426,6 +618,26 @@ impl Relayer {
         processor.spawn().instrument(span)
     }
 
+    fn run_optimistic_ism_watcher(
+        &self,
+        ism: Arc<dyn OptimisticIsm>,
+    ) -> Instrumented<JoinHandle<()>> {
+        let span = info_span!("OptimisticIsmWatcher", domain=%ism.domain(), ism=?ism.address());
+        let origin_dbs = self
+            .dbs
+            .iter()
+            .map(|(origin, db)| (origin.id(), db.clone()))
+            .collect();
+        let origin_mailboxes = self
+            .origin_mailboxes
+            .iter()
+            .map(|(origin, mailbox)| (origin.id(), mailbox.clone()))
+            .collect();
+        let watcher = OptimisticIsmWatcher::new(ism, origin_dbs, origin_mailboxes, None);
+        let processor = Processor::new(Box::new(watcher));
+        processor.spawn().instrument(span)
+    }
+
     #[allow(clippy::too_many_arguments)]
     #[tracing::instrument(skip(self, receiver))]
     fn run_destination_submitter(
@@ -437,6 +649,8 @@ impl Relayer {
             destination.clone(),
             receiver,
             SerialSubmitterMetrics::new(&self.core.metrics, destination),
+            self.prepare_conf.clone(),
+            self.scheduling.clone(),
         );
         let span = info_span!("SerialSubmitter", destination=%destination);
         let destination = destination.clone();
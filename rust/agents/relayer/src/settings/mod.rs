@@ -4,7 +4,11 @@
 //! and validations it defines are not applied here, we should mirror them.
 //! ANY CHANGES HERE NEED TO BE REFLECTED IN THE TYPESCRIPT SDK.
 
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
 
 use convert_case::Case;
 use derive_more::{AsMut, AsRef, Deref, DerefMut};
@@ -16,7 +20,7 @@ use hyperlane_base::{
         Settings,
     },
 };
-use hyperlane_core::{cfg_unwrap_all, config::*, HyperlaneDomain, U256};
+use hyperlane_core::{cfg_unwrap_all, config::*, HyperlaneDomain, H256, U256};
 use itertools::Itertools;
 use serde::Deserialize;
 use serde_json::Value;
@@ -24,6 +28,7 @@ use serde_json::Value;
 use crate::settings::matching_list::MatchingList;
 
 pub mod matching_list;
+pub mod reload;
 
 /// Settings for `Relayer`
 #[derive(Debug, AsRef, AsMut, Deref, DerefMut)]
@@ -56,6 +61,143 @@ pub struct RelayerSettings {
     pub allow_local_checkpoint_syncers: bool,
     /// App contexts used for metrics.
     pub metric_app_contexts: Vec<(MatchingList, String)>,
+    /// Optimistic ISMs to watch for pre-verified messages that were never
+    /// dispatched, flagging the submodules which pre-verified them as
+    /// fraudulent.
+    pub watch_optimistic_isms: Vec<(HyperlaneDomain, H256)>,
+    /// Caching of ISM module types and configuration.
+    pub ism_cache: IsmCacheConf,
+    /// Preparation of operations ahead of their submission.
+    pub prepare: PrepareConf,
+    /// Fetching of metadata from the gateways of CCIP read ISMs.
+    pub ccip_read: CcipReadConf,
+    /// Rate limits of the messages relayed on matching routes.
+    pub rate_limits: Vec<RateLimitConf>,
+    /// Scheduling of operations in the submitter queues.
+    pub scheduling: SchedulingConf,
+}
+
+/// Config for scheduling operations in the queues of a submitter
+#[derive(Debug, Clone, Default)]
+pub struct SchedulingConf {
+    /// Priority classes from highest to lowest priority. Operations matching
+    /// none of them are in a lowest priority default class.
+    pub priority_classes: Vec<PriorityClassConf>,
+    /// Weights of app contexts when scheduling operations fairly between
+    /// them. App contexts have a weight of 1 by default.
+    pub app_context_weights: HashMap<String, u32>,
+}
+
+impl SchedulingConf {
+    /// The name of the priority class of operations matching no class
+    pub const DEFAULT_PRIORITY_CLASS: &'static str = "default";
+}
+
+/// Config for a priority class of operations
+#[derive(Debug, Clone)]
+pub struct PriorityClassConf {
+    pub name: String,
+    /// The messages in the priority class
+    pub matching_list: MatchingList,
+}
+
+/// Config for a token bucket rate limit of the messages matching a list
+#[derive(Debug, Clone)]
+pub struct RateLimitConf {
+    /// The name of the rate limit in metrics
+    pub name: String,
+    /// The messages which are rate limited. All messages match if empty.
+    pub matching_list: MatchingList,
+    /// The number of messages which may be relayed at once
+    pub capacity: u32,
+    /// The number of messages which may be relayed per second once the
+    /// capacity is used up
+    pub refill_rate: f64,
+}
+
+/// Config for the cache of ISM module types and configuration
+#[derive(Debug, Clone)]
+pub struct IsmCacheConf {
+    /// How long a cached value is used before it is fetched again
+    pub ttl: Duration,
+    /// The maximum number of cached values of each kind, per destination
+    pub capacity: usize,
+}
+
+/// Config for preparing operations, e.g. building message metadata, ahead of
+/// their submission to a destination
+#[derive(Debug, Clone)]
+pub struct PrepareConf {
+    /// The number of operations prepared concurrently, per destination. With
+    /// more than one, an operation may be submitted before an earlier one
+    /// which is still being prepared.
+    pub workers: usize,
+    /// The number of prepared operations which may wait for submission, per
+    /// destination
+    pub lookahead: usize,
+}
+
+/// Config for fetching metadata from the gateways of CCIP read ISMs
+#[derive(Debug, Clone)]
+pub struct CcipReadConf {
+    /// Hosts metadata may be fetched from, including their subdomains. All
+    /// hosts are allowed if empty.
+    pub allowed_hosts: Vec<String>,
+    /// Hosts metadata is never fetched from, including their subdomains
+    pub denied_hosts: Vec<String>,
+    /// How many more times all gateways are tried after they all failed
+    pub retries: u32,
+    /// How long to wait before the first retry, doubled for each retry after
+    pub retry_backoff: Duration,
+    /// Timeout of each request to a gateway
+    pub timeout: Duration,
+    /// The maximum number of gateway urls of a lookup which are tried, since
+    /// they are set by the ISM
+    pub max_urls: usize,
+    /// Timeout of fetching the metadata of a lookup, across all its gateways
+    /// and retries
+    pub lookup_timeout: Duration,
+    /// The maximum size in bytes of a gateway response
+    pub max_response_size: usize,
+    /// How long fetched metadata is cached for
+    pub cache_ttl: Duration,
+    /// The maximum number of cached responses
+    pub cache_capacity: usize,
+}
+
+impl Default for CcipReadConf {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: vec![],
+            denied_hosts: vec![],
+            retries: 2,
+            retry_backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(10),
+            max_urls: 5,
+            lookup_timeout: Duration::from_secs(30),
+            max_response_size: 1 << 20,
+            cache_ttl: Duration::from_secs(60),
+            cache_capacity: 1000,
+        }
+    }
+}
+
+impl Default for PrepareConf {
+    fn default() -> Self {
+        Self {
+            workers: 1,
+            lookahead: 1,
+        }
+    }
+}
+
+impl Default for IsmCacheConf {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60 * 10),
+            capacity: 1000,
+        }
+    }
 }
 
 /// Config for gas payment enforcement
@@ -210,6 +352,208 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             .parse_bool()
             .unwrap_or(false);
 
+        let ism_cache_default = IsmCacheConf::default();
+        let ism_cache = IsmCacheConf {
+            ttl: p
+                .chain(&mut err)
+                .get_opt_key("ismCacheTtl")
+                .parse_u64()
+                .map(Duration::from_secs)
+                .unwrap_or(ism_cache_default.ttl),
+            capacity: p
+                .chain(&mut err)
+                .get_opt_key("ismCacheCapacity")
+                .parse_u64()
+                .map(|capacity| capacity as usize)
+                .unwrap_or(ism_cache_default.capacity),
+        };
+
+        let prepare_default = PrepareConf::default();
+        let prepare = PrepareConf {
+            workers: p
+                .chain(&mut err)
+                .get_opt_key("prepareWorkers")
+                .parse_u64()
+                .map(|workers| workers as usize)
+                .unwrap_or(prepare_default.workers),
+            lookahead: p
+                .chain(&mut err)
+                .get_opt_key("prepareLookahead")
+                .parse_u64()
+                .map(|lookahead| lookahead as usize)
+                .unwrap_or(prepare_default.lookahead),
+        };
+
+        let ccip_read_default = CcipReadConf::default();
+        let parse_hosts = |hosts: &str| {
+            hosts
+                .split(',')
+                .map(|host| host.trim().to_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect_vec()
+        };
+        let ccip_read = CcipReadConf {
+            allowed_hosts: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadAllowedHosts")
+                .parse_string()
+                .map(parse_hosts)
+                .unwrap_or_default(),
+            denied_hosts: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadDeniedHosts")
+                .parse_string()
+                .map(parse_hosts)
+                .unwrap_or_default(),
+            retries: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadRetries")
+                .parse_u32()
+                .unwrap_or(ccip_read_default.retries),
+            retry_backoff: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadRetryBackoffMs")
+                .parse_u64()
+                .map(Duration::from_millis)
+                .unwrap_or(ccip_read_default.retry_backoff),
+            timeout: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadTimeout")
+                .parse_u64()
+                .map(Duration::from_secs)
+                .unwrap_or(ccip_read_default.timeout),
+            max_urls: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadMaxUrls")
+                .parse_u64()
+                .map(|max_urls| max_urls as usize)
+                .unwrap_or(ccip_read_default.max_urls),
+            lookup_timeout: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadLookupTimeout")
+                .parse_u64()
+                .map(Duration::from_secs)
+                .unwrap_or(ccip_read_default.lookup_timeout),
+            max_response_size: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadMaxResponseSize")
+                .parse_u64()
+                .map(|size| size as usize)
+                .unwrap_or(ccip_read_default.max_response_size),
+            cache_ttl: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadCacheTtl")
+                .parse_u64()
+                .map(Duration::from_secs)
+                .unwrap_or(ccip_read_default.cache_ttl),
+            cache_capacity: p
+                .chain(&mut err)
+                .get_opt_key("ccipReadCacheCapacity")
+                .parse_u64()
+                .map(|capacity| capacity as usize)
+                .unwrap_or(ccip_read_default.cache_capacity),
+        };
+
+        let (raw_rate_limits_path, raw_rate_limits) = p
+            .get_opt_key("rateLimits")
+            .take_config_err_flat(&mut err)
+            .and_then(parse_json_array)
+            .unwrap_or_else(|| (&p.cwp + "rate_limits", Value::Array(vec![])));
+        let rate_limits = ValueParser::new(raw_rate_limits_path, &raw_rate_limits)
+            .into_array_iter()
+            .map(|itr| {
+                itr.enumerate()
+                    .filter_map(|(i, limit)| {
+                        let name = limit
+                            .chain(&mut err)
+                            .get_opt_key("name")
+                            .parse_string()
+                            .map(|name| name.to_owned())
+                            .unwrap_or_else(|| i.to_string());
+                        let matching_list = limit
+                            .chain(&mut err)
+                            .get_opt_key("matchingList")
+                            .and_then(parse_matching_list)
+                            .unwrap_or_default();
+                        let capacity = limit.chain(&mut err).get_key("capacity").parse_u32().end();
+                        let refill_rate = limit
+                            .chain(&mut err)
+                            .get_key("refillRate")
+                            .parse_f64()
+                            .end();
+                        if refill_rate.map_or(false, |rate| rate <= 0.) {
+                            Err(eyre!("Expected a positive refill rate"))
+                                .take_err(&mut err, || &limit.cwp + "refill_rate")
+                        } else {
+                            Some(RateLimitConf {
+                                name,
+                                matching_list,
+                                capacity: capacity?,
+                                refill_rate: refill_rate?,
+                            })
+                        }
+                    })
+                    .collect_vec()
+            })
+            .unwrap_or_default();
+
+        let (raw_priority_classes_path, raw_priority_classes) = p
+            .get_opt_key("priorityClasses")
+            .take_config_err_flat(&mut err)
+            .and_then(parse_json_array)
+            .unwrap_or_else(|| (&p.cwp + "priority_classes", Value::Array(vec![])));
+        let priority_classes = ValueParser::new(raw_priority_classes_path, &raw_priority_classes)
+            .into_array_iter()
+            .map(|itr| {
+                itr.filter_map(|class| {
+                    let name = class
+                        .chain(&mut err)
+                        .get_key("name")
+                        .parse_string()
+                        .end()?
+                        .to_owned();
+                    let matching_list = class
+                        .chain(&mut err)
+                        .get_key("matchingList")
+                        .and_then(parse_matching_list)
+                        .end()?;
+                    Some(PriorityClassConf {
+                        name,
+                        matching_list,
+                    })
+                })
+                .collect_vec()
+            })
+            .unwrap_or_default();
+
+        let (raw_app_context_weights_path, raw_app_context_weights) = p
+            .get_opt_key("appContextWeights")
+            .take_config_err_flat(&mut err)
+            .and_then(parse_json_array)
+            .unwrap_or_else(|| (&p.cwp + "app_context_weights", Value::Array(vec![])));
+        let app_context_weights =
+            ValueParser::new(raw_app_context_weights_path, &raw_app_context_weights)
+                .into_array_iter()
+                .map(|itr| {
+                    itr.filter_map(|entry| {
+                        let app_context = entry
+                            .chain(&mut err)
+                            .get_key("appContext")
+                            .parse_string()
+                            .end()?
+                            .to_owned();
+                        let weight = entry.chain(&mut err).get_key("weight").parse_u32().end()?;
+                        Some((app_context, weight))
+                    })
+                    .collect()
+                })
+                .unwrap_or_default();
+
+        let scheduling = SchedulingConf {
+            priority_classes,
+            app_context_weights,
+        };
+
         cfg_unwrap_all!(cwp, err: [base]);
 
         let skip_transaction_gas_limit_for = skip_transaction_gas_limit_for_names
@@ -260,6 +604,42 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             })
             .unwrap_or_default();
 
+        let (raw_watch_optimistic_isms_path, raw_watch_optimistic_isms) = p
+            .get_opt_key("watchOptimisticIsms")
+            .take_config_err_flat(&mut err)
+            .and_then(parse_json_array)
+            .unwrap_or_else(|| (&p.cwp + "watch_optimistic_isms", Value::Array(vec![])));
+
+        let watch_optimistic_isms_parser =
+            ValueParser::new(raw_watch_optimistic_isms_path, &raw_watch_optimistic_isms);
+        let watch_optimistic_isms = watch_optimistic_isms_parser
+            .into_array_iter()
+            .map(|itr| {
+                itr.filter_map(|ism| {
+                    let domain = ism
+                        .chain(&mut err)
+                        .get_key("chain")
+                        .parse_string()
+                        .end()
+                        .and_then(|chain| {
+                            base.lookup_domain(chain)
+                                .context(
+                                    "Missing configuration for a chain in `watchOptimisticIsms`",
+                                )
+                                .take_err(&mut err, || &ism.cwp + "chain")
+                        });
+                    let address = ism
+                        .chain(&mut err)
+                        .get_key("address")
+                        .parse_address_hash()
+                        .end();
+
+                    Some((domain?, address?))
+                })
+                .collect_vec()
+            })
+            .unwrap_or_default();
+
         err.into_result(RelayerSettings {
             base,
             db,
@@ -272,6 +652,12 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             skip_transaction_gas_limit_for,
             allow_local_checkpoint_syncers,
             metric_app_contexts,
+            watch_optimistic_isms,
+            ism_cache,
+            prepare,
+            ccip_read,
+            rate_limits,
+            scheduling,
         })
     }
 }
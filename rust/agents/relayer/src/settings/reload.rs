@@ -0,0 +1,182 @@
+//! Reloading the parts of the relayer configuration that can change without
+//! restarting, so in-memory state like the submission queues is kept.
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use derive_new::new;
+use eyre::Result;
+use hyperlane_base::{settings::loader::config_file_paths, LoadableFromSettings};
+use tokio::task::JoinHandle;
+use tracing::{error, info, info_span, instrument::Instrumented, Instrument};
+
+use crate::{
+    msg::gas_payment::GasPaymentEnforcer,
+    settings::{matching_list::MatchingList, GasPaymentEnforcementConf, RelayerSettings},
+};
+
+/// How often the config files are checked for changes.
+const CONFIG_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Relayer settings that are reloaded while the relayer is running.
+#[derive(Debug, Clone, Default)]
+pub struct ReloadableSettings {
+    /// Filter for what messages to relay.
+    pub whitelist: MatchingList,
+    /// Filter for what messages to block.
+    pub blacklist: MatchingList,
+    /// The gas payment enforcement policies
+    pub gas_payment_enforcement: Vec<GasPaymentEnforcementConf>,
+    /// App contexts used for metrics.
+    pub metric_app_contexts: Vec<(MatchingList, String)>,
+}
+
+impl From<&RelayerSettings> for ReloadableSettings {
+    fn from(settings: &RelayerSettings) -> Self {
+        Self {
+            whitelist: settings.whitelist.clone(),
+            blacklist: settings.blacklist.clone(),
+            gas_payment_enforcement: settings.gas_payment_enforcement.clone(),
+            metric_app_contexts: settings.metric_app_contexts.clone(),
+        }
+    }
+}
+
+/// Shared handle to the latest `ReloadableSettings`. Readers get a consistent
+/// view of all reloadable settings, which are swapped atomically.
+#[derive(Debug, Clone, Default)]
+pub struct ReloadableSettingsHandle(Arc<RwLock<Arc<ReloadableSettings>>>);
+
+impl ReloadableSettingsHandle {
+    pub fn new(settings: ReloadableSettings) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(settings))))
+    }
+
+    /// The latest settings
+    pub fn get(&self) -> Arc<ReloadableSettings> {
+        self.0.read().expect("poisoned lock").clone()
+    }
+
+    fn set(&self, settings: ReloadableSettings) {
+        *self.0.write().expect("poisoned lock") = Arc::new(settings);
+    }
+}
+
+/// Reloads the relayer settings when its config files change and swaps the
+/// reloadable ones into the running relayer. Invalid configs are rejected and
+/// the current settings are kept.
+///
+/// Messages skipped because of the whitelist or blacklist aren't reconsidered
+/// after a reload.
+#[derive(Debug, Clone, new)]
+pub struct SettingsReloader {
+    settings: ReloadableSettingsHandle,
+    gas_payment_enforcers: Vec<Arc<GasPaymentEnforcer>>,
+}
+
+impl SettingsReloader {
+    /// Loads and validates the settings, then swaps in the reloadable ones.
+    pub fn reload(&self) -> Result<()> {
+        let settings = RelayerSettings::load()?;
+        self.apply(ReloadableSettings::from(&settings));
+        Ok(())
+    }
+
+    fn apply(&self, settings: ReloadableSettings) {
+        for enforcer in &self.gas_payment_enforcers {
+            enforcer.set_policies(settings.gas_payment_enforcement.clone());
+        }
+        info!(
+            whitelist=%settings.whitelist,
+            blacklist=%settings.blacklist,
+            gas_enforcement_policies=?settings.gas_payment_enforcement,
+            "Reloaded relayer settings"
+        );
+        self.settings.set(settings);
+    }
+
+    /// Polls the config files for changes, reloading the settings whenever
+    /// any of them is modified.
+    pub fn spawn(self) -> Instrumented<JoinHandle<()>> {
+        tokio::spawn(async move {
+            let mut modified = modification_times();
+            loop {
+                tokio::time::sleep(CONFIG_RELOAD_INTERVAL).await;
+                let latest = modification_times();
+                if latest == modified {
+                    continue;
+                }
+                modified = latest;
+                if let Err(err) = self.reload() {
+                    error!(?err, "Invalid relayer settings, keeping the current ones");
+                }
+            }
+        })
+        .instrument(info_span!("SettingsReloader"))
+    }
+}
+
+fn modification_times() -> Vec<(PathBuf, Option<SystemTime>)> {
+    config_file_paths()
+        .into_iter()
+        .map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
+    use hyperlane_core::{HyperlaneDomain, HyperlaneMessage, TxCostEstimate, U256};
+
+    use super::*;
+    use crate::settings::GasPaymentEnforcementPolicy;
+
+    #[tokio::test]
+    async fn test_apply_swaps_settings_and_policies() {
+        test_utils::run_test_db(|db| async move {
+            let hyperlane_db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("test_apply_swaps_settings_and_policies"),
+                db,
+            );
+            let enforcer = Arc::new(GasPaymentEnforcer::new(
+                [GasPaymentEnforcementConf::default()],
+                hyperlane_db,
+            ));
+            let handle = ReloadableSettingsHandle::default();
+            let reloader = SettingsReloader::new(handle.clone(), vec![enforcer.clone()]);
+            let message = HyperlaneMessage::default();
+            let tx_cost_estimate = TxCostEstimate::default();
+            assert!(enforcer
+                .message_meets_gas_payment_requirement(&message, &tx_cost_estimate)
+                .await
+                .unwrap()
+                .is_some());
+
+            let blacklist: MatchingList = serde_json::from_str(r#"[{"origindomain": 0}]"#).unwrap();
+            reloader.apply(ReloadableSettings {
+                blacklist,
+                gas_payment_enforcement: vec![GasPaymentEnforcementConf {
+                    policy: GasPaymentEnforcementPolicy::Minimum {
+                        payment: U256::one(),
+                    },
+                    matching_list: Default::default(),
+                }],
+                ..Default::default()
+            });
+
+            assert!(handle.get().blacklist.msg_matches(&message, false));
+            assert!(enforcer
+                .message_meets_gas_payment_requirement(&message, &tx_cost_estimate)
+                .await
+                .unwrap()
+                .is_none());
+        })
+        .await;
+    }
+}
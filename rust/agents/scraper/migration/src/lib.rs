@@ -13,6 +13,14 @@ mod m20230309_000003_create_table_transaction;
 mod m20230309_000004_create_table_delivered_message;
 mod m20230309_000004_create_table_gas_payment;
 mod m20230309_000005_create_table_message;
+mod m20231018_000001_create_table_merkle_tree_insertion;
+mod m20231018_000002_create_table_validator_announcement;
+mod m20231018_000003_alter_table_cursor_add_kind;
+mod m20231018_000004_create_view_message_lifecycle;
+mod m20231018_000005_recreate_view_message_for_sqlite;
+mod m20231018_000006_create_table_message_lifecycle;
+
+pub use m20231018_000006_create_table_message_lifecycle::upsert_message_lifecycles_sql;
 
 pub struct Migrator;
 
@@ -30,6 +38,12 @@ impl MigratorTrait for Migrator {
             Box::new(m20230309_000004_create_table_gas_payment::Migration),
             Box::new(m20230309_000004_create_table_delivered_message::Migration),
             Box::new(m20230309_000005_create_table_message::Migration),
+            Box::new(m20231018_000001_create_table_merkle_tree_insertion::Migration),
+            Box::new(m20231018_000002_create_table_validator_announcement::Migration),
+            Box::new(m20231018_000003_alter_table_cursor_add_kind::Migration),
+            Box::new(m20231018_000004_create_view_message_lifecycle::Migration),
+            Box::new(m20231018_000005_recreate_view_message_for_sqlite::Migration),
+            Box::new(m20231018_000006_create_table_message_lifecycle::Migration),
         ]
     }
 }
@@ -0,0 +1,119 @@
+use sea_orm_migration::prelude::*;
+
+use crate::l20230309_types::*;
+use crate::m20230309_000001_create_table_domain::Domain;
+use crate::m20230309_000003_create_table_transaction::Transaction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ValidatorAnnouncement::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ValidatorAnnouncement::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ValidatorAnnouncement::TimeCreated)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(ValidatorAnnouncement::Domain)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new_with_type(ValidatorAnnouncement::ValidatorAnnounce, Address)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new_with_type(ValidatorAnnouncement::Validator, Address)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ValidatorAnnouncement::StorageLocation)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ValidatorAnnouncement::TxId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ValidatorAnnouncement::LogIndex)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_col(ValidatorAnnouncement::Domain)
+                            .to(Domain::Table, Domain::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_col(ValidatorAnnouncement::TxId)
+                            .to(Transaction::Table, Transaction::Id),
+                    )
+                    .index(
+                        Index::create()
+                            // don't need domain because TxId includes it
+                            .unique()
+                            .col(ValidatorAnnouncement::TxId)
+                            .col(ValidatorAnnouncement::LogIndex),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(ValidatorAnnouncement::Table)
+                    .name("validator_announcement_validator_idx")
+                    .col(ValidatorAnnouncement::Validator)
+                    .index_type(IndexType::Hash)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ValidatorAnnouncement::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum ValidatorAnnouncement {
+    Table,
+    /// Unique database ID
+    Id,
+    /// Time of record creation
+    TimeCreated,
+    /// Domain of the chain the announcement was made on
+    Domain,
+    /// Address of the validator announce contract the announcement was made to
+    ValidatorAnnounce,
+    /// Address of the announcing validator
+    Validator,
+    /// The announced location of the validator's signed checkpoints
+    StorageLocation,
+    /// Transaction the announcement was made in
+    TxId,
+    /// Index of the announcement event within the transaction's logs
+    LogIndex,
+}
@@ -0,0 +1,293 @@
+use sea_orm::{ConnectionTrait, DbBackend};
+use sea_orm_migration::prelude::*;
+
+use crate::m20230309_000001_create_table_domain::Domain;
+use crate::m20230309_000002_create_table_block::Block;
+use crate::m20230309_000003_create_table_transaction::Transaction;
+use crate::m20230309_000004_create_table_delivered_message::DeliveredMessage;
+use crate::m20230309_000004_create_table_gas_payment::{GasPayment, TotalGasPayment};
+use crate::m20230309_000005_create_table_message::Message;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite has no interval type, so latencies are in seconds there
+        let backend = manager.get_database_backend();
+        let latency = |end: &str, start: &str| match backend {
+            DbBackend::Sqlite => format!("(JULIANDAY({end}) - JULIANDAY({start})) * 86400"),
+            _ => format!("{end} - {start}"),
+        };
+        let dispatched_at = format!(r#""origin_block"."{}""#, Block::Timestamp.to_string());
+        let delivered_at = format!(r#""dest_block"."{}""#, Block::Timestamp.to_string());
+
+        let lifecycle_sql = format!(
+            r#"
+            CREATE VIEW "{lc_table}" AS
+            SELECT
+                "msg"."{msg_id}" AS "id",
+                "msg"."{msg_mid}" AS "{lc_mid}",
+                "msg"."{msg_nonce}" AS "nonce",
+                "msg"."{msg_sender}" AS "sender",
+                "msg"."{msg_recipient}" AS "recipient",
+
+                "msg"."{msg_origin}" AS "{lc_origin_id}",
+                "origin_domain"."{domain_name}" AS "{lc_origin}",
+                "msg"."{msg_dest}" AS "{lc_dest_id}",
+                "dest_domain"."{domain_name}" AS "{lc_dest}",
+
+                CASE
+                    WHEN "dmsg"."{dmsg_id}" IS NOT NULL THEN 'delivered'
+                    WHEN "gp"."first_paid_at" IS NOT NULL THEN 'gas_paid'
+                    ELSE 'dispatched'
+                END AS "{lc_status}",
+
+                "origin_block"."{block_timestamp}" AS "{lc_dispatched_at}",
+                "origin_tx"."{tx_hash}" AS "dispatch_tx_hash",
+
+                "gp"."first_paid_at" AS "first_gas_paid_at",
+                COALESCE("tgp"."{tgp_num_payments}", '0') AS "num_payments",
+                COALESCE("tgp"."{tgp_payment}", '0') AS "total_payment",
+                COALESCE("tgp"."{tgp_gas_amount}", '0') AS "total_gas_amount",
+
+                "dest_block"."{block_timestamp}" AS "{lc_delivered_at}",
+                "dest_tx"."{tx_hash}" AS "delivery_tx_hash",
+                "dest_tx"."{tx_sender}" AS "relayer",
+
+                {delivery_latency} AS "{lc_latency}",
+                CASE
+                    WHEN "dmsg"."{dmsg_id}" IS NULL THEN {pending_for}
+                END AS "pending_for"
+            FROM "{msg_table}" AS "msg"
+                LEFT JOIN "{domain_table}"
+                    AS "origin_domain"
+                    ON "origin_domain"."{domain_id}" = "msg"."{msg_origin}"
+                LEFT JOIN "{domain_table}"
+                    AS "dest_domain"
+                    ON "dest_domain"."{domain_id}" = "msg"."{msg_dest}"
+                LEFT JOIN "{tx_table}"
+                    AS "origin_tx"
+                    ON "origin_tx"."{tx_id}" = "msg"."{msg_oti}"
+                LEFT JOIN "{block_table}"
+                    AS "origin_block"
+                    ON "origin_block"."{block_id}" = "origin_tx"."{tx_block_id}"
+                LEFT JOIN (
+                    SELECT
+                        "payment"."{gp_mid}" AS "msg_id",
+                        MIN("payment_block"."{block_timestamp}") AS "first_paid_at"
+                    FROM "{gp_table}" AS "payment"
+                        INNER JOIN "{tx_table}"
+                            AS "payment_tx"
+                            ON "payment_tx"."{tx_id}" = "payment"."{gp_tx_id}"
+                        INNER JOIN "{block_table}"
+                            AS "payment_block"
+                            ON "payment_block"."{block_id}" = "payment_tx"."{tx_block_id}"
+                    GROUP BY "payment"."{gp_mid}"
+                ) AS "gp"
+                    ON "gp"."msg_id" = "msg"."{msg_mid}"
+                LEFT JOIN "{tgp_table}"
+                    AS "tgp"
+                    ON "tgp"."{tgp_mid}" = "msg"."{msg_mid}"
+                LEFT JOIN "{dmsg_table}"
+                    AS "dmsg"
+                    ON "dmsg"."{dmsg_mid}" = "msg"."{msg_mid}"
+                LEFT JOIN "{tx_table}"
+                    AS "dest_tx"
+                    ON "dest_tx"."{tx_id}" = "dmsg"."{dmsg_dti}"
+                LEFT JOIN "{block_table}"
+                    AS "dest_block"
+                    ON "dest_block"."{block_id}" = "dest_tx"."{tx_block_id}"
+            "#,
+            lc_table = MessageLifecycle::Table.to_string(),
+            lc_mid = MessageLifecycle::MsgId.to_string(),
+            lc_origin_id = MessageLifecycle::OriginDomainId.to_string(),
+            lc_origin = MessageLifecycle::OriginDomain.to_string(),
+            lc_dest_id = MessageLifecycle::DestinationDomainId.to_string(),
+            lc_dest = MessageLifecycle::DestinationDomain.to_string(),
+            lc_status = MessageLifecycle::Status.to_string(),
+            lc_dispatched_at = MessageLifecycle::DispatchedAt.to_string(),
+            lc_delivered_at = MessageLifecycle::DeliveredAt.to_string(),
+            lc_latency = MessageLifecycle::DeliveryLatency.to_string(),
+            msg_table = Message::Table.to_string(),
+            msg_id = Message::Id.to_string(),
+            msg_mid = Message::MsgId.to_string(),
+            msg_origin = Message::Origin.to_string(),
+            msg_dest = Message::Destination.to_string(),
+            msg_nonce = Message::Nonce.to_string(),
+            msg_sender = Message::Sender.to_string(),
+            msg_recipient = Message::Recipient.to_string(),
+            msg_oti = Message::OriginTxId.to_string(),
+            domain_table = Domain::Table.to_string(),
+            domain_id = Domain::Id.to_string(),
+            domain_name = Domain::Name.to_string(),
+            tx_table = Transaction::Table.to_string(),
+            tx_id = Transaction::Id.to_string(),
+            tx_hash = Transaction::Hash.to_string(),
+            tx_block_id = Transaction::BlockId.to_string(),
+            tx_sender = Transaction::Sender.to_string(),
+            block_table = Block::Table.to_string(),
+            block_id = Block::Id.to_string(),
+            block_timestamp = Block::Timestamp.to_string(),
+            gp_table = GasPayment::Table.to_string(),
+            gp_mid = GasPayment::MsgId.to_string(),
+            gp_tx_id = GasPayment::TxId.to_string(),
+            tgp_table = TotalGasPayment::Table.to_string(),
+            tgp_mid = TotalGasPayment::MsgId.to_string(),
+            tgp_num_payments = TotalGasPayment::NumPayments.to_string(),
+            tgp_payment = TotalGasPayment::TotalPayment.to_string(),
+            tgp_gas_amount = TotalGasPayment::TotalGasAmount.to_string(),
+            dmsg_table = DeliveredMessage::Table.to_string(),
+            dmsg_id = DeliveredMessage::Id.to_string(),
+            dmsg_mid = DeliveredMessage::MsgId.to_string(),
+            dmsg_dti = DeliveredMessage::DestinationTxId.to_string(),
+            delivery_latency = latency(&delivered_at, &dispatched_at),
+            pending_for = latency("CURRENT_TIMESTAMP", &dispatched_at),
+        );
+        manager
+            .get_connection()
+            .execute_unprepared(&lifecycle_sql)
+            .await?;
+
+        // These rely on Postgres aggregates and functions, so they are not
+        // available with other backends.
+        if backend != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        // End-to-end latency of delivered messages per route, bucketed by the
+        // hour of delivery.
+        let route_latency_sql = format!(
+            r#"
+            CREATE VIEW "{rl_table}" AS
+            SELECT
+                "lc"."{lc_origin_id}" AS "{lc_origin_id}",
+                "lc"."{lc_origin}" AS "{lc_origin}",
+                "lc"."{lc_dest_id}" AS "{lc_dest_id}",
+                "lc"."{lc_dest}" AS "{lc_dest}",
+                DATE_TRUNC('hour', "lc"."{lc_delivered_at}") AS "hour",
+                COUNT(*) AS "num_delivered",
+                AVG("lc"."{lc_latency}") AS "avg_latency",
+                PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY "lc"."{lc_latency}") AS "p50_latency",
+                PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY "lc"."{lc_latency}") AS "p95_latency",
+                MAX("lc"."{lc_latency}") AS "max_latency"
+            FROM "{lc_table}" AS "lc"
+            WHERE "lc"."{lc_status}" = 'delivered'
+            GROUP BY
+                "lc"."{lc_origin_id}",
+                "lc"."{lc_origin}",
+                "lc"."{lc_dest_id}",
+                "lc"."{lc_dest}",
+                DATE_TRUNC('hour', "lc"."{lc_delivered_at}")
+            "#,
+            rl_table = RouteLatency::Table.to_string(),
+            lc_table = MessageLifecycle::Table.to_string(),
+            lc_origin_id = MessageLifecycle::OriginDomainId.to_string(),
+            lc_origin = MessageLifecycle::OriginDomain.to_string(),
+            lc_dest_id = MessageLifecycle::DestinationDomainId.to_string(),
+            lc_dest = MessageLifecycle::DestinationDomain.to_string(),
+            lc_status = MessageLifecycle::Status.to_string(),
+            lc_delivered_at = MessageLifecycle::DeliveredAt.to_string(),
+            lc_latency = MessageLifecycle::DeliveryLatency.to_string(),
+        );
+        manager
+            .get_connection()
+            .execute_unprepared(&route_latency_sql)
+            .await?;
+
+        // Messages which have not been delivered within `threshold` of being
+        // dispatched, e.g. `SELECT * FROM undelivered_messages('30 minutes')`.
+        let undelivered_sql = format!(
+            r#"
+            CREATE FUNCTION "{undelivered}"("threshold" INTERVAL DEFAULT INTERVAL '1 hour')
+            RETURNS SETOF "{lc_table}" AS $$
+                SELECT * FROM "{lc_table}" AS "lc"
+                WHERE "lc"."{lc_status}" <> 'delivered'
+                    AND "lc"."{lc_dispatched_at}" < NOW() - "threshold"
+                ORDER BY "lc"."{lc_dispatched_at}"
+            $$ LANGUAGE SQL STABLE
+            "#,
+            undelivered = UndeliveredMessages::Table.to_string(),
+            lc_table = MessageLifecycle::Table.to_string(),
+            lc_status = MessageLifecycle::Status.to_string(),
+            lc_dispatched_at = MessageLifecycle::DispatchedAt.to_string(),
+        );
+        manager
+            .get_connection()
+            .execute_unprepared(&undelivered_sql)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        if manager.get_database_backend() == DbBackend::Postgres {
+            conn.execute_unprepared(&format!(
+                r#"DROP FUNCTION IF EXISTS "{}""#,
+                UndeliveredMessages::Table.to_string()
+            ))
+            .await?;
+            conn.execute_unprepared(&format!(
+                r#"DROP VIEW IF EXISTS "{}""#,
+                RouteLatency::Table.to_string()
+            ))
+            .await?;
+        }
+        conn.execute_unprepared(&format!(
+            r#"DROP VIEW IF EXISTS "{}""#,
+            MessageLifecycle::Table.to_string()
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+/// Per-message record of how far a message got between dispatch and delivery.
+/// A view in this migration, and a table from
+/// `m20231018_000006_create_table_message_lifecycle` on.
+#[derive(Iden)]
+pub enum MessageLifecycle {
+    Table,
+    /// Unique database ID
+    Id,
+    /// Time of the last update to this row
+    TimeUpdated,
+    /// Id of the message on the blockchain
+    MsgId,
+    OriginDomainId,
+    OriginDomain,
+    DestinationDomainId,
+    DestinationDomain,
+    Nonce,
+    /// One of `dispatched`, `gas_paid` or `delivered`
+    Status,
+    /// Timestamp of the block the message was dispatched in
+    DispatchedAt,
+    /// Transaction the message was dispatched in
+    DispatchTxId,
+    /// Timestamp of the block of the first gas payment for the message
+    FirstGasPaidAt,
+    /// Number of gas payments for the message
+    NumPayments,
+    /// Timestamp of the block the message was delivered in
+    DeliveredAt,
+    /// Transaction the message was delivered in
+    DeliveryTxId,
+    /// Sender of the delivery transaction
+    Relayer,
+    /// Time between the dispatch and delivery blocks, in seconds with SQLite
+    /// and, from the lifecycle table on, with Postgres too
+    DeliveryLatency,
+}
+
+#[derive(Iden)]
+pub enum RouteLatency {
+    Table,
+}
+
+#[derive(Iden)]
+pub enum UndeliveredMessages {
+    Table,
+}
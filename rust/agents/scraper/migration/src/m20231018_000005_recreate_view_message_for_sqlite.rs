@@ -0,0 +1,48 @@
+use sea_orm::{ConnectionTrait, DbBackend};
+use sea_orm_migration::prelude::*;
+
+use crate::m20230309_000005_create_table_message::{
+    message_view_sql, timestamp_difference, Message,
+};
+
+/// Recreates the message view on SQLite, which has no interval type, so the
+/// latencies are in seconds instead of the difference of the timestamps.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            return Ok(());
+        }
+        recreate_view(manager, |end, start| {
+            format!("(JULIANDAY({end}) - JULIANDAY({start})) * 86400")
+        })
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            return Ok(());
+        }
+        recreate_view(manager, timestamp_difference).await
+    }
+}
+
+/// Replaces the message view with one computing the latency between the
+/// timestamps `end` and `start` with `latency`
+async fn recreate_view(
+    manager: &SchemaManager<'_>,
+    latency: impl Fn(&str, &str) -> String,
+) -> Result<(), DbErr> {
+    let conn = manager.get_connection();
+    conn.execute_unprepared(&format!(
+        r#"DROP VIEW IF EXISTS "{}_view""#,
+        Message::Table.to_string()
+    ))
+    .await?;
+
+    conn.execute_unprepared(&message_view_sql(latency)).await?;
+    Ok(())
+}
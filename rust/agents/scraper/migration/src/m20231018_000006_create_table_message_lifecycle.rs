@@ -0,0 +1,385 @@
+use sea_orm::{ConnectionTrait, DbBackend};
+use sea_orm_migration::prelude::*;
+
+use crate::l20230309_types::*;
+use crate::m20230309_000001_create_table_domain::Domain;
+use crate::m20230309_000002_create_table_block::Block;
+use crate::m20230309_000003_create_table_transaction::Transaction;
+use crate::m20230309_000004_create_table_delivered_message::DeliveredMessage;
+use crate::m20230309_000004_create_table_gas_payment::GasPayment;
+use crate::m20230309_000005_create_table_message::Message;
+use crate::m20231018_000004_create_view_message_lifecycle::{
+    self as lifecycle_view, MessageLifecycle, RouteLatency, UndeliveredMessages,
+};
+
+/// Replaces the message lifecycle view with a table the scraper keeps up to
+/// date as it stores messages, gas payments and deliveries, and rebuilds the
+/// views on top of it.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let backend = manager.get_database_backend();
+        drop_views(manager).await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageLifecycle::Table)
+                    .col(
+                        ColumnDef::new(MessageLifecycle::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MessageLifecycle::TimeUpdated)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new_with_type(MessageLifecycle::MsgId, Hash).not_null())
+                    .col(ColumnDef::new(MessageLifecycle::OriginDomainId).unsigned())
+                    .col(ColumnDef::new(MessageLifecycle::DestinationDomainId).unsigned())
+                    .col(ColumnDef::new(MessageLifecycle::Nonce).unsigned())
+                    .col(ColumnDef::new(MessageLifecycle::Status).text().not_null())
+                    .col(ColumnDef::new(MessageLifecycle::DispatchedAt).timestamp())
+                    .col(ColumnDef::new(MessageLifecycle::DispatchTxId).big_integer())
+                    .col(ColumnDef::new(MessageLifecycle::FirstGasPaidAt).timestamp())
+                    .col(
+                        ColumnDef::new(MessageLifecycle::NumPayments)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MessageLifecycle::DeliveredAt).timestamp())
+                    .col(ColumnDef::new(MessageLifecycle::DeliveryTxId).big_integer())
+                    .col(ColumnDef::new_with_type(MessageLifecycle::Relayer, Address))
+                    .col(ColumnDef::new(MessageLifecycle::DeliveryLatency).double())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_col(MessageLifecycle::DispatchTxId)
+                            .to(Transaction::Table, Transaction::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_col(MessageLifecycle::DeliveryTxId)
+                            .to(Transaction::Table, Transaction::Id),
+                    )
+                    .index(Index::create().unique().col(MessageLifecycle::MsgId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(MessageLifecycle::Table)
+                    .name("message_lifecycle_status_dispatched_at_idx")
+                    .col(MessageLifecycle::Status)
+                    .col(MessageLifecycle::DispatchedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Backfill the lifecycle of every message scraped so far
+        let all_msg_ids = format!(
+            r#"
+            SELECT "{msg_mid}" AS "msg_id" FROM "{msg_table}"
+            UNION SELECT "{gp_mid}" FROM "{gp_table}"
+            UNION SELECT "{dmsg_mid}" FROM "{dmsg_table}"
+            "#,
+            msg_table = Message::Table.to_string(),
+            msg_mid = Message::MsgId.to_string(),
+            gp_table = GasPayment::Table.to_string(),
+            gp_mid = GasPayment::MsgId.to_string(),
+            dmsg_table = DeliveredMessage::Table.to_string(),
+            dmsg_mid = DeliveredMessage::MsgId.to_string(),
+        );
+        manager
+            .get_connection()
+            .execute_unprepared(&upsert_message_lifecycles_sql(backend, &all_msg_ids))
+            .await?;
+
+        // These rely on Postgres aggregates and functions, so they are not
+        // available with other backends.
+        if backend != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        // Latency in seconds of delivered messages per route, bucketed by the
+        // hour of delivery.
+        let route_latency_sql = format!(
+            r#"
+            CREATE VIEW "{rl_table}" AS
+            SELECT
+                "lc"."{lc_origin_id}" AS "{lc_origin_id}",
+                "origin_domain"."{domain_name}" AS "{lc_origin}",
+                "lc"."{lc_dest_id}" AS "{lc_dest_id}",
+                "dest_domain"."{domain_name}" AS "{lc_dest}",
+                DATE_TRUNC('hour', "lc"."{lc_delivered_at}") AS "hour",
+                COUNT(*) AS "num_delivered",
+                AVG("lc"."{lc_latency}") AS "avg_latency",
+                PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY "lc"."{lc_latency}") AS "p50_latency",
+                PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY "lc"."{lc_latency}") AS "p95_latency",
+                MAX("lc"."{lc_latency}") AS "max_latency"
+            FROM "{lc_table}" AS "lc"
+                LEFT JOIN "{domain_table}"
+                    AS "origin_domain"
+                    ON "origin_domain"."{domain_id}" = "lc"."{lc_origin_id}"
+                LEFT JOIN "{domain_table}"
+                    AS "dest_domain"
+                    ON "dest_domain"."{domain_id}" = "lc"."{lc_dest_id}"
+            WHERE "lc"."{lc_status}" = 'delivered'
+                AND "lc"."{lc_latency}" IS NOT NULL
+            GROUP BY
+                "lc"."{lc_origin_id}",
+                "origin_domain"."{domain_name}",
+                "lc"."{lc_dest_id}",
+                "dest_domain"."{domain_name}",
+                DATE_TRUNC('hour', "lc"."{lc_delivered_at}")
+            "#,
+            rl_table = RouteLatency::Table.to_string(),
+            lc_table = MessageLifecycle::Table.to_string(),
+            lc_origin_id = MessageLifecycle::OriginDomainId.to_string(),
+            lc_origin = MessageLifecycle::OriginDomain.to_string(),
+            lc_dest_id = MessageLifecycle::DestinationDomainId.to_string(),
+            lc_dest = MessageLifecycle::DestinationDomain.to_string(),
+            lc_status = MessageLifecycle::Status.to_string(),
+            lc_delivered_at = MessageLifecycle::DeliveredAt.to_string(),
+            lc_latency = MessageLifecycle::DeliveryLatency.to_string(),
+            domain_table = Domain::Table.to_string(),
+            domain_id = Domain::Id.to_string(),
+            domain_name = Domain::Name.to_string(),
+        );
+        manager
+            .get_connection()
+            .execute_unprepared(&route_latency_sql)
+            .await?;
+
+        // Messages which have not been delivered within `threshold` of being
+        // dispatched, e.g. `SELECT * FROM undelivered_messages('30 minutes')`.
+        let undelivered_sql = format!(
+            r#"
+            CREATE FUNCTION "{undelivered}"("threshold" INTERVAL DEFAULT INTERVAL '1 hour')
+            RETURNS SETOF "{lc_table}" AS $$
+                SELECT * FROM "{lc_table}" AS "lc"
+                WHERE "lc"."{lc_status}" <> 'delivered'
+                    AND "lc"."{lc_dispatched_at}" < NOW() - "threshold"
+                ORDER BY "lc"."{lc_dispatched_at}"
+            $$ LANGUAGE SQL STABLE
+            "#,
+            undelivered = UndeliveredMessages::Table.to_string(),
+            lc_table = MessageLifecycle::Table.to_string(),
+            lc_status = MessageLifecycle::Status.to_string(),
+            lc_dispatched_at = MessageLifecycle::DispatchedAt.to_string(),
+        );
+        manager
+            .get_connection()
+            .execute_unprepared(&undelivered_sql)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_views(manager).await?;
+        manager
+            .drop_table(Table::drop().table(MessageLifecycle::Table).to_owned())
+            .await?;
+        lifecycle_view::Migration.up(manager).await
+    }
+}
+
+/// Drops the views and functions which depend on the message lifecycle, and
+/// the lifecycle view itself if it is one.
+async fn drop_views(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let conn = manager.get_connection();
+    if manager.get_database_backend() == DbBackend::Postgres {
+        conn.execute_unprepared(&format!(
+            r#"DROP FUNCTION IF EXISTS "{}""#,
+            UndeliveredMessages::Table.to_string()
+        ))
+        .await?;
+        conn.execute_unprepared(&format!(
+            r#"DROP VIEW IF EXISTS "{}""#,
+            RouteLatency::Table.to_string()
+        ))
+        .await?;
+    }
+    conn.execute_unprepared(&format!(
+        r#"DROP VIEW IF EXISTS "{}""#,
+        MessageLifecycle::Table.to_string()
+    ))
+    .await?;
+    Ok(())
+}
+
+/// The SQL inserting or updating the lifecycle of the messages whose ids are
+/// selected by `msg_ids`, a query with a single `msg_id` column, from the
+/// scraped messages, gas payments and deliveries.
+///
+/// A message can be paid for or delivered before it is scraped on its origin,
+/// in which case the columns about its dispatch are null until it is.
+pub fn upsert_message_lifecycles_sql(backend: DbBackend, msg_ids: &str) -> String {
+    // SQLite has no interval type, so both compute the latency in seconds
+    let delivery_latency = match backend {
+        DbBackend::Sqlite => {
+            r#"(JULIANDAY("lc"."delivered_at") - JULIANDAY("lc"."dispatched_at")) * 86400"#
+        }
+        _ => r#"EXTRACT(EPOCH FROM ("lc"."delivered_at" - "lc"."dispatched_at"))"#,
+    };
+    let updated_columns = [
+        MessageLifecycle::TimeUpdated,
+        MessageLifecycle::OriginDomainId,
+        MessageLifecycle::DestinationDomainId,
+        MessageLifecycle::Nonce,
+        MessageLifecycle::Status,
+        MessageLifecycle::DispatchedAt,
+        MessageLifecycle::DispatchTxId,
+        MessageLifecycle::FirstGasPaidAt,
+        MessageLifecycle::NumPayments,
+        MessageLifecycle::DeliveredAt,
+        MessageLifecycle::DeliveryTxId,
+        MessageLifecycle::Relayer,
+        MessageLifecycle::DeliveryLatency,
+    ]
+    .map(|column| {
+        let column = column.to_string();
+        format!(r#""{column}" = "excluded"."{column}""#)
+    })
+    .join(",\n                ");
+
+    format!(
+        r#"
+        INSERT INTO "{lc_table}" (
+            "{lc_time_updated}",
+            "{lc_mid}",
+            "{lc_origin_id}",
+            "{lc_dest_id}",
+            "{lc_nonce}",
+            "{lc_status}",
+            "{lc_dispatched_at}",
+            "{lc_dispatch_tx_id}",
+            "{lc_first_gas_paid_at}",
+            "{lc_num_payments}",
+            "{lc_delivered_at}",
+            "{lc_delivery_tx_id}",
+            "{lc_relayer}",
+            "{lc_latency}"
+        )
+        SELECT
+            CURRENT_TIMESTAMP,
+            "lc"."msg_id",
+            "lc"."origin",
+            "lc"."destination",
+            "lc"."nonce",
+            CASE
+                WHEN "lc"."delivery_tx_id" IS NOT NULL THEN 'delivered'
+                WHEN "lc"."first_gas_paid_at" IS NOT NULL THEN 'gas_paid'
+                ELSE 'dispatched'
+            END,
+            "lc"."dispatched_at",
+            "lc"."dispatch_tx_id",
+            "lc"."first_gas_paid_at",
+            "lc"."num_payments",
+            "lc"."delivered_at",
+            "lc"."delivery_tx_id",
+            "lc"."relayer",
+            {delivery_latency}
+        FROM (
+            SELECT
+                "ids"."msg_id" AS "msg_id",
+                "msg"."{msg_origin}" AS "origin",
+                "msg"."{msg_dest}" AS "destination",
+                "msg"."{msg_nonce}" AS "nonce",
+                "origin_block"."{block_timestamp}" AS "dispatched_at",
+                "msg"."{msg_oti}" AS "dispatch_tx_id",
+                (
+                    SELECT MIN("payment_block"."{block_timestamp}")
+                    FROM "{gp_table}" AS "payment"
+                        INNER JOIN "{tx_table}"
+                            AS "payment_tx"
+                            ON "payment_tx"."{tx_id}" = "payment"."{gp_tx_id}"
+                        INNER JOIN "{block_table}"
+                            AS "payment_block"
+                            ON "payment_block"."{block_id}" = "payment_tx"."{tx_block_id}"
+                    WHERE "payment"."{gp_mid}" = "ids"."msg_id"
+                ) AS "first_gas_paid_at",
+                (
+                    SELECT COUNT(*)
+                    FROM "{gp_table}" AS "any_payment"
+                    WHERE "any_payment"."{gp_mid}" = "ids"."msg_id"
+                ) AS "num_payments",
+                "dest_block"."{block_timestamp}" AS "delivered_at",
+                "dmsg"."{dmsg_dti}" AS "delivery_tx_id",
+                "dest_tx"."{tx_sender}" AS "relayer"
+            FROM ({msg_ids}) AS "ids"
+                LEFT JOIN "{msg_table}"
+                    AS "msg"
+                    ON "msg"."{msg_id}" = (
+                        SELECT MIN("any_msg"."{msg_id}")
+                        FROM "{msg_table}" AS "any_msg"
+                        WHERE "any_msg"."{msg_mid}" = "ids"."msg_id"
+                    )
+                LEFT JOIN "{tx_table}"
+                    AS "origin_tx"
+                    ON "origin_tx"."{tx_id}" = "msg"."{msg_oti}"
+                LEFT JOIN "{block_table}"
+                    AS "origin_block"
+                    ON "origin_block"."{block_id}" = "origin_tx"."{tx_block_id}"
+                LEFT JOIN "{dmsg_table}"
+                    AS "dmsg"
+                    ON "dmsg"."{dmsg_mid}" = "ids"."msg_id"
+                LEFT JOIN "{tx_table}"
+                    AS "dest_tx"
+                    ON "dest_tx"."{tx_id}" = "dmsg"."{dmsg_dti}"
+                LEFT JOIN "{block_table}"
+                    AS "dest_block"
+                    ON "dest_block"."{block_id}" = "dest_tx"."{tx_block_id}"
+        ) AS "lc"
+        -- Skips ids nothing has been scraped for. SQLite also needs a WHERE
+        -- clause here to tell the upsert clause apart from a join constraint.
+        WHERE "lc"."dispatch_tx_id" IS NOT NULL
+            OR "lc"."num_payments" > 0
+            OR "lc"."delivery_tx_id" IS NOT NULL
+        ON CONFLICT ("{lc_mid}") DO UPDATE SET
+                {updated_columns}
+        "#,
+        lc_table = MessageLifecycle::Table.to_string(),
+        lc_time_updated = MessageLifecycle::TimeUpdated.to_string(),
+        lc_mid = MessageLifecycle::MsgId.to_string(),
+        lc_origin_id = MessageLifecycle::OriginDomainId.to_string(),
+        lc_dest_id = MessageLifecycle::DestinationDomainId.to_string(),
+        lc_nonce = MessageLifecycle::Nonce.to_string(),
+        lc_status = MessageLifecycle::Status.to_string(),
+        lc_dispatched_at = MessageLifecycle::DispatchedAt.to_string(),
+        lc_dispatch_tx_id = MessageLifecycle::DispatchTxId.to_string(),
+        lc_first_gas_paid_at = MessageLifecycle::FirstGasPaidAt.to_string(),
+        lc_num_payments = MessageLifecycle::NumPayments.to_string(),
+        lc_delivered_at = MessageLifecycle::DeliveredAt.to_string(),
+        lc_delivery_tx_id = MessageLifecycle::DeliveryTxId.to_string(),
+        lc_relayer = MessageLifecycle::Relayer.to_string(),
+        lc_latency = MessageLifecycle::DeliveryLatency.to_string(),
+        msg_table = Message::Table.to_string(),
+        msg_id = Message::Id.to_string(),
+        msg_mid = Message::MsgId.to_string(),
+        msg_origin = Message::Origin.to_string(),
+        msg_dest = Message::Destination.to_string(),
+        msg_nonce = Message::Nonce.to_string(),
+        msg_oti = Message::OriginTxId.to_string(),
+        tx_table = Transaction::Table.to_string(),
+        tx_id = Transaction::Id.to_string(),
+        tx_block_id = Transaction::BlockId.to_string(),
+        tx_sender = Transaction::Sender.to_string(),
+        block_table = Block::Table.to_string(),
+        block_id = Block::Id.to_string(),
+        block_timestamp = Block::Timestamp.to_string(),
+        gp_table = GasPayment::Table.to_string(),
+        gp_mid = GasPayment::MsgId.to_string(),
+        gp_tx_id = GasPayment::TxId.to_string(),
+        dmsg_table = DeliveredMessage::Table.to_string(),
+        dmsg_mid = DeliveredMessage::MsgId.to_string(),
+        dmsg_dti = DeliveredMessage::DestinationTxId.to_string(),
+    )
+}
@@ -4,14 +4,18 @@ use async_trait::async_trait;
 use derive_more::AsRef;
 use futures::future::try_join_all;
 use hyperlane_base::{
-    metrics::AgentMetrics, settings::IndexSettings, BaseAgent, ChainMetrics, ContractSyncMetrics,
-    CoreMetrics, HyperlaneAgentCore, MetricsUpdater,
+    metrics::AgentMetrics,
+    notifications::{Notifier, NotifyingStore},
+    settings::IndexSettings,
+    BaseAgent, ChainMetrics, ContractSyncMetrics, CoreMetrics, HyperlaneAgentCore, MetricsUpdater,
 };
-use hyperlane_core::HyperlaneDomain;
+use hyperlane_core::{HyperlaneDomain, HyperlaneDomainProtocol};
 use tokio::task::JoinHandle;
 use tracing::{info_span, instrument::Instrumented, trace, Instrument};
 
-use crate::{chain_scraper::HyperlaneSqlDb, db::ScraperDb, settings::ScraperSettings};
+use crate::{
+    api::ScraperApi, chain_scraper::HyperlaneSqlDb, db::ScraperDb, settings::ScraperSettings,
+};
 
 /// A message explorer scraper agent
 #[derive(Debug, AsRef)]
@@ -25,6 +29,8 @@ pub struct Scraper {
     core_metrics: Arc<CoreMetrics>,
     agent_metrics: AgentMetrics,
     chain_metrics: ChainMetrics,
+    db: ScraperDb,
+    notifier: Notifier,
 }
 
 #[derive(Debug)]
@@ -50,6 +56,10 @@ impl BaseAgent for Scraper {
     {
         let db = ScraperDb::connect(&settings.db).await?;
         let core = settings.build_hyperlane_core(metrics.clone());
+        let notifier = settings
+            .notifications
+            .build(Self::AGENT_NAME, &metrics)
+            .await?;
 
         let contract_sync_metrics = Arc::new(ContractSyncMetrics::new(&metrics));
         let mut scrapers: HashMap<u32, ChainScraper> = HashMap::new();
@@ -58,7 +68,7 @@ impl BaseAgent for Scraper {
             let chain_setup = settings.chain_setup(domain).expect("Missing chain config");
             let db = HyperlaneSqlDb::new(
                 db.clone(),
-                chain_setup.addresses.mailbox,
+                &chain_setup.addresses,
                 domain.clone(),
                 settings
                     .build_provider(domain, &metrics.clone())
@@ -87,6 +97,8 @@ impl BaseAgent for Scraper {
             core_metrics: metrics,
             agent_metrics,
             chain_metrics,
+            db,
+            notifier,
         })
     }
 
@@ -100,7 +112,12 @@ impl BaseAgent for Scraper {
             .settings
             .server(self.core_metrics.clone())
             .expect("Failed to create server");
-        let server_task = server.run(vec![]).instrument(info_span!("Relayer server"));
+        let routes = if self.settings.serve_api {
+            vec![("/api", ScraperApi::new(self.db.clone()).router())]
+        } else {
+            vec![]
+        };
+        let server_task = server.run(routes).instrument(info_span!("Relayer server"));
         tasks.push(server_task);
 
         for (domain, scraper) in self.scrapers.iter() {
@@ -133,7 +150,7 @@ impl Scraper {
         let index_settings = scraper.index_settings.clone();
         let domain = scraper.domain.clone();
 
-        let mut tasks = Vec::with_capacity(2);
+        let mut tasks = Vec::with_capacity(5);
         tasks.push(
             self.build_message_indexer(
                 domain.clone(),
@@ -156,14 +173,37 @@ impl Scraper {
         );
         tasks.push(
             self.build_interchain_gas_payment_indexer(
-                domain,
+                domain.clone(),
                 self.core_metrics.clone(),
                 self.contract_sync_metrics.clone(),
-                db,
+                db.clone(),
+                index_settings.clone(),
+            )
+            .await,
+        );
+        tasks.push(
+            self.build_merkle_tree_insertion_indexer(
+                domain.clone(),
+                self.core_metrics.clone(),
+                self.contract_sync_metrics.clone(),
+                db.clone(),
                 index_settings.clone(),
             )
             .await,
         );
+        // Validator announcements can only be indexed on EVM chains
+        if domain.domain_protocol() == HyperlaneDomainProtocol::Ethereum {
+            tasks.push(
+                self.build_validator_announce_indexer(
+                    domain,
+                    self.core_metrics.clone(),
+                    self.contract_sync_metrics.clone(),
+                    db,
+                    index_settings.clone(),
+                )
+                .await,
+            );
+        }
 
         tokio::spawn(async move {
             // If any of the tasks panic, we want to propagate it, so we unwrap
@@ -191,7 +231,7 @@ macro_rules! spawn_sync_task {
                     &domain,
                     &metrics.clone(),
                     &contract_sync_metrics.clone(),
-                    Arc::new(db.clone()),
+                    Arc::new(NotifyingStore::new(db.clone(), self.notifier.clone(), domain.clone())),
                 )
                 .await
                 .unwrap();
@@ -224,7 +264,11 @@ impl Scraper {
                 &domain,
                 &metrics.clone(),
                 &contract_sync_metrics.clone(),
-                Arc::new(db.clone()),
+                Arc::new(NotifyingStore::new(
+                    db.clone(),
+                    self.notifier.clone(),
+                    domain.clone(),
+                )),
             )
             .await
             .unwrap();
@@ -245,6 +289,45 @@ impl Scraper {
         )
     }
 
+    async fn build_merkle_tree_insertion_indexer(
+        &self,
+        domain: HyperlaneDomain,
+        metrics: Arc<CoreMetrics>,
+        contract_sync_metrics: Arc<ContractSyncMetrics>,
+        db: HyperlaneSqlDb,
+        index_settings: IndexSettings,
+    ) -> Instrumented<JoinHandle<()>> {
+        let sync = self
+            .as_ref()
+            .settings
+            .build_merkle_tree_hook_indexer(
+                &domain,
+                &metrics.clone(),
+                &contract_sync_metrics.clone(),
+                Arc::new(db.clone()),
+            )
+            .await
+            .unwrap();
+        let latest_leaf_index = self
+            .scrapers
+            .get(&domain.id())
+            .unwrap()
+            .db
+            .last_merkle_tree_leaf_index()
+            .await
+            .unwrap_or(None)
+            .unwrap_or(0);
+        let cursor = sync
+            .forward_message_sync_cursor(
+                index_settings.clone(),
+                latest_leaf_index.saturating_sub(1),
+            )
+            .await;
+        tokio::spawn(async move { sync.sync("merkle_tree_insertion", cursor).await }).instrument(
+            info_span!("ChainContractSync", chain=%domain.name(), event="merkle_tree_insertion"),
+        )
+    }
+
     spawn_sync_task!(
         build_delivery_indexer,
         rate_limited_cursor,
@@ -255,4 +338,9 @@ impl Scraper {
         rate_limited_cursor,
         "gas_payment"
     );
+    spawn_sync_task!(
+        build_validator_announce_indexer,
+        rate_limited_cursor,
+        "validator_announcement"
+    );
 }
@@ -0,0 +1,496 @@
+//! A read-only HTTP API over the scraped data, served on the agent's server
+//! under `/api` when `serveApi` is set.
+//!
+//! Routes
+//! - `GET /messages/:msg_id` - a message along with its delivery and gas
+//!   payments
+//! - `GET /messages?sender=&recipient=&origin=&origin_tx_hash=` - messages
+//!   matching all of the given filters
+//! - `GET /gas_payments?msg_id=&domain=` - gas payments matching all of the
+//!   given filters
+//! - `GET /domains/:domain/deliveries` - messages delivered on a domain
+//!
+//! Lists are paged by database id: they take `after` and `limit` query
+//! parameters, and return the `next` value of `after` if there may be more
+//! records.
+
+use std::str::FromStr;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use derive_new::new;
+use ethers::types::Bytes;
+use hyperlane_core::{H160, H256, H512};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::warn;
+
+use crate::conversions::h512_to_bytes;
+use crate::db::{DeliveryRecord, MessageFilter, MessageRecord, Page, PaymentRecord, ScraperDb};
+
+const DEFAULT_PAGE_LIMIT: u64 = 100;
+const MAX_PAGE_LIMIT: u64 = 1000;
+
+#[derive(new)]
+pub struct ScraperApi {
+    db: ScraperDb,
+}
+
+impl ScraperApi {
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/messages", get(messages))
+            .route("/messages/:msg_id", get(message))
+            .route("/gas_payments", get(gas_payments))
+            .route("/domains/:domain/deliveries", get(deliveries))
+            .with_state(self.db.clone())
+    }
+}
+
+#[derive(Debug)]
+enum ApiError {
+    BadRequest(String),
+    NotFound,
+    Internal(eyre::Report),
+}
+
+impl From<eyre::Report> for ApiError {
+    fn from(err: eyre::Report) -> Self {
+        Self::Internal(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            ApiError::BadRequest(error) => (StatusCode::BAD_REQUEST, error),
+            ApiError::NotFound => (StatusCode::NOT_FOUND, "Not found".to_owned()),
+            ApiError::Internal(err) => {
+                warn!(error = ?err, "Failed to serve scraper API request");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_owned(),
+                )
+            }
+        };
+        (status, Json(json!({ "error": error }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+fn parse_h256(name: &str, value: &str) -> Result<H256, ApiError> {
+    H256::from_str(value).map_err(|_| ApiError::BadRequest(format!("Invalid {name} `{value}`")))
+}
+
+/// Parses a 20 byte EVM address or a 32 byte address.
+fn parse_address(name: &str, value: &str) -> Result<H256, ApiError> {
+    if value.trim_start_matches("0x").len() == 40 {
+        H160::from_str(value)
+            .map(Into::into)
+            .map_err(|_| ApiError::BadRequest(format!("Invalid {name} `{value}`")))
+    } else {
+        parse_h256(name, value)
+    }
+}
+
+/// Parses a 32 byte transaction hash or a 64 byte transaction signature.
+fn parse_txn_hash(name: &str, value: &str) -> Result<H512, ApiError> {
+    if value.trim_start_matches("0x").len() == 64 {
+        parse_h256(name, value).map(Into::into)
+    } else {
+        H512::from_str(value).map_err(|_| ApiError::BadRequest(format!("Invalid {name} `{value}`")))
+    }
+}
+
+/// Transaction hashes are returned at their own length.
+fn txn_hash_bytes(hash: &H512) -> Bytes {
+    h512_to_bytes(hash).into()
+}
+
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    after: Option<i64>,
+    limit: Option<u64>,
+}
+
+impl From<PageQuery> for Page {
+    fn from(query: PageQuery) -> Self {
+        Page {
+            after: query.after,
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_LIMIT)
+                .clamp(1, MAX_PAGE_LIMIT),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PageResponse<T> {
+    items: Vec<T>,
+    /// The value of `after` to get the next page with, if there may be one
+    next: Option<i64>,
+}
+
+impl<T> PageResponse<T> {
+    fn new<R>(records: Vec<R>, page: Page, id: impl Fn(&R) -> i64, f: impl Fn(R) -> T) -> Self {
+        let next = match records.last() {
+            Some(last) if records.len() as u64 == page.limit => Some(id(last)),
+            _ => None,
+        };
+        Self {
+            items: records.into_iter().map(f).collect(),
+            next,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageResponse {
+    msg_id: H256,
+    origin: u32,
+    destination: u32,
+    nonce: u32,
+    sender: H256,
+    recipient: H256,
+    body: Bytes,
+    origin_mailbox: H256,
+    origin_tx_hash: Bytes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery: Option<DeliveryResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_payments: Option<Vec<PaymentResponse>>,
+}
+
+impl From<MessageRecord> for MessageResponse {
+    fn from(record: MessageRecord) -> Self {
+        Self {
+            msg_id: record.msg_id,
+            origin: record.origin,
+            destination: record.destination,
+            nonce: record.nonce,
+            sender: record.sender,
+            recipient: record.recipient,
+            body: record.body.into(),
+            origin_mailbox: record.origin_mailbox,
+            origin_tx_hash: txn_hash_bytes(&record.origin_tx_hash),
+            delivery: None,
+            gas_payments: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliveryResponse {
+    msg_id: H256,
+    domain: u32,
+    destination_mailbox: H256,
+    destination_tx_hash: Bytes,
+}
+
+impl From<DeliveryRecord> for DeliveryResponse {
+    fn from(record: DeliveryRecord) -> Self {
+        Self {
+            msg_id: record.msg_id,
+            domain: record.domain,
+            destination_mailbox: record.destination_mailbox,
+            destination_tx_hash: txn_hash_bytes(&record.destination_tx_hash),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PaymentResponse {
+    msg_id: H256,
+    domain: u32,
+    /// Amount of native tokens paid, as a decimal string
+    payment: String,
+    /// Amount of destination gas paid for, as a decimal string
+    gas_amount: String,
+    tx_hash: Bytes,
+    log_index: u64,
+}
+
+impl From<PaymentRecord> for PaymentResponse {
+    fn from(record: PaymentRecord) -> Self {
+        Self {
+            msg_id: record.msg_id,
+            domain: record.domain,
+            payment: record.payment.to_string(),
+            gas_amount: record.gas_amount.to_string(),
+            tx_hash: txn_hash_bytes(&record.tx_hash),
+            log_index: record.log_index,
+        }
+    }
+}
+
+async fn message(
+    State(db): State<ScraperDb>,
+    Path(msg_id): Path<String>,
+) -> ApiResult<MessageResponse> {
+    let msg_id = parse_h256("msg_id", &msg_id)?;
+    let record = db
+        .retrieve_message_by_id(&msg_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let delivery = db.retrieve_delivery_by_msg_id(&msg_id).await?;
+    let payments = db
+        .retrieve_payments(
+            None,
+            Some(&msg_id),
+            Page {
+                after: None,
+                limit: MAX_PAGE_LIMIT,
+            },
+        )
+        .await?;
+    Ok(Json(MessageResponse {
+        delivery: delivery.map(Into::into),
+        gas_payments: Some(payments.into_iter().map(Into::into).collect()),
+        ..record.into()
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesQuery {
+    origin: Option<u32>,
+    sender: Option<String>,
+    recipient: Option<String>,
+    origin_tx_hash: Option<String>,
+}
+
+async fn messages(
+    State(db): State<ScraperDb>,
+    Query(query): Query<MessagesQuery>,
+    Query(page): Query<PageQuery>,
+) -> ApiResult<PageResponse<MessageResponse>> {
+    let filter = MessageFilter {
+        origin: query.origin,
+        sender: query
+            .sender
+            .map(|s| parse_address("sender", &s))
+            .transpose()?,
+        recipient: query
+            .recipient
+            .map(|r| parse_address("recipient", &r))
+            .transpose()?,
+        origin_tx_hash: query
+            .origin_tx_hash
+            .map(|h| parse_txn_hash("origin_tx_hash", &h))
+            .transpose()?,
+    };
+    let page = page.into();
+    let records = db.retrieve_messages(&filter, page).await?;
+    Ok(Json(PageResponse::new(records, page, |r| r.id, Into::into)))
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentsQuery {
+    domain: Option<u32>,
+    msg_id: Option<String>,
+}
+
+async fn gas_payments(
+    State(db): State<ScraperDb>,
+    Query(query): Query<PaymentsQuery>,
+    Query(page): Query<PageQuery>,
+) -> ApiResult<PageResponse<PaymentResponse>> {
+    let msg_id = query
+        .msg_id
+        .map(|id| parse_h256("msg_id", &id))
+        .transpose()?;
+    let page = page.into();
+    let records = db
+        .retrieve_payments(query.domain, msg_id.as_ref(), page)
+        .await?;
+    Ok(Json(PageResponse::new(records, page, |r| r.id, Into::into)))
+}
+
+async fn deliveries(
+    State(db): State<ScraperDb>,
+    Path(domain): Path<u32>,
+    Query(page): Query<PageQuery>,
+) -> ApiResult<PageResponse<DeliveryResponse>> {
+    let page = page.into();
+    let records = db.retrieve_deliveries(domain, page).await?;
+    Ok(Json(PageResponse::new(records, page, |r| r.id, Into::into)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{SocketAddr, TcpListener};
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::db::test_utils::seeded_db;
+
+    async fn serve(db: ScraperDb) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().nest("/api", ScraperApi::new(db).router());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        addr
+    }
+
+    async fn fetch(addr: SocketAddr, path: &str) -> (StatusCode, Value) {
+        let response = reqwest::get(format!("http://{addr}/api{path}"))
+            .await
+            .unwrap();
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+        (status, response.json().await.unwrap())
+    }
+
+    fn hex_str(byte: u8, len: usize) -> String {
+        format!("0x{}", format!("{byte:02x}").repeat(len))
+    }
+
+    #[tokio::test]
+    async fn test_message_by_id() {
+        let addr = serve(seeded_db().await).await;
+
+        let (status, body) = fetch(addr, &format!("/messages/{}", hex_str(1, 32))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["nonce"], 0);
+        assert_eq!(
+            body["sender"],
+            format!("0x{}{}", "00".repeat(12), "aa".repeat(20))
+        );
+        assert_eq!(body["body"], "0x0102");
+        assert_eq!(body["originTxHash"], hex_str(0xa1, 32));
+        assert_eq!(body["delivery"]["destinationTxHash"], hex_str(0xa3, 32));
+        let payments = body["gasPayments"].as_array().unwrap();
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0]["payment"], "100");
+        assert_eq!(payments[0]["txHash"], hex_str(0xa1, 32));
+
+        let (status, body) = fetch(addr, &format!("/messages/{}", hex_str(3, 32))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("delivery").is_none());
+
+        let (status, _) = fetch(addr, &format!("/messages/{}", hex_str(9, 32))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = fetch(addr, "/messages/0x1234").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_list_messages() {
+        let addr = serve(seeded_db().await).await;
+
+        let sender = hex_str(0xaa, 20);
+        let (status, body) = fetch(addr, &format!("/messages?sender={sender}&limit=1")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["msgId"], hex_str(1, 32));
+        assert_eq!(body["next"], 1);
+
+        let (_, body) = fetch(addr, &format!("/messages?sender={sender}&limit=1&after=1")).await;
+        assert_eq!(body["items"][0]["msgId"], hex_str(2, 32));
+        let (_, body) = fetch(addr, &format!("/messages?sender={sender}&limit=1&after=2")).await;
+        assert_eq!(body["items"], json!([]));
+        assert_eq!(body["next"], Value::Null);
+
+        let (_, body) = fetch(
+            addr,
+            &format!("/messages?origin_tx_hash={}", hex_str(0xa2, 32)),
+        )
+        .await;
+        let items = body["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["msgId"], hex_str(3, 32));
+
+        let (_, body) = fetch(
+            addr,
+            &format!("/messages?origin_tx_hash={}", hex_str(0xff, 32)),
+        )
+        .await;
+        assert_eq!(body["items"], json!([]));
+
+        let (status, _) = fetch(addr, "/messages?recipient=0xzz").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_list_gas_payments() {
+        let addr = serve(seeded_db().await).await;
+
+        let (status, body) = fetch(addr, "/gas_payments").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["items"].as_array().unwrap().len(), 3);
+        assert_eq!(body["next"], Value::Null);
+
+        let (_, body) = fetch(addr, &format!("/gas_payments?msg_id={}", hex_str(1, 32))).await;
+        let items = body["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["msgId"], hex_str(1, 32));
+        assert_eq!(items[0]["domain"], 13371);
+        assert_eq!(items[0]["payment"], "100");
+        assert_eq!(items[0]["gasAmount"], "10");
+        assert_eq!(items[0]["txHash"], hex_str(0xa1, 32));
+        assert_eq!(items[0]["logIndex"], 1);
+
+        let (_, body) = fetch(addr, "/gas_payments?domain=13372").await;
+        let items = body["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["msgId"], hex_str(4, 32));
+        assert_eq!(items[0]["payment"], "300");
+        assert_eq!(items[0]["txHash"], hex_str(0xa3, 32));
+
+        // Filters must all match
+        let (_, body) = fetch(
+            addr,
+            &format!("/gas_payments?domain=13371&msg_id={}", hex_str(4, 32)),
+        )
+        .await;
+        assert_eq!(body["items"], json!([]));
+
+        // A limit of 0 is raised to 1
+        let (_, body) = fetch(addr, "/gas_payments?domain=13371&limit=0").await;
+        let items = body["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["msgId"], hex_str(1, 32));
+        assert_eq!(body["next"], 1);
+        let (_, body) = fetch(addr, "/gas_payments?domain=13371&limit=1&after=1").await;
+        assert_eq!(body["items"][0]["msgId"], hex_str(2, 32));
+
+        let (status, _) = fetch(addr, "/gas_payments?msg_id=0x1234").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_page_limit_is_clamped() {
+        let page = |limit| Page::from(PageQuery { after: None, limit }).limit;
+        assert_eq!(page(None), DEFAULT_PAGE_LIMIT);
+        assert_eq!(page(Some(0)), 1);
+        assert_eq!(page(Some(MAX_PAGE_LIMIT + 1)), MAX_PAGE_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn test_list_deliveries() {
+        let addr = serve(seeded_db().await).await;
+
+        let (status, body) = fetch(addr, "/domains/13372/deliveries").await;
+        assert_eq!(status, StatusCode::OK);
+        let items = body["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["msgId"], hex_str(1, 32));
+        assert_eq!(items[0]["destinationTxHash"], hex_str(0xa3, 32));
+
+        let (_, body) = fetch(addr, "/domains/13371/deliveries").await;
+        assert_eq!(body["items"], json!([]));
+    }
+}
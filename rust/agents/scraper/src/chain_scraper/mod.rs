@@ -4,19 +4,20 @@
 use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
-use eyre::Result;
-use hyperlane_base::settings::IndexSettings;
+use eyre::{eyre, Result};
+use hyperlane_base::settings::{CoreContractAddresses, IndexSettings};
 use hyperlane_core::{
-    unwrap_or_none_result, BlockInfo, Delivery, HyperlaneDomain, HyperlaneLogStore,
-    HyperlaneMessage, HyperlaneProvider, HyperlaneSequenceAwareIndexerStoreReader,
-    HyperlaneWatermarkedLogStore, InterchainGasPayment, LogMeta, H256,
+    unwrap_or_none_result, BlockInfo, Delivery, HyperlaneDomain, HyperlaneDomainProtocol,
+    HyperlaneLogStore, HyperlaneMessage, HyperlaneProvider,
+    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, IndexMode,
+    InterchainGasPayment, LogMeta, MerkleTreeInsertion, ValidatorAnnouncement, H256, H512,
 };
 use itertools::Itertools;
 use tracing::trace;
 
 use crate::db::{
-    BasicBlock, BlockCursor, ScraperDb, StorableDelivery, StorableMessage, StorablePayment,
-    StorableTxn,
+    BasicBlock, BlockCursor, ScraperDb, StorableDelivery, StorableMerkleTreeInsertion,
+    StorableMessage, StorablePayment, StorableTxn, StorableValidatorAnnouncement,
 };
 
 /// Maximum number of records to query at a time. This came about because when a
@@ -25,36 +26,84 @@ use crate::db::{
 /// actually save it to the database.
 const CHUNK_SIZE: usize = 50;
 
+/// Kind of the block cursor used for validator announcements, which are
+/// indexed separately so they can be backfilled independently.
+const VALIDATOR_ANNOUNCEMENT_CURSOR: &str = "validator_announcement";
+
+/// Kinds of the cursors used for deliveries and gas payments when a chain is
+/// indexed by sequence, as each has its own sequence.
+const DELIVERY_CURSOR: &str = "message_delivery";
+const GAS_PAYMENT_CURSOR: &str = "gas_payment";
+
 /// A chain scraper is comprised of all the information and contract/provider
 /// connections needed to scrape the contracts on a single blockchain.
 #[derive(Clone, Debug)]
 pub struct HyperlaneSqlDb {
     mailbox_address: H256,
+    merkle_tree_hook_address: H256,
+    validator_announce_address: H256,
     domain: HyperlaneDomain,
     db: ScraperDb,
     provider: Arc<dyn HyperlaneProvider>,
     cursor: Arc<BlockCursor>,
+    delivery_cursor: Arc<BlockCursor>,
+    gas_payment_cursor: Arc<BlockCursor>,
+    validator_announcement_cursor: Arc<BlockCursor>,
 }
 
 #[allow(unused)]
 impl HyperlaneSqlDb {
     pub async fn new(
         db: ScraperDb,
-        mailbox_address: H256,
+        addresses: &CoreContractAddresses,
         domain: HyperlaneDomain,
         provider: Arc<dyn HyperlaneProvider>,
         index_settings: &IndexSettings,
     ) -> Result<Self> {
         let cursor = Arc::new(
-            db.block_cursor(domain.id(), index_settings.from as u64)
+            db.block_cursor(domain.id(), None, index_settings.from as u64)
                 .await?,
         );
+        let (delivery_cursor, gas_payment_cursor) = match index_settings.mode {
+            IndexMode::Block => (cursor.clone(), cursor.clone()),
+            IndexMode::Sequence => (
+                Arc::new(
+                    db.block_cursor(
+                        domain.id(),
+                        Some(DELIVERY_CURSOR),
+                        index_settings.from as u64,
+                    )
+                    .await?,
+                ),
+                Arc::new(
+                    db.block_cursor(
+                        domain.id(),
+                        Some(GAS_PAYMENT_CURSOR),
+                        index_settings.from as u64,
+                    )
+                    .await?,
+                ),
+            ),
+        };
+        let validator_announcement_cursor = Arc::new(
+            db.block_cursor(
+                domain.id(),
+                Some(VALIDATOR_ANNOUNCEMENT_CURSOR),
+                index_settings.from as u64,
+            )
+            .await?,
+        );
         Ok(Self {
             db,
             domain,
             provider,
-            mailbox_address,
+            mailbox_address: addresses.mailbox,
+            merkle_tree_hook_address: addresses.merkle_tree_hook,
+            validator_announce_address: addresses.validator_announce,
             cursor,
+            delivery_cursor,
+            gas_payment_cursor,
+            validator_announcement_cursor,
         })
     }
 
@@ -68,6 +117,12 @@ impl HyperlaneSqlDb {
             .await
     }
 
+    pub async fn last_merkle_tree_leaf_index(&self) -> Result<Option<u32>> {
+        self.db
+            .last_merkle_tree_leaf_index(self.domain.id(), &self.merkle_tree_hook_address)
+            .await
+    }
+
     /// Takes a list of txn and block hashes and ensure they are all in the
     /// database. If any are not it will fetch the data and insert them.
     ///
@@ -76,13 +131,14 @@ impl HyperlaneSqlDb {
         &self,
         log_meta: impl Iterator<Item = &LogMeta>,
     ) -> Result<impl Iterator<Item = TxnWithId>> {
-        let block_hash_by_txn_hash: HashMap<H256, H256> = log_meta
+        let block_by_txn_hash: HashMap<H512, BlockHashAndNumber> = log_meta
             .map(|meta| {
                 (
-                    meta.transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                    meta.block_hash,
+                    meta.transaction_id,
+                    BlockHashAndNumber {
+                        hash: meta.block_hash,
+                        number: meta.block_number,
+                    },
                 )
             })
             .collect();
@@ -90,23 +146,21 @@ impl HyperlaneSqlDb {
         // all blocks we care about
         // hash of block maps to the block id and timestamp
         let blocks: HashMap<_, _> = self
-            .ensure_blocks(block_hash_by_txn_hash.values().copied())
+            .ensure_blocks(block_by_txn_hash.values().copied())
             .await?
             .map(|block| (block.hash, block))
             .collect();
         trace!(?blocks, "Ensured blocks");
 
         // all txns we care about
-        let txns_with_ids =
-            self.ensure_txns(block_hash_by_txn_hash.into_iter().map(
-                move |(txn_hash, block_hash)| {
-                    let block_info = *blocks.get(&block_hash).as_ref().unwrap();
-                    TxnWithBlockId {
-                        txn_hash,
-                        block_id: block_info.id,
-                    }
-                },
-            ))
+        let txns_with_ids = self
+            .ensure_txns(block_by_txn_hash.into_iter().map(move |(txn_hash, block)| {
+                let block_info = *blocks.get(&block.hash).as_ref().unwrap();
+                TxnWithBlockId {
+                    txn_hash,
+                    block_id: block_info.id,
+                }
+            }))
             .await?;
 
         Ok(txns_with_ids.map(move |TxnWithId { hash, id: txn_id }| TxnWithId { hash, id: txn_id }))
@@ -123,7 +177,7 @@ impl HyperlaneSqlDb {
         txns: impl Iterator<Item = TxnWithBlockId>,
     ) -> Result<impl Iterator<Item = TxnWithId>> {
         // mapping of txn hash to (txn_id, block_id).
-        let mut txns: HashMap<H256, (Option<i64>, i64)> = txns
+        let mut txns: HashMap<H512, (Option<i64>, i64)> = txns
             .map(|TxnWithBlockId { txn_hash, block_id }| (txn_hash, (None, block_id)))
             .collect();
 
@@ -147,9 +201,9 @@ impl HyperlaneSqlDb {
         let mut txns_to_fetch = txns.iter_mut().filter(|(_, id)| id.0.is_none());
 
         let mut txns_to_insert: Vec<StorableTxn> = Vec::with_capacity(CHUNK_SIZE);
-        let mut hashes_to_insert: Vec<&H256> = Vec::with_capacity(CHUNK_SIZE);
+        let mut hashes_to_insert: Vec<&H512> = Vec::with_capacity(CHUNK_SIZE);
 
-        for mut chunk in as_chunks::<(&H256, &mut (Option<i64>, i64))>(txns_to_fetch, CHUNK_SIZE) {
+        for mut chunk in as_chunks::<(&H512, &mut (Option<i64>, i64))>(txns_to_fetch, CHUNK_SIZE) {
             for (hash, (_, block_id)) in chunk.iter() {
                 let info = self.provider.get_txn_by_hash(hash).await?;
                 hashes_to_insert.push(*hash);
@@ -175,21 +229,22 @@ impl HyperlaneSqlDb {
             }))
     }
 
-    /// Takes a list of block hashes for each block
+    /// Takes a list of block hashes and heights for each block
     /// if it is in the database already:
     ///     Fetches its associated database id
     /// if it is not in the database already:
-    ///     Looks up its data with ethers and then returns the database id after
-    ///     inserting it into the database.
+    ///     Looks up its data with `fetch_block` and then returns the database id
+    ///     after inserting it into the database.
     async fn ensure_blocks(
         &self,
-        block_hashes: impl Iterator<Item = H256>,
+        blocks: impl Iterator<Item = BlockHashAndNumber>,
     ) -> Result<impl Iterator<Item = BasicBlock>> {
-        // mapping of block hash to the database id and block timestamp. Optionals are
-        // in place because we will find the timestamp first if the block was not
-        // already in the db.
-        let mut blocks: HashMap<H256, Option<BasicBlock>> =
-            block_hashes.map(|b| (b, None)).collect();
+        // mapping of block hash to its height and the database id. Optionals are
+        // in place because we will find the database id later if the block was
+        // not already in the db.
+        let mut blocks: HashMap<H256, (u64, Option<BasicBlock>)> = blocks
+            .map(|block| (block.hash, (block.number, None)))
+            .collect();
 
         let db_blocks: Vec<BasicBlock> = if !blocks.is_empty() {
             // check database to see which blocks we already know and fetch their IDs
@@ -202,6 +257,7 @@ impl HyperlaneSqlDb {
             let _ = blocks
                 .get_mut(&block.hash)
                 .expect("We found a block that we did not request")
+                .1
                 .insert(block);
         }
 
@@ -212,15 +268,21 @@ impl HyperlaneSqlDb {
         // inserted into db.
         let blocks_to_fetch = blocks
             .iter_mut()
-            .filter(|(_, block_info)| block_info.is_none());
+            .filter(|(_, (_, block_info))| block_info.is_none());
 
         let mut blocks_to_insert: Vec<(&mut BasicBlock, Option<BlockInfo>)> =
             Vec::with_capacity(CHUNK_SIZE);
         let mut hashes_to_insert: Vec<&H256> = Vec::with_capacity(CHUNK_SIZE);
         for chunk in as_chunks(blocks_to_fetch, CHUNK_SIZE) {
             debug_assert!(!chunk.is_empty());
-            for (hash, block_info) in chunk {
-                let info = self.provider.get_block_by_hash(hash).await?;
+            for (hash, (number, block_info)) in chunk {
+                let info = fetch_block(
+                    self.provider.as_ref(),
+                    self.domain().domain_protocol(),
+                    hash,
+                    *number,
+                )
+                .await?;
                 let basic_info_ref = block_info.insert(BasicBlock {
                     id: -1,
                     hash: *hash,
@@ -254,7 +316,7 @@ impl HyperlaneSqlDb {
         // ensure we have updated all the block ids and that we have info for all of
         // them.
         #[cfg(debug_assertions)]
-        for (hash, block) in blocks.iter() {
+        for (hash, (_, block)) in blocks.iter() {
             let block = block.as_ref().unwrap();
             assert_eq!(hash, &block.hash);
             assert!(block.id > 0);
@@ -262,7 +324,32 @@ impl HyperlaneSqlDb {
 
         Ok(blocks
             .into_iter()
-            .map(|(hash, block_info)| block_info.unwrap()))
+            .map(|(hash, (_, block_info))| block_info.unwrap()))
+    }
+}
+
+/// Looks up the block with `hash` at height `number`. Blocks are looked up by
+/// hash so a block which has since been reorged out is still found, except on
+/// chains which can only look them up by height, where it's checked that the
+/// block at the height is the same one.
+async fn fetch_block(
+    provider: &dyn HyperlaneProvider,
+    protocol: HyperlaneDomainProtocol,
+    hash: &H256,
+    number: u64,
+) -> Result<BlockInfo> {
+    match protocol {
+        HyperlaneDomainProtocol::Sealevel => {
+            let info = provider.get_block_by_height(number).await?;
+            if info.hash != *hash {
+                return Err(eyre!(
+                    "Block at height {number} has hash {:?} but logs were indexed in block {hash:?}",
+                    info.hash
+                ));
+            }
+            Ok(info)
+        }
+        _ => Ok(provider.get_block_by_hash(hash).await?),
     }
 }
 
@@ -273,19 +360,13 @@ impl HyperlaneLogStore<HyperlaneMessage> for HyperlaneSqlDb {
         if messages.is_empty() {
             return Ok(0);
         }
-        let txns: HashMap<H256, TxnWithId> = self
+        let txns: HashMap<H512, TxnWithId> = self
             .ensure_blocks_and_txns(messages.iter().map(|r| &r.1))
             .await?
             .map(|t| (t.hash, t))
             .collect();
         let storable = messages.iter().map(|m| {
-            let txn = txns
-                .get(
-                    &m.1.transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                )
-                .unwrap();
+            let txn = txns.get(&m.1.transaction_id).unwrap();
             StorableMessage {
                 msg: m.0.clone(),
                 meta: &m.1,
@@ -296,6 +377,9 @@ impl HyperlaneLogStore<HyperlaneMessage> for HyperlaneSqlDb {
             .db
             .store_dispatched_messages(self.domain().id(), &self.mailbox_address, storable)
             .await?;
+        self.db
+            .update_message_lifecycles(messages.iter().map(|m| m.0.id()))
+            .await?;
         Ok(stored as u32)
     }
 }
@@ -306,21 +390,13 @@ impl HyperlaneLogStore<Delivery> for HyperlaneSqlDb {
         if deliveries.is_empty() {
             return Ok(0);
         }
-        let txns: HashMap<Delivery, TxnWithId> = self
+        let txns: HashMap<H512, TxnWithId> = self
             .ensure_blocks_and_txns(deliveries.iter().map(|r| &r.1))
             .await?
             .map(|t| (t.hash, t))
             .collect();
         let storable = deliveries.iter().map(|(message_id, meta)| {
-            let txn_id = txns
-                .get(
-                    &meta
-                        .transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                )
-                .unwrap()
-                .id;
+            let txn_id = txns.get(&meta.transaction_id).unwrap().id;
             StorableDelivery {
                 message_id: *message_id,
                 meta,
@@ -332,6 +408,9 @@ impl HyperlaneLogStore<Delivery> for HyperlaneSqlDb {
             .db
             .store_deliveries(self.domain().id(), self.mailbox_address, storable)
             .await?;
+        self.db
+            .update_message_lifecycles(deliveries.iter().map(|(message_id, _)| *message_id))
+            .await?;
         Ok(stored as u32)
     }
 }
@@ -342,21 +421,13 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneSqlDb {
         if payments.is_empty() {
             return Ok(0);
         }
-        let txns: HashMap<H256, TxnWithId> = self
+        let txns: HashMap<H512, TxnWithId> = self
             .ensure_blocks_and_txns(payments.iter().map(|r| &r.1))
             .await?
             .map(|t| (t.hash, t))
             .collect();
         let storable = payments.iter().map(|(payment, meta)| {
-            let txn_id = txns
-                .get(
-                    &meta
-                        .transaction_id
-                        .try_into()
-                        .expect("256-bit transaction ids are the maximum supported at this time"),
-                )
-                .unwrap()
-                .id;
+            let txn_id = txns.get(&meta.transaction_id).unwrap().id;
             StorablePayment {
                 payment,
                 meta,
@@ -365,6 +436,73 @@ impl HyperlaneLogStore<InterchainGasPayment> for HyperlaneSqlDb {
         });
 
         let stored = self.db.store_payments(self.domain().id(), storable).await?;
+        self.db
+            .update_message_lifecycles(payments.iter().map(|(payment, _)| payment.message_id))
+            .await?;
+        Ok(stored as u32)
+    }
+}
+
+#[async_trait]
+impl HyperlaneLogStore<MerkleTreeInsertion> for HyperlaneSqlDb {
+    async fn store_logs(&self, insertions: &[(MerkleTreeInsertion, LogMeta)]) -> Result<u32> {
+        if insertions.is_empty() {
+            return Ok(0);
+        }
+        let txns: HashMap<H512, TxnWithId> = self
+            .ensure_blocks_and_txns(insertions.iter().map(|r| &r.1))
+            .await?
+            .map(|t| (t.hash, t))
+            .collect();
+        let storable = insertions.iter().map(|(insertion, meta)| {
+            let txn_id = txns.get(&meta.transaction_id).unwrap().id;
+            StorableMerkleTreeInsertion {
+                insertion,
+                meta,
+                txn_id,
+            }
+        });
+
+        let stored = self
+            .db
+            .store_merkle_tree_insertions(
+                self.domain().id(),
+                &self.merkle_tree_hook_address,
+                storable,
+            )
+            .await?;
+        Ok(stored as u32)
+    }
+}
+
+#[async_trait]
+impl HyperlaneLogStore<ValidatorAnnouncement> for HyperlaneSqlDb {
+    async fn store_logs(&self, announcements: &[(ValidatorAnnouncement, LogMeta)]) -> Result<u32> {
+        if announcements.is_empty() {
+            return Ok(0);
+        }
+        let txns: HashMap<H512, TxnWithId> = self
+            .ensure_blocks_and_txns(announcements.iter().map(|r| &r.1))
+            .await?
+            .map(|t| (t.hash, t))
+            .collect();
+        let storable = announcements.iter().map(|(announcement, meta)| {
+            let txn_id = txns.get(&meta.transaction_id).unwrap().id;
+            StorableValidatorAnnouncement {
+                announcement,
+                meta,
+                txn_id,
+            }
+        });
+
+        let stored = self
+            .db
+            .store_validator_announcements(
+                self.domain().id(),
+                &self.validator_announce_address,
+                storable,
+            )
+            .await?;
         Ok(stored as u32)
     }
 }
@@ -393,33 +531,82 @@ impl HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> for HyperlaneSql
 }
 
 #[async_trait]
-impl<T> HyperlaneWatermarkedLogStore<T> for HyperlaneSqlDb
-where
-    HyperlaneSqlDb: HyperlaneLogStore<T>,
-{
-    /// Gets the block number high watermark
-    async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
-        Ok(Some(self.cursor.height().await.try_into()?))
+impl HyperlaneSequenceAwareIndexerStoreReader<MerkleTreeInsertion> for HyperlaneSqlDb {
+    /// Gets a merkle tree insertion by its leaf index.
+    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<MerkleTreeInsertion>> {
+        let insertion = self
+            .db
+            .retrieve_merkle_tree_insertion_by_leaf_index(
+                self.domain().id(),
+                &self.merkle_tree_hook_address,
+                sequence,
+            )
+            .await?;
+        Ok(insertion.map(|(insertion, _)| insertion))
     }
-    /// Stores the block number high watermark
-    async fn store_high_watermark(&self, block_number: u32) -> Result<()> {
-        self.cursor.update(block_number.into()).await;
-        Ok(())
+
+    /// Gets the block number at which the log occurred.
+    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>> {
+        let (_, tx_id) = unwrap_or_none_result!(
+            self.db
+                .retrieve_merkle_tree_insertion_by_leaf_index(
+                    self.domain().id(),
+                    &self.merkle_tree_hook_address,
+                    sequence,
+                )
+                .await?
+        );
+        let block_id = unwrap_or_none_result!(self.db.retrieve_block_id(tx_id).await?);
+        Ok(self.db.retrieve_block_number(block_id).await?)
     }
 }
 
+/// Implement `HyperlaneWatermarkedLogStore` using the given block cursor of
+/// the `HyperlaneSqlDb`.
+macro_rules! impl_watermarked_log_store {
+    ($($ty:ty => $cursor:ident),+ $(,)?) => {
+        $(
+            #[async_trait]
+            impl HyperlaneWatermarkedLogStore<$ty> for HyperlaneSqlDb {
+                /// Gets the block number high watermark
+                async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
+                    Ok(Some(self.$cursor.height().await.try_into()?))
+                }
+                /// Stores the block number high watermark
+                async fn store_high_watermark(&self, block_number: u32) -> Result<()> {
+                    self.$cursor.update(block_number.into()).await;
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+impl_watermarked_log_store!(
+    HyperlaneMessage => cursor,
+    Delivery => delivery_cursor,
+    InterchainGasPayment => gas_payment_cursor,
+    ValidatorAnnouncement => validator_announcement_cursor,
+);
+
 #[derive(Debug, Clone)]
 struct TxnWithId {
-    hash: H256,
+    hash: H512,
     id: i64,
 }
 
 #[derive(Debug, Clone)]
 struct TxnWithBlockId {
-    txn_hash: H256,
+    txn_hash: H512,
     block_id: i64,
 }
 
+#[derive(Debug, Clone, Copy)]
+struct BlockHashAndNumber {
+    hash: H256,
+    number: u64,
+}
+
 fn as_chunks<T>(iter: impl Iterator<Item = T>, chunk_size: usize) -> impl Iterator<Item = Vec<T>> {
     // the itertools chunks function uses refcell which cannot be used across an
     // await so this stabilizes the result by putting it into a vec of vecs and
@@ -430,3 +617,318 @@ fn as_chunks<T>(iter: impl Iterator<Item = T>, chunk_size: usize) -> impl Iterat
         .collect_vec()
         .into_iter()
 }
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{
+        ChainInfo, ChainResult, HyperlaneChain, KnownHyperlaneDomain, TxnInfo, TxnReceiptInfo, U256,
+    };
+
+    use super::*;
+    use crate::db::Page;
+
+    /// Serves made up blocks and transactions for any hash.
+    #[derive(Debug, Clone)]
+    struct TestProvider(HyperlaneDomain);
+
+    impl HyperlaneChain for TestProvider {
+        fn domain(&self) -> &HyperlaneDomain {
+            &self.0
+        }
+
+        fn provider(&self) -> Box<dyn HyperlaneProvider> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneProvider for TestProvider {
+        async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo> {
+            Ok(BlockInfo {
+                hash: *hash,
+                timestamp: 1_700_000_000,
+                number: hash.to_low_u64_be(),
+            })
+        }
+
+        async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+            self.get_block_by_hash(&H256::from_low_u64_be(height)).await
+        }
+
+        async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+            Ok(TxnInfo {
+                hash: *hash,
+                gas_limit: 100_000.into(),
+                max_priority_fee_per_gas: None,
+                max_fee_per_gas: None,
+                gas_price: Some(1.into()),
+                nonce: hash.to_low_u64_be(),
+                sender: H256::from_low_u64_be(0xee),
+                recipient: Some(H256::from_low_u64_be(0x11)),
+                receipt: Some(TxnReceiptInfo {
+                    gas_used: 50_000.into(),
+                    cumulative_gas_used: 50_000.into(),
+                    effective_gas_price: Some(1.into()),
+                }),
+            })
+        }
+
+        async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
+            Ok(true)
+        }
+
+        async fn get_balance(&self, _address: String) -> ChainResult<U256> {
+            Ok(U256::zero())
+        }
+
+        async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
+            Ok(None)
+        }
+
+        async fn get_chain_id(&self) -> ChainResult<Option<U256>> {
+            Ok(None)
+        }
+    }
+
+    fn meta(block: u64, tx: u64, log_index: u64) -> LogMeta {
+        LogMeta {
+            address: H256::from_low_u64_be(0x11),
+            block_number: block,
+            block_hash: H256::from_low_u64_be(block),
+            transaction_id: H256::from_low_u64_be(tx).into(),
+            transaction_index: 0,
+            log_index: log_index.into(),
+        }
+    }
+
+    fn message(nonce: u32) -> HyperlaneMessage {
+        HyperlaneMessage {
+            version: 3,
+            nonce,
+            origin: KnownHyperlaneDomain::Test1 as u32,
+            sender: H256::from_low_u64_be(0xaa),
+            destination: KnownHyperlaneDomain::Test2 as u32,
+            recipient: H256::from_low_u64_be(0xbb),
+            body: vec![nonce as u8; 4],
+        }
+    }
+
+    /// A `HyperlaneSqlDb` for test1 backed by a fresh SQLite file.
+    async fn sqlite_db(dir: &tempfile::TempDir) -> HyperlaneSqlDb {
+        let path = dir.path().join("scraper.db");
+        let db = ScraperDb::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Test1);
+        let addresses = CoreContractAddresses {
+            mailbox: H256::from_low_u64_be(0x11),
+            interchain_gas_paymaster: H256::from_low_u64_be(0x12),
+            validator_announce: H256::from_low_u64_be(0x13),
+            merkle_tree_hook: H256::from_low_u64_be(0x14),
+        };
+        HyperlaneSqlDb::new(
+            db,
+            &addresses,
+            domain.clone(),
+            Arc::new(TestProvider(domain)),
+            &IndexSettings::default(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_block() {
+        let provider = TestProvider(HyperlaneDomain::Known(KnownHyperlaneDomain::Test1));
+        // A block which is no longer the one at its height after a reorg
+        let reorged = H256::repeat_byte(0xbb);
+        let info = fetch_block(&provider, HyperlaneDomainProtocol::Ethereum, &reorged, 7)
+            .await
+            .unwrap();
+        assert_eq!(info.hash, reorged);
+        assert!(
+            fetch_block(&provider, HyperlaneDomainProtocol::Sealevel, &reorged, 7)
+                .await
+                .is_err()
+        );
+
+        let canonical = H256::from_low_u64_be(7);
+        let info = fetch_block(&provider, HyperlaneDomainProtocol::Sealevel, &canonical, 7)
+            .await
+            .unwrap();
+        assert_eq!(info.hash, canonical);
+    }
+
+    #[tokio::test]
+    async fn test_store_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sqlite_db(&dir).await;
+
+        let messages = [(message(0), meta(1, 1, 0)), (message(1), meta(1, 1, 1))];
+        assert_eq!(db.store_logs(&messages).await.unwrap(), 2);
+        // Storing logs again updates them in place
+        assert_eq!(db.store_logs(&messages).await.unwrap(), 0);
+        assert_eq!(
+            db.store_logs(&[(message(2), meta(2, 2, 0))]).await.unwrap(),
+            1
+        );
+
+        assert_eq!(db.last_message_nonce().await.unwrap(), Some(2));
+        let reader: &dyn HyperlaneSequenceAwareIndexerStoreReader<HyperlaneMessage> = &db;
+        assert_eq!(
+            reader.retrieve_by_sequence(1).await.unwrap(),
+            Some(message(1))
+        );
+        assert_eq!(
+            reader
+                .retrieve_log_block_number_by_sequence(2)
+                .await
+                .unwrap(),
+            Some(2)
+        );
+        assert_eq!(reader.retrieve_by_sequence(3).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_store_deliveries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sqlite_db(&dir).await;
+
+        let delivered = message(0).id();
+        let deliveries = [(delivered, meta(3, 3, 0))];
+        assert_eq!(db.store_logs(&deliveries).await.unwrap(), 1);
+        assert_eq!(db.store_logs(&deliveries).await.unwrap(), 0);
+
+        let delivery = db
+            .db
+            .retrieve_delivery_by_msg_id(&delivered)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.domain, KnownHyperlaneDomain::Test1 as u32);
+        assert_eq!(
+            delivery.destination_tx_hash,
+            H256::from_low_u64_be(3).into()
+        );
+        assert!(db
+            .db
+            .retrieve_delivery_by_msg_id(&message(1).id())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_store_payments() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sqlite_db(&dir).await;
+
+        let payment = |nonce, amount: u64| InterchainGasPayment {
+            message_id: message(nonce).id(),
+            destination: KnownHyperlaneDomain::Test2 as u32,
+            payment: amount.into(),
+            gas_amount: (amount * 10).into(),
+        };
+        // Two payments for the same message in one transaction
+        let payments = [
+            (payment(0, 100), meta(4, 4, 0)),
+            (payment(0, 200), meta(4, 4, 1)),
+            (payment(1, 300), meta(5, 5, 0)),
+        ];
+        assert_eq!(db.store_logs(&payments).await.unwrap(), 3);
+        assert_eq!(db.store_logs(&payments).await.unwrap(), 0);
+
+        let stored = db
+            .db
+            .retrieve_payments(
+                None,
+                Some(&message(0).id()),
+                Page {
+                    after: None,
+                    limit: 10,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].payment, 100.into());
+        assert_eq!(stored[0].gas_amount, 1000.into());
+        assert_eq!(stored[1].payment, 200.into());
+        assert_eq!(stored[1].log_index, 1);
+        assert_eq!(stored[1].tx_hash, H256::from_low_u64_be(4).into());
+    }
+
+    #[tokio::test]
+    async fn test_message_lifecycle_progression() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sqlite_db(&dir).await;
+        let msg_id = message(0).id();
+        let status = || async { db.db.retrieve_message_status(&msg_id).await.unwrap() };
+        assert_eq!(status().await, None);
+
+        // The payment can be scraped before the message it pays for
+        let payment = InterchainGasPayment {
+            message_id: msg_id,
+            destination: KnownHyperlaneDomain::Test2 as u32,
+            payment: 100.into(),
+            gas_amount: 1000.into(),
+        };
+        db.store_logs(&[(payment, meta(2, 2, 0))]).await.unwrap();
+        assert_eq!(status().await.as_deref(), Some("gas_paid"));
+
+        db.store_logs(&[(message(0), meta(1, 1, 0))]).await.unwrap();
+        assert_eq!(status().await.as_deref(), Some("gas_paid"));
+
+        db.store_logs(&[(msg_id, meta(3, 3, 0))]).await.unwrap();
+        assert_eq!(status().await.as_deref(), Some("delivered"));
+        assert_eq!(
+            db.db
+                .retrieve_message_status(&message(1).id())
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_logs_with_512_bit_txn_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sqlite_db(&dir).await;
+
+        // e.g. Sealevel transaction signatures
+        let signature = H512::repeat_byte(0x5e);
+        let messages = [(
+            message(0),
+            LogMeta {
+                transaction_id: signature,
+                ..meta(6, 0, 0)
+            },
+        )];
+        assert_eq!(db.store_logs(&messages).await.unwrap(), 1);
+
+        let stored = db
+            .db
+            .retrieve_message_by_id(&message(0).id())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.origin_tx_hash, signature);
+    }
+
+    #[tokio::test]
+    async fn test_store_logs_from_other_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = sqlite_db(&dir).await;
+
+        // The block at this height has a different hash, e.g. after a reorg
+        let messages = [(
+            message(0),
+            LogMeta {
+                block_hash: H256::repeat_byte(0xbb),
+                ..meta(7, 7, 0)
+            },
+        )];
+        assert!(db.store_logs(&messages).await.is_err());
+        assert_eq!(db.last_message_nonce().await.unwrap(), None);
+    }
+}
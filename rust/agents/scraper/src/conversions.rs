@@ -1,7 +1,7 @@
 use num_bigint::{BigInt, Sign};
 use sea_orm::prelude::BigDecimal;
 
-use hyperlane_core::{H256, U256};
+use hyperlane_core::{H256, H512, U256};
 
 // Creates a big-endian hex representation of the address
 pub fn address_to_bytes(data: &H256) -> Vec<u8> {
@@ -32,6 +32,34 @@ pub fn h256_to_bytes(data: &H256) -> Vec<u8> {
     data.as_fixed_bytes().as_slice().into()
 }
 
+// Parses a big-endian hex representation of a hash
+pub fn bytes_to_h256(data: &[u8]) -> eyre::Result<H256> {
+    if data.len() != 32 {
+        return Err(eyre::eyre!("Invalid hash length"));
+    }
+    Ok(H256::from_slice(data))
+}
+
+// Creates a big-endian hex representation of a transaction hash. 256-bit
+// hashes, which are stored in the lower 32 bytes, are kept at their own length
+pub fn h512_to_bytes(data: &H512) -> Vec<u8> {
+    let bytes = data.as_fixed_bytes();
+    if bytes[..32].iter().all(|b| *b == 0) {
+        bytes[32..].into()
+    } else {
+        bytes.as_slice().into()
+    }
+}
+
+// Parses a big-endian hex representation of a 256 or 512-bit transaction hash
+pub fn bytes_to_h512(data: &[u8]) -> eyre::Result<H512> {
+    match data.len() {
+        32 => Ok(H256::from_slice(data).into()),
+        64 => Ok(H512::from_slice(data)),
+        _ => Err(eyre::eyre!("Invalid hash length")),
+    }
+}
+
 pub fn u256_to_decimal(v: U256) -> BigDecimal {
     let mut buf = [0u8; 32];
     v.to_little_endian(&mut buf);
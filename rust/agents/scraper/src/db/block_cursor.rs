@@ -5,7 +5,7 @@ use sea_orm::{prelude::*, ActiveValue, Insert, Order, QueryOrder, QuerySelect};
 use tokio::sync::RwLock;
 use tracing::{debug, info, instrument, warn};
 
-use crate::db::ScraperDb;
+use crate::{date_time, db::ScraperDb};
 
 use super::generated::cursor;
 
@@ -27,18 +27,31 @@ pub struct BlockCursor {
     db: DbConn,
     /// The hyperlane domain this block cursor is for.
     domain: u32,
+    /// What this block cursor indexes, `None` for the cursor shared by
+    /// messages, deliveries and gas payments.
+    kind: Option<&'static str>,
     inner: RwLock<BlockCursorInner>,
 }
 
 impl BlockCursor {
-    async fn new(db: DbConn, domain: u32, default_height: u64) -> Result<Self> {
+    async fn new(
+        db: DbConn,
+        domain: u32,
+        kind: Option<&'static str>,
+        default_height: u64,
+    ) -> Result<Self> {
         #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
         enum QueryAs {
             Height,
         }
 
+        let kind_filter = match kind {
+            Some(kind) => cursor::Column::Kind.eq(kind),
+            None => cursor::Column::Kind.is_null(),
+        };
         let height = (cursor::Entity::find())
             .filter(cursor::Column::Domain.eq(domain))
+            .filter(kind_filter)
             .order_by(cursor::Column::Height, Order::Desc)
             .select_only()
             .column_as(cursor::Column::Height, QueryAs::Height)
@@ -54,11 +67,16 @@ impl BlockCursor {
                 "Cursor height loaded from the database is lower than the default height!"
             )
         } else {
-            info!(height, "Restored current cursor position from database")
+            info!(
+                height,
+                ?kind,
+                "Restored current cursor position from database"
+            )
         }
         Ok(Self {
             db,
             domain,
+            kind,
             inner: RwLock::new(BlockCursorInner {
                 height,
                 last_saved_at: Instant::now(),
@@ -86,8 +104,9 @@ impl BlockCursor {
             let model = cursor::ActiveModel {
                 id: ActiveValue::NotSet,
                 domain: ActiveValue::Set(self.domain as i32),
-                time_created: ActiveValue::NotSet,
+                time_created: ActiveValue::Set(date_time::now()),
                 height: ActiveValue::Set(height as i64),
+                kind: ActiveValue::Set(self.kind.map(str::to_owned)),
             };
             debug!(?model, "Inserting cursor");
             if let Err(e) = Insert::one(model).exec(&self.db).await {
@@ -100,7 +119,12 @@ impl BlockCursor {
 }
 
 impl ScraperDb {
-    pub async fn block_cursor(&self, domain: u32, default_height: u64) -> Result<BlockCursor> {
-        BlockCursor::new(self.0.clone(), domain, default_height).await
+    pub async fn block_cursor(
+        &self,
+        domain: u32,
+        kind: Option<&'static str>,
+        default_height: u64,
+    ) -> Result<BlockCursor> {
+        BlockCursor::new(self.0.clone(), domain, kind, default_height).await
     }
 }
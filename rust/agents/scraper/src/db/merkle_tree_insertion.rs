@@ -0,0 +1,158 @@
+use eyre::Result;
+use itertools::Itertools;
+use sea_orm::{prelude::*, ActiveValue::*, DeriveColumn, EnumIter, Insert, QuerySelect};
+use tracing::{debug, instrument, trace};
+
+use hyperlane_core::{LogMeta, MerkleTreeInsertion, H256};
+use migration::OnConflict;
+
+use crate::conversions::{address_to_bytes, bytes_to_h256, h256_to_bytes};
+use crate::date_time;
+use crate::db::ScraperDb;
+
+use super::generated::merkle_tree_insertion;
+
+pub struct StorableMerkleTreeInsertion<'a> {
+    pub insertion: &'a MerkleTreeInsertion,
+    pub meta: &'a LogMeta,
+    /// The database id of the transaction the insertion was made in
+    pub txn_id: i64,
+}
+
+impl ScraperDb {
+    /// Get the highest leaf index that is stored in the database.
+    #[instrument(skip(self))]
+    pub async fn last_merkle_tree_leaf_index(
+        &self,
+        domain: u32,
+        merkle_tree_hook: &H256,
+    ) -> Result<Option<u32>> {
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+        enum QueryAs {
+            LeafIndex,
+        }
+
+        let last_leaf_index = merkle_tree_insertion::Entity::find()
+            .filter(merkle_tree_insertion::Column::Domain.eq(domain))
+            .filter(
+                merkle_tree_insertion::Column::MerkleTreeHook
+                    .eq(address_to_bytes(merkle_tree_hook)),
+            )
+            .select_only()
+            .column_as(
+                merkle_tree_insertion::Column::LeafIndex.max(),
+                QueryAs::LeafIndex,
+            )
+            .into_values::<i32, QueryAs>()
+            .one(&self.0)
+            .await?
+            .map(|idx| idx as u32);
+        debug!(
+            ?last_leaf_index,
+            domain,
+            ?merkle_tree_hook,
+            "Queried last merkle tree leaf index from database"
+        );
+        Ok(last_leaf_index)
+    }
+
+    /// Get the merkle tree insertion with a leaf index.
+    #[instrument(skip(self))]
+    pub async fn retrieve_merkle_tree_insertion_by_leaf_index(
+        &self,
+        domain: u32,
+        merkle_tree_hook: &H256,
+        leaf_index: u32,
+    ) -> Result<Option<(MerkleTreeInsertion, i64)>> {
+        merkle_tree_insertion::Entity::find()
+            .filter(merkle_tree_insertion::Column::Domain.eq(domain))
+            .filter(
+                merkle_tree_insertion::Column::MerkleTreeHook
+                    .eq(address_to_bytes(merkle_tree_hook)),
+            )
+            .filter(merkle_tree_insertion::Column::LeafIndex.eq(leaf_index))
+            .one(&self.0)
+            .await?
+            .map(|insertion| {
+                Ok((
+                    MerkleTreeInsertion::new(
+                        insertion.leaf_index as u32,
+                        bytes_to_h256(&insertion.msg_id)?,
+                    ),
+                    insertion.tx_id,
+                ))
+            })
+            .transpose()
+    }
+
+    async fn merkle_tree_insertions_count(
+        &self,
+        domain: u32,
+        merkle_tree_hook: Vec<u8>,
+    ) -> Result<u64> {
+        Ok(merkle_tree_insertion::Entity::find()
+            .filter(merkle_tree_insertion::Column::Domain.eq(domain))
+            .filter(merkle_tree_insertion::Column::MerkleTreeHook.eq(merkle_tree_hook))
+            .count(&self.0)
+            .await?)
+    }
+
+    /// Store merkle tree insertions from a merkle tree hook into the database
+    /// (or update an existing one).
+    #[instrument(skip_all)]
+    pub async fn store_merkle_tree_insertions(
+        &self,
+        domain: u32,
+        merkle_tree_hook: &H256,
+        insertions: impl Iterator<Item = StorableMerkleTreeInsertion<'_>>,
+    ) -> Result<u64> {
+        let merkle_tree_hook = address_to_bytes(merkle_tree_hook);
+        let insertions_count_before = self
+            .merkle_tree_insertions_count(domain, merkle_tree_hook.clone())
+            .await?;
+        let models = insertions
+            .map(|storable| merkle_tree_insertion::ActiveModel {
+                id: NotSet,
+                time_created: Set(date_time::now()),
+                domain: Unchanged(domain as i32),
+                merkle_tree_hook: Unchanged(merkle_tree_hook.clone()),
+                leaf_index: Unchanged(storable.insertion.index() as i32),
+                msg_id: Set(h256_to_bytes(&storable.insertion.message_id())),
+                tx_id: Set(storable.txn_id),
+                log_index: Set(storable.meta.log_index.as_u64() as i64),
+            })
+            .collect_vec();
+
+        debug_assert!(!models.is_empty());
+        trace!(?models, "Writing merkle tree insertions to database");
+
+        Insert::many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    merkle_tree_insertion::Column::MerkleTreeHook,
+                    merkle_tree_insertion::Column::Domain,
+                    merkle_tree_insertion::Column::LeafIndex,
+                ])
+                .update_columns([
+                    merkle_tree_insertion::Column::TimeCreated,
+                    merkle_tree_insertion::Column::MsgId,
+                    merkle_tree_insertion::Column::TxId,
+                    merkle_tree_insertion::Column::LogIndex,
+                ])
+                .to_owned(),
+            )
+            .exec(&self.0)
+            .await?;
+        let insertions_count_after = self
+            .merkle_tree_insertions_count(domain, merkle_tree_hook)
+            .await?;
+        let difference = insertions_count_after.saturating_sub(insertions_count_before);
+        if difference > 0 {
+            debug!(
+                insertions = difference,
+                "Wrote new merkle tree insertions to database"
+            );
+        }
+        Ok(difference)
+    }
+}
@@ -0,0 +1,69 @@
+use eyre::Result;
+use itertools::Itertools;
+use sea_orm::{ConnectionTrait, DbBackend, Statement, Value};
+use tracing::{instrument, trace};
+
+use hyperlane_core::H256;
+use migration::upsert_message_lifecycles_sql;
+
+use crate::conversions::h256_to_bytes;
+use crate::db::ScraperDb;
+
+/// Number of messages to update the lifecycle of per statement, well below
+/// the bound parameter limit of SQLite.
+const LIFECYCLE_BATCH_SIZE: usize = 100;
+
+impl ScraperDb {
+    /// Recompute the lifecycle of these messages from what has been scraped
+    /// of them so far. Called after storing any of their dispatches, gas
+    /// payments or deliveries.
+    #[instrument(skip_all)]
+    pub async fn update_message_lifecycles(
+        &self,
+        msg_ids: impl Iterator<Item = H256>,
+    ) -> Result<()> {
+        let backend = self.0.get_database_backend();
+        let msg_ids = msg_ids.unique().collect_vec();
+        for batch in msg_ids.chunks(LIFECYCLE_BATCH_SIZE) {
+            let values: Vec<Value> = batch.iter().map(|id| h256_to_bytes(id).into()).collect();
+            let ids_sql = (1..=values.len())
+                .map(|i| match backend {
+                    DbBackend::Postgres => format!(r#"SELECT ${i} AS "msg_id""#),
+                    _ => r#"SELECT ? AS "msg_id""#.to_owned(),
+                })
+                .join(" UNION ");
+            trace!(count = values.len(), "Updating message lifecycles");
+            self.0
+                .execute(Statement::from_sql_and_values(
+                    backend,
+                    &upsert_message_lifecycles_sql(backend, &ids_sql),
+                    values,
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl ScraperDb {
+    /// The lifecycle status of a message, if anything has been scraped for it.
+    pub async fn retrieve_message_status(&self, msg_id: &H256) -> Result<Option<String>> {
+        let backend = self.0.get_database_backend();
+        let placeholder = match backend {
+            DbBackend::Postgres => "$1",
+            _ => "?",
+        };
+        let row = self
+            .0
+            .query_one(Statement::from_sql_and_values(
+                backend,
+                &format!(
+                    r#"SELECT "status" FROM "message_lifecycle" WHERE "msg_id" = {placeholder}"#
+                ),
+                [h256_to_bytes(msg_id).into()],
+            ))
+            .await?;
+        Ok(row.map(|row| row.try_get("", "status")).transpose()?)
+    }
+}
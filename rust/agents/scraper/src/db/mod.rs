@@ -1,11 +1,15 @@
 pub use block::*;
 pub use block_cursor::BlockCursor;
 use eyre::Result;
+pub use merkle_tree_insertion::*;
 pub use message::*;
+use migration::{Migrator, MigratorTrait};
 pub use payment::*;
-use sea_orm::{Database, DbConn};
+pub use query::*;
+use sea_orm::{ConnectionTrait, Database, DbBackend, DbConn};
 use tracing::instrument;
 pub use txn::*;
+pub use validator_announcement::*;
 
 #[allow(clippy::all)]
 mod generated;
@@ -13,9 +17,15 @@ mod generated;
 // These modules implement additional functionality for the ScraperDb
 mod block;
 mod block_cursor;
+mod merkle_tree_insertion;
 mod message;
+mod message_lifecycle;
 mod payment;
+mod query;
+#[cfg(test)]
+pub(crate) mod test_utils;
 mod txn;
+mod validator_announcement;
 
 /// Database interface to the message explorer database for the scraper. This is
 /// focused on writing data to the database.
@@ -26,6 +36,165 @@ impl ScraperDb {
     #[instrument]
     pub async fn connect(url: &str) -> Result<Self> {
         let db = Database::connect(url).await?;
+        // SQLite databases are local files for development and small
+        // deployments, so there is no separate step to set them up.
+        if db.get_database_backend() == DbBackend::Sqlite {
+            Migrator::up(&db, None).await?;
+        }
         Ok(Self(db))
     }
 }
+
+#[cfg(test)]
+impl ScraperDb {
+    /// Run raw SQL against the database, e.g. to set up a schema in tests.
+    pub async fn execute_unprepared(&self, sql: &str) -> Result<()> {
+        self.0.execute_unprepared(sql).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::H256;
+    use sea_orm::Statement;
+
+    use super::{
+        test_utils::{seed, seeded_db},
+        *,
+    };
+
+    async fn lifecycle_count(db: &ScraperDb) -> i64 {
+        db.0.query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"SELECT COUNT(*) AS "count" FROM "message_lifecycle""#.to_owned(),
+        ))
+        .await
+        .unwrap()
+        .unwrap()
+        .try_get("", "count")
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_message_lifecycle() {
+        let db = seeded_db().await;
+        // Nothing has been scraped for these
+        db.update_message_lifecycles([1, 2].into_iter().map(H256::from_low_u64_be))
+            .await
+            .unwrap();
+        assert_eq!(lifecycle_count(&db).await, 0);
+
+        let msg_ids = (1..=3).map(H256::repeat_byte);
+        db.update_message_lifecycles(msg_ids.clone()).await.unwrap();
+        // Updating them again leaves one row per message
+        db.update_message_lifecycles(msg_ids).await.unwrap();
+        assert_eq!(lifecycle_count(&db).await, 3);
+
+        let rows = db
+            .0
+            .query_all(Statement::from_string(
+                DbBackend::Sqlite,
+                r#"SELECT "nonce", "status", "delivery_latency", "relayer", "num_payments", "delivered_at" IS NULL AS "pending"
+                FROM "message_lifecycle" ORDER BY "nonce""#
+                    .to_owned(),
+            ))
+            .await
+            .unwrap();
+        let lifecycles = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get::<i64>("", "nonce").unwrap(),
+                    row.try_get::<String>("", "status").unwrap(),
+                    row.try_get::<Option<f64>>("", "delivery_latency").unwrap(),
+                    row.try_get::<Option<Vec<u8>>>("", "relayer").unwrap(),
+                    row.try_get::<i64>("", "num_payments").unwrap(),
+                    row.try_get::<bool>("", "pending").unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lifecycles.len(), 3);
+
+        let (nonce, status, latency, relayer, num_payments, pending) = &lifecycles[0];
+        assert_eq!((*nonce, status.as_str()), (0, "delivered"));
+        assert!((latency.unwrap() - 90.).abs() < 1e-3);
+        assert_eq!(relayer.as_deref(), Some(&[0xe3; 20][..]));
+        assert_eq!((*num_payments, *pending), (1, false));
+
+        let (nonce, status, latency, relayer, num_payments, pending) = &lifecycles[1];
+        assert_eq!((*nonce, status.as_str()), (1, "gas_paid"));
+        assert_eq!((*latency, relayer), (None, &None));
+        assert_eq!((*num_payments, *pending), (1, true));
+
+        let (nonce, status, _, _, num_payments, pending) = &lifecycles[2];
+        assert_eq!((*nonce, status.as_str()), (2, "dispatched"));
+        assert_eq!((*num_payments, *pending), (0, true));
+    }
+
+    /// Resets and seeds the Postgres database at `DATABASE_URL`, e.g. with
+    /// `DATABASE_URL=postgresql://... cargo test -p scraper -- --ignored`.
+    #[tokio::test]
+    #[ignore]
+    async fn test_postgres_lifecycle_views() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let db = ScraperDb::connect(&url).await.unwrap();
+        assert_eq!(db.0.get_database_backend(), DbBackend::Postgres);
+        Migrator::down(&db.0, None).await.unwrap();
+        Migrator::up(&db.0, None).await.unwrap();
+        seed(&db).await;
+        db.update_message_lifecycles((1..=3).map(H256::repeat_byte))
+            .await
+            .unwrap();
+
+        let routes = db
+            .0
+            .query_all(Statement::from_string(
+                DbBackend::Postgres,
+                r#"SELECT "origin_domain", "destination_domain", "num_delivered", "avg_latency", "max_latency"
+                FROM "route_latency""#
+                    .to_owned(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+        assert_eq!(
+            route.try_get::<String>("", "origin_domain").unwrap(),
+            "test1"
+        );
+        assert_eq!(
+            route.try_get::<String>("", "destination_domain").unwrap(),
+            "test2"
+        );
+        assert_eq!(route.try_get::<i64>("", "num_delivered").unwrap(), 1);
+        assert!((route.try_get::<f64>("", "avg_latency").unwrap() - 90.).abs() < 1e-3);
+        assert!((route.try_get::<f64>("", "max_latency").unwrap() - 90.).abs() < 1e-3);
+
+        let undelivered = |threshold: &str| {
+            Statement::from_string(
+                DbBackend::Postgres,
+                format!(
+                    r#"SELECT "nonce", "status" FROM undelivered_messages('{threshold}') ORDER BY "nonce""#
+                ),
+            )
+        };
+        let rows = db.0.query_all(undelivered("1 minute")).await.unwrap();
+        let pending = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get::<i32>("", "nonce").unwrap(),
+                    row.try_get::<String>("", "status").unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pending,
+            [(1, "gas_paid".to_owned()), (2, "dispatched".to_owned())]
+        );
+        // The seeded messages were dispatched in 2023
+        let rows = db.0.query_all(undelivered("100 years")).await.unwrap();
+        assert!(rows.is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+use hyperlane_core::{H256, H512};
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Select};
+use tracing::instrument;
+
+use crate::conversions::{
+    address_to_bytes, bytes_to_address, bytes_to_h256, bytes_to_h512, h256_to_bytes,
+};
+use crate::db::ScraperDb;
+
+use super::generated::{delivered_message, gas_payment, message, transaction};
+
+/// A page of records ordered by their database id. The next page starts after
+/// the id of the last record of this one.
+#[derive(Debug, Clone, Copy)]
+pub struct Page {
+    /// Only return records with a database id greater than this
+    pub after: Option<i64>,
+    /// Maximum number of records to return
+    pub limit: u64,
+}
+
+/// Filters for looking up dispatched messages. Unset filters match any
+/// message.
+#[derive(Debug, Clone, Default)]
+pub struct MessageFilter {
+    pub origin: Option<u32>,
+    pub sender: Option<H256>,
+    pub recipient: Option<H256>,
+    pub origin_tx_hash: Option<H512>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageRecord {
+    /// The database id of the message
+    pub id: i64,
+    pub msg_id: H256,
+    pub origin: u32,
+    pub destination: u32,
+    pub nonce: u32,
+    pub sender: H256,
+    pub recipient: H256,
+    pub body: Vec<u8>,
+    pub origin_mailbox: H256,
+    pub origin_tx_hash: H512,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeliveryRecord {
+    /// The database id of the delivery
+    pub id: i64,
+    pub msg_id: H256,
+    pub domain: u32,
+    pub destination_mailbox: H256,
+    pub destination_tx_hash: H512,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaymentRecord {
+    /// The database id of the payment
+    pub id: i64,
+    pub msg_id: H256,
+    pub domain: u32,
+    pub payment: BigDecimal,
+    pub gas_amount: BigDecimal,
+    pub tx_hash: H512,
+    pub log_index: u64,
+}
+
+fn paged<E: EntityTrait>(select: Select<E>, id: E::Column, page: Page) -> Select<E> {
+    let select = match page.after {
+        Some(after) => select.filter(id.gt(after)),
+        None => select,
+    };
+    select.order_by_asc(id).limit(page.limit)
+}
+
+impl ScraperDb {
+    /// Lookup transaction hashes by their database ids. Any transactions which
+    /// are not found are excluded from the hashmap.
+    async fn get_txn_hashes(&self, ids: impl Iterator<Item = i64>) -> Result<HashMap<i64, H512>> {
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+        enum QueryAs {
+            Id,
+            Hash,
+        }
+
+        transaction::Entity::find()
+            .filter(transaction::Column::Id.is_in(ids))
+            .select_only()
+            .column_as(transaction::Column::Id, QueryAs::Id)
+            .column_as(transaction::Column::Hash, QueryAs::Hash)
+            .into_values::<(i64, Vec<u8>), QueryAs>()
+            .all(&self.0)
+            .await?
+            .into_iter()
+            .map(|(id, hash)| Ok((id, bytes_to_h512(&hash)?)))
+            .collect()
+    }
+
+    /// Get the dispatched message with the given message id.
+    #[instrument(skip(self))]
+    pub async fn retrieve_message_by_id(&self, msg_id: &H256) -> Result<Option<MessageRecord>> {
+        let messages = message::Entity::find()
+            .filter(message::Column::MsgId.eq(h256_to_bytes(msg_id)))
+            .all(&self.0)
+            .await?;
+        Ok(self.message_records(messages).await?.into_iter().next())
+    }
+
+    /// Get a page of dispatched messages matching the filter.
+    #[instrument(skip(self))]
+    pub async fn retrieve_messages(
+        &self,
+        filter: &MessageFilter,
+        page: Page,
+    ) -> Result<Vec<MessageRecord>> {
+        let mut select = message::Entity::find();
+        if let Some(origin) = filter.origin {
+            select = select.filter(message::Column::Origin.eq(origin));
+        }
+        if let Some(sender) = &filter.sender {
+            select = select.filter(message::Column::Sender.eq(address_to_bytes(sender)));
+        }
+        if let Some(recipient) = &filter.recipient {
+            select = select.filter(message::Column::Recipient.eq(address_to_bytes(recipient)));
+        }
+        if let Some(tx_hash) = &filter.origin_tx_hash {
+            let Some(txn_id) = self
+                .get_txn_ids([tx_hash].into_iter())
+                .await?
+                .get(tx_hash)
+                .copied()
+            else {
+                return Ok(vec![]);
+            };
+            select = select.filter(message::Column::OriginTxId.eq(txn_id));
+        }
+        let messages = paged(select, message::Column::Id, page)
+            .all(&self.0)
+            .await?;
+        self.message_records(messages).await
+    }
+
+    async fn message_records(&self, messages: Vec<message::Model>) -> Result<Vec<MessageRecord>> {
+        let tx_hashes = self
+            .get_txn_hashes(messages.iter().map(|m| m.origin_tx_id))
+            .await?;
+        messages
+            .into_iter()
+            .map(|m| {
+                Ok(MessageRecord {
+                    id: m.id,
+                    msg_id: bytes_to_h256(&m.msg_id)?,
+                    origin: m.origin as u32,
+                    destination: m.destination as u32,
+                    nonce: m.nonce as u32,
+                    sender: bytes_to_address(m.sender)?,
+                    recipient: bytes_to_address(m.recipient)?,
+                    body: m.msg_body.unwrap_or_default(),
+                    origin_mailbox: bytes_to_address(m.origin_mailbox)?,
+                    origin_tx_hash: tx_hashes.get(&m.origin_tx_id).copied().unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Get the delivery of the message with the given message id.
+    #[instrument(skip(self))]
+    pub async fn retrieve_delivery_by_msg_id(
+        &self,
+        msg_id: &H256,
+    ) -> Result<Option<DeliveryRecord>> {
+        let deliveries = delivered_message::Entity::find()
+            .filter(delivered_message::Column::MsgId.eq(h256_to_bytes(msg_id)))
+            .all(&self.0)
+            .await?;
+        Ok(self.delivery_records(deliveries).await?.into_iter().next())
+    }
+
+    /// Get a page of the messages delivered on a domain.
+    #[instrument(skip(self))]
+    pub async fn retrieve_deliveries(
+        &self,
+        domain: u32,
+        page: Page,
+    ) -> Result<Vec<DeliveryRecord>> {
+        let select =
+            delivered_message::Entity::find().filter(delivered_message::Column::Domain.eq(domain));
+        let deliveries = paged(select, delivered_message::Column::Id, page)
+            .all(&self.0)
+            .await?;
+        self.delivery_records(deliveries).await
+    }
+
+    async fn delivery_records(
+        &self,
+        deliveries: Vec<delivered_message::Model>,
+    ) -> Result<Vec<DeliveryRecord>> {
+        let tx_hashes = self
+            .get_txn_hashes(deliveries.iter().map(|d| d.destination_tx_id))
+            .await?;
+        deliveries
+            .into_iter()
+            .map(|d| {
+                Ok(DeliveryRecord {
+                    id: d.id,
+                    msg_id: bytes_to_h256(&d.msg_id)?,
+                    domain: d.domain as u32,
+                    destination_mailbox: bytes_to_address(d.destination_mailbox)?,
+                    destination_tx_hash: tx_hashes
+                        .get(&d.destination_tx_id)
+                        .copied()
+                        .unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Get a page of gas payments, optionally only those made on a domain or
+    /// for a message.
+    #[instrument(skip(self))]
+    pub async fn retrieve_payments(
+        &self,
+        domain: Option<u32>,
+        msg_id: Option<&H256>,
+        page: Page,
+    ) -> Result<Vec<PaymentRecord>> {
+        let mut select = gas_payment::Entity::find();
+        if let Some(domain) = domain {
+            select = select.filter(gas_payment::Column::Domain.eq(domain));
+        }
+        if let Some(msg_id) = msg_id {
+            select = select.filter(gas_payment::Column::MsgId.eq(h256_to_bytes(msg_id)));
+        }
+        let payments = paged(select, gas_payment::Column::Id, page)
+            .all(&self.0)
+            .await?;
+        let tx_hashes = self
+            .get_txn_hashes(payments.iter().map(|p| p.tx_id))
+            .await?;
+        payments
+            .into_iter()
+            .map(|p| {
+                Ok(PaymentRecord {
+                    id: p.id,
+                    msg_id: bytes_to_h256(&p.msg_id)?,
+                    domain: p.domain as u32,
+                    payment: p.payment,
+                    gas_amount: p.gas_amount,
+                    tx_hash: tx_hashes.get(&p.tx_id).copied().unwrap_or_default(),
+                    log_index: p.log_index as u64,
+                })
+            })
+            .collect()
+    }
+}
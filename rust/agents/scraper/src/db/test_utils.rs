@@ -0,0 +1,103 @@
+//! Test data for the scraper database, shared by the tests of the queries,
+//! views and API on top of it.
+
+use sea_orm::{ConnectionTrait, DbBackend};
+
+use super::ScraperDb;
+
+/// A SQL blob literal of `len` bytes of `byte`.
+pub fn blob(backend: DbBackend, byte: u8, len: usize) -> String {
+    bytes(backend, &format!("{byte:02x}").repeat(len))
+}
+
+/// A SQL blob literal of the bytes `hex` encodes.
+fn bytes(backend: DbBackend, hex: &str) -> String {
+    match backend {
+        DbBackend::Postgres => format!(r"'\x{hex}'::BYTEA"),
+        _ => format!("X'{hex}'"),
+    }
+}
+
+/// An in-memory database set up by [`seed`].
+pub async fn seeded_db() -> ScraperDb {
+    let db = ScraperDb::connect("sqlite::memory:").await.unwrap();
+    seed(&db).await;
+    db
+}
+
+/// Fills a migrated, empty database with three messages from test1 (13371)
+/// to test2 (13372), with ids `blob(1..=3, 32)` and nonces 0 to 2. The first
+/// two were dispatched in transaction 1 and paid for in it, the third was
+/// dispatched in transaction 2, and both are in the block at 00:00:00. The
+/// first message was delivered in transaction 3, in the block at 00:01:30.
+/// Transaction 3 also paid 300 on test2 for a message with id
+/// `blob(4, 32)` which has not been scraped.
+///
+/// Transaction `n` has hash `blob(0xa0 + n, 32)` and sender
+/// `blob(0xe0 + n, 20)`.
+pub async fn seed(db: &ScraperDb) {
+    let backend = db.0.get_database_backend();
+    let blob = |byte, len| blob(backend, byte, len);
+    for (block, domain, timestamp) in [
+        (1u8, 13371, "2023-10-18 00:00:00"),
+        (2, 13372, "2023-10-18 00:01:30"),
+    ] {
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO "block" ("id", "time_created", "domain", "hash", "height", "timestamp")
+            VALUES ({block}, '{timestamp}', {domain}, {hash}, 1, '{timestamp}')"#,
+            hash = blob(0xb0 + block, 32),
+        ))
+        .await
+        .unwrap();
+    }
+    for (tx, block, timestamp) in [
+        (1u8, 1, "2023-10-18 00:00:00"),
+        (2, 1, "2023-10-18 00:00:00"),
+        (3, 2, "2023-10-18 00:01:30"),
+    ] {
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO "transaction" ("id", "time_created", "hash", "block_id", "gas_limit", "nonce", "sender", "gas_used", "cumulative_gas_used")
+            VALUES ({tx}, '{timestamp}', {hash}, {block}, 21000, {tx}, {sender}, 21000, 21000)"#,
+            hash = blob(0xa0 + tx, 32),
+            sender = blob(0xe0 + tx, 20),
+        ))
+        .await
+        .unwrap();
+    }
+    for (id, tx_id, sender) in [(1u8, 1, 0xaa), (2, 1, 0xaa), (3, 2, 0xbb)] {
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO "message" ("id", "time_created", "msg_id", "origin", "destination", "nonce", "sender", "recipient", "msg_body", "origin_mailbox", "origin_tx_id")
+            VALUES ({id}, '2023-10-18 00:00:00', {msg_id}, 13371, 13372, {nonce}, {sender}, {recipient}, {body}, {mailbox}, {tx_id})"#,
+            msg_id = blob(id, 32),
+            nonce = id - 1,
+            sender = blob(sender, 20),
+            recipient = blob(0xcc, 20),
+            body = bytes(backend, "0102"),
+            mailbox = blob(0x11, 20),
+        ))
+        .await
+        .unwrap();
+    }
+    for (id, domain, msg_id, payment, tx_id) in [
+        (1u8, 13371, 1, 100, 1),
+        (2, 13371, 2, 100, 1),
+        (3, 13372, 4, 300, 3),
+    ] {
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO "gas_payment" ("id", "time_created", "domain", "msg_id", "payment", "gas_amount", "tx_id", "log_index")
+            VALUES ({id}, '2023-10-18 00:00:00', {domain}, {msg_id}, {payment}, {gas_amount}, {tx_id}, {id})"#,
+            msg_id = blob(msg_id, 32),
+            gas_amount = payment / 10,
+        ))
+        .await
+        .unwrap();
+    }
+    db.execute_unprepared(&format!(
+        r#"INSERT INTO "delivered_message" ("id", "time_created", "msg_id", "domain", "destination_mailbox", "destination_tx_id")
+        VALUES (1, '2023-10-18 00:01:30', {msg_id}, 13372, {mailbox}, 3)"#,
+        msg_id = blob(1, 32),
+        mailbox = blob(0x22, 20),
+    ))
+    .await
+    .unwrap();
+}
@@ -0,0 +1,92 @@
+use eyre::Result;
+use itertools::Itertools;
+use sea_orm::{prelude::*, ActiveValue::*, Insert};
+use tracing::{debug, instrument, trace};
+
+use hyperlane_core::{LogMeta, ValidatorAnnouncement, H256};
+use migration::OnConflict;
+
+use crate::conversions::address_to_bytes;
+use crate::date_time;
+use crate::db::ScraperDb;
+
+use super::generated::validator_announcement;
+
+pub struct StorableValidatorAnnouncement<'a> {
+    pub announcement: &'a ValidatorAnnouncement,
+    pub meta: &'a LogMeta,
+    /// The database id of the transaction the announcement was made in
+    pub txn_id: i64,
+}
+
+impl ScraperDb {
+    /// Store announcements made to a validator announce contract into the
+    /// database (or update an existing one).
+    #[instrument(skip_all)]
+    pub async fn store_validator_announcements(
+        &self,
+        domain: u32,
+        validator_announce: &H256,
+        announcements: impl Iterator<Item = StorableValidatorAnnouncement<'_>>,
+    ) -> Result<u64> {
+        let validator_announce = address_to_bytes(validator_announce);
+        let announcements_count_before = self
+            .validator_announcements_count(domain, validator_announce.clone())
+            .await?;
+        let models = announcements
+            .map(|storable| validator_announcement::ActiveModel {
+                id: NotSet,
+                time_created: Set(date_time::now()),
+                domain: Unchanged(domain as i32),
+                validator_announce: Unchanged(validator_announce.clone()),
+                validator: Set(address_to_bytes(&storable.announcement.validator)),
+                storage_location: Set(storable.announcement.storage_location.clone()),
+                tx_id: Unchanged(storable.txn_id),
+                log_index: Unchanged(storable.meta.log_index.as_u64() as i64),
+            })
+            .collect_vec();
+
+        debug_assert!(!models.is_empty());
+        trace!(?models, "Writing validator announcements to database");
+
+        Insert::many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    // don't need domain because TxId includes it
+                    validator_announcement::Column::TxId,
+                    validator_announcement::Column::LogIndex,
+                ])
+                .update_columns([
+                    validator_announcement::Column::TimeCreated,
+                    validator_announcement::Column::Validator,
+                    validator_announcement::Column::StorageLocation,
+                ])
+                .to_owned(),
+            )
+            .exec(&self.0)
+            .await?;
+        let announcements_count_after = self
+            .validator_announcements_count(domain, validator_announce)
+            .await?;
+        let difference = announcements_count_after.saturating_sub(announcements_count_before);
+        if difference > 0 {
+            debug!(
+                announcements = difference,
+                "Wrote new validator announcements to database"
+            );
+        }
+        Ok(difference)
+    }
+
+    async fn validator_announcements_count(
+        &self,
+        domain: u32,
+        validator_announce: Vec<u8>,
+    ) -> Result<u64> {
+        Ok(validator_announcement::Entity::find()
+            .filter(validator_announcement::Column::Domain.eq(domain))
+            .filter(validator_announcement::Column::ValidatorAnnounce.eq(validator_announce))
+            .count(&self.0)
+            .await?)
+    }
+}
@@ -20,6 +20,7 @@ use hyperlane_base::agent_main;
 mod db;
 
 mod agent;
+mod api;
 mod chain_scraper;
 mod conversions;
 mod date_time;
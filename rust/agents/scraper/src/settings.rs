@@ -30,6 +30,8 @@ pub struct ScraperSettings {
 
     pub db: String,
     pub chains_to_scrape: Vec<HyperlaneDomain>,
+    /// Whether to serve the read-only query API on the agent's server
+    pub serve_api: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -69,6 +71,12 @@ impl FromRawConf<RawScraperSettings> for ScraperSettings {
             .end()
             .map(|v| v.to_owned());
 
+        let serve_api = p
+            .chain(&mut err)
+            .get_opt_key("serveApi")
+            .parse_bool()
+            .unwrap_or(false);
+
         let chains_to_scrape = if let (Some(base), Some(chains)) = (&base, chains_names_to_scrape) {
             chains
                 .into_iter()
@@ -84,11 +92,17 @@ impl FromRawConf<RawScraperSettings> for ScraperSettings {
         };
 
         cfg_unwrap_all!(&p.cwp, err: [base, db]);
+        let mut base = base;
+        // The scraper stores the block and transaction of every log
+        for chain in base.chains.values_mut() {
+            chain.index.log_meta = true;
+        }
 
         err.into_result(Self {
             base,
             db,
             chains_to_scrape,
+            serve_api,
         })
     }
 }
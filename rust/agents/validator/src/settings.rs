@@ -15,7 +15,9 @@ use hyperlane_base::{
         CheckpointSyncerConf, Settings, SignerConf,
     },
 };
-use hyperlane_core::{cfg_unwrap_all, config::*, HyperlaneDomain, HyperlaneDomainProtocol};
+use hyperlane_core::{
+    cfg_unwrap_all, config::*, BlsSecretKey, HyperlaneDomain, HyperlaneDomainProtocol,
+};
 use serde::Deserialize;
 use serde_json::Value;
 
@@ -40,6 +42,8 @@ pub struct ValidatorSettings {
     pub reorg_period: u64,
     /// How frequently to check for new checkpoints
     pub interval: Duration,
+    /// Key to additionally sign checkpoints with for BLS multisig ISMs
+    pub bls_key: Option<BlsSecretKey>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -114,6 +118,17 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             .map(Duration::from_secs)
             .unwrap_or(Duration::from_secs(5));
 
+        let bls_key = p
+            .chain(&mut err)
+            .get_opt_key("blsKey")
+            .parse_private_key()
+            .end()
+            .and_then(|key| {
+                BlsSecretKey::from_bytes(key)
+                    .context("Expected a valid BLS secret key")
+                    .take_err(&mut err, || cwp + "bls_key")
+            });
+
         cfg_unwrap_all!(cwp, err: [origin_chain_name]);
 
         let reorg_period = p
@@ -143,6 +158,7 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             checkpoint_syncer,
             reorg_period,
             interval,
+            bls_key,
         })
     }
 }
@@ -7,11 +7,14 @@ use hyperlane_core::rpc_clients::call_and_retry_indefinitely;
 use hyperlane_core::{ChainCommunicationError, ChainResult, MerkleTreeHook};
 use prometheus::IntGauge;
 use tokio::time::sleep;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use hyperlane_base::{db::HyperlaneRocksDB, CheckpointSyncer, CoreMetrics};
+use hyperlane_base::{
+    db::{HyperlaneRocksDB, MERKLE_TREE_SNAPSHOT_INTERVAL},
+    CheckpointSyncer, CoreMetrics,
+};
 use hyperlane_core::{
-    accumulator::incremental::IncrementalMerkle, Checkpoint, CheckpointWithMessageId,
+    accumulator::incremental::IncrementalMerkle, BlsSecretKey, Checkpoint, CheckpointWithMessageId,
     HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneSignerExt,
 };
 use hyperlane_ethereum::SingletonSignerHandle;
@@ -24,6 +27,7 @@ pub(crate) struct ValidatorSubmitter {
     merkle_tree_hook: Arc<dyn MerkleTreeHook>,
     checkpoint_syncer: Arc<dyn CheckpointSyncer>,
     message_db: HyperlaneRocksDB,
+    bls_key: Option<BlsSecretKey>,
     metrics: ValidatorSubmitterMetrics,
 }
 
@@ -35,6 +39,7 @@ impl ValidatorSubmitter {
         signer: SingletonSignerHandle,
         checkpoint_syncer: Arc<dyn CheckpointSyncer>,
         message_db: HyperlaneRocksDB,
+        bls_key: Option<BlsSecretKey>,
         metrics: ValidatorSubmitterMetrics,
     ) -> Self {
         Self {
@@ -44,6 +49,7 @@ impl ValidatorSubmitter {
             signer,
             checkpoint_syncer,
             message_db,
+            bls_key,
             metrics,
         }
     }
@@ -57,16 +63,23 @@ impl ValidatorSubmitter {
         }
     }
 
-    /// Submits signed checkpoints from index 0 until the target checkpoint (inclusive).
+    /// Submits signed checkpoints from index 0, or from the latest verified
+    /// merkle tree snapshot, until the target checkpoint (inclusive).
+    /// Snapshots are only stored by the backfill once all checkpoints up to
+    /// them were submitted, so checkpoints before a snapshot are skipped.
     /// Runs idly forever once the target checkpoint is reached to avoid exiting the task.
     pub(crate) async fn backfill_checkpoint_submitter(self, target_checkpoint: Checkpoint) {
-        let mut tree = IncrementalMerkle::default();
+        let mut tree = self.snapshot_or_default_tree(&target_checkpoint).await;
         call_and_retry_indefinitely(|| {
             let target_checkpoint = target_checkpoint;
             let self_clone = self.clone();
             Box::pin(async move {
                 self_clone
-                    .submit_checkpoints_until_correctness_checkpoint(&mut tree, &target_checkpoint)
+                    .submit_checkpoints_until_correctness_checkpoint(
+                        &mut tree,
+                        &target_checkpoint,
+                        true,
+                    )
                     .await?;
                 Ok(())
             })
@@ -141,6 +154,7 @@ impl ValidatorSubmitter {
                         .submit_checkpoints_until_correctness_checkpoint(
                             &mut tree,
                             &latest_checkpoint,
+                            false,
                         )
                         .await?;
                     Ok(tree)
@@ -156,12 +170,44 @@ impl ValidatorSubmitter {
         }
     }
 
+    /// Returns the latest verified merkle tree snapshot that doesn't exceed
+    /// the target checkpoint, or an empty tree if there is none.
+    async fn snapshot_or_default_tree(&self, target_checkpoint: &Checkpoint) -> IncrementalMerkle {
+        match self
+            .message_db
+            .retrieve_verified_merkle_tree_snapshot(
+                self.merkle_tree_hook.as_ref(),
+                self.reorg_period,
+            )
+            .await
+        {
+            Ok(Some(tree)) if !tree_exceeds_checkpoint(target_checkpoint, &tree) => {
+                info!(
+                    count = tree.count(),
+                    "Resuming backfill from merkle tree snapshot"
+                );
+                tree
+            }
+            Ok(_) => IncrementalMerkle::default(),
+            Err(err) => {
+                warn!(
+                    ?err,
+                    "Failed to retrieve merkle tree snapshot, backfilling from index 0"
+                );
+                IncrementalMerkle::default()
+            }
+        }
+    }
+
     /// Submits signed checkpoints relating to the given tree until the correctness checkpoint (inclusive).
     /// Only submits the signed checkpoints once the correctness checkpoint is reached.
+    /// If `store_snapshot` is set, the latest tree eligible for a snapshot is
+    /// stored once the checkpoints are submitted.
     async fn submit_checkpoints_until_correctness_checkpoint(
         &self,
         tree: &mut IncrementalMerkle,
         correctness_checkpoint: &Checkpoint,
+        store_snapshot: bool,
     ) -> ChainResult<()> {
         // This should never be called with a tree that is ahead of the correctness checkpoint.
         assert!(
@@ -174,6 +220,8 @@ impl ValidatorSubmitter {
         // All intermediate checkpoints will be stored here and signed once the correctness
         // checkpoint is reached.
         let mut checkpoint_queue = vec![];
+        // The latest tree with a leaf count eligible for a snapshot.
+        let mut snapshot = None;
 
         // If the correctness checkpoint is ahead of the tree, we need to ingest more messages.
         //
@@ -191,6 +239,9 @@ impl ValidatorSubmitter {
                 );
                 let message_id = insertion.message_id();
                 tree.ingest(message_id);
+                if tree.count() as u32 % MERKLE_TREE_SNAPSHOT_INTERVAL == 0 {
+                    snapshot = Some(*tree);
+                }
 
                 let checkpoint = self.checkpoint(tree);
 
@@ -245,6 +296,12 @@ impl ValidatorSubmitter {
             );
         }
 
+        // The snapshot is a prefix of the tree that was just verified against
+        // the correctness checkpoint.
+        if let Some(snapshot) = snapshot.filter(|_| store_snapshot) {
+            self.message_db.store_merkle_tree_snapshot(&snapshot)?;
+        }
+
         Ok(())
     }
 
@@ -265,16 +322,34 @@ impl ValidatorSubmitter {
                     index = queued_checkpoint.index,
                     "Checkpoint already submitted"
                 );
-                continue;
+            } else {
+                let signed_checkpoint = self.signer.sign(queued_checkpoint).await?;
+                self.checkpoint_syncer
+                    .write_checkpoint(&signed_checkpoint)
+                    .await?;
+                debug!(
+                    index = queued_checkpoint.index,
+                    "Signed and submitted checkpoint"
+                );
+            }
+
+            // BLS signed checkpoints are checked separately, so enabling a BLS
+            // key also backfills them for checkpoints signed before.
+            if let Some(bls_key) = &self.bls_key {
+                let existing = self
+                    .checkpoint_syncer
+                    .fetch_bls_checkpoint(queued_checkpoint.index)
+                    .await?;
+                if existing.is_none() {
+                    self.checkpoint_syncer
+                        .write_bls_checkpoint(&bls_key.sign_checkpoint(queued_checkpoint))
+                        .await?;
+                    debug!(
+                        index = queued_checkpoint.index,
+                        "Signed and submitted BLS checkpoint"
+                    );
+                }
             }
-            let signed_checkpoint = self.signer.sign(queued_checkpoint).await?;
-            self.checkpoint_syncer
-                .write_checkpoint(&signed_checkpoint)
-                .await?;
-            debug!(
-                index = queued_checkpoint.index,
-                "Signed and submitted checkpoint"
-            );
 
             // TODO: move these into S3 implementations
             // small sleep before signing next checkpoint to avoid rate limiting
@@ -18,9 +18,9 @@ use hyperlane_base::{
 };
 
 use hyperlane_core::{
-    Announcement, ChainResult, HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneSigner,
-    HyperlaneSignerExt, Mailbox, MerkleTreeHook, MerkleTreeInsertion, TxOutcome, ValidatorAnnounce,
-    H256, U256,
+    Announcement, BlsSecretKey, ChainResult, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
+    HyperlaneSigner, HyperlaneSignerExt, Mailbox, MerkleTreeHook, MerkleTreeInsertion, TxOutcome,
+    ValidatorAnnounce, H256, U256,
 };
 use hyperlane_ethereum::{SingletonSigner, SingletonSignerHandle};
 
@@ -46,6 +46,7 @@ pub struct Validator {
     signer_instance: Option<Box<SingletonSigner>>,
     reorg_period: u64,
     interval: Duration,
+    bls_key: Option<BlsSecretKey>,
     checkpoint_syncer: Arc<dyn CheckpointSyncer>,
     core_metrics: Arc<CoreMetrics>,
     agent_metrics: AgentMetrics,
@@ -119,6 +120,7 @@ impl BaseAgent for Validator {
             signer_instance: Some(Box::new(signer_instance)),
             reorg_period: settings.reorg_period,
             interval: settings.interval,
+            bls_key: settings.bls_key,
             checkpoint_syncer,
             agent_metrics,
             chain_metrics,
@@ -206,14 +208,21 @@ impl BaseAgent for Validator {
 
 impl Validator {
     async fn run_merkle_tree_hook_sync(&self) -> Instrumented<JoinHandle<()>> {
-        let index_settings =
-            self.as_ref().settings.chains[self.origin_chain.name()].index_settings();
+        let chain_conf = &self.as_ref().settings.chains[self.origin_chain.name()];
+        let index_settings = chain_conf.index_settings();
+        let chunk_size = index_settings.chunk_size;
+        let subscriber = chain_conf
+            .build_merkle_tree_hook_log_subscriber()
+            .map(Arc::from);
         let contract_sync = self.merkle_tree_hook_sync.clone();
         let cursor = contract_sync
             .forward_backward_message_sync_cursor(index_settings)
             .await;
         tokio::spawn(async move {
-            contract_sync.clone().sync("merkle_tree_hook", cursor).await;
+            contract_sync
+                .clone()
+                .sync_with_subscriber("merkle_tree_hook", cursor, subscriber, chunk_size)
+                .await;
         })
         .instrument(info_span!("MerkleTreeHookSyncer"))
     }
@@ -226,6 +235,7 @@ impl Validator {
             self.signer.clone(),
             self.checkpoint_syncer.clone(),
             self.db.clone(),
+            self.bls_key.clone(),
             ValidatorSubmitterMetrics::new(&self.core.metrics, &self.origin_chain),
         );
 
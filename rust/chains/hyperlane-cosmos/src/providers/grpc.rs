@@ -156,6 +156,9 @@ impl WasmGrpcProvider {
             .collect();
         let mut builder = FallbackProvider::builder();
         builder = builder.add_providers(channels?);
+        if let Some(observer) = conf.get_fallback_health_observer() {
+            builder = builder.with_health_observer(observer);
+        }
         let fallback_provider = builder.build();
         let provider = CosmosFallbackProvider::new(fallback_provider);
 
@@ -1,11 +1,18 @@
 use async_trait::async_trait;
+use cosmrs::{
+    cosmwasm::MsgExecuteContract,
+    tx::{Msg, SignerPublicKey},
+    Tx,
+};
 use hyperlane_core::{
-    BlockInfo, ChainInfo, ChainResult, ContractLocator, HyperlaneChain, HyperlaneDomain,
-    HyperlaneProvider, TxnInfo, H256, U256,
+    BlockInfo, ChainCommunicationError, ChainInfo, ChainResult, ContractLocator, HyperlaneChain,
+    HyperlaneDomain, HyperlaneProvider, HyperlaneProviderError, TxnInfo, TxnReceiptInfo, H256,
+    H512, U256,
 };
-use tendermint_rpc::{client::CompatMode, HttpClient};
+use tendermint::{block::Height, hash::Algorithm, Block, Hash};
+use tendermint_rpc::{client::CompatMode, Client, HttpClient};
 
-use crate::{ConnectionConf, CosmosAmount, HyperlaneCosmosError, Signer};
+use crate::{address::CosmosAddress, ConnectionConf, CosmosAmount, HyperlaneCosmosError, Signer};
 
 use self::grpc::WasmGrpcProvider;
 
@@ -19,6 +26,7 @@ pub mod rpc;
 pub struct CosmosProvider {
     domain: HyperlaneDomain,
     canonical_asset: String,
+    bech32_prefix: String,
     grpc_client: WasmGrpcProvider,
     rpc_client: HttpClient,
 }
@@ -54,6 +62,7 @@ impl CosmosProvider {
             rpc_client,
             grpc_client,
             canonical_asset: conf.get_canonical_asset(),
+            bech32_prefix: conf.get_bech32_prefix(),
         })
     }
 
@@ -66,6 +75,45 @@ impl CosmosProvider {
     pub fn rpc(&self) -> &HttpClient {
         &self.rpc_client
     }
+
+    fn block_info(block: &Block) -> BlockInfo {
+        BlockInfo {
+            hash: H256::from_slice(block.header.hash().as_bytes()),
+            timestamp: block.header.time.unix_timestamp() as u64,
+            number: block.header.height.value(),
+        }
+    }
+
+    /// The account which signed the transaction, derived from the public key
+    /// of its first signer.
+    fn txn_sender(&self, tx: &Tx) -> ChainResult<H256> {
+        let signer =
+            tx.auth_info.signer_infos.first().ok_or_else(|| {
+                ChainCommunicationError::from_other_str("Transaction has no signers")
+            })?;
+        let Some(SignerPublicKey::Single(public_key)) = signer.public_key.clone() else {
+            return Err(ChainCommunicationError::from_other_str(
+                "Transaction signer does not have a single public key",
+            ));
+        };
+        Ok(CosmosAddress::from_pubkey(public_key, &self.bech32_prefix)?.digest())
+    }
+
+    /// The contract called by the first contract execution in the
+    /// transaction, if there is one.
+    fn txn_recipient(tx: &Tx) -> ChainResult<Option<H256>> {
+        let Some(msg) = tx
+            .body
+            .messages
+            .iter()
+            .find_map(|any| MsgExecuteContract::from_any(any).ok())
+        else {
+            return Ok(None);
+        };
+        Ok(Some(
+            msg.contract.as_ref().parse::<CosmosAddress>()?.digest(),
+        ))
+    }
 }
 
 impl HyperlaneChain for CosmosProvider {
@@ -80,12 +128,78 @@ impl HyperlaneChain for CosmosProvider {
 
 #[async_trait]
 impl HyperlaneProvider for CosmosProvider {
-    async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
-        todo!() // FIXME
+    async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo> {
+        let tendermint_hash = Hash::from_bytes(Algorithm::Sha256, hash.as_bytes())
+            .map_err(ChainCommunicationError::from_other)?;
+        let response = self
+            .rpc_client
+            .block_by_hash(tendermint_hash)
+            .await
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        let block = response
+            .block
+            .ok_or(HyperlaneProviderError::CouldNotFindObjectByHash(*hash))?;
+        Ok(Self::block_info(&block))
     }
 
-    async fn get_txn_by_hash(&self, _hash: &H256) -> ChainResult<TxnInfo> {
-        todo!() // FIXME
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+        let tendermint_height =
+            Height::try_from(height).map_err(ChainCommunicationError::from_other)?;
+        let response = self
+            .rpc_client
+            .block(tendermint_height)
+            .await
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        Ok(Self::block_info(&response.block))
+    }
+
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+        let txn_hash: H256 = (*hash).into();
+        let tendermint_hash = Hash::from_bytes(Algorithm::Sha256, txn_hash.as_bytes())
+            .map_err(ChainCommunicationError::from_other)?;
+        let response = self
+            .rpc_client
+            .tx(tendermint_hash, false)
+            .await
+            .map_err(Into::<HyperlaneCosmosError>::into)?;
+        let tx = Tx::from_bytes(&response.tx).map_err(Into::<HyperlaneCosmosError>::into)?;
+        let signer = tx
+            .auth_info
+            .signer_infos
+            .first()
+            .ok_or(HyperlaneProviderError::CouldNotFindTransactionByHash(*hash))?;
+
+        let gas_limit = tx.auth_info.fee.gas_limit;
+        // Cosmos has no notion of a per-gas price in a txn, only the total fee
+        // offered for its gas limit. This is rounded down to whole units of
+        // the canonical asset.
+        let fee = tx
+            .auth_info
+            .fee
+            .amount
+            .iter()
+            .filter(|coin| coin.denom.as_ref() == self.canonical_asset)
+            .map(|coin| U256::from(coin.amount))
+            .fold(U256::zero(), |acc, amount| acc + amount);
+        let gas_price = (gas_limit > 0).then(|| fee / U256::from(gas_limit));
+        let gas_used = U256::from(response.tx_result.gas_used.max(0) as u64);
+
+        Ok(TxnInfo {
+            hash: *hash,
+            gas_limit: gas_limit.into(),
+            max_priority_fee_per_gas: None,
+            max_fee_per_gas: None,
+            gas_price,
+            nonce: signer.sequence,
+            sender: self.txn_sender(&tx)?,
+            recipient: Self::txn_recipient(&tx)?,
+            receipt: Some(TxnReceiptInfo {
+                gas_used,
+                // Cosmos does not report cumulative usage within the block
+                cumulative_gas_used: gas_used,
+                effective_gas_price: gas_price,
+            }),
+        })
     }
 
     async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
@@ -103,4 +217,8 @@ impl HyperlaneProvider for CosmosProvider {
     async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
         Ok(None)
     }
+
+    async fn get_chain_id(&self) -> ChainResult<Option<U256>> {
+        Ok(None)
+    }
 }
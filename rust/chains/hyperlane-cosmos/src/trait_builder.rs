@@ -1,7 +1,9 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use derive_new::new;
-use hyperlane_core::{ChainCommunicationError, FixedPointNumber};
+use hyperlane_core::{
+    rpc_clients::ProviderHealthObserver, ChainCommunicationError, FixedPointNumber,
+};
 use url::Url;
 
 /// Cosmos connection configuration
@@ -25,6 +27,8 @@ pub struct ConnectionConf {
     /// Cosmos address lengths are sometimes less than 32 bytes, so this helps to serialize it in
     /// bech32 with the appropriate length.
     contract_address_bytes: usize,
+    /// Notified whenever the health of one of the GRPC providers changes.
+    fallback_health_observer: Option<Arc<dyn ProviderHealthObserver>>,
 }
 
 /// Untyped cosmos amount
@@ -111,6 +115,20 @@ impl ConnectionConf {
         self.contract_address_bytes
     }
 
+    /// Get the observer of the GRPC providers' health, if any
+    pub fn get_fallback_health_observer(&self) -> Option<Arc<dyn ProviderHealthObserver>> {
+        self.fallback_health_observer.clone()
+    }
+
+    /// Notify `observer` whenever the health of one of the GRPC providers changes
+    pub fn with_fallback_health_observer(
+        mut self,
+        observer: Arc<dyn ProviderHealthObserver>,
+    ) -> Self {
+        self.fallback_health_observer = Some(observer);
+        self
+    }
+
     /// Create a new connection configuration
     pub fn new(
         grpc_urls: Vec<Url>,
@@ -129,6 +147,7 @@ impl ConnectionConf {
             canonical_asset,
             gas_price: minimum_gas_price,
             contract_address_bytes,
+            fallback_health_observer: None,
         }
     }
 }
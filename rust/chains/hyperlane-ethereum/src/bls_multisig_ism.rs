@@ -0,0 +1,201 @@
+#![allow(clippy::enum_variant_names)]
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use tracing::instrument;
+
+use hyperlane_core::{
+    BlsMultisigIsm, BlsPublicKey, ChainCommunicationError, ChainResult, ContractLocator,
+    HyperlaneAbi, HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneProvider, H160,
+    H256,
+};
+
+use crate::contracts::i_bls_multisig_ism::{
+    IBlsMultisigIsm as EthereumBlsMultisigIsmInternal, IBLSMULTISIGISM_ABI,
+};
+use crate::trait_builder::BuildableWithProvider;
+use crate::EthereumProvider;
+
+impl<M> std::fmt::Display for EthereumBlsMultisigIsmInternal<M>
+where
+    M: Middleware,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub struct BlsMultisigIsmBuilder {}
+
+#[async_trait]
+impl BuildableWithProvider for BlsMultisigIsmBuilder {
+    type Output = Box<dyn BlsMultisigIsm>;
+
+    async fn build_with_provider<M: Middleware + 'static>(
+        &self,
+        provider: M,
+        locator: &ContractLocator,
+    ) -> Self::Output {
+        Box::new(EthereumBlsMultisigIsm::new(Arc::new(provider), locator))
+    }
+}
+
+/// A reference to a BlsMultisigIsm contract on some Ethereum chain
+#[derive(Debug)]
+pub struct EthereumBlsMultisigIsm<M>
+where
+    M: Middleware,
+{
+    contract: Arc<EthereumBlsMultisigIsmInternal<M>>,
+    domain: HyperlaneDomain,
+}
+
+impl<M> EthereumBlsMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    /// Create a reference to a BLS multisig ISM at a specific Ethereum
+    /// address on some chain
+    pub fn new(provider: Arc<M>, locator: &ContractLocator) -> Self {
+        Self {
+            contract: Arc::new(EthereumBlsMultisigIsmInternal::new(
+                locator.address,
+                provider,
+            )),
+            domain: locator.domain.clone(),
+        }
+    }
+}
+
+impl<M> HyperlaneChain for EthereumBlsMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    fn domain(&self) -> &HyperlaneDomain {
+        &self.domain
+    }
+
+    fn provider(&self) -> Box<dyn HyperlaneProvider> {
+        Box::new(EthereumProvider::new(
+            self.contract.client(),
+            self.domain.clone(),
+        ))
+    }
+}
+
+impl<M> HyperlaneContract for EthereumBlsMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    fn address(&self) -> H256 {
+        self.contract.address().into()
+    }
+}
+
+#[async_trait]
+impl<M> BlsMultisigIsm for EthereumBlsMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    #[instrument(err)]
+    async fn registered_public_keys(&self, validators: &[H256]) -> ChainResult<Vec<BlsPublicKey>> {
+        let validators = validators
+            .iter()
+            .map(|validator| H160::from(*validator).into())
+            .collect();
+        let public_keys = self
+            .contract
+            .registered_public_keys(validators)
+            .call()
+            .await?;
+        public_keys
+            .iter()
+            .map(|public_key| {
+                BlsPublicKey::from_bytes(public_key).map_err(ChainCommunicationError::from_other)
+            })
+            .collect()
+    }
+}
+
+pub struct EthereumBlsMultisigIsmAbi;
+
+impl HyperlaneAbi for EthereumBlsMultisigIsmAbi {
+    const SELECTOR_SIZE_BYTES: usize = 4;
+
+    fn fn_map() -> HashMap<Vec<u8>, &'static str> {
+        super::extract_fn_map(&IBLSMULTISIGISM_ABI)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ethers::{
+        abi::{encode, Token},
+        providers::{MockProvider, Provider},
+        types::Bytes,
+    };
+    use hyperlane_core::{
+        BlsMultisigIsm, BlsSecretKey, ContractLocator, HyperlaneDomain, KnownHyperlaneDomain, H256,
+    };
+
+    use crate::EthereumBlsMultisigIsm;
+
+    fn ism(
+        mock_provider: Arc<MockProvider>,
+    ) -> EthereumBlsMultisigIsm<Provider<Arc<MockProvider>>> {
+        EthereumBlsMultisigIsm::new(
+            Arc::new(Provider::new(mock_provider)),
+            &ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+                // Address doesn't matter because we're using a MockProvider
+                address: H256::default(),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn decodes_registered_public_keys() {
+        let mock_provider = Arc::new(MockProvider::new());
+        let public_keys: Vec<_> = (1..=2)
+            .map(|n| {
+                BlsSecretKey::from_bytes(H256::from_low_u64_be(n))
+                    .unwrap()
+                    .public_key()
+            })
+            .collect();
+        let returned = encode(&[Token::Array(
+            public_keys
+                .iter()
+                .map(|key| Token::Bytes(key.to_bytes().to_vec()))
+                .collect(),
+        )]);
+        mock_provider.push(Bytes::from(returned)).unwrap();
+
+        let validators = [H256::repeat_byte(1), H256::repeat_byte(2)];
+        assert_eq!(
+            ism(mock_provider)
+                .registered_public_keys(&validators)
+                .await
+                .unwrap(),
+            public_keys
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_registered_public_keys() {
+        let mock_provider = Arc::new(MockProvider::new());
+        let returned = encode(&[Token::Array(vec![Token::Bytes(vec![1; 128])])]);
+        mock_provider.push(Bytes::from(returned)).unwrap();
+
+        assert!(ism(mock_provider)
+            .registered_public_keys(&[H256::repeat_byte(1)])
+            .await
+            .is_err());
+    }
+}
@@ -10,10 +10,11 @@ use ethers::prelude::{abi, Lazy, Middleware};
 
 #[cfg(not(doctest))]
 pub use self::{
-    aggregation_ism::*, ccip_read_ism::*, config::*, config::*, interchain_gas::*,
-    interchain_gas::*, interchain_security_module::*, interchain_security_module::*, mailbox::*,
-    mailbox::*, merkle_tree_hook::*, multisig_ism::*, provider::*, routing_ism::*, rpc_clients::*,
-    signers::*, singleton_signer::*, trait_builder::*, validator_announce::*,
+    aggregation_ism::*, bls_multisig_ism::*, ccip_read_ism::*, config::*, config::*, interchain_gas::*,
+    interchain_gas::*, interchain_security_module::*, interchain_security_module::*,
+    log_subscriber::*, mailbox::*, mailbox::*, merkle_tree_hook::*, multisig_ism::*,
+    optimistic_ism::*, provider::*, routing_ism::*, rpc_clients::*, signers::*,
+    singleton_signer::*, trait_builder::*, validator_announce::*, weighted_multisig_ism::*,
 };
 
 #[cfg(not(doctest))]
@@ -42,10 +43,26 @@ mod interchain_security_module;
 #[cfg(not(doctest))]
 mod merkle_tree_hook;
 
+/// Websocket log subscriptions
+#[cfg(not(doctest))]
+mod log_subscriber;
+
 /// MultisigIsm abi
 #[cfg(not(doctest))]
 mod multisig_ism;
 
+/// OptimisticIsm abi
+#[cfg(not(doctest))]
+mod optimistic_ism;
+
+/// WeightedMultisigIsm abi
+#[cfg(not(doctest))]
+mod weighted_multisig_ism;
+
+/// BlsMultisigIsm abi
+#[cfg(not(doctest))]
+mod bls_multisig_ism;
+
 /// RoutingIsm abi
 #[cfg(not(doctest))]
 mod routing_ism;
@@ -0,0 +1,156 @@
+#![allow(missing_docs)]
+
+use std::fmt::{Debug, Formatter};
+
+use async_trait::async_trait;
+use ethers::abi::RawLog;
+use ethers::prelude::{Address, EthEvent, Filter, Log, Middleware, Provider, Ws};
+use ethers_contract::LogMeta as EthersLogMeta;
+use futures_util::StreamExt;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{debug, instrument, warn};
+use url::Url;
+
+use hyperlane_core::{
+    ChainCommunicationError, ChainResult, ContractLocator, HyperlaneDomain, HyperlaneMessage,
+    InterchainGasPayment, LogMeta, LogSubscriber, MerkleTreeInsertion, SubscribedLog, H256,
+};
+
+use crate::contracts::i_interchain_gas_paymaster::GasPaymentFilter;
+use crate::contracts::i_mailbox::DispatchFilter;
+use crate::contracts::merkle_tree_hook::InsertedIntoTreeFilter;
+use crate::trait_builder::EthereumProviderConnectionError;
+
+/// Subscribes to the logs of a single event of a contract over a websocket
+/// connection using `eth_subscribe("logs")`.
+///
+/// A new connection is opened for every call to `subscribe`, so callers can
+/// resubscribe after the connection was lost.
+pub struct EthereumWsLogSubscriber<E, T> {
+    url: Url,
+    domain: HyperlaneDomain,
+    address: Address,
+    convert: fn(E) -> T,
+}
+
+impl<E, T> Debug for EthereumWsLogSubscriber<E, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The url is not included since it may contain API keys
+        f.debug_struct("EthereumWsLogSubscriber")
+            .field("domain", &self.domain)
+            .field("address", &self.address)
+            .field("event", &std::any::type_name::<E>())
+            .finish()
+    }
+}
+
+impl<E, T> EthereumWsLogSubscriber<E, T>
+where
+    E: EthEvent,
+{
+    fn new(url: Url, locator: &ContractLocator, convert: fn(E) -> T) -> Self {
+        Self {
+            url,
+            domain: locator.domain.clone(),
+            address: locator.address.into(),
+            convert,
+        }
+    }
+
+    fn filter(&self) -> Filter {
+        Filter::new().address(self.address).topic0(E::signature())
+    }
+
+    fn decode(&self, log: &Log) -> ChainResult<(T, LogMeta)> {
+        let event = E::decode_log(&RawLog::from(log.clone()))
+            .map_err(ChainCommunicationError::from_other)?;
+        Ok(((self.convert)(event), EthersLogMeta::from(log).into()))
+    }
+}
+
+#[async_trait]
+impl<E, T> LogSubscriber<T> for EthereumWsLogSubscriber<E, T>
+where
+    E: EthEvent + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    #[instrument(err, skip(self, sender), fields(domain = self.domain.name(), address = ?self.address))]
+    async fn subscribe(&self, sender: UnboundedSender<SubscribedLog<T>>) -> ChainResult<()> {
+        let ws = Ws::connect(&self.url)
+            .await
+            .map_err(EthereumProviderConnectionError::from)?;
+        let provider = Provider::new(ws);
+        let mut stream = provider
+            .subscribe_logs(&self.filter())
+            .await
+            .map_err(ChainCommunicationError::from_other)?;
+        debug!("Subscribed to logs");
+
+        while let Some(log) = stream.next().await {
+            let removed = log.removed.unwrap_or(false);
+            let (event, meta) = match self.decode(&log) {
+                Ok(decoded) => decoded,
+                Err(err) => {
+                    warn!(?err, ?log, "Failed to decode subscribed log");
+                    continue;
+                }
+            };
+            let subscribed_log = if removed {
+                SubscribedLog::Removed(event, meta)
+            } else {
+                SubscribedLog::Emitted(event, meta)
+            };
+            if sender.send(subscribed_log).is_err() {
+                // The receiver was dropped, so nobody is interested in the logs anymore
+                return Ok(());
+            }
+        }
+
+        Err(ChainCommunicationError::from_other_str(
+            "Websocket log subscription ended",
+        ))
+    }
+}
+
+/// Subscribe to messages dispatched by a mailbox
+pub fn dispatch_log_subscriber(
+    url: Url,
+    locator: &ContractLocator,
+) -> Box<dyn LogSubscriber<HyperlaneMessage>> {
+    Box::new(EthereumWsLogSubscriber::new(
+        url,
+        locator,
+        |event: DispatchFilter| HyperlaneMessage::from(event.message.to_vec()),
+    ))
+}
+
+/// Subscribe to gas payments made to an interchain gas paymaster
+pub fn gas_payment_log_subscriber(
+    url: Url,
+    locator: &ContractLocator,
+) -> Box<dyn LogSubscriber<InterchainGasPayment>> {
+    Box::new(EthereumWsLogSubscriber::new(
+        url,
+        locator,
+        |event: GasPaymentFilter| InterchainGasPayment {
+            message_id: H256::from(event.message_id),
+            destination: event.destination_domain,
+            payment: event.payment.into(),
+            gas_amount: event.gas_amount.into(),
+        },
+    ))
+}
+
+/// Subscribe to insertions into a merkle tree hook
+pub fn merkle_tree_insertion_log_subscriber(
+    url: Url,
+    locator: &ContractLocator,
+) -> Box<dyn LogSubscriber<MerkleTreeInsertion>> {
+    Box::new(EthereumWsLogSubscriber::new(
+        url,
+        locator,
+        |event: InsertedIntoTreeFilter| {
+            MerkleTreeInsertion::new(event.index, H256::from(event.message_id))
+        },
+    ))
+}
@@ -0,0 +1,261 @@
+#![allow(clippy::enum_variant_names)]
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use ethers_contract::builders::ContractCall;
+use ethers_core::types::U256 as EthersU256;
+use tracing::instrument;
+
+use hyperlane_core::{
+    ChainCommunicationError, ChainResult, ContractLocator, HyperlaneAbi, HyperlaneChain,
+    HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProtocolError,
+    HyperlaneProvider, OptimisticIsm, PreVerification, RawHyperlaneMessage, TxCostEstimate,
+    TxOutcome, H160, H256, U256,
+};
+
+use crate::contracts::i_optimistic_ism::{
+    IOptimisticIsm as EthereumOptimisticIsmInternal, IOPTIMISTICISM_ABI,
+};
+use crate::trait_builder::BuildableWithProvider;
+use crate::tx::{fill_tx_gas_params, report_tx};
+use crate::EthereumProvider;
+
+/// The maximum number of blocks to query pre-verifications for at once
+const MAX_BLOCK_RANGE: u64 = 1000;
+
+impl<M> std::fmt::Display for EthereumOptimisticIsmInternal<M>
+where
+    M: Middleware,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub struct OptimisticIsmBuilder {}
+
+#[async_trait]
+impl BuildableWithProvider for OptimisticIsmBuilder {
+    type Output = Box<dyn OptimisticIsm>;
+
+    async fn build_with_provider<M: Middleware + 'static>(
+        &self,
+        provider: M,
+        locator: &ContractLocator,
+    ) -> Self::Output {
+        Box::new(EthereumOptimisticIsm::new(Arc::new(provider), locator))
+    }
+}
+
+/// A reference to an OptimisticIsm contract on some Ethereum chain
+#[derive(Debug)]
+pub struct EthereumOptimisticIsm<M>
+where
+    M: Middleware,
+{
+    contract: Arc<EthereumOptimisticIsmInternal<M>>,
+    domain: HyperlaneDomain,
+    provider: Arc<M>,
+}
+
+impl<M> EthereumOptimisticIsm<M>
+where
+    M: Middleware + 'static,
+{
+    /// Create a reference to an optimistic ISM at a specific Ethereum address
+    /// on some chain
+    pub fn new(provider: Arc<M>, locator: &ContractLocator) -> Self {
+        Self {
+            contract: Arc::new(EthereumOptimisticIsmInternal::new(
+                locator.address,
+                provider.clone(),
+            )),
+            domain: locator.domain.clone(),
+            provider,
+        }
+    }
+
+    async fn pre_verify_contract_call(
+        &self,
+        metadata: &[u8],
+        message: &HyperlaneMessage,
+        tx_gas_limit: Option<U256>,
+    ) -> ChainResult<ContractCall<M, bool>> {
+        let tx = self.contract.pre_verify(
+            metadata.to_vec().into(),
+            RawHyperlaneMessage::from(message).to_vec().into(),
+        );
+        fill_tx_gas_params(tx, tx_gas_limit, self.provider.clone()).await
+    }
+}
+
+impl<M> HyperlaneChain for EthereumOptimisticIsm<M>
+where
+    M: Middleware + 'static,
+{
+    fn domain(&self) -> &HyperlaneDomain {
+        &self.domain
+    }
+
+    fn provider(&self) -> Box<dyn HyperlaneProvider> {
+        Box::new(EthereumProvider::new(
+            self.contract.client(),
+            self.domain.clone(),
+        ))
+    }
+}
+
+impl<M> HyperlaneContract for EthereumOptimisticIsm<M>
+where
+    M: Middleware + 'static,
+{
+    fn address(&self) -> H256 {
+        self.contract.address().into()
+    }
+}
+
+#[async_trait]
+impl<M> OptimisticIsm for EthereumOptimisticIsm<M>
+where
+    M: Middleware + 'static,
+{
+    #[instrument(err)]
+    async fn submodule(&self, message: &HyperlaneMessage) -> ChainResult<H256> {
+        let submodule = self
+            .contract
+            .submodule(RawHyperlaneMessage::from(message).to_vec().into())
+            .call()
+            .await?;
+        Ok(submodule.into())
+    }
+
+    #[instrument(err)]
+    async fn fraud_window(&self) -> ChainResult<u64> {
+        let fraud_window = self.contract.fraud_window().call().await?;
+        Ok(fraud_window.min(EthersU256::from(u64::MAX)).as_u64())
+    }
+
+    #[instrument(err)]
+    async fn pre_verified_at(&self, message: &HyperlaneMessage) -> ChainResult<Option<u64>> {
+        let timestamp = self
+            .contract
+            .pre_verified_at(message.id().into())
+            .call()
+            .await?;
+        Ok((!timestamp.is_zero()).then(|| timestamp.min(EthersU256::from(u64::MAX)).as_u64()))
+    }
+
+    #[instrument(skip(self, metadata), err)]
+    async fn pre_verify(
+        &self,
+        metadata: &[u8],
+        message: &HyperlaneMessage,
+        tx_gas_limit: Option<U256>,
+    ) -> ChainResult<TxOutcome> {
+        let contract_call = self
+            .pre_verify_contract_call(metadata, message, tx_gas_limit)
+            .await?;
+        let receipt = report_tx(contract_call).await?;
+        Ok(receipt.into())
+    }
+
+    #[instrument(skip(self, metadata), err)]
+    async fn pre_verify_estimate_costs(
+        &self,
+        metadata: &[u8],
+        message: &HyperlaneMessage,
+    ) -> ChainResult<TxCostEstimate> {
+        let contract_call = self
+            .pre_verify_contract_call(metadata, message, None)
+            .await?;
+        let gas_limit = contract_call
+            .tx
+            .gas()
+            .copied()
+            .ok_or(HyperlaneProtocolError::ProcessGasLimitRequired)?;
+        let gas_price: U256 = self
+            .provider
+            .get_gas_price()
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into();
+
+        Ok(TxCostEstimate {
+            gas_limit: gas_limit.into(),
+            gas_price: gas_price.try_into()?,
+            l2_gas_limit: None,
+        })
+    }
+
+    #[instrument(err)]
+    async fn is_fraudulent(&self, submodule: H256) -> ChainResult<bool> {
+        Ok(self
+            .contract
+            .is_fraudulent(H160::from(submodule).into())
+            .call()
+            .await?)
+    }
+
+    #[instrument(err)]
+    async fn mark_fraudulent(&self, submodule: H256) -> ChainResult<TxOutcome> {
+        let tx = self.contract.mark_fraudulent(H160::from(submodule).into());
+        let contract_call = fill_tx_gas_params(tx, None, self.provider.clone()).await?;
+        let receipt = report_tx(contract_call).await?;
+        Ok(receipt.into())
+    }
+
+    #[instrument(err)]
+    async fn pre_verifications(
+        &self,
+        from_block: Option<u64>,
+    ) -> ChainResult<(Vec<PreVerification>, u64)> {
+        let tip = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .as_u64();
+        let from_block = from_block.unwrap_or(tip);
+        if from_block > tip {
+            return Ok((vec![], from_block));
+        }
+        let to_block = tip.min(from_block + MAX_BLOCK_RANGE - 1);
+
+        let events = self
+            .contract
+            .pre_verified_filter()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query()
+            .await?;
+        let mut pre_verifications = Vec::with_capacity(events.len());
+        for event in events {
+            let pre_verified_at = self
+                .contract
+                .pre_verified_at(event.message_id)
+                .call()
+                .await?;
+            pre_verifications.push(PreVerification {
+                message_id: event.message_id.into(),
+                origin: event.origin,
+                submodule: event.submodule.into(),
+                pre_verified_at: pre_verified_at.min(EthersU256::from(u64::MAX)).as_u64(),
+            });
+        }
+        Ok((pre_verifications, to_block + 1))
+    }
+}
+
+pub struct EthereumOptimisticIsmAbi;
+
+impl HyperlaneAbi for EthereumOptimisticIsmAbi {
+    const SELECTOR_SIZE_BYTES: usize = 4;
+
+    fn fn_map() -> HashMap<Vec<u8>, &'static str> {
+        super::extract_fn_map(&IOPTIMISTICISM_ABI)
+    }
+}
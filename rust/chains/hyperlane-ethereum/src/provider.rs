@@ -14,6 +14,7 @@ use tracing::instrument;
 use hyperlane_core::{
     BlockInfo, ChainCommunicationError, ChainResult, ContractLocator, HyperlaneChain,
     HyperlaneDomain, HyperlaneProvider, HyperlaneProviderError, TxnInfo, TxnReceiptInfo, H256,
+    H512,
 };
 
 use crate::BuildableWithProvider;
@@ -49,10 +50,14 @@ where
 {
     #[instrument(err, skip(self))]
     async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo> {
-        let block = get_with_retry_on_none(hash, |h| {
-            let eth_h256: ethers_core_types::H256 = h.into();
-            self.provider.get_block(eth_h256)
-        })
+        let block = get_with_retry_on_none(
+            hash,
+            |h| {
+                let eth_h256: ethers_core_types::H256 = h.into();
+                self.provider.get_block(eth_h256)
+            },
+            |h| HyperlaneProviderError::CouldNotFindObjectByHash(*h),
+        )
         .await?;
         Ok(BlockInfo {
             hash: *hash,
@@ -65,11 +70,35 @@ where
     }
 
     #[instrument(err, skip(self))]
-    async fn get_txn_by_hash(&self, hash: &H256) -> ChainResult<TxnInfo> {
-        let txn = get_with_retry_on_none(hash, |h| self.provider.get_transaction(*h)).await?;
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+        let block = get_with_retry_on_none(
+            &height,
+            |h| self.provider.get_block(*h),
+            |h| HyperlaneProviderError::CouldNotFindBlockByHeight(*h),
+        )
+        .await?;
+        Ok(BlockInfo {
+            hash: block
+                .hash
+                .ok_or(HyperlaneProviderError::CouldNotFindBlockByHeight(height))?
+                .into(),
+            timestamp: block.timestamp.as_u64(),
+            number: height,
+        })
+    }
+
+    #[instrument(err, skip(self))]
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+        let txn_hash: H256 = (*hash).into();
+        let txn = get_with_retry_on_none(
+            &txn_hash,
+            |h| self.provider.get_transaction(*h),
+            |_| HyperlaneProviderError::CouldNotFindTransactionByHash(*hash),
+        )
+        .await?;
         let receipt = self
             .provider
-            .get_transaction_receipt(*hash)
+            .get_transaction_receipt(txn_hash)
             .await
             .map_err(ChainCommunicationError::from_other)?
             .map(|r| -> Result<_, HyperlaneProviderError> {
@@ -143,6 +172,16 @@ where
         );
         Ok(Some(chain_metrics))
     }
+
+    #[instrument(err, skip(self))]
+    async fn get_chain_id(&self) -> ChainResult<Option<U256>> {
+        let chain_id = self
+            .provider
+            .get_chainid()
+            .await
+            .map_err(ChainCommunicationError::from_other)?;
+        Ok(Some(chain_id.into()))
+    }
 }
 
 impl<M> EthereumProvider<M>
@@ -186,22 +225,23 @@ impl BuildableWithProvider for HyperlaneProviderBuilder {
 /// Call a get function that returns a Result<Option<T>> and retry if the inner
 /// option is None. This can happen because the provider has not discovered the
 /// object we are looking for yet.
-async fn get_with_retry_on_none<T, F, O, E>(hash: &H256, get: F) -> ChainResult<T>
+async fn get_with_retry_on_none<I, T, F, O, E>(
+    id: &I,
+    get: F,
+    not_found: impl FnOnce(&I) -> HyperlaneProviderError,
+) -> ChainResult<T>
 where
-    F: Fn(&H256) -> O,
+    F: Fn(&I) -> O,
     O: Future<Output = Result<Option<T>, E>>,
     E: std::error::Error + Send + Sync + 'static,
 {
     for _ in 0..3 {
-        if let Some(t) = get(hash)
-            .await
-            .map_err(ChainCommunicationError::from_other)?
-        {
+        if let Some(t) = get(id).await.map_err(ChainCommunicationError::from_other)? {
             return Ok(t);
         } else {
             sleep(Duration::from_secs(5)).await;
             continue;
         };
     }
-    Err(HyperlaneProviderError::CouldNotFindObjectByHash(*hash).into())
+    Err(not_found(id).into())
 }
@@ -2,7 +2,7 @@ use derive_new::new;
 use hyperlane_core::rpc_clients::{BlockNumberGetter, FallbackProvider};
 use std::fmt::{Debug, Formatter};
 use std::ops::Deref;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 use async_trait::async_trait;
@@ -99,19 +99,29 @@ where
             if !errors.is_empty() {
                 sleep(Duration::from_millis(100)).await;
             }
-            let priorities_snapshot = self.take_priorities_snapshot().await;
+            let priorities_snapshot = self.take_callable_priorities_snapshot().await;
             for (idx, priority) in priorities_snapshot.iter().enumerate() {
                 let provider = &self.inner.providers[priority.index];
                 let fut = match params {
                     Value::Null => provider.request(method, ()),
                     _ => provider.request(method, &params),
                 };
+                let start = Instant::now();
                 let resp = fut.await;
+                let latency = start.elapsed();
+                let categorized = {
+                    let _span =
+                        warn_span!("request", fallback_count=%idx, provider_index=%priority.index, ?provider).entered();
+                    categorize_client_response(method, resp)
+                };
+                // Non-retryable errors are caused by the request rather than the provider,
+                // so they don't count against its health
+                let provider_healthy = !matches!(categorized, RetryableErr(_) | RateLimitErr(_));
+                self.handle_provider_response(priority, latency, provider_healthy)
+                    .await;
                 self.handle_stalled_provider(priority, provider).await;
-                let _span =
-                    warn_span!("request", fallback_count=%idx, provider_index=%priority.index, ?provider).entered();
 
-                match categorize_client_response(method, resp) {
+                match categorized {
                     IsOk(v) => return Ok(serde_json::from_value(v)?),
                     RetryableErr(e) | RateLimitErr(e) => errors.push(e.into()),
                     NonRetryableErr(e) => return Err(e.into()),
@@ -10,7 +10,7 @@ use ethers::prelude::{
     Http, JsonRpcClient, Middleware, NonceManagerMiddleware, Provider, Quorum, QuorumProvider,
     SignerMiddleware, WeightedProvider, Ws, WsClientError,
 };
-use hyperlane_core::rpc_clients::FallbackProvider;
+use hyperlane_core::rpc_clients::{FallbackProvider, ProviderHealthObserver};
 use reqwest::{Client, Url};
 use thiserror::Error;
 
@@ -62,6 +62,7 @@ pub trait BuildableWithProvider {
         signer: Option<Signers>,
         rpc_metrics: Option<JsonRpcClientMetrics>,
         middleware_metrics: Option<(MiddlewareMetrics, PrometheusMiddlewareConf)>,
+        fallback_health_observer: Option<Arc<dyn ProviderHealthObserver>>,
     ) -> ChainResult<Self::Output> {
         Ok(match conn {
             ConnectionConf::HttpQuorum { urls } => {
@@ -111,6 +112,9 @@ pub trait BuildableWithProvider {
                     );
                     builder = builder.add_provider(metrics_provider);
                 }
+                if let Some(observer) = fallback_health_observer {
+                    builder = builder.with_health_observer(observer);
+                }
                 let fallback_provider = builder.build();
                 let ethereum_fallback_provider = EthereumFallbackProvider::<
                     _,
@@ -0,0 +1,200 @@
+#![allow(clippy::enum_variant_names)]
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use tracing::instrument;
+
+use hyperlane_core::{
+    ChainResult, ContractLocator, HyperlaneAbi, HyperlaneChain, HyperlaneContract, HyperlaneDomain,
+    HyperlaneMessage, HyperlaneProvider, RawHyperlaneMessage, WeightedMultisigIsm,
+    WeightedValidator, H256, U256,
+};
+
+use crate::contracts::i_weighted_multisig_ism::{
+    IWeightedMultisigIsm as EthereumWeightedMultisigIsmInternal, IWEIGHTEDMULTISIGISM_ABI,
+};
+use crate::trait_builder::BuildableWithProvider;
+use crate::EthereumProvider;
+
+impl<M> std::fmt::Display for EthereumWeightedMultisigIsmInternal<M>
+where
+    M: Middleware,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub struct WeightedMultisigIsmBuilder {}
+
+#[async_trait]
+impl BuildableWithProvider for WeightedMultisigIsmBuilder {
+    type Output = Box<dyn WeightedMultisigIsm>;
+
+    async fn build_with_provider<M: Middleware + 'static>(
+        &self,
+        provider: M,
+        locator: &ContractLocator,
+    ) -> Self::Output {
+        Box::new(EthereumWeightedMultisigIsm::new(
+            Arc::new(provider),
+            locator,
+        ))
+    }
+}
+
+/// A reference to a WeightedMultisigIsm contract on some Ethereum chain
+#[derive(Debug)]
+pub struct EthereumWeightedMultisigIsm<M>
+where
+    M: Middleware,
+{
+    contract: Arc<EthereumWeightedMultisigIsmInternal<M>>,
+    domain: HyperlaneDomain,
+}
+
+impl<M> EthereumWeightedMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    /// Create a reference to a weighted multisig ISM at a specific Ethereum
+    /// address on some chain
+    pub fn new(provider: Arc<M>, locator: &ContractLocator) -> Self {
+        Self {
+            contract: Arc::new(EthereumWeightedMultisigIsmInternal::new(
+                locator.address,
+                provider,
+            )),
+            domain: locator.domain.clone(),
+        }
+    }
+}
+
+impl<M> HyperlaneChain for EthereumWeightedMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    fn domain(&self) -> &HyperlaneDomain {
+        &self.domain
+    }
+
+    fn provider(&self) -> Box<dyn HyperlaneProvider> {
+        Box::new(EthereumProvider::new(
+            self.contract.client(),
+            self.domain.clone(),
+        ))
+    }
+}
+
+impl<M> HyperlaneContract for EthereumWeightedMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    fn address(&self) -> H256 {
+        self.contract.address().into()
+    }
+}
+
+#[async_trait]
+impl<M> WeightedMultisigIsm for EthereumWeightedMultisigIsm<M>
+where
+    M: Middleware + 'static,
+{
+    #[instrument(err)]
+    async fn validators_and_threshold_weight(
+        &self,
+        message: &HyperlaneMessage,
+    ) -> ChainResult<(Vec<WeightedValidator>, U256)> {
+        let (validator_infos, threshold_weight) = self
+            .contract
+            .validators_and_threshold_weight(RawHyperlaneMessage::from(message).to_vec().into())
+            .call()
+            .await?;
+        let validators = validator_infos
+            .iter()
+            .map(|info| WeightedValidator {
+                address: info.signing_address.into(),
+                weight: info.weight.into(),
+            })
+            .collect();
+        Ok((validators, threshold_weight.into()))
+    }
+}
+
+pub struct EthereumWeightedMultisigIsmAbi;
+
+impl HyperlaneAbi for EthereumWeightedMultisigIsmAbi {
+    const SELECTOR_SIZE_BYTES: usize = 4;
+
+    fn fn_map() -> HashMap<Vec<u8>, &'static str> {
+        super::extract_fn_map(&IWEIGHTEDMULTISIGISM_ABI)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ethers::{
+        abi::{encode, Token},
+        providers::{MockProvider, Provider},
+        types::{Bytes, H160 as EthersH160, U256 as EthersU256},
+    };
+    use hyperlane_core::{
+        ContractLocator, HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain,
+        WeightedMultisigIsm, WeightedValidator, H256, U256,
+    };
+
+    use crate::EthereumWeightedMultisigIsm;
+
+    #[tokio::test]
+    async fn decodes_validators_and_threshold_weight() {
+        let mock_provider = Arc::new(MockProvider::new());
+        let ism = EthereumWeightedMultisigIsm::new(
+            Arc::new(Provider::new(mock_provider.clone())),
+            &ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+                // Address doesn't matter because we're using a MockProvider
+                address: H256::default(),
+            },
+        );
+        let returned = encode(&[
+            Token::Array(
+                [(1, 60), (2, 40)]
+                    .into_iter()
+                    .map(|(byte, weight)| {
+                        Token::Tuple(vec![
+                            Token::Address(EthersH160::repeat_byte(byte)),
+                            Token::Uint(EthersU256::from(weight)),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Token::Uint(EthersU256::from(66)),
+        ]);
+        mock_provider.push(Bytes::from(returned)).unwrap();
+
+        let (validators, threshold_weight) = ism
+            .validators_and_threshold_weight(&HyperlaneMessage::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            validators,
+            vec![
+                WeightedValidator {
+                    address: EthersH160::repeat_byte(1).into(),
+                    weight: U256::from(60),
+                },
+                WeightedValidator {
+                    address: EthersH160::repeat_byte(2).into(),
+                    weight: U256::from(40),
+                },
+            ]
+        );
+        assert_eq!(threshold_weight, U256::from(66));
+    }
+}
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use hyperlane_core::{
     config::StrOrIntParseError, ChainCommunicationError, ChainResult, ContractLocator,
     HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneProvider, Indexer,
-    InterchainGasPaymaster, InterchainGasPayment, LogMeta, SequenceAwareIndexer, H256, H512,
+    InterchainGasPaymaster, InterchainGasPayment, LogMeta, SequenceAwareIndexer, H256,
 };
 use hyperlane_sealevel_igp::{
     accounts::{GasPaymentAccount, ProgramDataAccount},
@@ -17,7 +17,9 @@ use std::ops::RangeInclusive;
 use tracing::{info, instrument};
 
 use crate::{
-    client::RpcClientWithDebug, utils::get_finalized_block_number, ConnectionConf, SealevelProvider,
+    client::RpcClientWithDebug,
+    utils::{get_finalized_block_number, get_log_block_hash_and_transaction_id},
+    ConnectionConf, SealevelProvider,
 };
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 
@@ -101,13 +103,17 @@ impl InterchainGasPaymaster for SealevelInterchainGasPaymaster {}
 pub struct SealevelInterchainGasPaymasterIndexer {
     rpc_client: RpcClientWithDebug,
     igp: SealevelInterchainGasPaymaster,
+    /// Whether to look up the block hash and transaction id of payments
+    log_meta: bool,
 }
 
 /// IGP payment data on Sealevel
 #[derive(Debug, new)]
 pub struct SealevelGasPayment {
     payment: InterchainGasPayment,
-    log_meta: LogMeta,
+    sequence_number: u64,
+    slot: u64,
+    payment_pda_pubkey: Pubkey,
     igp_account_pubkey: H256,
 }
 
@@ -124,7 +130,18 @@ impl SealevelInterchainGasPaymasterIndexer {
         );
 
         let igp = SealevelInterchainGasPaymaster::new(conf, &igp_account_locator).await?;
-        Ok(Self { rpc_client, igp })
+        Ok(Self {
+            rpc_client,
+            igp,
+            log_meta: false,
+        })
+    }
+
+    /// Look up the block hash and transaction id of each payment, which takes
+    /// two more RPC calls per payment. Otherwise they are zeroed.
+    pub fn with_log_meta(mut self, log_meta: bool) -> Self {
+        self.log_meta = log_meta;
+        self
     }
 
     #[instrument(err, skip(self))]
@@ -224,19 +241,32 @@ impl SealevelInterchainGasPaymasterIndexer {
 
         Ok(SealevelGasPayment::new(
             igp_payment,
-            LogMeta {
-                address: self.igp.program_id.to_bytes().into(),
-                block_number: gas_payment_account.slot,
-                // TODO: get these when building out scraper support.
-                // It's inconvenient to get these :|
-                block_hash: H256::zero(),
-                transaction_id: H512::zero(),
-                transaction_index: 0,
-                log_index: sequence_number.into(),
-            },
+            sequence_number,
+            gas_payment_account.slot,
+            valid_payment_pda_pubkey,
             H256::from(gas_payment_account.igp.to_bytes()),
         ))
     }
+
+    /// Metadata for the event of a gas payment, looked up separately as it
+    /// takes a couple more RPC calls.
+    async fn log_meta(&self, payment: &SealevelGasPayment) -> ChainResult<LogMeta> {
+        let (block_hash, transaction_id) = get_log_block_hash_and_transaction_id(
+            &self.rpc_client,
+            &payment.payment_pda_pubkey,
+            payment.slot,
+            self.log_meta,
+        )
+        .await?;
+        Ok(LogMeta {
+            address: self.igp.program_id.to_bytes().into(),
+            block_number: payment.slot,
+            block_hash,
+            transaction_id,
+            transaction_index: 0,
+            log_index: payment.sequence_number.into(),
+        })
+    }
 }
 
 #[async_trait]
@@ -257,7 +287,8 @@ impl Indexer<InterchainGasPayment> for SealevelInterchainGasPaymasterIndexer {
             if let Ok(sealevel_payment) = self.get_payment_with_sequence(nonce.into()).await {
                 let igp_account_filter = self.igp.igp_account;
                 if igp_account_filter == sealevel_payment.igp_account_pubkey {
-                    payments.push((sealevel_payment.payment, sealevel_payment.log_meta));
+                    let log_meta = self.log_meta(&sealevel_payment).await?;
+                    payments.push((sealevel_payment.payment, log_meta));
                 } else {
                     tracing::debug!(sealevel_payment=?sealevel_payment, igp_account_filter=?igp_account_filter, "Found interchain gas payment for a different IGP account, skipping");
                 }
@@ -17,7 +17,7 @@ use hyperlane_sealevel_interchain_security_module_interface::{
     InterchainSecurityModuleInstruction, VerifyInstruction,
 };
 use hyperlane_sealevel_mailbox::{
-    accounts::{DispatchedMessageAccount, InboxAccount, OutboxAccount},
+    accounts::{DispatchedMessageAccount, InboxAccount, OutboxAccount, ProcessedMessageAccount},
     instruction::InboxProcess,
     mailbox_dispatched_message_pda_seeds, mailbox_inbox_pda_seeds, mailbox_outbox_pda_seeds,
     mailbox_process_authority_pda_seeds, mailbox_processed_message_pda_seeds,
@@ -53,7 +53,10 @@ use solana_transaction_status::{
 
 use crate::RpcClientWithDebug;
 use crate::{
-    utils::{get_account_metas, get_finalized_block_number, simulate_instruction},
+    utils::{
+        get_account_metas, get_finalized_block_number, get_log_block_hash_and_transaction_id,
+        simulate_instruction,
+    },
     ConnectionConf, SealevelProvider,
 };
 
@@ -499,6 +502,8 @@ impl Mailbox for SealevelMailbox {
 pub struct SealevelMailboxIndexer {
     mailbox: SealevelMailbox,
     program_id: Pubkey,
+    /// Whether to look up the block hash and transaction id of events
+    log_meta: bool,
 }
 
 impl SealevelMailboxIndexer {
@@ -508,9 +513,17 @@ impl SealevelMailboxIndexer {
         Ok(Self {
             program_id,
             mailbox,
+            log_meta: false,
         })
     }
 
+    /// Look up the block hash and transaction id of each event, which takes
+    /// two more RPC calls per event. Otherwise they are zeroed.
+    pub fn with_log_meta(mut self, log_meta: bool) -> Self {
+        self.log_meta = log_meta;
+        self
+    }
+
     fn rpc(&self) -> &RpcClientWithDebug {
         &self.mailbox.rpc()
     }
@@ -612,20 +625,136 @@ impl SealevelMailboxIndexer {
                 .into_inner();
         let hyperlane_message =
             HyperlaneMessage::read_from(&mut &dispatched_message_account.encoded_message[..])?;
+        let log_meta = self
+            .log_meta(
+                &valid_message_storage_pda_pubkey,
+                dispatched_message_account.slot,
+            )
+            .await?;
+
+        Ok((hyperlane_message, log_meta))
+    }
+
+    async fn get_delivered_message_with_sequence(
+        &self,
+        sequence: u32,
+    ) -> ChainResult<(H256, LogMeta)> {
+        let target_message_account_bytes = &[
+            &hyperlane_sealevel_mailbox::accounts::PROCESSED_MESSAGE_DISCRIMINATOR[..],
+            &u64::from(sequence).to_le_bytes()[..],
+        ]
+        .concat();
+        let target_message_account_bytes = base64::encode(target_message_account_bytes);
 
-        Ok((
-            hyperlane_message,
-            LogMeta {
-                address: self.mailbox.program_id.to_bytes().into(),
-                block_number: dispatched_message_account.slot,
-                // TODO: get these when building out scraper support.
-                // It's inconvenient to get these :|
-                block_hash: H256::zero(),
-                transaction_id: H512::zero(),
-                transaction_index: 0,
-                log_index: U256::zero(),
+        // As with dispatched messages, only request the `message_id` field of
+        // the matching accounts so the PDAs can be validated.
+        let memcmp = RpcFilterType::Memcmp(Memcmp {
+            // Ignore the first byte, which is the `initialized` bool flag.
+            offset: 1,
+            bytes: MemcmpEncodedBytes::Base64(target_message_account_bytes),
+            encoding: None,
+        });
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![memcmp]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 1 + 8 + 8, // the offset to get the `message_id` field
+                    length: 32,        // the length of the `message_id` field
+                }),
+                commitment: Some(CommitmentConfig::finalized()),
+                min_context_slot: None,
             },
-        ))
+            with_context: Some(false),
+        };
+        let accounts = self
+            .rpc()
+            .get_program_accounts_with_config(&self.mailbox.program_id, config)
+            .await
+            .map_err(ChainCommunicationError::from_other)?;
+
+        let mut valid_processed_message_pda_pubkey = Option::<Pubkey>::None;
+        for (pubkey, account) in accounts {
+            let message_id = H256::from_slice(&account.data);
+            let (expected_pubkey, _bump) = Pubkey::try_find_program_address(
+                mailbox_processed_message_pda_seeds!(message_id),
+                &self.mailbox.program_id,
+            )
+            .ok_or_else(|| {
+                ChainCommunicationError::from_other_str(
+                    "Could not find program address for message_id",
+                )
+            })?;
+            if expected_pubkey == pubkey {
+                valid_processed_message_pda_pubkey = Some(pubkey);
+                break;
+            }
+        }
+
+        let valid_processed_message_pda_pubkey =
+            valid_processed_message_pda_pubkey.ok_or_else(|| {
+                ChainCommunicationError::from_other_str(
+                    "Could not find valid processed message PDA pubkey",
+                )
+            })?;
+
+        let account = self
+            .rpc()
+            .get_account_with_commitment(
+                &valid_processed_message_pda_pubkey,
+                CommitmentConfig::finalized(),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .value
+            .ok_or_else(|| {
+                ChainCommunicationError::from_other_str("Could not find account data")
+            })?;
+        let processed_message_account = ProcessedMessageAccount::fetch(&mut account.data.as_ref())
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+        let log_meta = self
+            .log_meta(
+                &valid_processed_message_pda_pubkey,
+                processed_message_account.slot,
+            )
+            .await?;
+
+        Ok((processed_message_account.message_id, log_meta))
+    }
+
+    /// Metadata for the event of the mailbox which created `account` in
+    /// `slot`.
+    async fn log_meta(&self, account: &Pubkey, slot: u64) -> ChainResult<LogMeta> {
+        let (block_hash, transaction_id) =
+            get_log_block_hash_and_transaction_id(self.rpc(), account, slot, self.log_meta).await?;
+        Ok(LogMeta {
+            address: self.mailbox.program_id.to_bytes().into(),
+            block_number: slot,
+            block_hash,
+            transaction_id,
+            transaction_index: 0,
+            log_index: U256::zero(),
+        })
+    }
+
+    async fn processed_count(&self) -> ChainResult<u32> {
+        let inbox_account = self
+            .rpc()
+            .get_account_with_commitment(&self.mailbox.inbox.0, CommitmentConfig::finalized())
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .value
+            .ok_or_else(|| {
+                ChainCommunicationError::from_other_str("Could not find inbox account")
+            })?;
+        let inbox = InboxAccount::fetch(&mut inbox_account.data.as_ref())
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+        inbox
+            .processed_count
+            .try_into()
+            .map_err(ChainCommunicationError::from_other)
     }
 }
 
@@ -633,9 +762,10 @@ impl SealevelMailboxIndexer {
 impl SequenceAwareIndexer<HyperlaneMessage> for SealevelMailboxIndexer {
     #[instrument(err, skip(self))]
     async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
-        let tip = Indexer::<HyperlaneMessage>::get_finalized_block_number(self).await?;
-        // TODO: need to make sure the call and tip are at the same height?
+        // The count is read before the tip, so every counted message was
+        // dispatched at or below the tip.
         let count = Mailbox::count(&self.mailbox, None).await?;
+        let tip = Indexer::<HyperlaneMessage>::get_finalized_block_number(self).await?;
         Ok((Some(count), tip))
     }
 }
@@ -666,8 +796,15 @@ impl Indexer<HyperlaneMessage> for SealevelMailboxIndexer {
 
 #[async_trait]
 impl Indexer<H256> for SealevelMailboxIndexer {
-    async fn fetch_logs(&self, _range: RangeInclusive<u32>) -> ChainResult<Vec<(H256, LogMeta)>> {
-        todo!()
+    async fn fetch_logs(&self, range: RangeInclusive<u32>) -> ChainResult<Vec<(H256, LogMeta)>> {
+        info!(?range, "Fetching SealevelMailboxIndexer H256 delivery logs");
+
+        let delivery_capacity = range.end().saturating_sub(*range.start());
+        let mut deliveries = Vec::with_capacity(delivery_capacity as usize);
+        for sequence in range {
+            deliveries.push(self.get_delivered_message_with_sequence(sequence).await?);
+        }
+        Ok(deliveries)
     }
 
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
@@ -678,10 +815,11 @@ impl Indexer<H256> for SealevelMailboxIndexer {
 #[async_trait]
 impl SequenceAwareIndexer<H256> for SealevelMailboxIndexer {
     async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
-        // TODO: implement when sealevel scraper support is implemented
-        info!("Message delivery indexing not implemented");
+        // The count is read before the tip, so every counted message was
+        // processed at or below the tip.
+        let count = self.processed_count().await?;
         let tip = Indexer::<H256>::get_finalized_block_number(self).await?;
-        Ok((Some(1), tip))
+        Ok((Some(count), tip))
     }
 }
 
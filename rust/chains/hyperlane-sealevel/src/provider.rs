@@ -1,14 +1,30 @@
 use std::{str::FromStr, sync::Arc};
 
 use async_trait::async_trait;
-
+use borsh::BorshDeserialize;
 use hyperlane_core::{
-    BlockInfo, ChainInfo, ChainResult, HyperlaneChain, HyperlaneDomain, HyperlaneProvider, TxnInfo,
-    H256, U256,
+    BlockInfo, ChainCommunicationError, ChainInfo, ChainResult, HyperlaneChain, HyperlaneDomain,
+    HyperlaneProvider, HyperlaneProviderError, TxnInfo, TxnReceiptInfo, H256, H512, U256,
+};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction, pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{
+    client::RpcClientWithDebug, error::HyperlaneSealevelError, utils::get_block_info,
+    ConnectionConf,
 };
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 
-use crate::{client::RpcClientWithDebug, error::HyperlaneSealevelError, ConnectionConf};
+/// The compute unit limit of each instruction in a transaction which does
+/// not request a limit through the compute budget program.
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Fees are reported per compute unit in micro-lamports, the same unit
+/// priority fees are set in.
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
 
 /// A wrapper around a Sealevel provider to get generic blockchain information.
 #[derive(Debug)]
@@ -61,12 +77,86 @@ impl HyperlaneChain for SealevelProvider {
 
 #[async_trait]
 impl HyperlaneProvider for SealevelProvider {
-    async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
-        todo!() // FIXME
+    async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo> {
+        // Sealevel RPCs can only look blocks up by slot
+        Err(HyperlaneProviderError::CouldNotFindObjectByHash(*hash).into())
+    }
+
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo> {
+        get_block_info(&self.rpc_client, height).await
     }
 
-    async fn get_txn_by_hash(&self, _hash: &H256) -> ChainResult<TxnInfo> {
-        todo!() // FIXME
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo> {
+        let signature = Signature::new(hash.as_bytes());
+        let txn = self
+            .rpc_client
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::finalized()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .map_err(Into::<HyperlaneSealevelError>::into)?;
+        let meta = txn
+            .transaction
+            .meta
+            .ok_or(HyperlaneProviderError::CouldNotFindTransactionByHash(*hash))?;
+        let transaction = txn.transaction.transaction.decode().ok_or_else(|| {
+            ChainCommunicationError::from_other_str("Could not decode transaction")
+        })?;
+        let account_keys = transaction.message.static_account_keys();
+        let instructions = transaction.message.instructions();
+
+        // Compute budget instructions configure the transaction, the other
+        // instructions are the ones that call into programs.
+        let mut compute_unit_limit = None;
+        let mut program_instructions = 0;
+        let mut recipient = None;
+        for instruction in instructions {
+            let Some(program_id) = account_keys.get(instruction.program_id_index as usize) else {
+                continue;
+            };
+            if *program_id == solana_sdk::compute_budget::id() {
+                if let Ok(ComputeBudgetInstruction::SetComputeUnitLimit(limit)) =
+                    ComputeBudgetInstruction::try_from_slice(&instruction.data)
+                {
+                    compute_unit_limit = Some(limit);
+                }
+                continue;
+            }
+            program_instructions += 1;
+            recipient.get_or_insert_with(|| H256::from(program_id.to_bytes()));
+        }
+        let gas_limit = compute_unit_limit
+            .unwrap_or(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT * program_instructions);
+        let gas_used = Option::<u64>::from(meta.compute_units_consumed).unwrap_or_default();
+        let effective_gas_price =
+            (gas_used > 0).then(|| U256::from(meta.fee) * MICRO_LAMPORTS_PER_LAMPORT / gas_used);
+
+        Ok(TxnInfo {
+            hash: *hash,
+            gas_limit: gas_limit.into(),
+            max_priority_fee_per_gas: None,
+            max_fee_per_gas: None,
+            gas_price: None,
+            // Sealevel txns are deduplicated by their recent blockhash rather
+            // than a sender nonce.
+            nonce: 0,
+            // The fee payer is always the first account
+            sender: account_keys
+                .first()
+                .map(|payer| H256::from(payer.to_bytes()))
+                .unwrap_or_default(),
+            recipient,
+            receipt: Some(TxnReceiptInfo {
+                gas_used: gas_used.into(),
+                cumulative_gas_used: gas_used.into(),
+                effective_gas_price,
+            }),
+        })
     }
 
     async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
@@ -81,4 +171,8 @@ impl HyperlaneProvider for SealevelProvider {
     async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
         Ok(None)
     }
+
+    async fn get_chain_id(&self) -> ChainResult<Option<U256>> {
+        Ok(None)
+    }
 }
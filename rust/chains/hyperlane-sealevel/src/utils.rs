@@ -1,17 +1,24 @@
+use std::str::FromStr;
+
 use base64::Engine;
 use borsh::{BorshDeserialize, BorshSerialize};
-use hyperlane_core::{ChainCommunicationError, ChainResult};
+use hyperlane_core::{BlockInfo, ChainCommunicationError, ChainResult, H256, H512};
 
 use serializable_account_meta::{SerializableAccountMeta, SimulationReturnData};
-use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcBlockConfig,
+};
 use solana_sdk::{
     commitment_config::CommitmentConfig,
+    hash::Hash,
     instruction::{AccountMeta, Instruction},
     message::Message,
-    signature::{Keypair, Signer},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
     transaction::Transaction,
 };
-use solana_transaction_status::UiReturnDataEncoding;
+use solana_transaction_status::{TransactionDetails, UiReturnDataEncoding};
 
 use crate::client::RpcClientWithDebug;
 
@@ -91,3 +98,87 @@ pub async fn get_finalized_block_number(rpc_client: &RpcClientWithDebug) -> Chai
         .expect("sealevel block height exceeds u32::MAX");
     Ok(height)
 }
+
+/// Get the finalized block produced in `slot`. Sealevel blocks are identified
+/// by their slot, so it is used as the block number.
+pub async fn get_block_info(rpc_client: &RpcClient, slot: u64) -> ChainResult<BlockInfo> {
+    let block = rpc_client
+        .get_block_with_config(
+            slot,
+            RpcBlockConfig {
+                transaction_details: Some(TransactionDetails::None),
+                rewards: Some(false),
+                commitment: Some(CommitmentConfig::finalized()),
+                max_supported_transaction_version: Some(0),
+                ..RpcBlockConfig::default()
+            },
+        )
+        .await
+        .map_err(ChainCommunicationError::from_other)?;
+    let hash = Hash::from_str(&block.blockhash).map_err(ChainCommunicationError::from_other)?;
+    // Not every node stores the time of the blocks it returns, so fall back
+    // to `getBlockTime` rather than recording the block at the unix epoch.
+    let timestamp = match block.block_time {
+        Some(block_time) => block_time,
+        None => rpc_client
+            .get_block_time(slot)
+            .await
+            .map_err(ChainCommunicationError::from_other)?,
+    };
+    Ok(BlockInfo {
+        hash: H256::from(hash.to_bytes()),
+        timestamp: timestamp as u64,
+        number: slot,
+    })
+}
+
+/// Find the signature of the transaction which created `account` in `slot`.
+///
+/// The accounts written by the Hyperlane programs for dispatched messages,
+/// processed messages and gas payments are written once on creation, so the
+/// successful transaction in their creation slot is the one which emitted the
+/// corresponding event.
+pub async fn get_account_creation_signature(
+    rpc_client: &RpcClient,
+    account: &Pubkey,
+    slot: u64,
+) -> ChainResult<H512> {
+    let signatures = rpc_client
+        .get_signatures_for_address_with_config(
+            account,
+            GetConfirmedSignaturesForAddress2Config {
+                commitment: Some(CommitmentConfig::finalized()),
+                ..GetConfirmedSignaturesForAddress2Config::default()
+            },
+        )
+        .await
+        .map_err(ChainCommunicationError::from_other)?;
+    let status = signatures
+        .iter()
+        .find(|status| status.slot == slot && status.err.is_none())
+        .ok_or_else(|| {
+            ChainCommunicationError::from_other_str(
+                "Could not find the transaction which created the account",
+            )
+        })?;
+    let signature =
+        Signature::from_str(&status.signature).map_err(ChainCommunicationError::from_other)?;
+    Ok(signature.into())
+}
+
+/// Look up the block hash and transaction id for the log of the creation of
+/// `account` in `slot`, which takes two RPC calls. Zeroes are returned
+/// without any calls unless `enabled`.
+pub async fn get_log_block_hash_and_transaction_id(
+    rpc_client: &RpcClient,
+    account: &Pubkey,
+    slot: u64,
+    enabled: bool,
+) -> ChainResult<(H256, H512)> {
+    if !enabled {
+        return Ok((H256::zero(), H512::zero()));
+    }
+    let block = get_block_info(rpc_client, slot).await?;
+    let transaction_id = get_account_creation_signature(rpc_client, account, slot).await?;
+    Ok((block.hash, transaction_id))
+}
@@ -1,7 +1,7 @@
-use std::{env, fmt::Debug, sync::Arc};
+use std::{collections::BTreeMap, env, fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
-use eyre::Result;
+use eyre::{bail, Result};
 use hyperlane_core::config::*;
 use tracing::info;
 
@@ -26,6 +26,10 @@ pub trait LoadableFromSettings: AsRef<Settings> + Sized {
     /// Create a new instance of these settings by reading the configs and env
     /// vars.
     fn load() -> ConfigResult<Self>;
+
+    /// Like `load`, but reads program arguments from `args` instead of the
+    /// process's arguments, e.g. to leave out arguments which aren't settings.
+    fn load_with_args(args: Vec<String>) -> ConfigResult<Self>;
 }
 
 /// A fundamental agent which does not make any assumptions about the tools
@@ -53,9 +57,15 @@ pub trait BaseAgent: Send + Sync + Debug {
     async fn run(self);
 }
 
+/// Program argument to validate the config instead of running the agent.
+const VALIDATE_CONFIG_ARG: &str = "--validateConfig";
+
 /// Call this from `main` to fully initialize and run the agent for its entire
 /// lifecycle. This assumes only a single agent is being run. This will
 /// initialize the metrics server and tracing as well.
+///
+/// If `--validateConfig` is passed, the config is validated and the agent is
+/// not run, see [`validate_config`].
 pub async fn agent_main<A: BaseAgent>() -> Result<()> {
     if env::var("ONELINE_BACKTRACES")
         .map(|v| v.to_lowercase())
@@ -71,6 +81,11 @@ pub async fn agent_main<A: BaseAgent>() -> Result<()> {
         color_eyre::install()?;
     }
 
+    let (validate, settings_args) = parse_validate_config_arg(env::args().skip(1).collect());
+    if let Some(connect) = validate {
+        return validate_config::<A>(connect, settings_args).await;
+    }
+
     let settings = A::Settings::load()?;
     let core_settings: &Settings = settings.as_ref();
 
@@ -85,3 +100,95 @@ pub async fn agent_main<A: BaseAgent>() -> Result<()> {
     info!(agent = A::AGENT_NAME, "Shutting down agent...");
     Ok(())
 }
+
+/// Loads the agent settings from `settings_args` and checks the core contract
+/// addresses of each chain, reporting every problem found. If `connect` is
+/// set, also connects to each chain to check its chain id and that the
+/// contracts are deployed, reporting the checks its protocol doesn't allow.
+pub async fn validate_config<A: BaseAgent>(
+    connect: bool,
+    settings_args: Vec<String>,
+) -> Result<()> {
+    let settings = A::Settings::load_with_args(settings_args)?;
+    let settings: &Settings = settings.as_ref();
+    let metrics = settings.metrics(A::AGENT_NAME)?;
+
+    let mut num_issues = 0;
+    for (name, chain) in settings.chains.iter().collect::<BTreeMap<_, _>>() {
+        let mut issues = chain.validate_addresses();
+        if connect {
+            issues.extend(chain.validate_connection(&metrics).await);
+            for skipped in chain.skipped_connection_checks() {
+                println!("chains.{name}: skipped: {skipped}");
+            }
+        }
+        for issue in &issues {
+            println!("chains.{name}: {issue}");
+        }
+        num_issues += issues.len();
+    }
+    if num_issues > 0 {
+        bail!(
+            "Found {num_issues} problem(s) in the {} config",
+            A::AGENT_NAME
+        );
+    }
+    println!("The {} config is valid", A::AGENT_NAME);
+    Ok(())
+}
+
+/// Splits `--validateConfig` and its optional `rpc` value off the program
+/// arguments, returning the remaining arguments to load the settings from.
+/// The first value is `Some` if `--validateConfig` was passed, which is `true`
+/// if its value is `rpc`, i.e. the chains should be connected to.
+fn parse_validate_config_arg(args: Vec<String>) -> (Option<bool>, Vec<String>) {
+    let mut validate = None;
+    let mut settings_args = Vec::with_capacity(args.len());
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg == VALIDATE_CONFIG_ARG {
+            let connect = args.next_if(|value| value == "rpc").is_some();
+            validate = Some(validate.unwrap_or_default() || connect);
+        } else if let Some(value) = arg
+            .strip_prefix(VALIDATE_CONFIG_ARG)
+            .and_then(|v| v.strip_prefix('='))
+        {
+            validate = Some(validate.unwrap_or_default() || value == "rpc");
+        } else {
+            settings_args.push(arg);
+        }
+    }
+    (validate, settings_args)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn strips_validate_config_arg_from_settings_args() {
+        let (validate, settings_args) =
+            parse_validate_config_arg(args(&["--validateConfig", "rpc", "--db", "/tmp/db"]));
+        assert_eq!(validate, Some(true));
+        assert_eq!(settings_args, args(&["--db", "/tmp/db"]));
+
+        // A following setting is not taken as the value
+        let (validate, settings_args) =
+            parse_validate_config_arg(args(&["--validateConfig", "--db", "/tmp/db"]));
+        assert_eq!(validate, Some(false));
+        assert_eq!(settings_args, args(&["--db", "/tmp/db"]));
+
+        let (validate, settings_args) =
+            parse_validate_config_arg(args(&["--relayChains=test1,test2", "--validateConfig=rpc"]));
+        assert_eq!(validate, Some(true));
+        assert_eq!(settings_args, args(&["--relayChains=test1,test2"]));
+
+        let (validate, settings_args) = parse_validate_config_arg(args(&["--db", "/tmp/db"]));
+        assert_eq!(validate, None);
+        assert_eq!(settings_args, args(&["--db", "/tmp/db"]));
+    }
+}
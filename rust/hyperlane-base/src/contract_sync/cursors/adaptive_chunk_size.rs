@@ -0,0 +1,144 @@
+use std::error::Error;
+
+use hyperlane_core::{ChainCommunicationError, IndexMode};
+use tracing::debug;
+
+use crate::settings::IndexSettings;
+
+/// Substrings of errors returned by providers when a queried block range is
+/// too large, e.g. because it contains too many logs or took too long.
+const RANGE_TOO_LARGE_ERRORS: &[&str] = &[
+    "query returned more than",
+    "too many",
+    "limit exceeded",
+    "response size",
+    "block range",
+    "range is too large",
+    "timed out",
+    "timeout",
+];
+
+/// Bounds within which the number of blocks queried at once is adapted.
+/// The chunk size is halved when a query fails because the range was too
+/// large, and doubled when a queried range contains no logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AdaptiveChunkSize {
+    min: u32,
+    max: u32,
+}
+
+impl AdaptiveChunkSize {
+    /// Returns `None` unless a chunk size bound is configured and indexing is
+    /// done by block. Unset bounds default to `1` and `chunk_size`.
+    pub fn from_settings(settings: &IndexSettings) -> Option<Self> {
+        if !matches!(settings.mode, IndexMode::Block)
+            || (settings.min_chunk_size.is_none() && settings.max_chunk_size.is_none())
+        {
+            return None;
+        }
+        let min = settings.min_chunk_size.unwrap_or(1).max(1);
+        let max = settings
+            .max_chunk_size
+            .unwrap_or(settings.chunk_size)
+            .max(min);
+        Some(Self { min, max })
+    }
+
+    /// Clamps `chunk_size` within the bounds.
+    pub fn clamp(&self, chunk_size: u32) -> u32 {
+        chunk_size.clamp(self.min, self.max)
+    }
+
+    /// The chunk size to use after querying a range failed with `err`.
+    pub fn after_error(&self, chunk_size: u32, err: &ChainCommunicationError) -> u32 {
+        if !is_range_too_large(err) {
+            return chunk_size;
+        }
+        let shrunk = self.clamp(chunk_size / 2);
+        if shrunk != chunk_size {
+            debug!(from = chunk_size, to = shrunk, "Shrinking chunk size");
+        }
+        shrunk
+    }
+
+    /// The chunk size to use after querying a range returned `num_logs` logs.
+    pub fn after_success(&self, chunk_size: u32, num_logs: usize) -> u32 {
+        if num_logs > 0 {
+            return chunk_size;
+        }
+        let grown = self.clamp(chunk_size.saturating_mul(2));
+        if grown != chunk_size {
+            debug!(from = chunk_size, to = grown, "Growing chunk size");
+        }
+        grown
+    }
+}
+
+/// Whether `err` or any of its sources indicates that the queried range was
+/// too large, so a smaller range is likely to succeed.
+fn is_range_too_large(err: &ChainCommunicationError) -> bool {
+    let mut source: Option<&dyn Error> = Some(err);
+    while let Some(err) = source {
+        let msg = err.to_string().to_lowercase();
+        if RANGE_TOO_LARGE_ERRORS.iter().any(|e| msg.contains(e)) {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn adaptive(min: u32, max: u32) -> AdaptiveChunkSize {
+        AdaptiveChunkSize::from_settings(&IndexSettings {
+            chunk_size: 100,
+            min_chunk_size: Some(min),
+            max_chunk_size: Some(max),
+            mode: IndexMode::Block,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_disabled_without_bounds_or_in_sequence_mode() {
+        assert!(AdaptiveChunkSize::from_settings(&IndexSettings {
+            chunk_size: 100,
+            mode: IndexMode::Block,
+            ..Default::default()
+        })
+        .is_none());
+        assert!(AdaptiveChunkSize::from_settings(&IndexSettings {
+            chunk_size: 100,
+            min_chunk_size: Some(10),
+            mode: IndexMode::Sequence,
+            ..Default::default()
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn test_shrinks_on_range_too_large_errors() {
+        let adaptive = adaptive(30, 400);
+        let too_many =
+            ChainCommunicationError::from_other_str("query returned more than 10000 results");
+        assert_eq!(adaptive.after_error(100, &too_many), 50);
+        assert_eq!(adaptive.after_error(50, &too_many), 30);
+        assert_eq!(adaptive.after_error(30, &too_many), 30);
+
+        let unrelated = ChainCommunicationError::from_other_str("execution reverted");
+        assert_eq!(adaptive.after_error(100, &unrelated), 100);
+    }
+
+    #[test]
+    fn test_grows_on_empty_ranges() {
+        let adaptive = adaptive(30, 400);
+        assert_eq!(adaptive.after_success(100, 1), 100);
+        assert_eq!(adaptive.after_success(100, 0), 200);
+        assert_eq!(adaptive.after_success(200, 0), 400);
+        assert_eq!(adaptive.after_success(400, 0), 400);
+    }
+}
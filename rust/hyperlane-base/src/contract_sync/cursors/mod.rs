@@ -17,8 +17,10 @@ use tracing::warn;
 
 use crate::contract_sync::eta_calculator::SyncerEtaCalculator;
 
+mod adaptive_chunk_size;
 pub(crate) mod sequence_aware;
 
+pub(crate) use adaptive_chunk_size::AdaptiveChunkSize;
 pub(crate) use sequence_aware::{
     ForwardBackwardSequenceAwareSyncCursor, ForwardSequenceAwareSyncCursor,
 };
@@ -142,6 +144,7 @@ pub(crate) struct RateLimitedContractSyncCursor<T> {
     last_tip_update: Instant,
     eta_calculator: SyncerEtaCalculator,
     sync_state: SyncState,
+    adaptive_chunk_size: Option<AdaptiveChunkSize>,
 }
 
 impl<T> RateLimitedContractSyncCursor<T> {
@@ -152,8 +155,12 @@ impl<T> RateLimitedContractSyncCursor<T> {
         chunk_size: u32,
         initial_height: u32,
         mode: IndexMode,
+        adaptive_chunk_size: Option<AdaptiveChunkSize>,
     ) -> Result<Self> {
         let (max_sequence, tip) = indexer.latest_sequence_count_and_tip().await?;
+        let chunk_size = adaptive_chunk_size
+            .map(|adaptive| adaptive.clamp(chunk_size))
+            .unwrap_or(chunk_size);
         Ok(Self {
             indexer,
             db,
@@ -170,6 +177,7 @@ impl<T> RateLimitedContractSyncCursor<T> {
                 // The rate limited cursor currently only syncs in the forward direction.
                 SyncDirection::Forward,
             ),
+            adaptive_chunk_size,
         })
     }
 
@@ -262,7 +270,7 @@ where
         self.sync_state.next_block.saturating_sub(1)
     }
 
-    async fn update(&mut self, _: Vec<(T, LogMeta)>, _range: RangeInclusive<u32>) -> Result<()> {
+    async fn update(&mut self, logs: Vec<(T, LogMeta)>, _range: RangeInclusive<u32>) -> Result<()> {
         // Store a relatively conservative view of the high watermark, which should allow a single watermark to be
         // safely shared across multiple cursors, so long as they are running sufficiently in sync
         self.db
@@ -273,6 +281,126 @@ where
                     .saturating_sub(self.sync_state.chunk_size),
             ))
             .await?;
+        if let (IndexMode::Block, Some(adaptive)) =
+            (&self.sync_state.mode, &self.adaptive_chunk_size)
+        {
+            self.sync_state.chunk_size =
+                adaptive.after_success(self.sync_state.chunk_size, logs.len());
+        }
         Ok(())
     }
+
+    fn handle_query_error(&mut self, range: RangeInclusive<u32>, err: &ChainCommunicationError) {
+        // Without adaptive chunk sizing the failed range stays skipped over, as it was
+        // when it was returned by `next_action`
+        let (IndexMode::Block, Some(adaptive)) = (&self.sync_state.mode, &self.adaptive_chunk_size)
+        else {
+            return;
+        };
+        // Rewind to retry the range with the adapted chunk size
+        self.sync_state.next_block = *range.start();
+        self.sync_state.chunk_size = adaptive.after_error(self.sync_state.chunk_size, err);
+    }
+
+    fn chunk_size(&self) -> Option<u32> {
+        Some(self.sync_step())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{ChainResult, HyperlaneLogStore};
+
+    use super::*;
+    use crate::settings::IndexSettings;
+
+    #[derive(Debug)]
+    struct MockIndexer;
+
+    #[async_trait]
+    impl Indexer<()> for MockIndexer {
+        async fn fetch_logs(&self, _range: RangeInclusive<u32>) -> ChainResult<Vec<((), LogMeta)>> {
+            Ok(vec![])
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(1000)
+        }
+    }
+
+    #[async_trait]
+    impl SequenceAwareIndexer<()> for MockIndexer {
+        async fn latest_sequence_count_and_tip(&self) -> ChainResult<(Option<u32>, u32)> {
+            Ok((None, 1000))
+        }
+    }
+
+    #[derive(Debug)]
+    struct MockStore;
+
+    #[async_trait]
+    impl HyperlaneLogStore<()> for MockStore {
+        async fn store_logs(&self, logs: &[((), LogMeta)]) -> Result<u32> {
+            Ok(logs.len() as u32)
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneWatermarkedLogStore<()> for MockStore {
+        async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
+            Ok(None)
+        }
+
+        async fn store_high_watermark(&self, _block_number: u32) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn cursor(
+        adaptive_chunk_size: Option<AdaptiveChunkSize>,
+    ) -> RateLimitedContractSyncCursor<()> {
+        RateLimitedContractSyncCursor::new(
+            Arc::new(MockIndexer),
+            Arc::new(MockStore),
+            10,
+            0,
+            IndexMode::Block,
+            adaptive_chunk_size,
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn next_range(cursor: &mut RateLimitedContractSyncCursor<()>) -> RangeInclusive<u32> {
+        match cursor.next_action().await.unwrap().0 {
+            CursorAction::Query(range) => range,
+            CursorAction::Sleep(_) => panic!("Expected a query"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_skips_failed_range_without_adaptive_chunk_size() {
+        let mut cursor = cursor(None).await;
+        let range = next_range(&mut cursor).await;
+        assert_eq!(range, 0..=10);
+
+        cursor.handle_query_error(range, &ChainCommunicationError::from_other_str("timed out"));
+        assert_eq!(next_range(&mut cursor).await, 11..=21);
+    }
+
+    #[tokio::test]
+    async fn test_retries_failed_range_with_adaptive_chunk_size() {
+        let adaptive = AdaptiveChunkSize::from_settings(&IndexSettings {
+            chunk_size: 10,
+            min_chunk_size: Some(1),
+            mode: IndexMode::Block,
+            ..Default::default()
+        });
+        let mut cursor = cursor(adaptive).await;
+        let range = next_range(&mut cursor).await;
+        assert_eq!(range, 0..=10);
+
+        cursor.handle_query_error(range, &ChainCommunicationError::from_other_str("timed out"));
+        assert_eq!(next_range(&mut cursor).await, 0..=5);
+    }
 }
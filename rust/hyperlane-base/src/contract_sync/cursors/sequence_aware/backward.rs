@@ -5,13 +5,14 @@ use std::{collections::HashSet, fmt::Debug, ops::RangeInclusive, sync::Arc, time
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_core::{
-    ContractSyncCursor, CursorAction, HyperlaneSequenceAwareIndexerStoreReader, IndexMode, LogMeta,
-    Sequenced,
+    ChainCommunicationError, ContractSyncCursor, CursorAction,
+    HyperlaneSequenceAwareIndexerStoreReader, IndexMode, LogMeta, Sequenced,
 };
 use itertools::Itertools;
 use tracing::{debug, warn};
 
 use super::{LastIndexedSnapshot, TargetSnapshot};
+use crate::contract_sync::cursors::AdaptiveChunkSize;
 
 /// A sequence-aware cursor that syncs backward until there are no earlier logs to index.
 #[derive(Debug)]
@@ -31,6 +32,8 @@ pub(crate) struct BackwardSequenceAwareSyncCursor<T> {
     current_indexing_snapshot: Option<TargetSnapshot>,
     /// The mode of indexing to use.
     index_mode: IndexMode,
+    /// Bounds for adapting `chunk_size` in block mode, if enabled.
+    adaptive_chunk_size: Option<AdaptiveChunkSize>,
 }
 
 impl<T: Sequenced + Debug> BackwardSequenceAwareSyncCursor<T> {
@@ -55,9 +58,22 @@ impl<T: Sequenced + Debug> BackwardSequenceAwareSyncCursor<T> {
             current_indexing_snapshot: last_indexed_snapshot.previous_target(),
             last_indexed_snapshot,
             index_mode,
+            adaptive_chunk_size: None,
         }
     }
 
+    /// Adapt the number of blocks queried at once within the given bounds.
+    pub fn with_adaptive_chunk_size(
+        mut self,
+        adaptive_chunk_size: Option<AdaptiveChunkSize>,
+    ) -> Self {
+        if let Some(adaptive) = adaptive_chunk_size {
+            self.chunk_size = adaptive.clamp(self.chunk_size);
+        }
+        self.adaptive_chunk_size = adaptive_chunk_size;
+        self
+    }
+
     /// Gets the next range of logs to query.
     /// If the cursor is fully synced, this returns None.
     /// Otherwise, it returns the next range to query, either by block or sequence depending on the mode.
@@ -328,6 +344,10 @@ impl<T: Sequenced + Debug> ContractSyncCursor<T> for BackwardSequenceAwareSyncCu
     /// The logs to ingest. If any logs are duplicated or their sequence is higher than the current indexing snapshot,
     /// they are filtered out.
     async fn update(&mut self, logs: Vec<(T, LogMeta)>, range: RangeInclusive<u32>) -> Result<()> {
+        if let (IndexMode::Block, Some(adaptive)) = (&self.index_mode, &self.adaptive_chunk_size) {
+            self.chunk_size = adaptive.after_success(self.chunk_size, logs.len());
+        }
+
         let Some(current_indexing_snapshot) = self.current_indexing_snapshot.clone() else {
             // We're synced, no need to update at all.
             return Ok(());
@@ -361,6 +381,17 @@ impl<T: Sequenced + Debug> ContractSyncCursor<T> for BackwardSequenceAwareSyncCu
 
         Ok(())
     }
+
+    fn handle_query_error(&mut self, _range: RangeInclusive<u32>, err: &ChainCommunicationError) {
+        // The cursor only moves on `update`, so the failed range is retried
+        if let (IndexMode::Block, Some(adaptive)) = (&self.index_mode, &self.adaptive_chunk_size) {
+            self.chunk_size = adaptive.after_error(self.chunk_size, err);
+        }
+    }
+
+    fn chunk_size(&self) -> Option<u32> {
+        Some(self.chunk_size)
+    }
 }
 
 #[cfg(test)]
@@ -8,13 +8,14 @@ use std::{
 use async_trait::async_trait;
 use eyre::Result;
 use hyperlane_core::{
-    ContractSyncCursor, CursorAction, HyperlaneSequenceAwareIndexerStoreReader, IndexMode, LogMeta,
-    SequenceAwareIndexer, Sequenced,
+    ChainCommunicationError, ContractSyncCursor, CursorAction,
+    HyperlaneSequenceAwareIndexerStoreReader, IndexMode, LogMeta, SequenceAwareIndexer, Sequenced,
 };
 use itertools::Itertools;
 use tracing::{debug, warn};
 
 use super::{LastIndexedSnapshot, TargetSnapshot};
+use crate::contract_sync::cursors::AdaptiveChunkSize;
 
 /// A sequence-aware cursor that syncs forwards in perpetuity.
 #[derive(Debug)]
@@ -39,6 +40,8 @@ pub(crate) struct ForwardSequenceAwareSyncCursor<T> {
     target_snapshot: Option<TargetSnapshot>,
     /// The mode of indexing.
     index_mode: IndexMode,
+    /// Bounds for adapting `chunk_size` in block mode, if enabled.
+    adaptive_chunk_size: Option<AdaptiveChunkSize>,
 }
 
 impl<T: Sequenced + Debug> ForwardSequenceAwareSyncCursor<T> {
@@ -68,9 +71,22 @@ impl<T: Sequenced + Debug> ForwardSequenceAwareSyncCursor<T> {
             },
             target_snapshot: None,
             index_mode,
+            adaptive_chunk_size: None,
         }
     }
 
+    /// Adapt the number of blocks queried at once within the given bounds.
+    pub fn with_adaptive_chunk_size(
+        mut self,
+        adaptive_chunk_size: Option<AdaptiveChunkSize>,
+    ) -> Self {
+        if let Some(adaptive) = adaptive_chunk_size {
+            self.chunk_size = adaptive.clamp(self.chunk_size);
+        }
+        self.adaptive_chunk_size = adaptive_chunk_size;
+        self
+    }
+
     /// Gets the next range of logs to index.
     /// If there are no logs to index, returns `None`.
     /// If there are logs to index, returns the range of logs, either by sequence or block number
@@ -418,6 +434,10 @@ impl<T: Sequenced + Debug> ContractSyncCursor<T> for ForwardSequenceAwareSyncCur
     ///   This means that while gaps result in a rewind here, already known logs may be "fast forwarded" through,
     ///   and the cursor won't actually end up re-indexing already known logs.
     async fn update(&mut self, logs: Vec<(T, LogMeta)>, range: RangeInclusive<u32>) -> Result<()> {
+        if let (IndexMode::Block, Some(adaptive)) = (&self.index_mode, &self.adaptive_chunk_size) {
+            self.chunk_size = adaptive.after_success(self.chunk_size, logs.len());
+        }
+
         // Remove any sequence duplicates, filter out any logs preceding our current snapshot,
         // and sort in ascending order.
         let logs = logs
@@ -438,6 +458,17 @@ impl<T: Sequenced + Debug> ContractSyncCursor<T> for ForwardSequenceAwareSyncCur
         };
         Ok(())
     }
+
+    fn handle_query_error(&mut self, _range: RangeInclusive<u32>, err: &ChainCommunicationError) {
+        // The cursor only moves on `update`, so the failed range is retried
+        if let (IndexMode::Block, Some(adaptive)) = (&self.index_mode, &self.adaptive_chunk_size) {
+            self.chunk_size = adaptive.after_error(self.chunk_size, err);
+        }
+    }
+
+    fn chunk_size(&self) -> Option<u32> {
+        Some(self.chunk_size)
+    }
 }
 
 #[cfg(test)]
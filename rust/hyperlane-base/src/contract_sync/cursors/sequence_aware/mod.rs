@@ -8,6 +8,8 @@ use hyperlane_core::{
 };
 use std::ops::RangeInclusive;
 
+use super::AdaptiveChunkSize;
+
 mod backward;
 mod forward;
 
@@ -75,6 +77,7 @@ impl<T: Sequenced + Debug> ForwardBackwardSequenceAwareSyncCursor<T> {
         db: Arc<dyn HyperlaneSequenceAwareIndexerStoreReader<T>>,
         chunk_size: u32,
         mode: IndexMode,
+        adaptive_chunk_size: Option<AdaptiveChunkSize>,
     ) -> Result<Self> {
         let (sequence_count, tip) = latest_sequence_querier
             .latest_sequence_count_and_tip()
@@ -89,9 +92,11 @@ impl<T: Sequenced + Debug> ForwardBackwardSequenceAwareSyncCursor<T> {
             sequence_count,
             tip,
             mode,
-        );
+        )
+        .with_adaptive_chunk_size(adaptive_chunk_size);
         let backward_cursor =
-            BackwardSequenceAwareSyncCursor::new(chunk_size, db, sequence_count, tip, mode);
+            BackwardSequenceAwareSyncCursor::new(chunk_size, db, sequence_count, tip, mode)
+                .with_adaptive_chunk_size(adaptive_chunk_size);
         Ok(Self {
             forward: forward_cursor,
             backward: backward_cursor,
@@ -129,4 +134,15 @@ impl<T: Sequenced + Debug> ContractSyncCursor<T> for ForwardBackwardSequenceAwar
             SyncDirection::Backward => self.backward.update(logs, range).await,
         }
     }
+
+    fn handle_query_error(&mut self, range: RangeInclusive<u32>, err: &ChainCommunicationError) {
+        match self.last_direction {
+            SyncDirection::Forward => self.forward.handle_query_error(range, err),
+            SyncDirection::Backward => self.backward.handle_query_error(range, err),
+        }
+    }
+
+    fn chunk_size(&self) -> Option<u32> {
+        self.forward.chunk_size()
+    }
 }
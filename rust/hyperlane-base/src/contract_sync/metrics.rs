@@ -20,6 +20,13 @@ pub struct ContractSyncMetrics {
 
     /// See `last_known_message_nonce` in CoreMetrics.
     pub message_nonce: IntGaugeVec,
+
+    /// Max number of blocks or sequences the cursor currently queries at once.
+    ///
+    /// Labels:
+    /// - `data_type`: the data the indexer is recording. E.g. `messages` or `gas_payments`.
+    /// - `chain`: Chain the indexer is collecting data from.
+    pub chunk_size: IntGaugeVec,
 }
 
 impl ContractSyncMetrics {
@@ -43,10 +50,19 @@ impl ContractSyncMetrics {
 
         let message_nonce = metrics.last_known_message_nonce();
 
+        let chunk_size = metrics
+            .new_int_gauge(
+                "contract_sync_chunk_size",
+                "Max number of blocks or sequences currently queried at once",
+                &["data_type", "chain"],
+            )
+            .expect("failed to register chunk_size metric");
+
         ContractSyncMetrics {
             indexed_height,
             stored_events,
             message_nonce,
+            chunk_size,
         }
     }
 }
@@ -1,16 +1,21 @@
 use std::{
-    collections::HashSet, fmt::Debug, hash::Hash, marker::PhantomData, sync::Arc, time::Duration,
+    collections::HashSet, fmt::Debug, hash::Hash, marker::PhantomData, ops::RangeInclusive,
+    sync::Arc, time::Duration,
 };
 
 use cursors::*;
 use derive_new::new;
+use eyre::Result;
 use hyperlane_core::{
     utils::fmt_sync_time, ContractSyncCursor, CursorAction, HyperlaneDomain, HyperlaneLogStore,
-    HyperlaneSequenceAwareIndexerStore, HyperlaneWatermarkedLogStore, Indexer,
-    SequenceAwareIndexer, Sequenced,
+    HyperlaneSequenceAwareIndexerStore, HyperlaneWatermarkedLogStore, Indexer, LogMeta,
+    LogSubscriber, SequenceAwareIndexer, Sequenced, SubscribedLog,
 };
 pub use metrics::ContractSyncMetrics;
-use tokio::time::sleep;
+use tokio::{
+    sync::mpsc,
+    time::{interval_at, sleep, Instant},
+};
 use tracing::{debug, info, warn};
 
 use crate::settings::IndexSettings;
@@ -23,6 +28,16 @@ use cursors::{ForwardBackwardSequenceAwareSyncCursor, ForwardSequenceAwareSyncCu
 
 const SLEEP_DURATION: Duration = Duration::from_secs(5);
 
+/// How often logs pushed by a subscription are checked for finality
+const SUBSCRIPTION_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait before resubscribing after a subscription ended
+const RESUBSCRIBE_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(100)
+} else {
+    SLEEP_DURATION
+};
+
 /// Entity that drives the syncing of an agent's db with on-chain data.
 /// Extracts chain-specific data (emitted checkpoints, messages, etc) from an
 /// `indexer` and fills the agent's db with this data.
@@ -58,9 +73,16 @@ where
             .metrics
             .stored_events
             .with_label_values(&[label, chain_name]);
+        let chunk_size = self
+            .metrics
+            .chunk_size
+            .with_label_values(&[label, chain_name]);
 
         loop {
             indexed_height.set(cursor.latest_queried_block() as i64);
+            if let Some(size) = cursor.chunk_size() {
+                chunk_size.set(size as i64);
+            }
 
             let (action, eta) = match cursor.next_action().await {
                 Ok((action, eta)) => (action, eta),
@@ -81,6 +103,7 @@ where
                         Ok(logs) => logs,
                         Err(err) => {
                             warn!(?err, "Error fetching logs");
+                            cursor.handle_query_error(range, &err);
                             break SLEEP_DURATION;
                         }
                     };
@@ -115,6 +138,152 @@ where
             sleep(sleep_duration).await;
         }
     }
+
+    /// Sync logs using the cursor and, if a subscriber is given, concurrently
+    /// push logs from the subscriber into the LogStore as soon as they're
+    /// finalized. See `sync_subscription`.
+    pub async fn sync_with_subscriber(
+        &self,
+        label: &'static str,
+        cursor: Box<dyn ContractSyncCursor<T>>,
+        subscriber: Option<Arc<dyn LogSubscriber<T>>>,
+        chunk_size: u32,
+    ) {
+        match subscriber {
+            Some(subscriber) => {
+                tokio::join!(
+                    self.sync(label, cursor),
+                    self.sync_subscription(label, subscriber, chunk_size)
+                );
+            }
+            None => self.sync(label, cursor).await,
+        }
+    }
+
+    /// Store logs pushed by `subscriber` as soon as they're finalized,
+    /// resubscribing whenever the subscription ends.
+    ///
+    /// The cursor driven by `sync` remains responsible for completeness, this
+    /// only reduces latency. After a reconnect, the finalized blocks that may
+    /// have been missed while disconnected are queried with `fetch_logs` in
+    /// chunks of `chunk_size` blocks, so the gap is filled without waiting for
+    /// the cursor to get there.
+    #[tracing::instrument(name = "ContractSubscription", fields(domain=self.domain().name()), skip(self, subscriber))]
+    pub async fn sync_subscription(
+        &self,
+        label: &'static str,
+        subscriber: Arc<dyn LogSubscriber<T>>,
+        chunk_size: u32,
+    ) {
+        let chain_name = self.domain.as_ref();
+        let stored_logs = self
+            .metrics
+            .stored_events
+            .with_label_values(&[label, chain_name]);
+        // The finalized block up to which all logs pushed by the subscription were stored
+        let mut synced_up_to: Option<u32> = None;
+
+        loop {
+            if let Some(synced_block) = synced_up_to {
+                match self
+                    .store_finalized_range(synced_block + 1, chunk_size)
+                    .await
+                {
+                    Ok((tip, stored)) => {
+                        info!(
+                            from = synced_block + 1,
+                            tip, stored, "Reconciled logs missed while resubscribing"
+                        );
+                        stored_logs.inc_by(stored as u64);
+                        synced_up_to = Some(tip);
+                    }
+                    Err(err) => warn!(?err, "Error reconciling logs missed while resubscribing"),
+                }
+            }
+
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            let subscription = subscriber.subscribe(sender);
+            tokio::pin!(subscription);
+            let mut flush_interval = interval_at(
+                Instant::now() + SUBSCRIPTION_FLUSH_INTERVAL,
+                SUBSCRIPTION_FLUSH_INTERVAL,
+            );
+            let mut pending = vec![];
+
+            let result = loop {
+                tokio::select! {
+                    result = &mut subscription => break result,
+                    Some(log) = receiver.recv() => match log {
+                        SubscribedLog::Emitted(log, meta) => pending.push((log, meta)),
+                        SubscribedLog::Removed(log, meta) => {
+                            pending.retain(|(l, m)| l != &log || m != &meta)
+                        }
+                    },
+                    _ = flush_interval.tick() => {
+                        match self.store_finalized_pending(&mut pending).await {
+                            Ok((tip, stored)) => {
+                                stored_logs.inc_by(stored as u64);
+                                synced_up_to = Some(tip);
+                            }
+                            Err(err) => warn!(?err, "Error storing subscribed logs"),
+                        }
+                    }
+                }
+            };
+
+            // Unfinalized logs are dropped, since removals can't be observed while
+            // disconnected. Once finalized, they're picked up by the reconciliation.
+            warn!(
+                ?result,
+                dropped_logs = pending.len(),
+                "Log subscription ended, resubscribing"
+            );
+            sleep(RESUBSCRIBE_DELAY).await;
+        }
+    }
+
+    /// Stores the pending logs whose block is finalized, returning the
+    /// finalized block number and the number of logs stored.
+    async fn store_finalized_pending(&self, pending: &mut Vec<(T, LogMeta)>) -> Result<(u32, u32)> {
+        let tip = self.indexer.get_finalized_block_number().await?;
+        let (finalized, unfinalized): (Vec<_>, Vec<_>) = pending
+            .drain(..)
+            .partition(|(_, meta)| meta.block_number <= tip as u64);
+        *pending = unfinalized;
+        if finalized.is_empty() {
+            return Ok((tip, 0));
+        }
+
+        let logs = Vec::from_iter(HashSet::<_>::from_iter(finalized.clone()));
+        match self.db.store_logs(&logs).await {
+            Ok(stored) => {
+                debug!(num_logs = logs.len(), tip, "Stored subscribed log(s)");
+                Ok((tip, stored))
+            }
+            Err(err) => {
+                // Retry on the next flush
+                pending.extend(finalized);
+                Err(err)
+            }
+        }
+    }
+
+    /// Fetches and stores the logs from `from` up to the finalized block,
+    /// returning the finalized block number and the number of logs stored.
+    async fn store_finalized_range(&self, from: u32, chunk_size: u32) -> Result<(u32, u32)> {
+        let tip = self.indexer.get_finalized_block_number().await?;
+        let mut stored = 0;
+        let mut start = from;
+        while start <= tip {
+            let end = start.saturating_add(chunk_size.max(1) - 1);
+            let range: RangeInclusive<u32> = start..=u32::min(end, tip);
+            let logs = self.indexer.fetch_logs(range.clone()).await?;
+            let logs = Vec::from_iter(HashSet::<_>::from_iter(logs));
+            stored += self.db.store_logs(&logs).await?;
+            start = *range.end() + 1;
+        }
+        Ok((tip, stored))
+    }
 }
 
 /// A ContractSync for syncing events using a RateLimitedContractSyncCursor
@@ -132,8 +301,7 @@ where
         let watermark = self.db.retrieve_high_watermark().await.unwrap();
         let index_settings = IndexSettings {
             from: watermark.unwrap_or(index_settings.from),
-            chunk_size: index_settings.chunk_size,
-            mode: index_settings.mode,
+            ..index_settings
         };
         Box::new(
             RateLimitedContractSyncCursor::new(
@@ -142,6 +310,7 @@ where
                 index_settings.chunk_size,
                 index_settings.from,
                 index_settings.mode,
+                AdaptiveChunkSize::from_settings(&index_settings),
             )
             .await
             .unwrap(),
@@ -162,14 +331,17 @@ impl<T: Sequenced + Debug> SequencedDataContractSync<T> {
         index_settings: IndexSettings,
         next_nonce: u32,
     ) -> Box<dyn ContractSyncCursor<T>> {
-        Box::new(ForwardSequenceAwareSyncCursor::new(
-            index_settings.chunk_size,
-            self.indexer.clone(),
-            Arc::new(self.db.clone()),
-            next_nonce,
-            index_settings.from,
-            index_settings.mode,
-        ))
+        Box::new(
+            ForwardSequenceAwareSyncCursor::new(
+                index_settings.chunk_size,
+                self.indexer.clone(),
+                Arc::new(self.db.clone()),
+                next_nonce,
+                index_settings.from,
+                index_settings.mode,
+            )
+            .with_adaptive_chunk_size(AdaptiveChunkSize::from_settings(&index_settings)),
+        )
     }
 
     /// Returns a new cursor to be used for syncing dispatched messages from the indexer
@@ -183,9 +355,218 @@ impl<T: Sequenced + Debug> SequencedDataContractSync<T> {
                 Arc::new(self.db.clone()),
                 index_settings.chunk_size,
                 index_settings.mode,
+                AdaptiveChunkSize::from_settings(&index_settings),
             )
             .await
             .unwrap(),
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Mutex,
+        },
+    };
+
+    use async_trait::async_trait;
+    use hyperlane_core::{ChainCommunicationError, ChainResult, KnownHyperlaneDomain, H256};
+    use prometheus::Registry;
+    use tokio::{sync::Notify, time::timeout};
+
+    use super::*;
+    use crate::CoreMetrics;
+
+    fn log_meta_with_block(block_number: u64) -> LogMeta {
+        LogMeta {
+            address: H256::zero(),
+            block_number,
+            block_hash: H256::zero(),
+            transaction_id: Default::default(),
+            transaction_index: 0,
+            log_index: Default::default(),
+        }
+    }
+
+    /// Returns the `logs` in the queried range, recording the queried ranges
+    #[derive(Debug, Clone, Default)]
+    struct MockIndexer {
+        logs: Vec<(u32, LogMeta)>,
+        tip: Arc<AtomicU32>,
+        queried: Arc<Mutex<Vec<RangeInclusive<u32>>>>,
+    }
+
+    #[async_trait]
+    impl Indexer<u32> for MockIndexer {
+        async fn fetch_logs(&self, range: RangeInclusive<u32>) -> ChainResult<Vec<(u32, LogMeta)>> {
+            self.queried.lock().unwrap().push(range.clone());
+            Ok(self
+                .logs
+                .iter()
+                .filter(|(_, meta)| range.contains(&(meta.block_number as u32)))
+                .cloned()
+                .collect())
+        }
+
+        async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+            Ok(self.tip.load(Ordering::SeqCst))
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct MockStore {
+        logs: Arc<Mutex<Vec<(u32, LogMeta)>>>,
+    }
+
+    impl MockStore {
+        fn stored(&self) -> Vec<u32> {
+            let mut stored: Vec<_> = self
+                .logs
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(log, _)| *log)
+                .collect();
+            stored.sort();
+            stored
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneLogStore<u32> for MockStore {
+        async fn store_logs(&self, logs: &[(u32, LogMeta)]) -> Result<u32> {
+            self.logs.lock().unwrap().extend_from_slice(logs);
+            Ok(logs.len() as u32)
+        }
+    }
+
+    /// A subscription which pushes `logs`, then ends once `end` is notified,
+    /// as if the connection was lost while the finalized block moved to `tip`
+    #[derive(Debug)]
+    struct MockSubscription {
+        logs: Vec<SubscribedLog<u32>>,
+        end: Arc<Notify>,
+        tip: u32,
+    }
+
+    /// Serves `subscriptions` in order, and a subscription which never pushes
+    /// anything after them
+    #[derive(Debug, Default)]
+    struct MockSubscriber {
+        tip: Arc<AtomicU32>,
+        subscriptions: Mutex<VecDeque<MockSubscription>>,
+    }
+
+    #[async_trait]
+    impl LogSubscriber<u32> for MockSubscriber {
+        async fn subscribe(
+            &self,
+            sender: mpsc::UnboundedSender<SubscribedLog<u32>>,
+        ) -> ChainResult<()> {
+            let Some(subscription) = self.subscriptions.lock().unwrap().pop_front() else {
+                return futures_util::future::pending().await;
+            };
+            for log in subscription.logs {
+                sender.send(log).unwrap();
+            }
+            subscription.end.notified().await;
+            self.tip.store(subscription.tip, Ordering::SeqCst);
+            Err(ChainCommunicationError::from_other_str("Connection lost"))
+        }
+    }
+
+    fn contract_sync(
+        indexer: MockIndexer,
+        store: MockStore,
+    ) -> ContractSync<u32, MockStore, MockIndexer> {
+        let metrics = CoreMetrics::new("dummy_agent", 37584, Registry::new()).unwrap();
+        ContractSync::new(
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Test1),
+            store,
+            indexer,
+            ContractSyncMetrics::new(&metrics),
+        )
+    }
+
+    /// Waits until the logs in `store` are `expected`
+    async fn wait_for_stored(store: &MockStore, expected: &[u32]) {
+        timeout(Duration::from_secs(5), async {
+            while store.stored() != expected {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("Stored {:?} instead of {expected:?}", store.stored()));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_drops_reorged_and_unfinalized_logs() {
+        let tip = Arc::new(AtomicU32::new(10));
+        let indexer = MockIndexer {
+            tip: tip.clone(),
+            ..Default::default()
+        };
+        let store = MockStore::default();
+        let subscriber = MockSubscriber {
+            tip,
+            subscriptions: Mutex::new(VecDeque::from([MockSubscription {
+                logs: vec![
+                    SubscribedLog::Emitted(1, log_meta_with_block(5)),
+                    SubscribedLog::Emitted(2, log_meta_with_block(6)),
+                    SubscribedLog::Removed(2, log_meta_with_block(6)),
+                    SubscribedLog::Emitted(3, log_meta_with_block(12)),
+                ],
+                end: Arc::new(Notify::new()),
+                tip: 10,
+            }])),
+        };
+        let sync = contract_sync(indexer, store.clone());
+
+        tokio::select! {
+            _ = sync.sync_subscription("test", Arc::new(subscriber), 10) => unreachable!(),
+            _ = async {
+                wait_for_stored(&store, &[1]).await;
+                // Give the log above the finalized block a chance to be stored
+                sleep(SUBSCRIPTION_FLUSH_INTERVAL * 2).await;
+            } => {}
+        }
+        assert_eq!(store.stored(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_subscription_reconciles_gap_after_resubscribing() {
+        let tip = Arc::new(AtomicU32::new(10));
+        let indexer = MockIndexer {
+            // Emitted while the subscription was disconnected
+            logs: vec![(2, log_meta_with_block(15)), (3, log_meta_with_block(30))],
+            tip: tip.clone(),
+            ..Default::default()
+        };
+        let store = MockStore::default();
+        let end = Arc::new(Notify::new());
+        let subscriber = MockSubscriber {
+            tip,
+            subscriptions: Mutex::new(VecDeque::from([MockSubscription {
+                logs: vec![SubscribedLog::Emitted(1, log_meta_with_block(5))],
+                end: end.clone(),
+                tip: 30,
+            }])),
+        };
+        let sync = contract_sync(indexer.clone(), store.clone());
+
+        tokio::select! {
+            _ = sync.sync_subscription("test", Arc::new(subscriber), 10) => unreachable!(),
+            _ = async {
+                wait_for_stored(&store, &[1]).await;
+                end.notify_one();
+                wait_for_stored(&store, &[1, 2, 3]).await;
+            } => {}
+        }
+        // The blocks after the last flush are queried in chunks of 10 blocks
+        assert_eq!(*indexer.queried.lock().unwrap(), vec![11..=20, 21..=30]);
+    }
+}
@@ -1,2 +1,6 @@
 pub use rocks::*;
+pub use snapshot::*;
+
 mod rocks;
+/// Importing historical logs from snapshots
+mod snapshot;
@@ -1,17 +1,19 @@
+use std::num::NonZeroU64;
+
 use async_trait::async_trait;
 use eyre::Result;
 use paste::paste;
-use tracing::{debug, instrument, trace};
+use tracing::{debug, info, instrument, trace, warn};
 
 use hyperlane_core::{
-    GasPaymentKey, HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage,
-    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore,
-    InterchainGasExpenditure, InterchainGasPayment, InterchainGasPaymentMeta, LogMeta,
-    MerkleTreeInsertion, H256,
+    accumulator::incremental::IncrementalMerkle, Decode, GasPaymentKey, HyperlaneChain,
+    HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage, HyperlaneSequenceAwareIndexerStoreReader,
+    HyperlaneWatermarkedLogStore, InterchainGasExpenditure, InterchainGasPayment,
+    InterchainGasPaymentMeta, LogMeta, MerkleTreeHook, MerkleTreeInsertion, H256,
 };
 
 use super::{
-    storage_types::{InterchainGasExpenditureData, InterchainGasPaymentData},
+    storage_types::{InterchainGasExpenditureData, InterchainGasPaymentData, MerkleTreeLeaves},
     DbError, TypedDB, DB,
 };
 
@@ -31,7 +33,33 @@ const MERKLE_TREE_INSERTION: &str = "merkle_tree_insertion_";
 const MERKLE_LEAF_INDEX_BY_MESSAGE_ID: &str = "merkle_leaf_index_by_message_id_";
 const MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX: &str =
     "merkle_tree_insertion_block_number_by_leaf_index_";
+const MERKLE_TREE_LEAVES_BY_CHUNK_INDEX: &str = "merkle_tree_leaves_by_chunk_index_";
 const LATEST_INDEXED_GAS_PAYMENT_BLOCK: &str = "latest_indexed_gas_payment_block";
+const LATEST_MERKLE_TREE_SNAPSHOT: &str = "latest_merkle_tree_snapshot";
+const IMPORTED_SNAPSHOT_BY_DIGEST: &str = "imported_snapshot_by_digest_";
+
+/// The prefix of the keys of every table, which follows the domain prefix.
+pub const TABLE_KEY_PREFIXES: &[&str] = &[
+    MESSAGE_ID,
+    MESSAGE_DISPATCHED_BLOCK_NUMBER,
+    MESSAGE,
+    NONCE_PROCESSED,
+    GAS_PAYMENT_FOR_MESSAGE_ID,
+    GAS_PAYMENT_META_PROCESSED,
+    GAS_EXPENDITURE_FOR_MESSAGE_ID,
+    PENDING_MESSAGE_RETRY_COUNT_FOR_MESSAGE_ID,
+    MERKLE_TREE_INSERTION,
+    MERKLE_LEAF_INDEX_BY_MESSAGE_ID,
+    MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX,
+    MERKLE_TREE_LEAVES_BY_CHUNK_INDEX,
+    LATEST_INDEXED_GAS_PAYMENT_BLOCK,
+    LATEST_MERKLE_TREE_SNAPSHOT,
+    IMPORTED_SNAPSHOT_BY_DIGEST,
+];
+
+/// Number of leaves between merkle tree snapshots, which is also the number
+/// of leaves stored per chunk alongside them.
+pub const MERKLE_TREE_SNAPSHOT_INTERVAL: u32 = 10_000;
 
 type DbResult<T> = std::result::Result<T, DbError>;
 
@@ -107,6 +135,18 @@ impl HyperlaneRocksDB {
         }
     }
 
+    /// Delete a message and its dispatched block number by its nonce.
+    /// Returns whether a message was stored at the nonce.
+    pub fn delete_message_by_nonce(&self, nonce: u32) -> DbResult<bool> {
+        let Some(id) = self.retrieve_message_id_by_nonce(&nonce)? else {
+            return Ok(false);
+        };
+        self.delete_keyed(MESSAGE, &id)?;
+        self.delete_keyed(MESSAGE_ID, &nonce)?;
+        self.delete_keyed(MESSAGE_DISPATCHED_BLOCK_NUMBER, &nonce)?;
+        Ok(true)
+    }
+
     /// If the provided gas payment, identified by its metadata, has not been
     /// processed, processes the gas payment and records it as processed.
     /// Returns whether the gas payment was processed for the first time.
@@ -165,6 +205,206 @@ impl HyperlaneRocksDB {
         Ok(true)
     }
 
+    /// Delete a merkle tree insertion, its block number and the mapping from
+    /// its message id. Returns whether an insertion was stored at the index.
+    pub fn delete_merkle_tree_insertion_by_leaf_index(&self, leaf_index: u32) -> DbResult<bool> {
+        let Some(insertion) = self.retrieve_merkle_tree_insertion_by_leaf_index(&leaf_index)?
+        else {
+            return Ok(false);
+        };
+        if self.retrieve_merkle_leaf_index_by_message_id(&insertion.message_id())?
+            == Some(leaf_index)
+        {
+            self.delete_keyed(MERKLE_LEAF_INDEX_BY_MESSAGE_ID, &insertion.message_id())?;
+        }
+        self.delete_keyed(MERKLE_TREE_INSERTION, &leaf_index)?;
+        self.delete_keyed(
+            MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX,
+            &leaf_index,
+        )?;
+        Ok(true)
+    }
+
+    /// Retrieve the leaf indices of all stored merkle tree insertions in
+    /// ascending order.
+    pub fn retrieve_merkle_tree_insertion_leaf_indices(&self) -> DbResult<Vec<u32>> {
+        // Keys with a longer suffix belong to other prefixes starting with
+        // `MERKLE_TREE_INSERTION`, e.g. the insertion block numbers.
+        self.retrieve_keys(MERKLE_TREE_INSERTION)?
+            .into_iter()
+            .filter(|key| key.len() == 4)
+            .map(|key| u32::read_from(&mut key.as_slice()).map_err(Into::into))
+            .collect()
+    }
+
+    /// Store a snapshot of the merkle tree along with the chunks of its
+    /// leaves that aren't stored yet, so the tree can be restored without
+    /// replaying every insertion. Only trees with a multiple of
+    /// `MERKLE_TREE_SNAPSHOT_INTERVAL` leaves that are ahead of the latest
+    /// snapshot are stored. Returns whether the snapshot was stored, which
+    /// requires the insertions of all leaves not yet in a chunk.
+    pub fn store_merkle_tree_snapshot(&self, tree: &IncrementalMerkle) -> DbResult<bool> {
+        let count = tree.count() as u32;
+        if count == 0 || count % MERKLE_TREE_SNAPSHOT_INTERVAL != 0 {
+            return Ok(false);
+        }
+        let stored_count = self
+            .retrieve_latest_merkle_tree_snapshot()?
+            .map_or(0, |snapshot| snapshot.count() as u32);
+        if stored_count >= count {
+            return Ok(false);
+        }
+
+        // Chunks are derived from the stored insertions, so a partially
+        // stored range of chunks is overwritten with the same leaves later.
+        for chunk_index in
+            stored_count / MERKLE_TREE_SNAPSHOT_INTERVAL..count / MERKLE_TREE_SNAPSHOT_INTERVAL
+        {
+            let start = chunk_index * MERKLE_TREE_SNAPSHOT_INTERVAL;
+            let mut leaves = Vec::with_capacity(MERKLE_TREE_SNAPSHOT_INTERVAL as usize);
+            for leaf_index in start..start + MERKLE_TREE_SNAPSHOT_INTERVAL {
+                let Some(insertion) =
+                    self.retrieve_merkle_tree_insertion_by_leaf_index(&leaf_index)?
+                else {
+                    debug!(
+                        leaf_index,
+                        "Missing tree insertion, not storing merkle tree snapshot"
+                    );
+                    return Ok(false);
+                };
+                leaves.push(insertion.message_id());
+            }
+            self.store_merkle_tree_leaves_by_chunk_index(&chunk_index, &MerkleTreeLeaves(leaves))?;
+        }
+        self.store_encodable("", LATEST_MERKLE_TREE_SNAPSHOT, tree)?;
+        debug!(count, root=?tree.root(), "Stored merkle tree snapshot");
+        Ok(true)
+    }
+
+    /// Retrieve the latest merkle tree snapshot
+    pub fn retrieve_latest_merkle_tree_snapshot(&self) -> DbResult<Option<IncrementalMerkle>> {
+        self.retrieve_decodable("", LATEST_MERKLE_TREE_SNAPSHOT)
+    }
+
+    /// Discard the latest merkle tree snapshot, e.g. because it's inconsistent
+    /// with the onchain tree.
+    pub fn delete_latest_merkle_tree_snapshot(&self) -> DbResult<()> {
+        self.delete("", LATEST_MERKLE_TREE_SNAPSHOT)
+    }
+
+    /// Retrieve the first `count` leaves of the merkle tree from the chunks
+    /// stored alongside snapshots. `count` must be a multiple of
+    /// `MERKLE_TREE_SNAPSHOT_INTERVAL`.
+    pub fn retrieve_merkle_tree_snapshot_leaves(&self, count: u32) -> DbResult<Option<Vec<H256>>> {
+        let mut leaves = Vec::with_capacity(count as usize);
+        for chunk_index in 0..count / MERKLE_TREE_SNAPSHOT_INTERVAL {
+            let Some(chunk) = self.retrieve_merkle_tree_leaves_by_chunk_index(&chunk_index)? else {
+                return Ok(None);
+            };
+            leaves.extend(chunk.0);
+        }
+        Ok(Some(leaves))
+    }
+
+    /// Retrieve the latest merkle tree snapshot if it's consistent with the
+    /// tree of `merkle_tree_hook` at `lag` blocks behind the tip. The snapshot
+    /// is caught up with the onchain tree using the stored insertions. If
+    /// these aren't indexed yet, it's checked against the onchain tree at the
+    /// block of its last leaf instead, and if that isn't possible either it's
+    /// kept for later but not used. Inconsistent snapshots are discarded.
+    pub async fn retrieve_verified_merkle_tree_snapshot(
+        &self,
+        merkle_tree_hook: &dyn MerkleTreeHook,
+        lag: Option<NonZeroU64>,
+    ) -> Result<Option<IncrementalMerkle>> {
+        let Some(snapshot) = self.retrieve_latest_merkle_tree_snapshot()? else {
+            return Ok(None);
+        };
+        let onchain_tree = merkle_tree_hook.tree(lag).await?;
+        let mut consistent = self.snapshot_matches_tree(&snapshot, &onchain_tree)?;
+        if consistent.is_none() {
+            if let Some(tree) = self
+                .merkle_tree_at_snapshot_block(merkle_tree_hook, &snapshot, lag)
+                .await?
+            {
+                consistent = self.snapshot_matches_tree(&snapshot, &tree)?;
+            }
+        }
+
+        match consistent {
+            Some(true) => {
+                info!(count = snapshot.count(), root=?snapshot.root(), "Verified merkle tree snapshot");
+                Ok(Some(snapshot))
+            }
+            Some(false) => {
+                warn!(
+                    snapshot_count = snapshot.count(),
+                    onchain_count = onchain_tree.count(),
+                    "Merkle tree snapshot is inconsistent with the onchain tree, discarding it"
+                );
+                self.delete_latest_merkle_tree_snapshot()?;
+                Ok(None)
+            }
+            None => {
+                info!(
+                    snapshot_count = snapshot.count(),
+                    onchain_count = onchain_tree.count(),
+                    "Merkle tree snapshot can't be verified until more insertions are indexed"
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Whether `snapshot`, caught up with the stored insertions, has the same
+    /// root as `tree`, or `None` if the insertions to catch up with it aren't
+    /// all indexed.
+    fn snapshot_matches_tree(
+        &self,
+        snapshot: &IncrementalMerkle,
+        tree: &IncrementalMerkle,
+    ) -> DbResult<Option<bool>> {
+        if tree.count() < snapshot.count() {
+            return Ok(Some(false));
+        }
+        let mut caught_up = *snapshot;
+        while caught_up.count() < tree.count() {
+            let Some(insertion) =
+                self.retrieve_merkle_tree_insertion_by_leaf_index(&(caught_up.count() as u32))?
+            else {
+                return Ok(None);
+            };
+            caught_up.ingest(insertion.message_id());
+        }
+        Ok(Some(caught_up.root() == tree.root()))
+    }
+
+    /// The tree of `merkle_tree_hook` at the block the last leaf of
+    /// `snapshot` was inserted in, or at `lag` blocks behind the tip if that's
+    /// further back. `None` if the block or the tip isn't known.
+    async fn merkle_tree_at_snapshot_block(
+        &self,
+        merkle_tree_hook: &dyn MerkleTreeHook,
+        snapshot: &IncrementalMerkle,
+        lag: Option<NonZeroU64>,
+    ) -> Result<Option<IncrementalMerkle>> {
+        let Some(last_leaf_index) = (snapshot.count() as u32).checked_sub(1) else {
+            return Ok(None);
+        };
+        let Some(block_number) =
+            self.retrieve_merkle_tree_insertion_block_number_by_leaf_index(&last_leaf_index)?
+        else {
+            return Ok(None);
+        };
+        let Some(chain_info) = merkle_tree_hook.provider().get_chain_metrics().await? else {
+            return Ok(None);
+        };
+        let snapshot_lag = chain_info.latest_block.number.saturating_sub(block_number);
+        // `None` is the tip, so this is the one further back
+        let lag = NonZeroU64::new(snapshot_lag).max(lag);
+        Ok(Some(merkle_tree_hook.tree(lag).await?))
+    }
+
     /// Processes the gas expenditure and store the total expenditure for the
     /// message.
     pub fn process_gas_expenditure(&self, expenditure: InterchainGasExpenditure) -> DbResult<()> {
@@ -357,8 +597,14 @@ macro_rules! make_store_and_retrieve {
 }
 
 make_store_and_retrieve!(pub, message_id_by_nonce, MESSAGE_ID, u32, H256);
-make_store_and_retrieve!(pub(self), message_by_id, MESSAGE, H256, HyperlaneMessage);
-make_store_and_retrieve!(pub(self), dispatched_block_number_by_nonce, MESSAGE_DISPATCHED_BLOCK_NUMBER, u32, u64);
+make_store_and_retrieve!(pub, message_by_id, MESSAGE, H256, HyperlaneMessage);
+make_store_and_retrieve!(
+    pub,
+    dispatched_block_number_by_nonce,
+    MESSAGE_DISPATCHED_BLOCK_NUMBER,
+    u32,
+    u64
+);
 make_store_and_retrieve!(pub, processed_by_nonce, NONCE_PROCESSED, u32, bool);
 make_store_and_retrieve!(pub(self), processed_by_gas_payment_meta, GAS_PAYMENT_META_PROCESSED, InterchainGasPaymentMeta, bool);
 make_store_and_retrieve!(pub(self), interchain_gas_expenditure_data_by_message_id, GAS_EXPENDITURE_FOR_MESSAGE_ID, H256, InterchainGasExpenditureData);
@@ -391,3 +637,17 @@ make_store_and_retrieve!(
     u32,
     u64
 );
+make_store_and_retrieve!(
+    pub(self),
+    merkle_tree_leaves_by_chunk_index,
+    MERKLE_TREE_LEAVES_BY_CHUNK_INDEX,
+    u32,
+    MerkleTreeLeaves
+);
+make_store_and_retrieve!(
+    pub,
+    imported_snapshot_by_digest,
+    IMPORTED_SNAPSHOT_BY_DIGEST,
+    H256,
+    bool
+);
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 use std::{io, path::Path, sync::Arc};
 
 use hyperlane_core::{ChainCommunicationError, HyperlaneProtocolError};
-use rocksdb::{Options, DB as Rocks};
+use rocksdb::{Direction, IteratorMode, Options, DB as Rocks};
 use tracing::info;
 
 pub use hyperlane_db::*;
@@ -109,4 +109,24 @@ impl DB {
     pub fn retrieve(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         Ok(self.0.get(key)?)
     }
+
+    /// Delete a value from the DB
+    pub fn delete(&self, key: &[u8]) -> Result<()> {
+        Ok(self.0.delete(key)?)
+    }
+
+    /// Iterate over all key value pairs whose key starts with `prefix`,
+    /// ordered by key
+    pub fn iter_prefix<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>)>> + 'a {
+        self.0
+            .iterator(IteratorMode::From(prefix, Direction::Forward))
+            .map(|r| r.map_err(Into::into))
+            .take_while(move |r| match r {
+                Ok((k, _)) => k.starts_with(prefix),
+                Err(_) => true,
+            })
+    }
 }
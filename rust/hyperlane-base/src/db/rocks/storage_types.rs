@@ -121,3 +121,35 @@ impl Decode for InterchainGasExpenditureData {
         })
     }
 }
+
+/// Leaves of a chunk of the merkle tree, stored alongside merkle tree
+/// snapshots so the full tree can be rebuilt without replaying insertions.
+#[derive(Debug, Clone, Default)]
+pub(super) struct MerkleTreeLeaves(pub Vec<H256>);
+
+impl Encode for MerkleTreeLeaves {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let mut written = (self.0.len() as u32).write_to(writer)?;
+        for leaf in &self.0 {
+            written += leaf.write_to(writer)?;
+        }
+        Ok(written)
+    }
+}
+
+impl Decode for MerkleTreeLeaves {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        let len = u32::read_from(reader)?;
+        (0..len)
+            .map(|_| H256::read_from(reader))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
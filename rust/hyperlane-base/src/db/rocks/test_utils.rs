@@ -30,12 +30,17 @@ where
 
 #[cfg(test)]
 mod test {
+    use std::num::NonZeroU64;
+
+    use async_trait::async_trait;
     use hyperlane_core::{
-        HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage, LogMeta, RawHyperlaneMessage, H256,
-        H512, U256,
+        accumulator::incremental::IncrementalMerkle, BlockInfo, ChainInfo, ChainResult, Checkpoint,
+        HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage,
+        HyperlaneProvider, LogMeta, MerkleTreeHook, MerkleTreeInsertion, RawHyperlaneMessage,
+        TxnInfo, H256, H512, U256,
     };
 
-    use crate::db::HyperlaneRocksDB;
+    use crate::db::{HyperlaneRocksDB, MERKLE_TREE_SNAPSHOT_INTERVAL};
 
     use super::*;
 
@@ -75,4 +80,248 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn db_deletes_messages_and_lists_tree_insertions() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("db_deletes_messages_and_lists_tree_insertions"),
+                db,
+            );
+
+            let m = HyperlaneMessage {
+                nonce: 7,
+                ..Default::default()
+            };
+            db.store_message(&m, 1).unwrap();
+            assert!(db.delete_message_by_nonce(m.nonce).unwrap());
+            assert!(!db.delete_message_by_nonce(m.nonce).unwrap());
+            assert!(db.retrieve_message_by_nonce(m.nonce).unwrap().is_none());
+            assert!(db.retrieve_message_by_id(&m.id()).unwrap().is_none());
+            assert!(db
+                .retrieve_dispatched_block_number_by_nonce(&m.nonce)
+                .unwrap()
+                .is_none());
+
+            for leaf_index in [0, 1, 256, 3] {
+                let insertion =
+                    MerkleTreeInsertion::new(leaf_index, H256::from_low_u64_be(leaf_index as u64));
+                db.process_tree_insertion(&insertion, 1).unwrap();
+            }
+            assert_eq!(
+                db.retrieve_merkle_tree_insertion_leaf_indices().unwrap(),
+                vec![0, 1, 3, 256]
+            );
+            assert!(db.delete_merkle_tree_insertion_by_leaf_index(1).unwrap());
+            assert_eq!(
+                db.retrieve_merkle_tree_insertion_leaf_indices().unwrap(),
+                vec![0, 3, 256]
+            );
+            assert!(db
+                .retrieve_merkle_leaf_index_by_message_id(&H256::from_low_u64_be(1))
+                .unwrap()
+                .is_none());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_stores_and_retrieves_merkle_tree_snapshots() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("db_stores_and_retrieves_merkle_tree_snapshots"),
+                db,
+            );
+
+            let mut tree = IncrementalMerkle::default();
+            let mut leaves = vec![];
+            for leaf_index in 0..2 * MERKLE_TREE_SNAPSHOT_INTERVAL {
+                let message_id = H256::from_low_u64_be(leaf_index as u64);
+                db.process_tree_insertion(&MerkleTreeInsertion::new(leaf_index, message_id), 1)
+                    .unwrap();
+                tree.ingest(message_id);
+                leaves.push(message_id);
+                let stored = db.store_merkle_tree_snapshot(&tree).unwrap();
+                assert_eq!(
+                    stored,
+                    tree.count() as u32 % MERKLE_TREE_SNAPSHOT_INTERVAL == 0
+                );
+            }
+
+            assert_eq!(
+                db.retrieve_latest_merkle_tree_snapshot().unwrap(),
+                Some(tree)
+            );
+            assert_eq!(
+                db.retrieve_merkle_tree_snapshot_leaves(tree.count() as u32)
+                    .unwrap(),
+                Some(leaves)
+            );
+            // Snapshots never move backwards
+            let mut behind = IncrementalMerkle::default();
+            (0..MERKLE_TREE_SNAPSHOT_INTERVAL).for_each(|_| behind.ingest(H256::zero()));
+            assert!(!db.store_merkle_tree_snapshot(&behind).unwrap());
+
+            db.delete_latest_merkle_tree_snapshot().unwrap();
+            assert!(db.retrieve_latest_merkle_tree_snapshot().unwrap().is_none());
+        })
+        .await;
+    }
+
+    /// A merkle tree hook with the tree after each of `trees`' blocks, and
+    /// `tip` as the latest block if it's known.
+    #[derive(Debug, Clone)]
+    struct TestMerkleTreeHook {
+        trees: Vec<(u64, IncrementalMerkle)>,
+        tip: Option<u64>,
+    }
+
+    impl HyperlaneChain for TestMerkleTreeHook {
+        fn domain(&self) -> &HyperlaneDomain {
+            unimplemented!()
+        }
+
+        fn provider(&self) -> Box<dyn HyperlaneProvider> {
+            Box::new(self.clone())
+        }
+    }
+
+    impl HyperlaneContract for TestMerkleTreeHook {
+        fn address(&self) -> H256 {
+            H256::zero()
+        }
+    }
+
+    #[async_trait]
+    impl MerkleTreeHook for TestMerkleTreeHook {
+        async fn tree(&self, lag: Option<NonZeroU64>) -> ChainResult<IncrementalMerkle> {
+            let (tip, tree) = *self.trees.last().unwrap();
+            let block = self.tip.unwrap_or(tip) - lag.map_or(0, NonZeroU64::get);
+            Ok(self
+                .trees
+                .iter()
+                .rev()
+                .find(|(number, _)| *number <= block)
+                .map(|(_, tree)| *tree)
+                .unwrap_or(tree))
+        }
+
+        async fn count(&self, lag: Option<NonZeroU64>) -> ChainResult<u32> {
+            Ok(self.tree(lag).await?.count() as u32)
+        }
+
+        async fn latest_checkpoint(&self, _lag: Option<NonZeroU64>) -> ChainResult<Checkpoint> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait]
+    impl HyperlaneProvider for TestMerkleTreeHook {
+        async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
+            unimplemented!()
+        }
+
+        async fn get_block_by_height(&self, _height: u64) -> ChainResult<BlockInfo> {
+            unimplemented!()
+        }
+
+        async fn get_txn_by_hash(&self, _hash: &H512) -> ChainResult<TxnInfo> {
+            unimplemented!()
+        }
+
+        async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
+            unimplemented!()
+        }
+
+        async fn get_balance(&self, _address: String) -> ChainResult<U256> {
+            unimplemented!()
+        }
+
+        async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>> {
+            Ok(self.tip.map(|number| ChainInfo {
+                latest_block: BlockInfo {
+                    hash: H256::zero(),
+                    timestamp: 0,
+                    number,
+                },
+                min_gas_price: None,
+            }))
+        }
+
+        async fn get_chain_id(&self) -> ChainResult<Option<U256>> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn db_verifies_merkle_tree_snapshots_at_their_block() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain(
+                    "db_verifies_merkle_tree_snapshots_at_their_block",
+                ),
+                db,
+            );
+
+            // The leaves of chunk `n` are inserted in block `n + 1`, but only
+            // the first chunk is indexed
+            let mut onchain_tree = IncrementalMerkle::default();
+            let mut trees = vec![];
+            for block in 1..=3 {
+                for _ in 0..MERKLE_TREE_SNAPSHOT_INTERVAL {
+                    let leaf_index = onchain_tree.count() as u32;
+                    let message_id = H256::from_low_u64_be(leaf_index as u64 + 1);
+                    if block == 1 {
+                        let insertion = MerkleTreeInsertion::new(leaf_index, message_id);
+                        db.process_tree_insertion(&insertion, block).unwrap();
+                    }
+                    onchain_tree.ingest(message_id);
+                }
+                trees.push((block, onchain_tree));
+            }
+            let snapshot = trees[0].1;
+            assert!(db.store_merkle_tree_snapshot(&snapshot).unwrap());
+
+            // Without the tip, the block of the snapshot can't be queried, so
+            // it's kept but not used
+            let hook = TestMerkleTreeHook { trees, tip: None };
+            assert_eq!(
+                db.retrieve_verified_merkle_tree_snapshot(&hook, None)
+                    .await
+                    .unwrap(),
+                None
+            );
+            assert_eq!(
+                db.retrieve_latest_merkle_tree_snapshot().unwrap(),
+                Some(snapshot)
+            );
+
+            let hook = TestMerkleTreeHook {
+                tip: Some(3),
+                ..hook
+            };
+            assert_eq!(
+                db.retrieve_verified_merkle_tree_snapshot(&hook, None)
+                    .await
+                    .unwrap(),
+                Some(snapshot)
+            );
+
+            // A snapshot with the right count but a wrong leaf is discarded
+            let mut wrong_snapshot = IncrementalMerkle::default();
+            wrong_snapshot.ingest(H256::repeat_byte(0xff));
+            (1..MERKLE_TREE_SNAPSHOT_INTERVAL)
+                .for_each(|index| wrong_snapshot.ingest(H256::from_low_u64_be(index as u64 + 1)));
+            db.delete_latest_merkle_tree_snapshot().unwrap();
+            assert!(db.store_merkle_tree_snapshot(&wrong_snapshot).unwrap());
+            assert_eq!(
+                db.retrieve_verified_merkle_tree_snapshot(&hook, None)
+                    .await
+                    .unwrap(),
+                None
+            );
+            assert!(db.retrieve_latest_merkle_tree_snapshot().unwrap().is_none());
+        })
+        .await;
+    }
 }
@@ -67,6 +67,22 @@ impl TypedDB {
             .map_err(Into::into)
     }
 
+    /// Delete the value stored at a key
+    pub fn delete(&self, prefix: impl AsRef<[u8]>, key: impl AsRef<[u8]>) -> Result<()> {
+        self.db
+            .delete(&self.prefixed_key(prefix.as_ref(), key.as_ref()))
+    }
+
+    /// Retrieve the keys, stripped of the domain and `prefix`, of all values
+    /// stored under `prefix`, ordered by key.
+    pub fn retrieve_keys(&self, prefix: impl AsRef<[u8]>) -> Result<Vec<Vec<u8>>> {
+        let prefix = self.prefixed_key(prefix.as_ref(), &[]);
+        self.db
+            .iter_prefix(&prefix)
+            .map(|r| r.map(|(k, _)| k[prefix.len()..].to_vec()))
+            .collect()
+    }
+
     /// Store encodable kv pair
     pub fn store_keyed_encodable<K: Encode, V: Encode>(
         &self,
@@ -85,4 +101,9 @@ impl TypedDB {
     ) -> Result<Option<V>> {
         self.retrieve_decodable(prefix, key.to_vec())
     }
+
+    /// Delete the value stored at an encodable key
+    pub fn delete_keyed<K: Encode>(&self, prefix: impl AsRef<[u8]>, key: &K) -> Result<()> {
+        self.delete(prefix, key.to_vec())
+    }
 }
@@ -0,0 +1,738 @@
+//! Seeds a `HyperlaneRocksDB` with historical logs exported from another
+//! source, e.g. the scraper's database, instead of indexing them over RPC.
+//!
+//! A snapshot is either a single JSON file of the form
+//! `{ "messages": [..], "gasPayments": [..], "merkleTreeInsertions": [..] }`,
+//! or a directory containing any of `messages.csv`, `gas_payments.csv` and
+//! `merkle_tree_insertions.csv` with a header row. Field names are the same
+//! in both formats and may be camelCase or snake_case.
+
+use std::{collections::HashSet, fs::File, io::BufReader, num::NonZeroU64, path::Path, sync::Arc};
+
+use convert_case::{Case, Casing};
+use eyre::{bail, ensure, eyre, Context, Result};
+use hyperlane_core::{
+    accumulator::incremental::IncrementalMerkle, HyperlaneLogStore, HyperlaneMessage, Indexer,
+    InterchainGasPayment, LogMeta, Mailbox, MerkleTreeHook, MerkleTreeInsertion,
+    SequenceAwareIndexer, H256, H512, U256,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use tracing::{info, instrument, warn};
+
+use crate::{settings::ChainConf, CoreMetrics};
+
+use super::HyperlaneRocksDB;
+
+const MESSAGES_CSV: &str = "messages.csv";
+const GAS_PAYMENTS_CSV: &str = "gas_payments.csv";
+const MERKLE_TREE_INSERTIONS_CSV: &str = "merkle_tree_insertions.csv";
+
+/// The number of times the on-chain tree is queried when verifying merkle tree
+/// insertions, since the block the tree is queried at may move with the tip.
+const TREE_VERIFICATION_ATTEMPTS: usize = 3;
+
+/// A dispatched message, as exported by the scraper.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageRecord {
+    /// Hyperlane version number
+    pub version: u8,
+    /// Message nonce
+    pub nonce: u32,
+    /// Origin domain ID
+    pub origin: u32,
+    /// Sender address, left-padded to 32 bytes
+    pub sender: H256,
+    /// Destination domain ID
+    pub destination: u32,
+    /// Recipient address, left-padded to 32 bytes
+    pub recipient: H256,
+    /// Hex encoded message body
+    #[serde(deserialize_with = "deserialize_hex_bytes")]
+    pub body: Vec<u8>,
+    /// The block the message was dispatched in
+    pub block_number: u64,
+}
+
+impl MessageRecord {
+    /// The dispatched message
+    pub fn message(&self) -> HyperlaneMessage {
+        HyperlaneMessage {
+            version: self.version,
+            nonce: self.nonce,
+            origin: self.origin,
+            sender: self.sender,
+            destination: self.destination,
+            recipient: self.recipient,
+            body: self.body.clone(),
+        }
+    }
+}
+
+/// A gas payment made to an interchain gas paymaster.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasPaymentRecord {
+    /// Id of the message paid for
+    pub message_id: H256,
+    /// Destination domain paid for
+    pub destination: u32,
+    /// Amount of native tokens paid, as a decimal or hex string
+    #[serde(deserialize_with = "deserialize_u256")]
+    pub payment: U256,
+    /// Amount of destination gas paid for, as a decimal or hex string
+    #[serde(deserialize_with = "deserialize_u256")]
+    pub gas_amount: U256,
+    /// The block the payment was made in
+    pub block_number: u64,
+    /// The transaction the payment was made in. 32 byte hashes are
+    /// left-padded to 64 bytes.
+    #[serde(deserialize_with = "deserialize_transaction_id")]
+    pub transaction_id: H512,
+    /// The index of the payment's log in its block
+    pub log_index: u64,
+}
+
+/// An insertion into the merkle tree hook's tree.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleTreeInsertionRecord {
+    /// Index of the leaf in the tree
+    pub leaf_index: u32,
+    /// Id of the inserted message
+    pub message_id: H256,
+    /// The block the leaf was inserted in
+    pub block_number: u64,
+}
+
+/// Historical logs of a single origin chain.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogSnapshot {
+    /// Dispatched messages
+    pub messages: Vec<MessageRecord>,
+    /// Gas payments
+    pub gas_payments: Vec<GasPaymentRecord>,
+    /// Merkle tree insertions
+    pub merkle_tree_insertions: Vec<MerkleTreeInsertionRecord>,
+}
+
+impl LogSnapshot {
+    /// Read a snapshot from a JSON file or a directory of CSV files.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            Ok(Self {
+                messages: read_csv(&path.join(MESSAGES_CSV))?,
+                gas_payments: read_csv(&path.join(GAS_PAYMENTS_CSV))?,
+                merkle_tree_insertions: read_csv(&path.join(MERKLE_TREE_INSERTIONS_CSV))?,
+            })
+        } else {
+            let file =
+                File::open(path).with_context(|| format!("Opening snapshot {}", path.display()))?;
+            let value: serde_json::Value = serde_json::from_reader(BufReader::new(file))
+                .with_context(|| format!("Parsing snapshot {}", path.display()))?;
+            serde_json::from_value(camel_case_keys(value))
+                .with_context(|| format!("Parsing snapshot {}", path.display()))
+        }
+    }
+
+    /// Returns the messages and their block numbers sorted by nonce, checking
+    /// that their nonces are contiguous, that they were dispatched on `origin`
+    /// and that each of them was inserted into the merkle tree.
+    ///
+    /// Messages are matched with insertions by id rather than by leaf index,
+    /// since the merkle tree hook may have been deployed after the mailbox
+    /// dispatched its first messages. Messages with a lower nonce than the
+    /// message inserted at leaf index zero predate the hook, so they have no
+    /// insertion and are trusted. Once the insertions are verified against the
+    /// on-chain tree, this verifies the contents of all later messages too.
+    pub fn verified_messages(&self, origin: u32) -> Result<Vec<(HyperlaneMessage, u64)>> {
+        let mut messages: Vec<_> = self
+            .messages
+            .iter()
+            .map(|record| (record.message(), record.block_number))
+            .collect();
+        messages.sort_by_key(|(message, _)| message.nonce);
+        messages.dedup();
+
+        for pair in messages.windows(2) {
+            let (first, second) = (&pair[0].0, &pair[1].0);
+            ensure!(
+                first.nonce != second.nonce,
+                "Snapshot contains conflicting messages with nonce {}",
+                first.nonce
+            );
+            ensure!(
+                first.nonce + 1 == second.nonce,
+                "Snapshot is missing messages with nonces {} to {}",
+                first.nonce + 1,
+                second.nonce - 1
+            );
+        }
+        if let Some((message, _)) = messages
+            .iter()
+            .find(|(message, _)| message.origin != origin)
+        {
+            bail!(
+                "Snapshot contains message with nonce {} from origin {}, expected {}",
+                message.nonce,
+                message.origin,
+                origin
+            );
+        }
+
+        let inserted: HashSet<H256> = self
+            .merkle_tree_insertions
+            .iter()
+            .map(|insertion| insertion.message_id)
+            .collect();
+        let first_inserted_nonce = self
+            .merkle_tree_insertions
+            .iter()
+            .find(|insertion| insertion.leaf_index == 0)
+            .and_then(|first| {
+                messages
+                    .iter()
+                    .find(|(message, _)| message.id() == first.message_id)
+            })
+            .map(|(message, _)| message.nonce);
+        if let Some((message, _)) = messages
+            .iter()
+            .filter(|(message, _)| {
+                first_inserted_nonce.map_or(true, |nonce| message.nonce >= nonce)
+            })
+            .find(|(message, _)| !inserted.contains(&message.id()))
+        {
+            bail!(
+                "Snapshot contains message with nonce {} and id {:?}, which has no merkle tree insertion",
+                message.nonce,
+                message.id()
+            );
+        }
+        Ok(messages)
+    }
+
+    /// Returns the merkle tree built from the insertions, checking that their
+    /// leaf indices are contiguous from zero.
+    pub fn merkle_tree(&self) -> Result<IncrementalMerkle> {
+        let mut insertions: Vec<_> = self
+            .merkle_tree_insertions
+            .iter()
+            .map(|insertion| (insertion.leaf_index, insertion.message_id))
+            .collect();
+        insertions.sort();
+        insertions.dedup();
+
+        let mut tree = IncrementalMerkle::default();
+        for (leaf_index, message_id) in insertions {
+            ensure!(
+                tree.count() == 0 || leaf_index != tree.index(),
+                "Snapshot contains conflicting merkle tree insertions with leaf index {}",
+                leaf_index
+            );
+            ensure!(
+                leaf_index as usize == tree.count(),
+                "Snapshot is missing merkle tree insertions with leaf indices {} to {}",
+                tree.count(),
+                leaf_index.saturating_sub(1)
+            );
+            tree.ingest(message_id);
+        }
+        Ok(tree)
+    }
+
+    /// Returns a digest of the files the snapshot at `path` is read from,
+    /// which identifies it across restarts.
+    pub fn digest(path: &Path) -> Result<H256> {
+        let mut hasher = Sha256::new();
+        let mut update = |file: &Path| -> Result<()> {
+            let contents =
+                std::fs::read(file).with_context(|| format!("Reading {}", file.display()))?;
+            hasher.update((contents.len() as u64).to_be_bytes());
+            hasher.update(contents);
+            Ok(())
+        };
+        if path.is_dir() {
+            for name in [MESSAGES_CSV, GAS_PAYMENTS_CSV, MERKLE_TREE_INSERTIONS_CSV] {
+                let file = path.join(name);
+                if file.exists() {
+                    update(&file)?;
+                }
+            }
+        } else {
+            update(path)?;
+        }
+        Ok(H256::from_slice(&hasher.finalize()))
+    }
+
+    fn last_insertion_block(&self) -> Option<u64> {
+        self.merkle_tree_insertions
+            .iter()
+            .map(|insertion| insertion.block_number)
+            .max()
+    }
+}
+
+/// The number of new logs stored by a `SnapshotImporter`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SnapshotImportSummary {
+    /// Number of new messages
+    pub messages: u32,
+    /// Number of new gas payments
+    pub gas_payments: u32,
+    /// Number of new merkle tree insertions
+    pub merkle_tree_insertions: u32,
+}
+
+/// Verifies a `LogSnapshot` against the origin chain and stores it in a
+/// `HyperlaneRocksDB`. Nothing is stored unless the whole snapshot is valid.
+///
+/// Message nonces must be contiguous and below the mailbox's on-chain count.
+/// Merkle tree insertions must start at leaf index zero, and their root must
+/// match the on-chain tree at the block of the last insertion. Every message
+/// from the one inserted at leaf index zero on must have an insertion of its
+/// id, so its contents are covered by the on-chain root. Messages dispatched
+/// before the merkle tree hook was deployed and gas payments can't be verified
+/// without querying their logs, so they are trusted.
+///
+/// Imported snapshots are recorded in the database by their digest, so they
+/// aren't imported again on later starts.
+#[derive(Debug)]
+pub struct SnapshotImporter {
+    db: HyperlaneRocksDB,
+    mailbox: Arc<dyn Mailbox>,
+    merkle_tree_hook: Arc<dyn MerkleTreeHook>,
+    merkle_tree_hook_indexer: Arc<dyn SequenceAwareIndexer<MerkleTreeInsertion>>,
+    reorg_period: u32,
+}
+
+impl SnapshotImporter {
+    /// Build an importer for the chain `db` is scoped to.
+    pub async fn from_chain_conf(
+        chain_conf: &ChainConf,
+        db: HyperlaneRocksDB,
+        metrics: &CoreMetrics,
+    ) -> Result<Self> {
+        Ok(Self {
+            db,
+            mailbox: chain_conf.build_mailbox(metrics).await?.into(),
+            merkle_tree_hook: chain_conf.build_merkle_tree_hook(metrics).await?.into(),
+            merkle_tree_hook_indexer: chain_conf
+                .build_merkle_tree_hook_indexer(metrics)
+                .await?
+                .into(),
+            reorg_period: chain_conf.reorg_period,
+        })
+    }
+
+    /// Read the snapshot at `path`, verify it and store it, unless it was
+    /// already imported.
+    pub async fn import_path(&self, path: &Path) -> Result<SnapshotImportSummary> {
+        let digest = LogSnapshot::digest(path)?;
+        if self
+            .db
+            .retrieve_imported_snapshot_by_digest(&digest)?
+            .unwrap_or_default()
+        {
+            info!(path = %path.display(), ?digest, "Snapshot was already imported, skipping it");
+            return Ok(SnapshotImportSummary::default());
+        }
+        let snapshot = LogSnapshot::from_path(path)?;
+        let summary = self
+            .import(&snapshot)
+            .await
+            .with_context(|| format!("Importing snapshot {}", path.display()))?;
+        self.db.store_imported_snapshot_by_digest(&digest, &true)?;
+        Ok(summary)
+    }
+
+    /// Verify the snapshot and store it.
+    #[instrument(skip_all, fields(domain = self.db.domain().name()))]
+    pub async fn import(&self, snapshot: &LogSnapshot) -> Result<SnapshotImportSummary> {
+        let messages = snapshot.verified_messages(self.db.domain().id())?;
+        if let Some((last, _)) = messages.last() {
+            let count = self.mailbox.count(None).await?;
+            ensure!(
+                last.nonce < count,
+                "Snapshot contains message with nonce {} but the mailbox only dispatched {} messages",
+                last.nonce,
+                count
+            );
+        }
+        self.verify_merkle_tree(snapshot).await?;
+
+        let messages: Vec<_> = messages
+            .into_iter()
+            .map(|(message, block_number)| (message, block_meta(block_number)))
+            .collect();
+        let gas_payments: Vec<_> = snapshot
+            .gas_payments
+            .iter()
+            .map(|record| {
+                let payment = InterchainGasPayment {
+                    message_id: record.message_id,
+                    destination: record.destination,
+                    payment: record.payment,
+                    gas_amount: record.gas_amount,
+                };
+                let meta = LogMeta {
+                    transaction_id: record.transaction_id,
+                    log_index: record.log_index.into(),
+                    ..block_meta(record.block_number)
+                };
+                (payment, meta)
+            })
+            .collect();
+        let insertions: Vec<_> = snapshot
+            .merkle_tree_insertions
+            .iter()
+            .map(|record| {
+                (
+                    MerkleTreeInsertion::new(record.leaf_index, record.message_id),
+                    block_meta(record.block_number),
+                )
+            })
+            .collect();
+
+        let summary = SnapshotImportSummary {
+            messages: HyperlaneLogStore::<HyperlaneMessage>::store_logs(&self.db, &messages)
+                .await?,
+            gas_payments: HyperlaneLogStore::<InterchainGasPayment>::store_logs(
+                &self.db,
+                &gas_payments,
+            )
+            .await?,
+            merkle_tree_insertions: HyperlaneLogStore::<MerkleTreeInsertion>::store_logs(
+                &self.db,
+                &insertions,
+            )
+            .await?,
+        };
+        info!(?summary, "Imported snapshot");
+        Ok(summary)
+    }
+
+    /// Compares the root of the snapshot's tree with the on-chain tree at the
+    /// block of the last insertion.
+    async fn verify_merkle_tree(&self, snapshot: &LogSnapshot) -> Result<()> {
+        let Some(last_block) = snapshot.last_insertion_block() else {
+            return Ok(());
+        };
+        let tree = snapshot.merkle_tree()?;
+
+        for attempt in 1..=TREE_VERIFICATION_ATTEMPTS {
+            let finalized = self
+                .merkle_tree_hook_indexer
+                .get_finalized_block_number()
+                .await? as u64;
+            ensure!(
+                last_block <= finalized,
+                "Snapshot contains merkle tree insertions in block {last_block}, which isn't final yet"
+            );
+            let lag = NonZeroU64::new(finalized + self.reorg_period as u64 - last_block);
+            let onchain_tree = self.merkle_tree_hook.tree(lag).await?;
+
+            if onchain_tree.count() == tree.count() {
+                ensure!(
+                    onchain_tree.root() == tree.root(),
+                    "Snapshot merkle root {:?} doesn't match the on-chain root {:?} at block {}",
+                    tree.root(),
+                    onchain_tree.root(),
+                    last_block
+                );
+                return Ok(());
+            }
+            warn!(
+                attempt,
+                snapshot_count = tree.count(),
+                onchain_count = onchain_tree.count(),
+                last_block,
+                "Snapshot merkle tree count doesn't match the on-chain count"
+            );
+        }
+        Err(eyre!(
+            "Snapshot merkle tree has {} leaves, which doesn't match the on-chain tree at block {}. \
+            Snapshots must include all insertions of their last block.",
+            tree.count(),
+            last_block
+        ))
+    }
+}
+
+fn block_meta(block_number: u64) -> LogMeta {
+    LogMeta {
+        block_number,
+        ..Default::default()
+    }
+}
+
+/// Reads all records of a CSV file, or none if it doesn't exist.
+fn read_csv<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let mut reader =
+        csv::Reader::from_path(path).with_context(|| format!("Opening {}", path.display()))?;
+    let headers: csv::StringRecord = reader
+        .headers()?
+        .iter()
+        .map(|header| header.to_case(Case::Camel))
+        .collect();
+    reader
+        .records()
+        .map(|record| record?.deserialize(Some(&headers)).map_err(Into::into))
+        .collect::<Result<_>>()
+        .with_context(|| format!("Parsing {}", path.display()))
+}
+
+/// Recursively converts the keys of JSON objects to camelCase.
+fn camel_case_keys(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key.to_case(Case::Camel), camel_case_keys(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(camel_case_keys).collect()),
+        value => value,
+    }
+}
+
+fn deserialize_hex_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    hex::decode(s.trim_start_matches("0x")).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_u256<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::Number(n) => Ok(n.into()),
+        StringOrNumber::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16),
+            None => U256::from_dec_str(&s),
+        }
+        .map_err(serde::de::Error::custom),
+    }
+}
+
+fn deserialize_transaction_id<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<H512, D::Error> {
+    let bytes = deserialize_hex_bytes(deserializer)?;
+    match bytes.len() {
+        32 => Ok(H256::from_slice(&bytes).into()),
+        64 => Ok(H512::from_slice(&bytes)),
+        len => Err(serde::de::Error::custom(format!(
+            "expected a 32 or 64 byte transaction id, got {len} bytes"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::*;
+
+    fn message(nonce: u32) -> MessageRecord {
+        MessageRecord {
+            version: 3,
+            nonce,
+            origin: 1,
+            sender: H256::zero(),
+            destination: 2,
+            recipient: H256::zero(),
+            body: vec![nonce as u8],
+            block_number: 100 + nonce as u64,
+        }
+    }
+
+    fn insertion(leaf_index: u32) -> MerkleTreeInsertionRecord {
+        MerkleTreeInsertionRecord {
+            leaf_index,
+            message_id: H256::from_low_u64_be(leaf_index as u64 + 1),
+            block_number: 100,
+        }
+    }
+
+    /// Insertions of the messages' ids, as if they were the first messages
+    /// inserted into the tree
+    fn insertions_of(messages: &[MessageRecord]) -> Vec<MerkleTreeInsertionRecord> {
+        messages
+            .iter()
+            .enumerate()
+            .map(|(leaf_index, record)| MerkleTreeInsertionRecord {
+                leaf_index: leaf_index as u32,
+                message_id: record.message().id(),
+                block_number: record.block_number,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verified_messages_rejects_gaps_and_foreign_origins() {
+        let snapshot = LogSnapshot {
+            messages: vec![message(4), message(3), message(5), message(4)],
+            merkle_tree_insertions: insertions_of(&[message(3), message(4), message(5)]),
+            ..Default::default()
+        };
+        let nonces: Vec<_> = snapshot
+            .verified_messages(1)
+            .unwrap()
+            .iter()
+            .map(|(message, _)| message.nonce)
+            .collect();
+        assert_eq!(nonces, vec![3, 4, 5]);
+        assert!(snapshot.verified_messages(2).is_err());
+
+        let snapshot = LogSnapshot {
+            messages: vec![message(3), message(5)],
+            merkle_tree_insertions: insertions_of(&[message(3), message(5)]),
+            ..Default::default()
+        };
+        assert!(snapshot.verified_messages(1).is_err());
+    }
+
+    #[test]
+    fn test_verified_messages_rejects_messages_not_inserted() {
+        let messages = vec![message(3), message(4)];
+        let insertions = insertions_of(&messages);
+        let snapshot = LogSnapshot {
+            messages: messages.clone(),
+            merkle_tree_insertions: insertions.clone(),
+            ..Default::default()
+        };
+        assert_eq!(snapshot.verified_messages(1).unwrap().len(), 2);
+
+        // A message whose contents were tampered with no longer matches the
+        // id that was inserted into the tree
+        let mut tampered = message(4);
+        tampered.body = b"forged".to_vec();
+        let snapshot = LogSnapshot {
+            messages: vec![message(3), tampered],
+            merkle_tree_insertions: insertions,
+            ..Default::default()
+        };
+        assert!(snapshot.verified_messages(1).is_err());
+
+        // Messages can't be verified without insertions
+        let snapshot = LogSnapshot {
+            messages,
+            ..Default::default()
+        };
+        assert!(snapshot.verified_messages(1).is_err());
+    }
+
+    #[test]
+    fn test_verified_messages_trusts_messages_before_the_hook() {
+        // The hook was deployed after the messages with nonces 0 and 1 were
+        // dispatched, so the message with nonce 2 is inserted at leaf index 0
+        let messages: Vec<_> = (0..5).map(message).collect();
+        let insertions = insertions_of(&messages[2..]);
+        let snapshot = LogSnapshot {
+            messages: messages.clone(),
+            merkle_tree_insertions: insertions.clone(),
+            ..Default::default()
+        };
+        let nonces: Vec<_> = snapshot
+            .verified_messages(1)
+            .unwrap()
+            .iter()
+            .map(|(message, _)| message.nonce)
+            .collect();
+        assert_eq!(nonces, vec![0, 1, 2, 3, 4]);
+
+        // Messages after the hook's first insertion are still verified
+        let mut tampered = messages.clone();
+        tampered[3].body = b"forged".to_vec();
+        let snapshot = LogSnapshot {
+            messages: tampered,
+            merkle_tree_insertions: insertions.clone(),
+            ..Default::default()
+        };
+        assert!(snapshot.verified_messages(1).is_err());
+
+        // Without the message inserted at leaf index zero, it's unknown which
+        // messages predate the hook
+        let snapshot = LogSnapshot {
+            messages,
+            merkle_tree_insertions: [insertion(0)]
+                .into_iter()
+                .chain(insertions[1..].iter().cloned())
+                .collect(),
+            ..Default::default()
+        };
+        assert!(snapshot.verified_messages(1).is_err());
+    }
+
+    #[test]
+    fn test_merkle_tree_requires_leaves_from_zero() {
+        let snapshot = LogSnapshot {
+            merkle_tree_insertions: vec![insertion(1), insertion(0), insertion(2)],
+            ..Default::default()
+        };
+        let mut expected = IncrementalMerkle::default();
+        (0..3).for_each(|i| expected.ingest(insertion(i).message_id));
+        assert_eq!(snapshot.merkle_tree().unwrap(), expected);
+
+        let snapshot = LogSnapshot {
+            merkle_tree_insertions: vec![insertion(1), insertion(2)],
+            ..Default::default()
+        };
+        assert!(snapshot.merkle_tree().is_err());
+    }
+
+    #[test]
+    fn test_reads_json_and_csv_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let message_id = format!("0x{}", "11".repeat(32));
+
+        let json = dir.path().join("snapshot.json");
+        std::fs::write(
+            &json,
+            serde_json::json!({
+                "gas_payments": [{
+                    "message_id": message_id,
+                    "destination": 2,
+                    "payment": "1000000000000000000000",
+                    "gas_amount": "0x10",
+                    "block_number": 5,
+                    "transaction_id": message_id,
+                    "log_index": 3,
+                }],
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let snapshot = LogSnapshot::from_path(&json).unwrap();
+        let payment = &snapshot.gas_payments[0];
+        assert_eq!(
+            payment.payment,
+            U256::from_dec_str("1000000000000000000000").unwrap()
+        );
+        assert_eq!(payment.gas_amount, U256::from(16));
+        assert_eq!(payment.transaction_id, H256::from_slice(&[0x11; 32]).into());
+
+        let mut csv = File::create(dir.path().join(MERKLE_TREE_INSERTIONS_CSV)).unwrap();
+        writeln!(csv, "leaf_index,message_id,block_number").unwrap();
+        writeln!(csv, "0,{message_id},7").unwrap();
+        let snapshot = LogSnapshot::from_path(dir.path()).unwrap();
+        assert!(snapshot.messages.is_empty());
+        assert_eq!(snapshot.merkle_tree_insertions.len(), 1);
+        assert_eq!(snapshot.merkle_tree_insertions[0].block_number, 7);
+
+        let digest = LogSnapshot::digest(dir.path()).unwrap();
+        assert_eq!(LogSnapshot::digest(dir.path()).unwrap(), digest);
+        writeln!(csv, "1,{message_id},8").unwrap();
+        assert_ne!(LogSnapshot::digest(dir.path()).unwrap(), digest);
+        assert_ne!(LogSnapshot::digest(&json).unwrap(), digest);
+    }
+}
@@ -31,5 +31,8 @@ pub use types::*;
 /// Hyperlane database utils
 pub mod db;
 
+/// Message lifecycle notifications
+pub mod notifications;
+
 #[cfg(feature = "oneline-eyre")]
 pub mod oneline_eyre;
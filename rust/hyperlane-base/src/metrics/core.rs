@@ -15,7 +15,9 @@ use tokio::sync::RwLock;
 use ethers_prometheus::{json_rpc_client::JsonRpcClientMetrics, middleware::MiddlewareMetrics};
 
 use crate::metrics::{
+    fallback_provider::create_fallback_provider_metrics,
     json_rpc_client::create_json_rpc_client_metrics, provider::create_provider_metrics,
+    FallbackProviderMetrics,
 };
 
 /// Macro to prefix a string with the namespace.
@@ -38,6 +40,7 @@ pub struct CoreMetrics {
     span_events: IntCounterVec,
     last_known_message_nonce: IntGaugeVec,
     submitter_queue_length: IntGaugeVec,
+    submitter_priority_queue_length: IntGaugeVec,
 
     operations_processed_count: IntCounterVec,
     messages_processed_count: IntCounterVec,
@@ -51,6 +54,9 @@ pub struct CoreMetrics {
     /// Set of provider-specific metrics. These only need to get created once.
     provider_metrics: OnceLock<MiddlewareMetrics>,
 
+    /// Health of the inner providers of fallback providers.
+    fallback_provider_metrics: OnceLock<FallbackProviderMetrics>,
+
     /// Metrics that are used to observe validator sets.
     pub validator_metrics: ValidatorObservabilityMetricManager,
 }
@@ -136,6 +142,16 @@ impl CoreMetrics {
             registry
         )?;
 
+        let submitter_priority_queue_length = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("submitter_priority_queue_length"),
+                "Submitter queue length by priority class",
+                const_labels_ref
+            ),
+            &["remote", "queue_name", "priority_class"],
+            registry
+        )?;
+
         let latest_checkpoint = register_int_gauge_vec_with_registry!(
             opts!(
                 namespaced!("latest_checkpoint"),
@@ -178,6 +194,7 @@ impl CoreMetrics {
             last_known_message_nonce,
 
             submitter_queue_length,
+            submitter_priority_queue_length,
 
             operations_processed_count,
             messages_processed_count,
@@ -186,6 +203,7 @@ impl CoreMetrics {
 
             json_rpc_client_metrics: OnceLock::new(),
             provider_metrics: OnceLock::new(),
+            fallback_provider_metrics: OnceLock::new(),
 
             validator_metrics: ValidatorObservabilityMetricManager::new(
                 observed_validator_latest_index.clone(),
@@ -212,6 +230,17 @@ impl CoreMetrics {
             .clone()
     }
 
+    /// Create the fallback provider health metrics attached to this core
+    /// metrics instance.
+    pub fn fallback_provider_metrics(&self) -> FallbackProviderMetrics {
+        self.fallback_provider_metrics
+            .get_or_init(|| {
+                create_fallback_provider_metrics(self)
+                    .expect("Failed to create fallback provider metrics!")
+            })
+            .clone()
+    }
+
     /// Create and register a new int gauge.
     pub fn new_int_gauge(
         &self,
@@ -327,6 +356,17 @@ impl CoreMetrics {
         self.submitter_queue_length.clone()
     }
 
+    /// Measure of the queue lengths in Submitter instances by the priority
+    /// class of the operations
+    ///
+    /// Labels:
+    /// - `remote`: Remote chain the queue is for.
+    /// - `queue_name`: Which queue the message is in.
+    /// - `priority_class`: The priority class of the message.
+    pub fn submitter_priority_queue_length(&self) -> IntGaugeVec {
+        self.submitter_priority_queue_length.clone()
+    }
+
     /// The number of operations successfully submitted by this process during
     /// its lifetime.
     ///
@@ -0,0 +1,149 @@
+use std::fmt::{Debug, Formatter};
+
+use derive_builder::Builder;
+use eyre::Result;
+use hyperlane_core::rpc_clients::{ProviderHealthObserver, ProviderHealthSnapshot};
+use prometheus::{GaugeVec, IntGaugeVec};
+use url::Url;
+
+use crate::CoreMetrics;
+
+/// Expected label names for the fallback provider health metrics.
+pub const FALLBACK_PROVIDER_LABELS: &[&str] = &["chain", "provider_node"];
+/// Help string for the metric.
+pub const FALLBACK_PROVIDER_SCORE_HELP: &str =
+    "Health score in [0, 1] of an inner provider of a fallback provider, higher is healthier";
+/// Help string for the metric.
+pub const FALLBACK_PROVIDER_ERROR_RATE_HELP: &str =
+    "Ratio of recent requests to an inner provider of a fallback provider that failed";
+/// Expected label names for the latency metric.
+pub const FALLBACK_PROVIDER_LATENCY_LABELS: &[&str] = &["chain", "provider_node", "quantile"];
+/// Help string for the metric.
+pub const FALLBACK_PROVIDER_LATENCY_HELP: &str =
+    "Latency percentiles of recent requests to an inner provider of a fallback provider";
+/// Help string for the metric.
+pub const FALLBACK_PROVIDER_CIRCUIT_STATE_HELP: &str =
+    "Circuit breaker state of an inner provider of a fallback provider (0 = closed, 1 = half-open, 2 = open)";
+
+/// Metrics about the health of the inner providers of fallback providers.
+#[derive(Clone, Builder, Debug)]
+pub struct FallbackProviderMetrics {
+    /// Health score of the provider, see `ProviderHealth::score`.
+    /// - `chain`: the chain name of the fallback provider.
+    /// - `provider_node`: host of the inner provider.
+    #[builder(setter(into))]
+    score: GaugeVec,
+
+    /// Ratio of recent requests that failed.
+    /// - `chain`: the chain name of the fallback provider.
+    /// - `provider_node`: host of the inner provider.
+    #[builder(setter(into))]
+    error_rate: GaugeVec,
+
+    /// Latency percentiles of recent requests, in seconds.
+    /// - `chain`: the chain name of the fallback provider.
+    /// - `provider_node`: host of the inner provider.
+    /// - `quantile`: `0.5` or `0.9`.
+    #[builder(setter(into))]
+    latency_seconds: GaugeVec,
+
+    /// Circuit breaker state, see `CircuitState::as_metric_value`.
+    /// - `chain`: the chain name of the fallback provider.
+    /// - `provider_node`: host of the inner provider.
+    #[builder(setter(into))]
+    circuit_state: IntGaugeVec,
+}
+
+pub(crate) fn create_fallback_provider_metrics(
+    metrics: &CoreMetrics,
+) -> Result<FallbackProviderMetrics> {
+    Ok(FallbackProviderMetricsBuilder::default()
+        .score(metrics.new_gauge(
+            "fallback_provider_score",
+            FALLBACK_PROVIDER_SCORE_HELP,
+            FALLBACK_PROVIDER_LABELS,
+        )?)
+        .error_rate(metrics.new_gauge(
+            "fallback_provider_error_rate",
+            FALLBACK_PROVIDER_ERROR_RATE_HELP,
+            FALLBACK_PROVIDER_LABELS,
+        )?)
+        .latency_seconds(metrics.new_gauge(
+            "fallback_provider_latency_seconds",
+            FALLBACK_PROVIDER_LATENCY_HELP,
+            FALLBACK_PROVIDER_LATENCY_LABELS,
+        )?)
+        .circuit_state(metrics.new_int_gauge(
+            "fallback_provider_circuit_state",
+            FALLBACK_PROVIDER_CIRCUIT_STATE_HELP,
+            FALLBACK_PROVIDER_LABELS,
+        )?)
+        .build()?)
+}
+
+impl FallbackProviderMetrics {
+    /// Create an observer for the inner providers of a fallback provider
+    /// connecting to `urls`, in order of priority.
+    pub fn observer_for(&self, chain: &str, urls: &[Url]) -> FallbackProviderHealthReporter {
+        FallbackProviderHealthReporter {
+            chain: chain.to_owned(),
+            // Only the host is used as a label, since the rest of the url may contain API keys
+            hosts: urls.iter().map(node_host).collect(),
+            metrics: self.clone(),
+        }
+    }
+}
+
+/// Exports the health of the inner providers of a single fallback provider
+/// to `FallbackProviderMetrics`.
+pub struct FallbackProviderHealthReporter {
+    chain: String,
+    hosts: Vec<String>,
+    metrics: FallbackProviderMetrics,
+}
+
+impl Debug for FallbackProviderHealthReporter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FallbackProviderHealthReporter")
+            .field("chain", &self.chain)
+            .field("hosts", &self.hosts)
+            .finish()
+    }
+}
+
+impl ProviderHealthObserver for FallbackProviderHealthReporter {
+    fn observe(&self, provider_index: usize, health: &ProviderHealthSnapshot) {
+        let host = self
+            .hosts
+            .get(provider_index)
+            .map(|h| h.as_str())
+            .unwrap_or("unknown");
+        let labels = [self.chain.as_str(), host];
+        self.metrics
+            .score
+            .with_label_values(&labels)
+            .set(health.score);
+        self.metrics
+            .error_rate
+            .with_label_values(&labels)
+            .set(health.error_rate);
+        self.metrics
+            .circuit_state
+            .with_label_values(&labels)
+            .set(health.circuit.as_metric_value());
+        for (quantile, latency) in [("0.5", health.latency_p50), ("0.9", health.latency_p90)] {
+            self.metrics
+                .latency_seconds
+                .with_label_values(&[self.chain.as_str(), host, quantile])
+                .set(latency.as_secs_f64());
+        }
+    }
+}
+
+fn node_host(url: &Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_owned(),
+        _ => "unknown".to_owned(),
+    }
+}
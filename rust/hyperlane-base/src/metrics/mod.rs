@@ -8,7 +8,9 @@ pub const NAMESPACE: &str = "hyperlane";
 mod core;
 
 mod agent_metrics;
+mod fallback_provider;
 mod json_rpc_client;
 mod provider;
 
 pub use self::agent_metrics::*;
+pub use self::fallback_provider::*;
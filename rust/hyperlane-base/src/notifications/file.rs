@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use eyre::{Context, Result};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+};
+
+use super::NotificationSink;
+
+/// Appends notifications to a local file, one JSON object per line.
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl FileSink {
+    /// Create a sink appending to the file at `path`. The file is created if
+    /// it does not exist.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            file: None,
+        }
+    }
+
+    async fn append(&mut self, payload: &[u8]) -> Result<()> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            file @ None => file.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .await
+                    .with_context(|| format!("Opening {}", self.path.display()))?,
+            ),
+        };
+        let mut line = Vec::with_capacity(payload.len() + 1);
+        line.extend_from_slice(payload);
+        line.push(b'\n');
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationSink for FileSink {
+    async fn send(&mut self, _id: &str, payload: &[u8]) -> Result<()> {
+        let result = self.append(payload).await;
+        if result.is_err() {
+            // reopen the file on the next attempt
+            self.file = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn appends_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let mut sink = FileSink::new(&path);
+        sink.send("a", br#"{"n":1}"#).await.unwrap();
+        sink.send("b", br#"{"n":2}"#).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "{\"n\":1}\n{\"n\":2}\n");
+    }
+}
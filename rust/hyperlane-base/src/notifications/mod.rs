@@ -0,0 +1,745 @@
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use eyre::{Context, Result};
+use hyperlane_core::{H256, H512, U256};
+use prometheus::{IntCounterVec, IntGaugeVec};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs,
+    sync::mpsc::{self, error::TrySendError, Receiver, Sender},
+    time::sleep,
+};
+use tracing::{debug, info, warn};
+
+use crate::CoreMetrics;
+
+pub use file::FileSink;
+pub use nats::NatsSink;
+pub use store::NotifyingStore;
+pub use webhook::WebhookSink;
+
+mod file;
+mod nats;
+mod store;
+mod webhook;
+
+/// Delay before retrying a notification which could not be sent the first time.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound on the delay between retries of a notification.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Label of notifications dropped because the queue of their sink was full.
+const QUEUE_FULL_REASON: &str = "queue_full";
+/// Label of notifications dropped because their sink kept failing to accept
+/// them.
+const MAX_ATTEMPTS_REASON: &str = "max_attempts";
+
+/// Bounds on the notifications a sink holds on to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimits {
+    /// Number of notifications queued for a sink, including spooled ones,
+    /// beyond which new notifications are dropped
+    pub capacity: usize,
+    /// Number of times a notification is sent before it's dropped
+    pub max_attempts: u32,
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// Metrics of the notification queues.
+#[derive(Debug, Clone)]
+struct NotifierMetrics {
+    /// Notifications waiting to be sent, by sink
+    queued: IntGaugeVec,
+    /// Notifications which were dropped without being sent, by sink and reason
+    failed: IntCounterVec,
+}
+
+impl NotifierMetrics {
+    fn new(metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self {
+            queued: metrics.new_int_gauge(
+                "notifications_queued",
+                "Number of lifecycle notifications waiting to be sent, by sink",
+                &["sink"],
+            )?,
+            failed: metrics.new_int_counter(
+                "notifications_failed",
+                "Number of lifecycle notifications dropped without being sent, by sink and reason",
+                &["sink", "reason"],
+            )?,
+        })
+    }
+}
+
+/// An event in the lifecycle of a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum LifecycleEvent {
+    /// A dispatched message was indexed on the origin chain.
+    MessageIndexed {
+        /// Domain the message was dispatched from
+        origin: u32,
+        /// Domain the message is sent to
+        destination: u32,
+        /// Nonce of the message on the origin mailbox
+        nonce: u32,
+        /// Id of the message
+        message_id: H256,
+        /// Sender of the message
+        sender: H256,
+        /// Recipient of the message
+        recipient: H256,
+        /// Transaction which dispatched the message
+        transaction_id: H512,
+        /// Block the message was dispatched in
+        block_number: u64,
+    },
+    /// A gas payment for a message was indexed on the origin chain.
+    GasPaymentSeen {
+        /// Domain the payment was made on
+        origin: u32,
+        /// Domain the payment is for
+        destination: u32,
+        /// Id of the message paid for
+        message_id: H256,
+        /// Amount of native tokens paid
+        payment: U256,
+        /// Amount of destination gas paid for
+        gas_amount: U256,
+        /// Transaction which made the payment
+        transaction_id: H512,
+        /// Block the payment was made in
+        block_number: u64,
+    },
+    /// The relayer is about to submit a transaction processing the message.
+    SubmissionAttempted {
+        /// Domain the message was dispatched from
+        origin: u32,
+        /// Domain the message is sent to
+        destination: u32,
+        /// Nonce of the message on the origin mailbox
+        nonce: u32,
+        /// Id of the message
+        message_id: H256,
+        /// Number of previous attempts to deliver the message
+        attempt: u32,
+    },
+    /// A submission of the message failed or reverted.
+    SubmissionFailed {
+        /// Domain the message was dispatched from
+        origin: u32,
+        /// Domain the message is sent to
+        destination: u32,
+        /// Nonce of the message on the origin mailbox
+        nonce: u32,
+        /// Id of the message
+        message_id: H256,
+        /// Number of previous attempts to deliver the message
+        attempt: u32,
+        /// The reverted transaction, if one was included
+        transaction_id: Option<H512>,
+        /// Why the submission failed
+        reason: String,
+    },
+    /// A transaction processing the message was included on the destination
+    /// chain.
+    Delivered {
+        /// Domain the message was dispatched from, if known
+        origin: Option<u32>,
+        /// Domain the message was delivered to
+        destination: u32,
+        /// Id of the message
+        message_id: H256,
+        /// Transaction which processed the message
+        transaction_id: H512,
+        /// Block the message was processed in, if known
+        block_number: Option<u64>,
+    },
+    /// The delivery of the message was confirmed after waiting for the
+    /// transaction to be final.
+    Confirmed {
+        /// Domain the message was dispatched from
+        origin: u32,
+        /// Domain the message was delivered to
+        destination: u32,
+        /// Nonce of the message on the origin mailbox
+        nonce: u32,
+        /// Id of the message
+        message_id: H256,
+    },
+}
+
+/// A lifecycle event as it is sent to sinks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    /// Hex encoded hash of the event. Notifications are delivered at least
+    /// once, so receivers should use this to drop duplicates.
+    pub id: String,
+    /// Name of the agent which emitted the event
+    pub agent: String,
+    /// Milliseconds since the unix epoch when the event was emitted
+    pub timestamp: u64,
+    /// The event itself
+    pub event: LifecycleEvent,
+}
+
+impl Notification {
+    /// Wrap an event for sending.
+    pub fn new(agent: &str, event: LifecycleEvent) -> Result<Self> {
+        let id = hex::encode(Sha256::digest(serde_json::to_vec(&event)?));
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Ok(Self {
+            id,
+            agent: agent.to_owned(),
+            timestamp,
+            event,
+        })
+    }
+}
+
+/// A destination for notifications.
+#[async_trait]
+pub trait NotificationSink: Send + Debug {
+    /// Send the serialized notification with the given id. A notification is
+    /// only considered delivered once this returns `Ok`, otherwise it is
+    /// retried.
+    async fn send(&mut self, id: &str, payload: &[u8]) -> Result<()>;
+}
+
+/// Publishes lifecycle events to the configured sinks.
+///
+/// Each sink has a bounded queue which is drained by a background task,
+/// retrying every notification up to `max_attempts` times, so `notify` never
+/// blocks on a sink. Notifications which don't fit the queue, or which the
+/// sink keeps failing to accept, are dropped and counted. With a spool
+/// directory, queued notifications are also written to disk and re-sent after
+/// a restart if they were not delivered before.
+///
+/// The default notifier has no sinks and drops all events.
+#[derive(Debug, Clone, Default)]
+pub struct Notifier {
+    inner: Option<Arc<NotifierInner>>,
+}
+
+#[derive(Debug)]
+struct NotifierInner {
+    agent: String,
+    queues: Vec<SinkQueue>,
+}
+
+#[derive(Debug)]
+struct SinkQueue {
+    name: String,
+    spool_dir: Option<PathBuf>,
+    sender: Sender<Queued>,
+    metrics: NotifierMetrics,
+}
+
+#[derive(Debug)]
+struct Queued {
+    id: String,
+    payload: Vec<u8>,
+    spool_file: Option<PathBuf>,
+}
+
+impl Notifier {
+    /// Start a notifier sending to the given named sinks. Notifications left
+    /// over in `spool_dir` from a previous run are queued before any new ones.
+    pub async fn new(
+        agent: &str,
+        sinks: Vec<(String, Box<dyn NotificationSink>)>,
+        spool_dir: Option<&Path>,
+        limits: QueueLimits,
+        metrics: &CoreMetrics,
+    ) -> Result<Self> {
+        if sinks.is_empty() {
+            return Ok(Self::default());
+        }
+        let metrics = NotifierMetrics::new(metrics)?;
+
+        let mut queues = Vec::with_capacity(sinks.len());
+        for (name, sink) in sinks {
+            let (sender, receiver) = mpsc::channel(limits.capacity.max(1));
+            let sink_spool_dir = match spool_dir {
+                Some(dir) => {
+                    let dir = dir.join(&name);
+                    fs::create_dir_all(&dir).await.with_context(|| {
+                        format!("Creating notification spool directory {}", dir.display())
+                    })?;
+                    Some(dir)
+                }
+                None => None,
+            };
+            let queue = SinkQueue {
+                name,
+                spool_dir: sink_spool_dir,
+                sender,
+                metrics: metrics.clone(),
+            };
+            if let Some(dir) = &queue.spool_dir {
+                queue.requeue_spooled(dir).await?;
+            }
+            tokio::spawn(run_sink(
+                queue.name.clone(),
+                sink,
+                receiver,
+                limits.max_attempts,
+                metrics.clone(),
+            ));
+            queues.push(queue);
+        }
+
+        Ok(Self {
+            inner: Some(Arc::new(NotifierInner {
+                agent: agent.to_owned(),
+                queues,
+            })),
+        })
+    }
+
+    /// Queue an event to be sent to all sinks.
+    pub async fn notify(&self, event: LifecycleEvent) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let notification = match Notification::new(&inner.agent, event) {
+            Ok(notification) => notification,
+            Err(err) => {
+                warn!(?err, "Failed to create notification");
+                return;
+            }
+        };
+        let payload = match serde_json::to_vec(&notification) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!(?err, "Failed to serialize notification");
+                return;
+            }
+        };
+        for queue in &inner.queues {
+            queue.push(&notification, payload.clone()).await;
+        }
+    }
+}
+
+impl SinkQueue {
+    async fn push(&self, notification: &Notification, payload: Vec<u8>) {
+        // Reserve a slot before spooling, so the spool never holds more
+        // notifications than the queue
+        let permit = match self.sender.try_reserve() {
+            Ok(permit) => permit,
+            Err(TrySendError::Full(())) => {
+                warn!(
+                    sink = self.name,
+                    id = notification.id,
+                    "Notification queue is full, dropping notification"
+                );
+                self.metrics
+                    .failed
+                    .with_label_values(&[&self.name, QUEUE_FULL_REASON])
+                    .inc();
+                return;
+            }
+            Err(TrySendError::Closed(())) => {
+                warn!(sink = self.name, "Notification sink is no longer running");
+                return;
+            }
+        };
+        let spool_file = match &self.spool_dir {
+            Some(dir) => {
+                let path = dir.join(format!(
+                    "{:016}-{}.json",
+                    notification.timestamp, notification.id
+                ));
+                match fs::write(&path, &payload).await {
+                    Ok(()) => Some(path),
+                    Err(err) => {
+                        warn!(sink = self.name, ?err, "Failed to spool notification");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        permit.send(Queued {
+            id: notification.id.clone(),
+            payload,
+            spool_file,
+        });
+        self.metrics.queued.with_label_values(&[&self.name]).inc();
+    }
+
+    /// Queue the notifications spooled in `dir` by a previous run. Those which
+    /// don't fit the queue anymore are dropped, oldest first.
+    async fn requeue_spooled(&self, dir: &Path) -> Result<()> {
+        let mut spooled = read_spool(dir).await?;
+        let excess = spooled.len().saturating_sub(self.sender.max_capacity());
+        if excess > 0 {
+            warn!(
+                sink = self.name,
+                count = excess,
+                "Spooled notifications exceed the queue capacity, dropping the oldest"
+            );
+            self.metrics
+                .failed
+                .with_label_values(&[&self.name, QUEUE_FULL_REASON])
+                .inc_by(excess as u64);
+        }
+        for dropped in spooled.drain(..excess) {
+            if let Some(path) = dropped.spool_file {
+                fs::remove_file(&path).await?;
+            }
+        }
+        for queued in spooled {
+            self.sender
+                .try_send(queued)
+                .expect("queue has room for the spooled notifications");
+            self.metrics.queued.with_label_values(&[&self.name]).inc();
+        }
+        Ok(())
+    }
+}
+
+/// Read the notifications which were spooled but not delivered, in the
+/// order they were emitted.
+async fn read_spool(dir: &Path) -> Result<Vec<Queued>> {
+    let mut paths = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().map_or(false, |ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut spooled = Vec::with_capacity(paths.len());
+    for path in paths {
+        let Some(id) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.split_once('-'))
+            .map(|(_, id)| id.to_owned())
+        else {
+            continue;
+        };
+        let payload = fs::read(&path).await?;
+        spooled.push(Queued {
+            id,
+            payload,
+            spool_file: Some(path),
+        });
+    }
+    if !spooled.is_empty() {
+        info!(
+            count = spooled.len(),
+            dir = %dir.display(),
+            "Re-sending spooled notifications"
+        );
+    }
+    Ok(spooled)
+}
+
+async fn run_sink(
+    name: String,
+    mut sink: Box<dyn NotificationSink>,
+    mut receiver: Receiver<Queued>,
+    max_attempts: u32,
+    metrics: NotifierMetrics,
+) {
+    while let Some(queued) = receiver.recv().await {
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            match sink.send(&queued.id, &queued.payload).await {
+                Ok(()) => {
+                    debug!(sink = name, id = queued.id, "Sent notification");
+                    break;
+                }
+                Err(err) if attempt >= max_attempts => {
+                    warn!(
+                        sink = name,
+                        id = queued.id,
+                        ?err,
+                        attempt,
+                        "Failed to send notification, dropping it"
+                    );
+                    metrics
+                        .failed
+                        .with_label_values(&[&name, MAX_ATTEMPTS_REASON])
+                        .inc();
+                    break;
+                }
+                Err(err) => {
+                    warn!(
+                        sink = name,
+                        id = queued.id,
+                        ?err,
+                        attempt,
+                        ?delay,
+                        "Failed to send notification, retrying"
+                    );
+                    sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                    attempt += 1;
+                }
+            }
+        }
+        metrics.queued.with_label_values(&[&name]).dec();
+        if let Some(path) = queued.spool_file {
+            if let Err(err) = fs::remove_file(&path).await {
+                warn!(sink = name, ?err, path = %path.display(), "Failed to remove spooled notification");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use prometheus::Registry;
+
+    use super::*;
+
+    /// Records notifications, failing the first `failures` attempts. Sending
+    /// waits while `paused` is locked.
+    #[derive(Debug, Clone, Default)]
+    struct TestSink {
+        sent: Arc<Mutex<Vec<(String, Vec<u8>)>>>,
+        failures: Arc<Mutex<u32>>,
+        attempts: Arc<Mutex<u32>>,
+        paused: Arc<tokio::sync::Mutex<()>>,
+    }
+
+    #[async_trait]
+    impl NotificationSink for TestSink {
+        async fn send(&mut self, id: &str, payload: &[u8]) -> Result<()> {
+            *self.attempts.lock().unwrap() += 1;
+            let _unpaused = self.paused.lock().await;
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                eyre::bail!("sink unavailable");
+            }
+            self.sent
+                .lock()
+                .unwrap()
+                .push((id.to_owned(), payload.to_vec()));
+            Ok(())
+        }
+    }
+
+    fn confirmed(nonce: u32) -> LifecycleEvent {
+        LifecycleEvent::Confirmed {
+            origin: 1,
+            destination: 2,
+            nonce,
+            message_id: H256::from_low_u64_be(nonce as u64),
+        }
+    }
+
+    async fn notifier(sink: &TestSink, spool_dir: Option<&Path>, limits: QueueLimits) -> Notifier {
+        Notifier::new(
+            "relayer",
+            vec![("test".to_owned(), Box::new(sink.clone()))],
+            spool_dir,
+            limits,
+            &CoreMetrics::new("test", 9090, Registry::new()).unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    /// The number of queued notifications, and of those dropped for `reason`
+    fn queue_metrics(notifier: &Notifier, reason: &str) -> (i64, u64) {
+        let metrics = &notifier.inner.as_ref().unwrap().queues[0].metrics;
+        (
+            metrics.queued.with_label_values(&["test"]).get(),
+            metrics.failed.with_label_values(&["test", reason]).get(),
+        )
+    }
+
+    async fn wait_for_sent(sink: &TestSink, count: usize) -> Vec<Notification> {
+        for _ in 0..100 {
+            let sent = sink.sent.lock().unwrap().clone();
+            if sent.len() >= count {
+                return sent
+                    .iter()
+                    .map(|(_, payload)| serde_json::from_slice(payload).unwrap())
+                    .collect();
+            }
+            drop(sent);
+            sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Timed out waiting for notifications");
+    }
+
+    #[test]
+    fn notification_ids_are_deterministic() {
+        let a = Notification::new("relayer", confirmed(1)).unwrap();
+        let b = Notification::new("relayer", confirmed(1)).unwrap();
+        let c = Notification::new("relayer", confirmed(2)).unwrap();
+        assert_eq!(a.id, b.id);
+        assert_ne!(a.id, c.id);
+    }
+
+    #[test]
+    fn events_serialize_with_type_tag() {
+        let value = serde_json::to_value(confirmed(1)).unwrap();
+        assert_eq!(value["type"], "confirmed");
+        assert_eq!(
+            value["messageId"],
+            serde_json::to_value(H256::from_low_u64_be(1)).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_until_sink_accepts() {
+        let sink = TestSink::default();
+        *sink.failures.lock().unwrap() = 1;
+        let notifier = notifier(&sink, None, QueueLimits::default()).await;
+
+        notifier.notify(confirmed(1)).await;
+        notifier.notify(confirmed(2)).await;
+
+        let sent = wait_for_sent(&sink, 2).await;
+        assert_eq!(sent[0].event, confirmed(1));
+        assert_eq!(sent[1].event, confirmed(2));
+    }
+
+    #[tokio::test]
+    async fn resends_spooled_notifications() {
+        let spool = tempfile::tempdir().unwrap();
+        let sink_dir = spool.path().join("test");
+        std::fs::create_dir_all(&sink_dir).unwrap();
+        let leftover = Notification::new("relayer", confirmed(1)).unwrap();
+        std::fs::write(
+            sink_dir.join(format!("{:016}-{}.json", leftover.timestamp, leftover.id)),
+            serde_json::to_vec(&leftover).unwrap(),
+        )
+        .unwrap();
+
+        let sink = TestSink::default();
+        let notifier = notifier(&sink, Some(spool.path()), QueueLimits::default()).await;
+        notifier.notify(confirmed(2)).await;
+
+        let sent = wait_for_sent(&sink, 2).await;
+        assert_eq!(sent[0], leftover);
+        assert_eq!(sent[1].event, confirmed(2));
+
+        // delivered notifications are removed from the spool
+        for _ in 0..100 {
+            if std::fs::read_dir(&sink_dir).unwrap().next().is_none() {
+                return;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Spooled notifications were not removed");
+    }
+
+    #[tokio::test]
+    async fn drops_notifications_when_queue_is_full() {
+        let sink = TestSink::default();
+        let paused = sink.paused.clone().lock_owned().await;
+        let limits = QueueLimits {
+            capacity: 1,
+            ..Default::default()
+        };
+        let notifier = notifier(&sink, None, limits).await;
+
+        notifier.notify(confirmed(1)).await;
+        // wait for the sink to take the first notification off the queue
+        for _ in 0..100 {
+            if *sink.attempts.lock().unwrap() > 0 {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        notifier.notify(confirmed(2)).await;
+        notifier.notify(confirmed(3)).await;
+        assert_eq!(queue_metrics(&notifier, QUEUE_FULL_REASON), (2, 1));
+
+        drop(paused);
+        let sent = wait_for_sent(&sink, 2).await;
+        assert_eq!(sent[0].event, confirmed(1));
+        assert_eq!(sent[1].event, confirmed(2));
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(sink.sent.lock().unwrap().len(), 2);
+        assert_eq!(queue_metrics(&notifier, QUEUE_FULL_REASON), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let sink = TestSink::default();
+        *sink.failures.lock().unwrap() = 1;
+        let limits = QueueLimits {
+            max_attempts: 1,
+            ..Default::default()
+        };
+        let notifier = notifier(&sink, None, limits).await;
+
+        notifier.notify(confirmed(1)).await;
+        notifier.notify(confirmed(2)).await;
+
+        let sent = wait_for_sent(&sink, 1).await;
+        assert_eq!(sent[0].event, confirmed(2));
+        assert_eq!(queue_metrics(&notifier, MAX_ATTEMPTS_REASON), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn drops_the_oldest_spooled_notifications_beyond_the_capacity() {
+        let spool = tempfile::tempdir().unwrap();
+        let sink_dir = spool.path().join("test");
+        std::fs::create_dir_all(&sink_dir).unwrap();
+        for nonce in 1..=3 {
+            let leftover = Notification {
+                timestamp: nonce as u64,
+                ..Notification::new("relayer", confirmed(nonce)).unwrap()
+            };
+            std::fs::write(
+                sink_dir.join(format!("{:016}-{}.json", leftover.timestamp, leftover.id)),
+                serde_json::to_vec(&leftover).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let sink = TestSink::default();
+        let paused = sink.paused.clone().lock_owned().await;
+        let limits = QueueLimits {
+            capacity: 2,
+            ..Default::default()
+        };
+        let notifier = notifier(&sink, Some(spool.path()), limits).await;
+        assert_eq!(std::fs::read_dir(&sink_dir).unwrap().count(), 2);
+        assert_eq!(queue_metrics(&notifier, QUEUE_FULL_REASON), (2, 1));
+
+        drop(paused);
+        let sent = wait_for_sent(&sink, 2).await;
+        assert_eq!(sent[0].event, confirmed(2));
+        assert_eq!(sent[1].event, confirmed(3));
+    }
+}
@@ -0,0 +1,291 @@
+use async_trait::async_trait;
+use derive_new::new;
+use eyre::Result;
+use hyperlane_core::{
+    Delivery, HyperlaneDomain, HyperlaneLogStore, HyperlaneMessage,
+    HyperlaneSequenceAwareIndexerStoreReader, HyperlaneWatermarkedLogStore, InterchainGasPayment,
+    LogMeta, MerkleTreeInsertion, ValidatorAnnouncement,
+};
+
+use super::{LifecycleEvent, Notifier};
+
+/// Wraps a log store to emit lifecycle events for the logs stored in it.
+///
+/// Events are emitted for every log passed to `store_logs`, including logs
+/// which were already stored before, since notifications are delivered at
+/// least once anyway.
+#[derive(Debug, new)]
+pub struct NotifyingStore<S> {
+    inner: S,
+    notifier: Notifier,
+    /// The domain the logs are indexed on
+    domain: HyperlaneDomain,
+}
+
+#[async_trait]
+impl<S> HyperlaneLogStore<HyperlaneMessage> for NotifyingStore<S>
+where
+    S: HyperlaneLogStore<HyperlaneMessage>,
+{
+    async fn store_logs(&self, logs: &[(HyperlaneMessage, LogMeta)]) -> Result<u32> {
+        let stored = self.inner.store_logs(logs).await?;
+        for (message, meta) in logs {
+            self.notifier
+                .notify(LifecycleEvent::MessageIndexed {
+                    origin: message.origin,
+                    destination: message.destination,
+                    nonce: message.nonce,
+                    message_id: message.id(),
+                    sender: message.sender,
+                    recipient: message.recipient,
+                    transaction_id: meta.transaction_id,
+                    block_number: meta.block_number,
+                })
+                .await;
+        }
+        Ok(stored)
+    }
+}
+
+#[async_trait]
+impl<S> HyperlaneLogStore<InterchainGasPayment> for NotifyingStore<S>
+where
+    S: HyperlaneLogStore<InterchainGasPayment>,
+{
+    async fn store_logs(&self, logs: &[(InterchainGasPayment, LogMeta)]) -> Result<u32> {
+        let stored = self.inner.store_logs(logs).await?;
+        for (payment, meta) in logs {
+            self.notifier
+                .notify(LifecycleEvent::GasPaymentSeen {
+                    origin: self.domain.id(),
+                    destination: payment.destination,
+                    message_id: payment.message_id,
+                    payment: payment.payment,
+                    gas_amount: payment.gas_amount,
+                    transaction_id: meta.transaction_id,
+                    block_number: meta.block_number,
+                })
+                .await;
+        }
+        Ok(stored)
+    }
+}
+
+#[async_trait]
+impl<S> HyperlaneLogStore<Delivery> for NotifyingStore<S>
+where
+    S: HyperlaneLogStore<Delivery>,
+{
+    async fn store_logs(&self, logs: &[(Delivery, LogMeta)]) -> Result<u32> {
+        let stored = self.inner.store_logs(logs).await?;
+        for (message_id, meta) in logs {
+            self.notifier
+                .notify(LifecycleEvent::Delivered {
+                    origin: None,
+                    destination: self.domain.id(),
+                    message_id: *message_id,
+                    transaction_id: meta.transaction_id,
+                    block_number: Some(meta.block_number),
+                })
+                .await;
+        }
+        Ok(stored)
+    }
+}
+
+/// Logs without lifecycle events are stored as they are.
+macro_rules! impl_store_without_events {
+    ($($ty:ty),+) => {
+        $(
+            #[async_trait]
+            impl<S> HyperlaneLogStore<$ty> for NotifyingStore<S>
+            where
+                S: HyperlaneLogStore<$ty>,
+            {
+                async fn store_logs(&self, logs: &[($ty, LogMeta)]) -> Result<u32> {
+                    self.inner.store_logs(logs).await
+                }
+            }
+        )+
+    };
+}
+
+impl_store_without_events!(MerkleTreeInsertion, ValidatorAnnouncement);
+
+#[async_trait]
+impl<T, S> HyperlaneSequenceAwareIndexerStoreReader<T> for NotifyingStore<S>
+where
+    T: Send + 'static,
+    S: HyperlaneSequenceAwareIndexerStoreReader<T>,
+{
+    async fn retrieve_by_sequence(&self, sequence: u32) -> Result<Option<T>> {
+        self.inner.retrieve_by_sequence(sequence).await
+    }
+
+    async fn retrieve_log_block_number_by_sequence(&self, sequence: u32) -> Result<Option<u64>> {
+        self.inner
+            .retrieve_log_block_number_by_sequence(sequence)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T, S> HyperlaneWatermarkedLogStore<T> for NotifyingStore<S>
+where
+    T: Send + Sync + 'static,
+    S: HyperlaneWatermarkedLogStore<T>,
+    Self: HyperlaneLogStore<T>,
+{
+    async fn retrieve_high_watermark(&self) -> Result<Option<u32>> {
+        self.inner.retrieve_high_watermark().await
+    }
+
+    async fn store_high_watermark(&self, block_number: u32) -> Result<()> {
+        self.inner.store_high_watermark(block_number).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use hyperlane_core::{KnownHyperlaneDomain, H256, H512};
+    use prometheus::Registry;
+    use tokio::time::sleep;
+
+    use super::*;
+    use crate::{
+        notifications::{Notification, NotificationSink, QueueLimits},
+        CoreMetrics,
+    };
+
+    /// Stores nothing, but reports every log as stored.
+    #[derive(Debug)]
+    struct CountingStore;
+
+    #[async_trait]
+    impl<T: Send + Sync + 'static> HyperlaneLogStore<T> for CountingStore {
+        async fn store_logs(&self, logs: &[(T, LogMeta)]) -> Result<u32> {
+            Ok(logs.len() as u32)
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct RecordingSink(Arc<Mutex<Vec<Notification>>>);
+
+    #[async_trait]
+    impl NotificationSink for RecordingSink {
+        async fn send(&mut self, _id: &str, payload: &[u8]) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(serde_json::from_slice(payload)?);
+            Ok(())
+        }
+    }
+
+    async fn notifying_store() -> (NotifyingStore<CountingStore>, RecordingSink) {
+        let sink = RecordingSink::default();
+        let notifier = Notifier::new(
+            "scraper",
+            vec![("test".to_owned(), Box::new(sink.clone()))],
+            None,
+            QueueLimits::default(),
+            &CoreMetrics::new("test", 9090, Registry::new()).unwrap(),
+        )
+        .await
+        .unwrap();
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+        (NotifyingStore::new(CountingStore, notifier, domain), sink)
+    }
+
+    async fn wait_for_events(sink: &RecordingSink, count: usize) -> Vec<LifecycleEvent> {
+        for _ in 0..100 {
+            let sent = sink.0.lock().unwrap().clone();
+            if sent.len() >= count {
+                return sent.into_iter().map(|n| n.event).collect();
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Timed out waiting for notifications");
+    }
+
+    fn meta(block_number: u64) -> LogMeta {
+        LogMeta {
+            block_number,
+            transaction_id: H512::from_low_u64_be(block_number),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn emits_an_event_per_stored_message() {
+        let (store, sink) = notifying_store().await;
+        let messages: Vec<_> = (0..2)
+            .map(|nonce| {
+                let message = HyperlaneMessage {
+                    nonce,
+                    origin: 1,
+                    destination: 2,
+                    sender: H256::repeat_byte(1),
+                    recipient: H256::repeat_byte(2),
+                    ..Default::default()
+                };
+                (message, meta(10 + nonce as u64))
+            })
+            .collect();
+
+        assert_eq!(store.store_logs(&messages).await.unwrap(), 2);
+
+        let events = wait_for_events(&sink, 2).await;
+        for ((message, meta), event) in messages.iter().zip(events) {
+            assert_eq!(
+                event,
+                LifecycleEvent::MessageIndexed {
+                    origin: 1,
+                    destination: 2,
+                    nonce: message.nonce,
+                    message_id: message.id(),
+                    sender: message.sender,
+                    recipient: message.recipient,
+                    transaction_id: meta.transaction_id,
+                    block_number: meta.block_number,
+                }
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn emits_deliveries_on_the_indexed_domain() {
+        let (store, sink) = notifying_store().await;
+        let message_id = H256::repeat_byte(3);
+
+        assert_eq!(store.store_logs(&[(message_id, meta(5))]).await.unwrap(), 1);
+
+        let events = wait_for_events(&sink, 1).await;
+        assert_eq!(
+            events,
+            vec![LifecycleEvent::Delivered {
+                origin: None,
+                destination: store.domain.id(),
+                message_id,
+                transaction_id: H512::from_low_u64_be(5),
+                block_number: Some(5),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn stores_other_logs_without_events() {
+        let (store, sink) = notifying_store().await;
+        let insertion = MerkleTreeInsertion::new(0, H256::repeat_byte(4));
+
+        assert_eq!(store.store_logs(&[(insertion, meta(7))]).await.unwrap(), 1);
+
+        sleep(Duration::from_millis(100)).await;
+        assert!(sink.0.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, Client};
+use sha2::Sha256;
+use url::Url;
+
+use super::NotificationSink;
+
+/// Header with the hex encoded HMAC-SHA256 of the request body, prefixed with
+/// `sha256=`.
+pub const SIGNATURE_HEADER: &str = "X-Hyperlane-Signature";
+/// Header with the id of the notification, for receivers to drop duplicates.
+pub const ID_HEADER: &str = "X-Hyperlane-Notification-Id";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POSTs notifications as JSON to a URL, optionally signing them with a
+/// shared secret.
+#[derive(Debug)]
+pub struct WebhookSink {
+    client: Client,
+    url: Url,
+    secret: Option<String>,
+}
+
+impl WebhookSink {
+    /// Create a sink posting to `url`. When a secret is given every request
+    /// has a signature header receivers can verify the body with.
+    pub fn new(url: Url, secret: Option<String>) -> Result<Self> {
+        let client = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(Self {
+            client,
+            url,
+            secret,
+        })
+    }
+}
+
+/// Sign a payload with the shared secret of a webhook.
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    async fn send(&mut self, id: &str, payload: &[u8]) -> Result<()> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(ID_HEADER, id)
+            .body(payload.to_vec());
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, payload));
+        }
+        let response = request.send().await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(eyre!("Webhook responded with {}", response.status()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn signs_payload() {
+        // test vector 2 of RFC 4231
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -6,11 +6,11 @@ use hyperlane_core::{
     Delivery, HyperlaneChain, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider,
     HyperlaneSequenceAwareIndexerStore, HyperlaneWatermarkedLogStore, InterchainGasPaymaster,
     InterchainGasPayment, Mailbox, MerkleTreeHook, MerkleTreeInsertion, MultisigIsm,
-    ValidatorAnnounce, H256,
+    ValidatorAnnounce, ValidatorAnnouncement, H256,
 };
 
 use crate::{
-    settings::{chains::ChainConf, trace::TracingConfig},
+    settings::{chains::ChainConf, trace::TracingConfig, NotificationsConf},
     ContractSync, ContractSyncMetrics, CoreMetrics, HyperlaneAgentCore, SequencedDataContractSync,
     Server, WatermarkContractSync,
 };
@@ -47,6 +47,8 @@ pub struct Settings {
     pub metrics_port: u16,
     /// The tracing configuration
     pub tracing: TracingConfig,
+    /// Where to send message lifecycle notifications
+    pub notifications: NotificationsConf,
 }
 
 impl Settings {
@@ -107,6 +109,7 @@ impl Settings {
             chains: self.chains.clone(),
             metrics_port: self.metrics_port,
             tracing: self.tracing.clone(),
+            notifications: self.notifications.clone(),
         }
     }
 }
@@ -192,4 +195,5 @@ impl Settings {
     build_indexer_fns!(build_message_indexer, build_message_indexers -> dyn HyperlaneSequenceAwareIndexerStore<HyperlaneMessage>, SequencedDataContractSync<HyperlaneMessage>);
     build_indexer_fns!(build_interchain_gas_payment_indexer, build_interchain_gas_payment_indexers -> dyn HyperlaneWatermarkedLogStore<InterchainGasPayment>, WatermarkContractSync<InterchainGasPayment>);
     build_indexer_fns!(build_merkle_tree_hook_indexer, build_merkle_tree_hook_indexers -> dyn HyperlaneSequenceAwareIndexerStore<MerkleTreeInsertion>, SequencedDataContractSync<MerkleTreeInsertion>);
+    build_indexer_fns!(build_validator_announce_indexer, build_validator_announce_indexers -> dyn HyperlaneWatermarkedLogStore<ValidatorAnnouncement>, WatermarkContractSync<ValidatorAnnouncement>);
 }
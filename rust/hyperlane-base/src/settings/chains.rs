@@ -1,16 +1,17 @@
 use ethers::prelude::Selector;
 use h_cosmos::CosmosProvider;
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use eyre::{eyre, Context, Result};
 
 use ethers_prometheus::middleware::{ChainInfo, ContractInfo, PrometheusMiddlewareConf};
 use hyperlane_core::{
-    AggregationIsm, CcipReadIsm, ContractLocator, HyperlaneAbi, HyperlaneDomain,
-    HyperlaneDomainProtocol, HyperlaneMessage, HyperlaneProvider, IndexMode,
-    InterchainGasPaymaster, InterchainGasPayment, InterchainSecurityModule, Mailbox,
-    MerkleTreeHook, MerkleTreeInsertion, MultisigIsm, RoutingIsm, SequenceAwareIndexer,
-    ValidatorAnnounce, H256,
+    rpc_clients::ProviderHealthObserver, AggregationIsm, BlsMultisigIsm, CcipReadIsm,
+    ContractLocator, HyperlaneAbi, HyperlaneDomain, HyperlaneDomainProtocol, HyperlaneMessage,
+    HyperlaneProvider, IndexMode, InterchainGasPaymaster, InterchainGasPayment,
+    InterchainSecurityModule, LogSubscriber, Mailbox, MerkleTreeHook, MerkleTreeInsertion,
+    MultisigIsm, OptimisticIsm, RoutingIsm, SequenceAwareIndexer, ValidatorAnnounce,
+    ValidatorAnnouncement, WeightedMultisigIsm, H256,
 };
 use hyperlane_cosmos as h_cosmos;
 use hyperlane_ethereum::{
@@ -48,6 +49,9 @@ pub struct ChainConf {
     pub metrics_conf: PrometheusMiddlewareConf,
     /// Settings for event indexing
     pub index: IndexSettings,
+    /// The chain id of EVM chains, if configured. The RPC is only checked
+    /// against it when validating the config.
+    pub chain_id: Option<u64>,
 }
 
 /// A connection to _some_ blockchain.
@@ -95,8 +99,22 @@ pub struct IndexSettings {
     pub from: u32,
     /// The number of blocks to query at once when indexing contracts.
     pub chunk_size: u32,
+    /// The lower bound the number of blocks queried at once may shrink to
+    /// when queries fail because the range is too large. Setting either bound
+    /// enables adapting the chunk size in block mode.
+    pub min_chunk_size: Option<u32>,
+    /// The upper bound the number of blocks queried at once may grow to when
+    /// queried ranges are empty.
+    pub max_chunk_size: Option<u32>,
     /// The indexing mode.
     pub mode: IndexMode,
+    /// A snapshot of historical logs to import before indexing, see
+    /// `LogSnapshot::from_path`.
+    pub snapshot: Option<PathBuf>,
+    /// Whether to look up the block hash and transaction id of logs where it
+    /// takes extra RPC calls per log, as on Sealevel. Only the scraper needs
+    /// them, so it is not configurable and only set by the scraper.
+    pub log_meta: bool,
 }
 
 impl ChainConf {
@@ -123,6 +141,7 @@ impl ChainConf {
                 conf,
             )) as Box<dyn HyperlaneProvider>),
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let provider = CosmosProvider::new(
                     locator.domain.clone(),
                     conf.clone(),
@@ -158,6 +177,7 @@ impl ChainConf {
                     .map_err(Into::into)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 h_cosmos::CosmosMailbox::new(conf.clone(), locator.clone(), signer.clone())
                     .map(|m| Box::new(m) as Box<dyn Mailbox>)
@@ -189,6 +209,7 @@ impl ChainConf {
                     .map_err(Into::into)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let hook =
                     h_cosmos::CosmosMerkleTreeHook::new(conf.clone(), locator.clone(), signer)?;
@@ -221,10 +242,14 @@ impl ChainConf {
             }
             ChainConnectionConf::Fuel(_) => todo!(),
             ChainConnectionConf::Sealevel(conf) => {
-                let indexer = Box::new(h_sealevel::SealevelMailboxIndexer::new(conf, locator)?);
+                let indexer = Box::new(
+                    h_sealevel::SealevelMailboxIndexer::new(conf, locator)?
+                        .with_log_meta(self.index.log_meta),
+                );
                 Ok(indexer as Box<dyn SequenceAwareIndexer<HyperlaneMessage>>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let indexer = Box::new(h_cosmos::CosmosMailboxIndexer::new(
                     conf.clone(),
@@ -260,10 +285,14 @@ impl ChainConf {
             }
             ChainConnectionConf::Fuel(_) => todo!(),
             ChainConnectionConf::Sealevel(conf) => {
-                let indexer = Box::new(h_sealevel::SealevelMailboxIndexer::new(conf, locator)?);
+                let indexer = Box::new(
+                    h_sealevel::SealevelMailboxIndexer::new(conf, locator)?
+                        .with_log_meta(self.index.log_meta),
+                );
                 Ok(indexer as Box<dyn SequenceAwareIndexer<H256>>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let indexer = Box::new(h_cosmos::CosmosMailboxIndexer::new(
                     conf.clone(),
@@ -304,6 +333,7 @@ impl ChainConf {
                 Ok(paymaster as Box<dyn InterchainGasPaymaster>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let paymaster = Box::new(h_cosmos::CosmosInterchainGasPaymaster::new(
                     conf.clone(),
@@ -340,11 +370,14 @@ impl ChainConf {
             ChainConnectionConf::Fuel(_) => todo!(),
             ChainConnectionConf::Sealevel(conf) => {
                 let indexer = Box::new(
-                    h_sealevel::SealevelInterchainGasPaymasterIndexer::new(conf, locator).await?,
+                    h_sealevel::SealevelInterchainGasPaymasterIndexer::new(conf, locator)
+                        .await?
+                        .with_log_meta(self.index.log_meta),
                 );
                 Ok(indexer as Box<dyn SequenceAwareIndexer<InterchainGasPayment>>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let indexer = Box::new(h_cosmos::CosmosInterchainGasPaymasterIndexer::new(
                     conf.clone(),
                     locator,
@@ -378,14 +411,17 @@ impl ChainConf {
             }
             ChainConnectionConf::Fuel(_) => todo!(),
             ChainConnectionConf::Sealevel(conf) => {
-                let mailbox_indexer =
-                    Box::new(h_sealevel::SealevelMailboxIndexer::new(conf, locator)?);
+                let mailbox_indexer = Box::new(
+                    h_sealevel::SealevelMailboxIndexer::new(conf, locator)?
+                        .with_log_meta(self.index.log_meta),
+                );
                 let indexer = Box::new(h_sealevel::SealevelMerkleTreeHookIndexer::new(
                     *mailbox_indexer,
                 ));
                 Ok(indexer as Box<dyn SequenceAwareIndexer<MerkleTreeInsertion>>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let indexer = Box::new(h_cosmos::CosmosMerkleTreeHookIndexer::new(
                     conf.clone(),
@@ -400,6 +436,68 @@ impl ChainConf {
         .context(ctx)
     }
 
+    /// Try to convert the chain settings into a ValidatorAnnounce indexer
+    pub async fn build_validator_announce_indexer(
+        &self,
+        metrics: &CoreMetrics,
+    ) -> Result<Box<dyn SequenceAwareIndexer<ValidatorAnnouncement>>> {
+        let ctx = "Building validator announce indexer";
+        let locator = self.locator(self.addresses.validator_announce);
+
+        match &self.connection {
+            ChainConnectionConf::Ethereum(conf) => {
+                self.build_ethereum(
+                    conf,
+                    &locator,
+                    metrics,
+                    h_eth::ValidatorAnnounceIndexerBuilder {
+                        reorg_period: self.reorg_period,
+                    },
+                )
+                .await
+            }
+            ChainConnectionConf::Fuel(_) => todo!(),
+            ChainConnectionConf::Sealevel(_) | ChainConnectionConf::Cosmos(_) => Err(eyre!(
+                "Indexing announcements is not supported for {:?}",
+                self.connection.protocol()
+            )),
+        }
+        .context(ctx)
+    }
+
+    /// Build a subscriber pushing messages dispatched by the mailbox as they
+    /// are emitted. Only available for EVM chains connected over websocket.
+    pub fn build_message_log_subscriber(&self) -> Option<Box<dyn LogSubscriber<HyperlaneMessage>>> {
+        self.ethereum_ws_url()
+            .map(|url| h_eth::dispatch_log_subscriber(url, &self.locator(self.addresses.mailbox)))
+    }
+
+    /// Build a subscriber pushing gas payments as they are emitted. Only
+    /// available for EVM chains connected over websocket.
+    pub fn build_interchain_gas_payment_log_subscriber(
+        &self,
+    ) -> Option<Box<dyn LogSubscriber<InterchainGasPayment>>> {
+        self.ethereum_ws_url().map(|url| {
+            h_eth::gas_payment_log_subscriber(
+                url,
+                &self.locator(self.addresses.interchain_gas_paymaster),
+            )
+        })
+    }
+
+    /// Build a subscriber pushing merkle tree insertions as they are emitted.
+    /// Only available for EVM chains connected over websocket.
+    pub fn build_merkle_tree_hook_log_subscriber(
+        &self,
+    ) -> Option<Box<dyn LogSubscriber<MerkleTreeInsertion>>> {
+        self.ethereum_ws_url().map(|url| {
+            h_eth::merkle_tree_insertion_log_subscriber(
+                url,
+                &self.locator(self.addresses.merkle_tree_hook),
+            )
+        })
+    }
+
     /// Try to convert the chain settings into a ValidatorAnnounce
     pub async fn build_validator_announce(
         &self,
@@ -418,6 +516,7 @@ impl ChainConf {
                 Ok(va as Box<dyn ValidatorAnnounce>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let va = Box::new(h_cosmos::CosmosValidatorAnnounce::new(
                     conf.clone(),
@@ -460,6 +559,7 @@ impl ChainConf {
                 Ok(ism as Box<dyn InterchainSecurityModule>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let ism = Box::new(h_cosmos::CosmosInterchainSecurityModule::new(
                     conf, locator, signer,
@@ -492,6 +592,7 @@ impl ChainConf {
                 Ok(ism as Box<dyn MultisigIsm>)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let ism = Box::new(h_cosmos::CosmosMultisigIsm::new(
                     conf.clone(),
@@ -504,6 +605,86 @@ impl ChainConf {
         .context(ctx)
     }
 
+    /// Try to convert the chain setting into an OptimisticIsm contract
+    pub async fn build_optimistic_ism(
+        &self,
+        address: H256,
+        metrics: &CoreMetrics,
+    ) -> Result<Box<dyn OptimisticIsm>> {
+        let ctx = "Building optimistic ISM";
+        let locator = self.locator(address);
+
+        match &self.connection {
+            ChainConnectionConf::Ethereum(conf) => {
+                self.build_ethereum(conf, &locator, metrics, h_eth::OptimisticIsmBuilder {})
+                    .await
+            }
+            ChainConnectionConf::Fuel(_) => todo!(),
+            ChainConnectionConf::Sealevel(_) => {
+                Err(eyre!("Sealevel does not support optimistic ISM yet")).context(ctx)
+            }
+            ChainConnectionConf::Cosmos(_) => {
+                Err(eyre!("Cosmos does not support optimistic ISM yet")).context(ctx)
+            }
+        }
+        .context(ctx)
+    }
+
+    /// Try to convert the chain setting into a BlsMultisigIsm contract
+    pub async fn build_bls_multisig_ism(
+        &self,
+        address: H256,
+        metrics: &CoreMetrics,
+    ) -> Result<Box<dyn BlsMultisigIsm>> {
+        let ctx = "Building BLS multisig ISM";
+        let locator = self.locator(address);
+
+        match &self.connection {
+            ChainConnectionConf::Ethereum(conf) => {
+                self.build_ethereum(conf, &locator, metrics, h_eth::BlsMultisigIsmBuilder {})
+                    .await
+            }
+            ChainConnectionConf::Fuel(_) => todo!(),
+            ChainConnectionConf::Sealevel(_) => {
+                Err(eyre!("Sealevel does not support BLS multisig ISM yet")).context(ctx)
+            }
+            ChainConnectionConf::Cosmos(_) => {
+                Err(eyre!("Cosmos does not support BLS multisig ISM yet")).context(ctx)
+            }
+        }
+        .context(ctx)
+    }
+
+    /// Try to convert the chain setting into a WeightedMultisigIsm contract
+    pub async fn build_weighted_multisig_ism(
+        &self,
+        address: H256,
+        metrics: &CoreMetrics,
+    ) -> Result<Box<dyn WeightedMultisigIsm>> {
+        let ctx = "Building weighted multisig ISM";
+        let locator = self.locator(address);
+
+        match &self.connection {
+            ChainConnectionConf::Ethereum(conf) => {
+                self.build_ethereum(
+                    conf,
+                    &locator,
+                    metrics,
+                    h_eth::WeightedMultisigIsmBuilder {},
+                )
+                .await
+            }
+            ChainConnectionConf::Fuel(_) => todo!(),
+            ChainConnectionConf::Sealevel(_) => {
+                Err(eyre!("Sealevel does not support weighted multisig ISM yet")).context(ctx)
+            }
+            ChainConnectionConf::Cosmos(_) => {
+                Err(eyre!("Cosmos does not support weighted multisig ISM yet")).context(ctx)
+            }
+        }
+        .context(ctx)
+    }
+
     /// Try to convert the chain setting into a RoutingIsm Ism contract
     pub async fn build_routing_ism(
         &self,
@@ -526,6 +707,7 @@ impl ChainConf {
                 Err(eyre!("Sealevel does not support routing ISM yet")).context(ctx)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let ism = Box::new(h_cosmos::CosmosRoutingIsm::new(
                     &conf.clone(),
@@ -560,6 +742,7 @@ impl ChainConf {
                 Err(eyre!("Sealevel does not support aggregation ISM yet")).context(ctx)
             }
             ChainConnectionConf::Cosmos(conf) => {
+                let conf = &self.cosmos_conf(conf, metrics);
                 let signer = self.cosmos_signer().await.context(ctx)?;
                 let ism = Box::new(h_cosmos::CosmosAggregationIsm::new(
                     conf.clone(),
@@ -710,6 +893,25 @@ impl ChainConf {
         }
     }
 
+    fn ethereum_ws_url(&self) -> Option<url::Url> {
+        match &self.connection {
+            ChainConnectionConf::Ethereum(h_eth::ConnectionConf::Ws { url }) => Some(url.clone()),
+            _ => None,
+        }
+    }
+
+    fn cosmos_conf(
+        &self,
+        conf: &h_cosmos::ConnectionConf,
+        metrics: &CoreMetrics,
+    ) -> h_cosmos::ConnectionConf {
+        let observer = metrics
+            .fallback_provider_metrics()
+            .observer_for(self.domain.name(), &conf.get_grpc_urls());
+        conf.clone()
+            .with_fallback_health_observer(Arc::new(observer))
+    }
+
     async fn build_ethereum<B>(
         &self,
         conf: &h_eth::ConnectionConf,
@@ -724,8 +926,24 @@ impl ChainConf {
         let metrics_conf = self.metrics_conf();
         let rpc_metrics = Some(metrics.json_rpc_client_metrics());
         let middleware_metrics = Some((metrics.provider_metrics(), metrics_conf));
+        let fallback_health_observer = match conf {
+            h_eth::ConnectionConf::HttpFallback { urls } => Some(Arc::new(
+                metrics
+                    .fallback_provider_metrics()
+                    .observer_for(self.domain.name(), urls),
+            )
+                as Arc<dyn ProviderHealthObserver>),
+            _ => None,
+        };
         let res = builder
-            .build_with_connection_conf(conf, locator, signer, rpc_metrics, middleware_metrics)
+            .build_with_connection_conf(
+                conf,
+                locator,
+                signer,
+                rpc_metrics,
+                middleware_metrics,
+                fallback_health_observer,
+            )
             .await;
         Ok(res?)
     }
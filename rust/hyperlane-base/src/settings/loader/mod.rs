@@ -7,17 +7,45 @@ use convert_case::Case;
 use eyre::{eyre, Context, Result};
 use hyperlane_core::config::*;
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 
 use crate::settings::loader::{
     arguments::CommandLineArguments, case_adapter::CaseAdapter, environment::Environment,
+    secrets::SecretResolver,
 };
 
 mod arguments;
 mod case_adapter;
 mod environment;
+pub mod secrets;
+
+/// The config files settings are loaded from, i.e. the default config files
+/// and those specified via `CONFIG_FILES`, e.g. to watch them for changes.
+pub fn config_file_paths() -> Vec<PathBuf> {
+    let default_paths = PathBuf::from("./config")
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension() == Some("json".as_ref()));
+    let config_file_paths = env::var("CONFIG_FILES")
+        .map(|s| s.split(',').map(PathBuf::from).collect::<Vec<_>>())
+        .unwrap_or_default();
+    default_paths.chain(config_file_paths).collect()
+}
 
 /// Deserialize a settings object from the configs.
 pub fn load_settings<T, R>() -> ConfigResult<R>
+where
+    T: DeserializeOwned + Debug,
+    R: FromRawConf<T>,
+{
+    load_settings_with_args::<T, R>(None)
+}
+
+/// Deserialize a settings object from the configs, reading program arguments
+/// from `args` instead of the process's arguments if set.
+pub fn load_settings_with_args<T, R>(args: Option<Vec<String>>) -> ConfigResult<R>
 where
     T: DeserializeOwned + Debug,
     R: FromRawConf<T>,
@@ -84,7 +112,10 @@ where
             Case::Flat,
         ))
         .add_source(CaseAdapter::new(
-            CommandLineArguments::default().separator("."),
+            match args {
+                Some(args) => CommandLineArguments::default().separator(".").source(args),
+                None => CommandLineArguments::default().separator("."),
+            },
             Case::Flat,
         ))
         .build()
@@ -104,7 +135,14 @@ where
         }
     };
 
-    let raw_config = Config::try_deserialize::<T>(config_deserializer)
+    // Secrets are resolved after formatting the config for debugging so they
+    // are never printed
+    let mut config_value = Config::try_deserialize::<Value>(config_deserializer)
+        .context("Failed to load config sources")
+        .into_config_result(|| root_path.clone())?;
+    SecretResolver::default().resolve(&mut config_value, &root_path)?;
+
+    let raw_config = serde_json::from_value::<T>(config_value)
         .or_else(|err| {
             let mut err = if let Some(source_err) = err.source() {
                 let source = format!("Config error source: {source_err}");
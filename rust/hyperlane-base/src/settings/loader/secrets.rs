@@ -0,0 +1,350 @@
+//! Resolve references to secrets in config values, so signer keys and RPC
+//! URLs with API keys don't need to be given in plaintext.
+//!
+//! A string config value is replaced by the secret it references if it has one
+//! of the forms
+//! * `secret://vault/<path>#<key>`, the `key` field of the HashiCorp Vault
+//!   secret at `<path>`, e.g. `secret://vault/secret/data/relayer#key` for a
+//!   KV v2 secret engine mounted at `secret`. Vault is reached at `VAULT_ADDR`
+//!   using the token in `VAULT_TOKEN`.
+//! * `secret://env/<name>`, the value of the environment variable `<name>`.
+//! * `secret://file/<path>` or `file://<path>`, the contents of the file at
+//!   `<path>` with surrounding whitespace trimmed.
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    sync::{Arc, Mutex},
+};
+
+use eyre::{eyre, Context, Result};
+use hyperlane_core::config::{ConfigParsingError, ConfigPath, ConfigResult};
+use serde_json::Value;
+
+const SECRET_PREFIX: &str = "secret://";
+const FILE_PREFIX: &str = "file://";
+
+/// A source of secrets which can be referenced from config values.
+pub trait SecretBackend: Send + Sync {
+    /// Fetch the secret at `path`, selecting the field `key` if the secret has
+    /// multiple fields.
+    fn fetch(&self, path: &str, key: Option<&str>) -> Result<String>;
+}
+
+/// Reads secrets from environment variables.
+#[derive(Debug, Default)]
+pub struct EnvSecretBackend;
+
+impl SecretBackend for EnvSecretBackend {
+    fn fetch(&self, path: &str, key: Option<&str>) -> Result<String> {
+        if key.is_some() {
+            return Err(eyre!("Environment secrets do not have fields"));
+        }
+        env::var(path).with_context(|| format!("Failed to read environment variable `{path}`"))
+    }
+}
+
+/// Reads secrets from files, e.g. mounted into `/run/secrets`.
+#[derive(Debug, Default)]
+pub struct FileSecretBackend;
+
+impl SecretBackend for FileSecretBackend {
+    fn fetch(&self, path: &str, key: Option<&str>) -> Result<String> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("Failed to read file `{path}`"))?;
+        match key {
+            None => Ok(contents.trim().to_owned()),
+            Some(key) => {
+                let secret: Value = serde_json::from_str(&contents)
+                    .with_context(|| format!("Expected file `{path}` to contain a JSON object"))?;
+                secret_field(&secret, key)
+            }
+        }
+    }
+}
+
+/// Reads secrets from a HashiCorp Vault KV secret engine over its HTTP API.
+/// Secrets are cached, so several fields of a secret are fetched only once.
+#[derive(Debug)]
+pub struct VaultSecretBackend {
+    addr: String,
+    token: String,
+    cache: Mutex<HashMap<String, Arc<Value>>>,
+}
+
+impl VaultSecretBackend {
+    /// Create a backend for the Vault server at `addr`, e.g.
+    /// `http://127.0.0.1:8200`, authenticating with `token`.
+    pub fn new(addr: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into().trim_end_matches('/').to_owned(),
+            token: token.into(),
+            cache: Default::default(),
+        }
+    }
+
+    /// Create a backend from `VAULT_ADDR` and `VAULT_TOKEN`, if set.
+    pub fn from_env() -> Option<Self> {
+        Some(Self::new(
+            env::var("VAULT_ADDR").ok()?,
+            env::var("VAULT_TOKEN").ok()?,
+        ))
+    }
+
+    fn read(&self, path: &str) -> Result<Arc<Value>> {
+        if let Some(secret) = self.cache.lock().expect("poisoned lock").get(path) {
+            return Ok(secret.clone());
+        }
+        let url = format!("{}/v1/{}", self.addr, path.trim_start_matches('/'));
+        // Settings are loaded synchronously, possibly from within an async
+        // runtime, so the request is made on a separate thread with its own
+        // runtime.
+        let response = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(async {
+                            let response = reqwest::Client::new()
+                                .get(&url)
+                                .header("X-Vault-Token", &self.token)
+                                .send()
+                                .await?
+                                .error_for_status()?;
+                            Ok::<_, eyre::Report>(response.text().await?)
+                        })
+                })
+                .join()
+                .map_err(|_| eyre!("Vault request panicked"))?
+        })
+        .with_context(|| format!("Failed to read Vault secret `{path}`"))?;
+        let response: Value = serde_json::from_str(&response)
+            .with_context(|| format!("Invalid response for Vault secret `{path}`"))?;
+        let secret = Arc::new(response["data"].clone());
+        self.cache
+            .lock()
+            .expect("poisoned lock")
+            .insert(path.to_owned(), secret.clone());
+        Ok(secret)
+    }
+}
+
+impl SecretBackend for VaultSecretBackend {
+    fn fetch(&self, path: &str, key: Option<&str>) -> Result<String> {
+        let key = key.ok_or_else(|| eyre!("Vault secrets must specify a field with `#<key>`"))?;
+        let secret = self.read(path)?;
+        // KV v2 engines nest the secret's fields in `data`
+        match secret.get("data") {
+            Some(data) if data.is_object() && secret.get("metadata").is_some() => {
+                secret_field(data, key)
+            }
+            _ => secret_field(&secret, key),
+        }
+    }
+}
+
+fn secret_field(secret: &Value, key: &str) -> Result<String> {
+    match secret.get(key) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(v) => Ok(v.to_string()),
+        None => Err(eyre!("Secret has no field `{key}`")),
+    }
+}
+
+/// Resolves secret references in config values using the registered
+/// backends.
+pub struct SecretResolver {
+    backends: HashMap<String, Box<dyn SecretBackend>>,
+}
+
+impl Default for SecretResolver {
+    /// The `env` and `file` backends, and the `vault` backend if
+    /// `VAULT_ADDR` and `VAULT_TOKEN` are set.
+    fn default() -> Self {
+        let resolver = Self {
+            backends: HashMap::new(),
+        }
+        .with_backend("env", EnvSecretBackend)
+        .with_backend("file", FileSecretBackend);
+        match VaultSecretBackend::from_env() {
+            Some(vault) => resolver.with_backend("vault", vault),
+            None => resolver,
+        }
+    }
+}
+
+impl SecretResolver {
+    /// Register a backend used for references of the form
+    /// `secret://<name>/<path>#<key>`, replacing any backend of the same name.
+    pub fn with_backend(mut self, name: &str, backend: impl SecretBackend + 'static) -> Self {
+        self.backends.insert(name.to_owned(), Box::new(backend));
+        self
+    }
+
+    /// Replace every secret reference in `value` with the secret. Reports all
+    /// references which could not be resolved.
+    pub fn resolve(&self, value: &mut Value, cwp: &ConfigPath) -> ConfigResult<()> {
+        let mut err = ConfigParsingError::default();
+        self.resolve_into(value, cwp, &mut err);
+        err.into_result(())
+    }
+
+    fn resolve_into(&self, value: &mut Value, cwp: &ConfigPath, err: &mut ConfigParsingError) {
+        match value {
+            Value::String(s) => match self.resolve_str(s) {
+                Ok(Some(secret)) => *s = secret,
+                Ok(None) => {}
+                Err(e) => err.push(cwp.clone(), e),
+            },
+            Value::Array(arr) => {
+                for (i, v) in arr.iter_mut().enumerate() {
+                    self.resolve_into(v, &cwp.join(i.to_string()), err);
+                }
+            }
+            Value::Object(obj) => {
+                for (k, v) in obj.iter_mut() {
+                    self.resolve_into(v, &cwp.join(k.as_str()), err);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns `None` if `s` is not a secret reference.
+    fn resolve_str(&self, s: &str) -> Result<Option<String>> {
+        let (backend, reference) = if let Some(path) = s.strip_prefix(FILE_PREFIX) {
+            ("file", path)
+        } else if let Some(reference) = s.strip_prefix(SECRET_PREFIX) {
+            reference
+                .split_once('/')
+                .ok_or_else(|| eyre!("Expected a secret reference `secret://<backend>/<path>`"))?
+        } else {
+            return Ok(None);
+        };
+        let (path, key) = match reference.split_once('#') {
+            Some((path, key)) => (path, Some(key)),
+            None => (reference, None),
+        };
+        let backend = self
+            .backends
+            .get(backend)
+            .ok_or_else(|| eyre!("Unknown or unconfigured secret backend `{backend}`"))?;
+        backend.fetch(path, key).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{SocketAddr, TcpListener};
+
+    use axum::{
+        extract::Path,
+        http::{HeaderMap, StatusCode},
+        routing::get,
+        Json, Router,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    const TOKEN: &str = "dev-only-token";
+
+    /// Serves the KV v2 read API of a Vault dev server, which has a KV v2
+    /// engine mounted at `secret`. The server runs on its own thread since
+    /// secrets are resolved synchronously.
+    fn vault_dev_server() -> SocketAddr {
+        async fn read_secret(
+            Path(path): Path<String>,
+            headers: HeaderMap,
+        ) -> Result<Json<Value>, StatusCode> {
+            if headers.get("X-Vault-Token").map(|t| t.as_bytes()) != Some(TOKEN.as_bytes()) {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            match path.trim_start_matches('/') {
+                "secret/data/relayer" => Ok(Json(json!({
+                    "data": {
+                        "data": { "key": "0x1234", "rpc": "https://rpc.example.com/api-key" },
+                        "metadata": { "version": 1 },
+                    }
+                }))),
+                _ => Err(StatusCode::NOT_FOUND),
+            }
+        }
+
+        let app = Router::new().route("/v1/*path", get(read_secret));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(
+                    axum::Server::from_tcp(listener)
+                        .unwrap()
+                        .serve(app.into_make_service()),
+                )
+                .unwrap();
+        });
+        addr
+    }
+
+    #[test]
+    fn test_resolves_secret_references() {
+        let addr = vault_dev_server();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("key");
+        fs::write(&file, "0xabcd\n").unwrap();
+        env::set_var("TEST_RESOLVES_SECRET_REFERENCES", "secret value");
+
+        let resolver = SecretResolver::default().with_backend(
+            "vault",
+            VaultSecretBackend::new(format!("http://{addr}"), TOKEN),
+        );
+        let mut value = json!({
+            "chains": {
+                "test1": {
+                    "signer": { "key": "secret://vault/secret/data/relayer#key" },
+                    "rpcurls": [{ "http": "secret://vault/secret/data/relayer#rpc" }],
+                },
+                "test2": {
+                    "signer": { "key": format!("file://{}", file.display()) },
+                    "name": "secret://env/TEST_RESOLVES_SECRET_REFERENCES",
+                },
+            },
+            "db": "/tmp/db",
+        });
+        let original = value.clone();
+        resolver
+            .resolve(&mut value, &ConfigPath::default())
+            .unwrap();
+
+        assert_eq!(value["chains"]["test1"]["signer"]["key"], "0x1234");
+        assert_eq!(
+            value["chains"]["test1"]["rpcurls"][0]["http"],
+            "https://rpc.example.com/api-key"
+        );
+        assert_eq!(value["chains"]["test2"]["signer"]["key"], "0xabcd");
+        assert_eq!(value["chains"]["test2"]["name"], "secret value");
+        assert_eq!(value["db"], original["db"]);
+    }
+
+    #[test]
+    fn test_reports_unresolved_references() {
+        let addr = vault_dev_server();
+        let resolver = SecretResolver::default().with_backend(
+            "vault",
+            VaultSecretBackend::new(format!("http://{addr}"), "wrong"),
+        );
+        let mut value = json!({
+            "a": "secret://vault/secret/data/relayer#key",
+            "b": "secret://unknown/path",
+            "c": "secret://env/TEST_REPORTS_UNRESOLVED_REFERENCES_UNSET",
+        });
+        let err = resolver
+            .resolve(&mut value, &ConfigPath::default())
+            .unwrap_err();
+        assert_eq!(err.to_string().matches("config_path:").count(), 3);
+    }
+}
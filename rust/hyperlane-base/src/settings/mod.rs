@@ -61,10 +61,27 @@
 //!    E.g. `export HYP_CHAINS_ARBITRUM_DOMAINID=3000`
 //! 5. Arguments passed to the agent on the command line.
 //!    E.g. `--originChainName ethereum`
+//!
+//! ### Secrets
+//!
+//! Any string value may instead reference a secret which is resolved when the
+//! settings are loaded, e.g. `secret://vault/secret/data/relayer#key`,
+//! `secret://env/RPC_URL` or `file:///run/secrets/relayer-key`. See
+//! [`loader::secrets`] for the supported backends.
+//!
+//! ### Validating configuration
+//!
+//! Passing `--validateConfig` makes an agent load its settings, report every
+//! problem found and exit instead of running. With `--validateConfig rpc` it
+//! also connects to each chain to check the chain id against the configured
+//! `chainId` of EVM chains and that the core contracts are deployed. Sealevel
+//! and Cosmos providers can't tell if an address is a contract, so that check
+//! is reported as skipped for them.
 
 pub use base::*;
 pub use chains::*;
 pub use checkpoint_syncer::*;
+pub use notifications::*;
 /// Export this so they don't need to import paste.
 #[doc(hidden)]
 pub use paste;
@@ -84,10 +101,13 @@ mod base;
 /// Chain configuration
 mod chains;
 pub mod loader;
+mod notifications;
 /// Signer configuration
 mod signers;
 /// Tracing subscriber management
 mod trace;
+/// Checks of settings beyond parsing them
+mod validate;
 
 mod checkpoint_syncer;
 pub mod parser;
@@ -105,6 +125,12 @@ macro_rules! impl_loadable_from_settings {
             fn load() -> hyperlane_core::config::ConfigResult<Self> {
                 hyperlane_base::settings::loader::load_settings::<$settingsparser, Self>()
             }
+
+            fn load_with_args(args: Vec<String>) -> hyperlane_core::config::ConfigResult<Self> {
+                hyperlane_base::settings::loader::load_settings_with_args::<$settingsparser, Self>(
+                    Some(args),
+                )
+            }
         }
     };
 }
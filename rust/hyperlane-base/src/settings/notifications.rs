@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use eyre::Result;
+use url::Url;
+
+use crate::{
+    notifications::{FileSink, NatsSink, NotificationSink, Notifier, QueueLimits, WebhookSink},
+    CoreMetrics,
+};
+
+/// Where to send message lifecycle notifications
+#[derive(Debug, Clone, Default)]
+pub struct NotificationsConf {
+    /// The sinks to send every notification to
+    pub sinks: Vec<NotificationSinkConf>,
+    /// Directory to persist notifications in until they are delivered, so
+    /// they are re-sent after a restart
+    pub spool_dir: Option<PathBuf>,
+    /// Bounds on the notifications queued for each sink
+    pub limits: QueueLimits,
+}
+
+/// A named notification sink
+#[derive(Debug, Clone)]
+pub struct NotificationSinkConf {
+    /// Name of the sink, which must be stable across restarts when spooling
+    pub name: String,
+    /// Where the sink sends notifications
+    pub sink_type: NotificationSinkType,
+}
+
+/// Notification sink types
+#[derive(Debug, Clone)]
+pub enum NotificationSinkType {
+    /// POST notifications to an HTTP endpoint
+    Webhook {
+        /// Endpoint url
+        url: Url,
+        /// Shared secret to sign the request bodies with
+        secret: Option<String>,
+    },
+    /// Append notifications to a local file
+    File {
+        /// Path
+        path: PathBuf,
+    },
+    /// Publish notifications to a NATS compatible broker
+    Nats {
+        /// Server url, e.g. `nats://localhost:4222`. TLS is not supported.
+        url: Url,
+        /// Subject to publish to
+        subject: String,
+    },
+}
+
+impl NotificationSinkType {
+    fn build(&self) -> Result<Box<dyn NotificationSink>> {
+        Ok(match self {
+            Self::Webhook { url, secret } => {
+                Box::new(WebhookSink::new(url.clone(), secret.clone())?)
+            }
+            Self::File { path } => Box::new(FileSink::new(path)),
+            Self::Nats { url, subject } => Box::new(NatsSink::new(url.clone(), subject.clone())?),
+        })
+    }
+}
+
+impl NotificationsConf {
+    /// Start a notifier for the agent with the given name. Without sinks the
+    /// notifier drops all events.
+    pub async fn build(&self, agent_name: &str, metrics: &CoreMetrics) -> Result<Notifier> {
+        let sinks = self
+            .sinks
+            .iter()
+            .map(|conf| Ok((conf.name.clone(), conf.sink_type.build()?)))
+            .collect::<Result<Vec<_>>>()?;
+        Notifier::new(
+            agent_name,
+            sinks,
+            self.spool_dir.as_deref(),
+            self.limits,
+            metrics,
+        )
+        .await
+    }
+}
@@ -7,6 +7,7 @@
 use std::{
     collections::{HashMap, HashSet},
     default::Default,
+    path::PathBuf,
 };
 
 use convert_case::{Case, Casing};
@@ -23,9 +24,13 @@ use url::Url;
 
 pub use self::json_value_parser::ValueParser;
 pub use super::envs::*;
-use crate::settings::{
-    chains::IndexSettings, parser::connection_parser::build_connection_conf, trace::TracingConfig,
-    ChainConf, CoreContractAddresses, Settings, SignerConf,
+use crate::{
+    notifications::QueueLimits,
+    settings::{
+        chains::IndexSettings, parser::connection_parser::build_connection_conf,
+        trace::TracingConfig, ChainConf, CoreContractAddresses, NotificationSinkConf,
+        NotificationSinkType, NotificationsConf, Settings, SignerConf,
+    },
 };
 
 mod connection_parser;
@@ -106,14 +111,128 @@ impl FromRawConf<RawAgentConf, Option<&HashSet<&str>>> for Settings {
             })
             .collect();
 
+        let notifications = p
+            .chain(&mut err)
+            .get_opt_key("notifications")
+            .and_then(parse_notifications)
+            .unwrap_or_default();
+
         err.into_result(Self {
             chains,
             metrics_port,
             tracing: TracingConfig { fmt, level },
+            notifications,
         })
     }
 }
 
+/// Parse the lifecycle notification sinks
+fn parse_notifications(notifications: ValueParser) -> ConfigResult<NotificationsConf> {
+    let mut err = ConfigParsingError::default();
+
+    let spool_dir = notifications
+        .chain(&mut err)
+        .get_opt_key("spoolDir")
+        .parse_string()
+        .end()
+        .map(PathBuf::from);
+
+    let default_limits = QueueLimits::default();
+    let capacity = notifications
+        .chain(&mut err)
+        .get_opt_key("queueCapacity")
+        .parse_u64()
+        .end()
+        .map(|capacity| capacity as usize)
+        .unwrap_or(default_limits.capacity);
+    let max_attempts = notifications
+        .chain(&mut err)
+        .get_opt_key("maxAttempts")
+        .parse_u32()
+        .end()
+        .unwrap_or(default_limits.max_attempts);
+
+    let sinks = notifications
+        .chain(&mut err)
+        .get_opt_key("sinks")
+        .into_array_iter()
+        .map(|sinks| {
+            sinks
+                .enumerate()
+                .filter_map(|(i, sink)| parse_notification_sink(sink, i).take_config_err(&mut err))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    err.into_result(NotificationsConf {
+        sinks,
+        spool_dir,
+        limits: QueueLimits {
+            capacity,
+            max_attempts,
+        },
+    })
+}
+
+fn parse_notification_sink(sink: ValueParser, index: usize) -> ConfigResult<NotificationSinkConf> {
+    let mut err = ConfigParsingError::default();
+
+    let Some(sink_type) = sink.chain(&mut err).get_key("type").parse_string().end() else {
+        return Err(err);
+    };
+
+    let name = sink
+        .chain(&mut err)
+        .get_opt_key("name")
+        .parse_string()
+        .end()
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("{sink_type}-{index}"));
+
+    let sink_type = match sink_type {
+        "webhook" => {
+            let url = sink
+                .chain(&mut err)
+                .get_key("url")
+                .parse_from_str("Invalid webhook url")
+                .end();
+            let secret = sink
+                .chain(&mut err)
+                .get_opt_key("secret")
+                .parse_string()
+                .end()
+                .map(str::to_owned);
+            url.map(|url| NotificationSinkType::Webhook { url, secret })
+        }
+        "file" => sink
+            .chain(&mut err)
+            .get_key("path")
+            .parse_string()
+            .end()
+            .map(|path| NotificationSinkType::File { path: path.into() }),
+        "nats" => {
+            let url = sink
+                .chain(&mut err)
+                .get_key("url")
+                .parse_from_str("Invalid NATS url")
+                .end();
+            let subject = sink
+                .chain(&mut err)
+                .get_key("subject")
+                .parse_string()
+                .end()
+                .map(str::to_owned);
+            url.zip(subject)
+                .map(|(url, subject)| NotificationSinkType::Nats { url, subject })
+        }
+        _ => Err(eyre!("Unknown notification sink type `{sink_type}`"))
+            .take_err(&mut err, || &sink.cwp + "type"),
+    };
+
+    cfg_unwrap_all!(&sink.cwp, err: [sink_type]);
+    err.into_result(NotificationSinkConf { name, sink_type })
+}
+
 /// The chain name and ChainMetadata
 fn parse_chain(
     chain: ValueParser,
@@ -150,6 +269,32 @@ fn parse_chain(
         .get_opt_key("chunk")
         .parse_u32()
         .unwrap_or(1999);
+    let min_chunk_size = chain
+        .chain(&mut err)
+        .get_opt_key("index")
+        .get_opt_key("minChunk")
+        .parse_u32()
+        .end();
+    let max_chunk_size = chain
+        .chain(&mut err)
+        .get_opt_key("index")
+        .get_opt_key("maxChunk")
+        .parse_u32()
+        .end();
+    if let (Some(min), Some(max)) = (min_chunk_size, max_chunk_size) {
+        if min > max {
+            err.push(
+                &chain.cwp + "index" + "minchunk",
+                eyre!("minChunk must not be greater than maxChunk"),
+            );
+        }
+    }
+    let snapshot = chain
+        .chain(&mut err)
+        .get_opt_key("index")
+        .get_opt_key("snapshot")
+        .parse_from_str("Expected snapshot path")
+        .end();
     let mode = chain
         .chain(&mut err)
         .get_opt_key("index")
@@ -188,6 +333,15 @@ fn parse_chain(
         .end();
 
     cfg_unwrap_all!(&chain.cwp, err: [domain]);
+    // Other protocols use chain ids which aren't numbers
+    let chain_id = match domain.domain_protocol() {
+        HyperlaneDomainProtocol::Ethereum => chain
+            .chain(&mut err)
+            .get_opt_key("chainId")
+            .parse_u64()
+            .end(),
+        _ => None,
+    };
     let connection = build_connection_conf(
         domain.domain_protocol(),
         &rpcs,
@@ -212,8 +366,13 @@ fn parse_chain(
         index: IndexSettings {
             from,
             chunk_size,
+            min_chunk_size,
+            max_chunk_size,
             mode,
+            snapshot,
+            log_meta: false,
         },
+        chain_id,
     })
 }
 
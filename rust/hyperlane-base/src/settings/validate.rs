@@ -0,0 +1,177 @@
+//! Checks of the agent settings beyond what is needed to parse them, used to
+//! validate a config without running the agent.
+
+use hyperlane_core::{HyperlaneDomainProtocol, H256, U256};
+
+use crate::{settings::ChainConf, CoreMetrics};
+
+impl ChainConf {
+    /// The core contract addresses of this chain along with their config keys.
+    fn named_addresses(&self) -> [(&'static str, H256); 4] {
+        [
+            ("mailbox", self.addresses.mailbox),
+            (
+                "interchainGasPaymaster",
+                self.addresses.interchain_gas_paymaster,
+            ),
+            ("validatorAnnounce", self.addresses.validator_announce),
+            ("merkleTreeHook", self.addresses.merkle_tree_hook),
+        ]
+    }
+
+    /// Checks that the core contract addresses are well-formed for the
+    /// protocol of this chain. Returns a description of every problem found.
+    pub fn validate_addresses(&self) -> Vec<String> {
+        let protocol = self.connection.protocol();
+        self.named_addresses()
+            .into_iter()
+            .filter_map(|(name, address)| {
+                if address.is_zero() {
+                    Some(format!("{name} address is zero"))
+                } else if protocol == HyperlaneDomainProtocol::Ethereum
+                    && address[..12].iter().any(|b| *b != 0)
+                {
+                    Some(format!(
+                        "{name} address {address:?} is not a 20 byte address"
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Whether providers of this chain's protocol can tell if an address is
+    /// a deployed contract. Sealevel and Cosmos providers report every
+    /// address as one.
+    fn can_check_contracts(&self) -> bool {
+        !matches!(
+            self.connection.protocol(),
+            HyperlaneDomainProtocol::Sealevel | HyperlaneDomainProtocol::Cosmos
+        )
+    }
+
+    /// Describes the checks `validate_connection` can't do for this chain.
+    pub fn skipped_connection_checks(&self) -> Vec<String> {
+        let protocol = self.connection.protocol();
+        if protocol == HyperlaneDomainProtocol::Fuel {
+            return vec![format!("{protocol} chains can't be connected to yet")];
+        }
+        let mut skipped = vec![];
+        if self.chain_id.is_none() && protocol == HyperlaneDomainProtocol::Ethereum {
+            skipped.push("the RPC chain id is not checked without a configured chainId".into());
+        }
+        if !self.can_check_contracts() {
+            skipped.push(format!(
+                "{protocol} providers can't tell if the core contracts are deployed"
+            ));
+        }
+        skipped
+    }
+
+    /// Connects to the chain and checks that the chain id reported by the RPC
+    /// matches the configured `chainId`, if any, and that the core contracts
+    /// are deployed where the protocol allows checking it. Returns a
+    /// description of every problem found.
+    pub async fn validate_connection(&self, metrics: &CoreMetrics) -> Vec<String> {
+        // Fuel providers are not implemented yet
+        if self.connection.protocol() == HyperlaneDomainProtocol::Fuel {
+            return vec![];
+        }
+        let provider = match self.build_provider(metrics).await {
+            Ok(provider) => provider,
+            Err(err) => return vec![format!("Failed to build provider: {err:#}")],
+        };
+
+        let mut issues = vec![];
+        if let Some(expected) = self.chain_id {
+            match provider.get_chain_id().await {
+                Ok(Some(chain_id)) if chain_id != U256::from(expected) => {
+                    issues.push(format!(
+                        "RPC reports chain id {chain_id}, which differs from the configured chain id {expected}"
+                    ));
+                }
+                Ok(_) => {}
+                Err(err) => issues.push(format!("Failed to fetch chain id: {err}")),
+            }
+        }
+        if !self.can_check_contracts() {
+            return issues;
+        }
+        for (name, address) in self.named_addresses() {
+            let address_str = self.connection.protocol().fmt_address(address);
+            match provider.is_contract(&address).await {
+                Ok(true) => {}
+                Ok(false) => issues.push(format!(
+                    "{name} address {address_str} is not a deployed contract"
+                )),
+                Err(err) => issues.push(format!(
+                    "Failed to check {name} address {address_str}: {err}"
+                )),
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use super::*;
+    use crate::settings::{ChainConnectionConf, CoreContractAddresses};
+
+    fn chain_conf(addresses: CoreContractAddresses) -> ChainConf {
+        ChainConf {
+            domain: HyperlaneDomain::Known(KnownHyperlaneDomain::Test1),
+            signer: Default::default(),
+            reorg_period: Default::default(),
+            addresses,
+            connection: ChainConnectionConf::Ethereum(hyperlane_ethereum::ConnectionConf::Http {
+                url: "http://example.com".parse().unwrap(),
+            }),
+            metrics_conf: Default::default(),
+            index: Default::default(),
+            chain_id: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_addresses() {
+        let evm_address = H256::from_low_u64_be(1);
+        let valid = chain_conf(CoreContractAddresses {
+            mailbox: evm_address,
+            interchain_gas_paymaster: evm_address,
+            validator_announce: evm_address,
+            merkle_tree_hook: evm_address,
+        });
+        assert!(valid.validate_addresses().is_empty());
+
+        let invalid = chain_conf(CoreContractAddresses {
+            mailbox: H256::repeat_byte(1),
+            interchain_gas_paymaster: evm_address,
+            validator_announce: evm_address,
+            merkle_tree_hook: H256::zero(),
+        });
+        let issues = invalid.validate_addresses();
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].starts_with("mailbox address"));
+        assert_eq!(issues[1], "merkleTreeHook address is zero");
+    }
+
+    #[test]
+    fn test_skipped_connection_checks() {
+        let mut conf = chain_conf(Default::default());
+        assert_eq!(conf.skipped_connection_checks().len(), 1);
+        conf.chain_id = Some(13371);
+        assert!(conf.skipped_connection_checks().is_empty());
+
+        conf.connection = ChainConnectionConf::Sealevel(hyperlane_sealevel::ConnectionConf {
+            url: "http://example.com".parse().unwrap(),
+        });
+        assert_eq!(
+            conf.skipped_connection_checks(),
+            vec!["sealevel providers can't tell if the core contracts are deployed"]
+        );
+    }
+}
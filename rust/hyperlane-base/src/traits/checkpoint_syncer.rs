@@ -3,7 +3,9 @@ use std::fmt::Debug;
 use async_trait::async_trait;
 use eyre::Result;
 
-use hyperlane_core::{SignedAnnouncement, SignedCheckpointWithMessageId};
+use hyperlane_core::{
+    BlsSignedCheckpointWithMessageId, SignedAnnouncement, SignedCheckpointWithMessageId,
+};
 
 /// A generic trait to read/write Checkpoints offchain
 #[async_trait]
@@ -27,6 +29,17 @@ pub trait CheckpointSyncer: Debug + Send + Sync {
         &self,
         signed_checkpoint: &SignedCheckpointWithMessageId,
     ) -> Result<()>;
+    /// Attempt to fetch the BLS signed (checkpoint, messageId) tuple at this
+    /// index
+    async fn fetch_bls_checkpoint(
+        &self,
+        index: u32,
+    ) -> Result<Option<BlsSignedCheckpointWithMessageId>>;
+    /// Write the BLS signed (checkpoint, messageId) tuple to this syncer
+    async fn write_bls_checkpoint(
+        &self,
+        signed_checkpoint: &BlsSignedCheckpointWithMessageId,
+    ) -> Result<()>;
     /// Write the signed announcement to this syncer
     async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()>;
     /// Return the announcement storage location for this syncer
@@ -2,7 +2,9 @@ use crate::CheckpointSyncer;
 use async_trait::async_trait;
 use derive_new::new;
 use eyre::{bail, Result};
-use hyperlane_core::{SignedAnnouncement, SignedCheckpointWithMessageId};
+use hyperlane_core::{
+    BlsSignedCheckpointWithMessageId, SignedAnnouncement, SignedCheckpointWithMessageId,
+};
 use std::fmt;
 use ya_gcp::{storage::StorageClient, AuthFlow, ClientBuilder, ClientBuilderConfig};
 
@@ -101,6 +103,9 @@ impl GcsStorageClient {
     fn get_checkpoint_key(index: u32) -> String {
         format!("checkpoint_{index}_with_id.json")
     }
+    fn get_bls_checkpoint_key(index: u32) -> String {
+        format!("bls_checkpoint_{index}_with_id.json")
+    }
     // #test only method[s]
     #[cfg(test)]
     pub(crate) async fn get_by_path(&self, path: impl AsRef<str>) -> Result<()> {
@@ -174,6 +179,36 @@ impl CheckpointSyncer for GcsStorageClient {
         Ok(())
     }
 
+    /// Attempt to fetch the BLS signed (checkpoint, messageId) tuple at this index
+    async fn fetch_bls_checkpoint(
+        &self,
+        index: u32,
+    ) -> Result<Option<BlsSignedCheckpointWithMessageId>> {
+        let res = self
+            .inner
+            .get_object(
+                &self.bucket,
+                GcsStorageClient::get_bls_checkpoint_key(index),
+            )
+            .await?;
+        Ok(Some(serde_json::from_slice(res.as_ref())?))
+    }
+
+    /// Write the BLS signed (checkpoint, messageId) tuple to this syncer
+    async fn write_bls_checkpoint(
+        &self,
+        signed_checkpoint: &BlsSignedCheckpointWithMessageId,
+    ) -> Result<()> {
+        self.inner
+            .insert_object(
+                &self.bucket,
+                GcsStorageClient::get_bls_checkpoint_key(signed_checkpoint.value.index),
+                serde_json::to_vec(signed_checkpoint)?,
+            )
+            .await?;
+        Ok(())
+    }
+
     /// Write the signed announcement to this syncer
     async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
         self.inner
@@ -2,7 +2,9 @@ use std::path::PathBuf;
 
 use async_trait::async_trait;
 use eyre::{Context, Result};
-use hyperlane_core::{SignedAnnouncement, SignedCheckpointWithMessageId};
+use hyperlane_core::{
+    BlsSignedCheckpointWithMessageId, SignedAnnouncement, SignedCheckpointWithMessageId,
+};
 use prometheus::IntGauge;
 
 use crate::traits::CheckpointSyncer;
@@ -33,6 +35,10 @@ impl LocalStorage {
         self.path.join(format!("{}_with_id.json", index))
     }
 
+    fn bls_checkpoint_file_path(&self, index: u32) -> PathBuf {
+        self.path.join(format!("bls_{}_with_id.json", index))
+    }
+
     fn latest_index_file_path(&self) -> PathBuf {
         self.path.join("index.json")
     }
@@ -91,6 +97,30 @@ impl CheckpointSyncer for LocalStorage {
         Ok(())
     }
 
+    async fn fetch_bls_checkpoint(
+        &self,
+        index: u32,
+    ) -> Result<Option<BlsSignedCheckpointWithMessageId>> {
+        let Ok(data) = tokio::fs::read(self.bls_checkpoint_file_path(index)).await else {
+            return Ok(None);
+        };
+        let checkpoint = serde_json::from_slice(&data)?;
+        Ok(Some(checkpoint))
+    }
+
+    async fn write_bls_checkpoint(
+        &self,
+        signed_checkpoint: &BlsSignedCheckpointWithMessageId,
+    ) -> Result<()> {
+        let serialized_checkpoint = serde_json::to_string_pretty(signed_checkpoint)?;
+        let path = self.bls_checkpoint_file_path(signed_checkpoint.value.index);
+        tokio::fs::write(&path, &serialized_checkpoint)
+            .await
+            .with_context(|| format!("Writing BLS (checkpoint, messageId) to {path:?}"))?;
+
+        Ok(())
+    }
+
     async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
         let serialized_announcement = serde_json::to_string_pretty(signed_announcement)?;
         let path = self.announcement_file_path();
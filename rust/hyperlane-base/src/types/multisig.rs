@@ -2,11 +2,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use derive_new::new;
-use eyre::Result;
+use eyre::{ensure, Result};
 use tracing::{debug, instrument};
 
 use hyperlane_core::{
-    HyperlaneDomain, MultisigSignedCheckpoint, SignedCheckpointWithMessageId, H160, H256,
+    BlsAggregatedCheckpoint, BlsPublicKey, BlsSignedCheckpointWithMessageId, HyperlaneDomain,
+    MultisigSignedCheckpoint, SignedCheckpointWithMessageId, WeightedValidator, H160, H256, U256,
 };
 
 use crate::{CheckpointSyncer, CoreMetrics};
@@ -75,6 +76,21 @@ impl MultisigCheckpointSyncer {
         latest_indices.values().copied().flatten().collect()
     }
 
+    /// Returns whether a checkpoint syncer was found for the validator, i.e.
+    /// it announced a valid storage location.
+    pub fn has_checkpoint_syncer(&self, validator: H256) -> bool {
+        self.checkpoint_syncers.contains_key(&H160::from(validator))
+    }
+
+    /// Gets the latest checkpoint index of a validator, or `None` if it has no
+    /// checkpoint syncer or has not published a checkpoint yet.
+    pub async fn latest_index(&self, validator: H256) -> Result<Option<u32>> {
+        match self.checkpoint_syncers.get(&H160::from(validator)) {
+            Some(checkpoint_syncer) => checkpoint_syncer.latest_index().await,
+            None => Ok(None),
+        }
+    }
+
     /// Attempts to get the latest checkpoint with a quorum of signatures among
     /// validators.
     ///
@@ -152,70 +168,455 @@ impl MultisigCheckpointSyncer {
             HashMap::new();
 
         for validator in validators.iter() {
-            let addr = H160::from(*validator);
-            if let Some(checkpoint_syncer) = self.checkpoint_syncers.get(&addr) {
-                // Gracefully ignore an error fetching the checkpoint from a validator's
-                // checkpoint syncer, which can happen if the validator has not
-                // signed the checkpoint at `index`.
-                if let Ok(Some(signed_checkpoint)) = checkpoint_syncer.fetch_checkpoint(index).await
-                {
-                    // If the signed checkpoint is for a different index, ignore it
-                    if signed_checkpoint.value.index != index {
-                        debug!(
-                            validator = format!("{:#x}", validator),
-                            index = index,
-                            checkpoint_index = signed_checkpoint.value.index,
-                            "Checkpoint index mismatch"
-                        );
-                        continue;
-                    }
+            let Some(signed_checkpoint) = self.fetch_signed_checkpoint(validator, index).await?
+            else {
+                continue;
+            };
 
-                    // Ensure that the signature is actually by the validator
-                    let signer = signed_checkpoint.recover()?;
+            // Push the signed checkpoint into the hashmap
+            let root = signed_checkpoint.value.root;
+            let signed_checkpoints = signed_checkpoints_per_root.entry(root).or_default();
+            signed_checkpoints.push(signed_checkpoint);
 
-                    if H256::from(signer) != *validator {
-                        debug!(
-                            validator = format!("{:#x}", validator),
-                            index = index,
-                            "Checkpoint signature mismatch"
-                        );
-                        continue;
-                    }
+            // Count the number of signatures for this signed checkpoint
+            let signature_count = signed_checkpoints.len();
+            debug!(
+                validator = format!("{:#x}", validator),
+                index = index,
+                root = format!("{:#x}", root),
+                signature_count = signature_count,
+                "Found signed checkpoint"
+            );
 
-                    // Push the signed checkpoint into the hashmap
-                    let root = signed_checkpoint.value.root;
-                    let signed_checkpoints = signed_checkpoints_per_root.entry(root).or_default();
-                    signed_checkpoints.push(signed_checkpoint);
-
-                    // Count the number of signatures for this signed checkpoint
-                    let signature_count = signed_checkpoints.len();
-                    debug!(
-                        validator = format!("{:#x}", validator),
-                        index = index,
-                        root = format!("{:#x}", root),
-                        signature_count = signature_count,
-                        "Found signed checkpoint"
-                    );
-
-                    // If we've hit a quorum, create a MultisigSignedCheckpoint
-                    if signature_count >= threshold {
-                        let checkpoint: MultisigSignedCheckpoint = signed_checkpoints.try_into()?;
-                        debug!(checkpoint=?checkpoint, "Fetched multisig checkpoint");
-                        return Ok(Some(checkpoint));
-                    }
-                } else {
-                    debug!(
-                        validator = format!("{:#x}", validator),
-                        index = index,
-                        "Unable to find signed checkpoint"
-                    );
-                }
-            } else {
+            // If we've hit a quorum, create a MultisigSignedCheckpoint
+            if signature_count >= threshold {
+                let checkpoint: MultisigSignedCheckpoint = signed_checkpoints.try_into()?;
+                debug!(checkpoint=?checkpoint, "Fetched multisig checkpoint");
+                return Ok(Some(checkpoint));
+            }
+        }
+        debug!("No quorum checkpoint found for message");
+        Ok(None)
+    }
+
+    /// Fetches a MultisigSignedCheckpointWithMessageId whose signers reach
+    /// `threshold_weight`. Validators must reflect the onchain ordering of
+    /// the set, which is the order of the returned signatures.
+    ///
+    /// Checkpoints are fetched from the heaviest validators first, so the
+    /// first set of signers reaching the threshold weight is the smallest one,
+    /// i.e. the cheapest to verify onchain.
+    /// Returns Ok(None) if there is no quorum.
+    #[instrument(err, skip(self))]
+    pub async fn fetch_weighted_checkpoint(
+        &self,
+        validators: &[WeightedValidator],
+        threshold_weight: U256,
+        index: u32,
+    ) -> Result<Option<MultisigSignedCheckpoint>> {
+        let mut heaviest_first: Vec<_> = validators.iter().enumerate().collect();
+        // Stable, so validators of equal weight keep their onchain order
+        heaviest_first.sort_by(|(_, a), (_, b)| b.weight.cmp(&a.weight));
+
+        // Signed checkpoints with the position of their signer in the
+        // validator set and their total weight, for a particular root
+        let mut signed_checkpoints_per_root: HashMap<
+            H256,
+            (U256, Vec<(usize, SignedCheckpointWithMessageId)>),
+        > = HashMap::new();
+
+        for (validator_index, validator) in heaviest_first {
+            let Some(signed_checkpoint) = self
+                .fetch_signed_checkpoint(&validator.address, index)
+                .await?
+            else {
+                continue;
+            };
+
+            let root = signed_checkpoint.value.root;
+            let (signed_weight, signed_checkpoints) =
+                signed_checkpoints_per_root.entry(root).or_default();
+            *signed_weight = signed_weight.saturating_add(validator.weight);
+            signed_checkpoints.push((validator_index, signed_checkpoint));
+            debug!(
+                validator = format!("{:#x}", validator.address),
+                index = index,
+                root = format!("{:#x}", root),
+                signed_weight = %signed_weight,
+                "Found signed checkpoint"
+            );
+
+            if *signed_weight >= threshold_weight {
+                signed_checkpoints.sort_by_key(|(validator_index, _)| *validator_index);
+                let mut signed_checkpoints = std::mem::take(signed_checkpoints)
+                    .into_iter()
+                    .map(|(_, signed_checkpoint)| signed_checkpoint)
+                    .collect::<Vec<_>>();
+                let checkpoint: MultisigSignedCheckpoint = (&mut signed_checkpoints).try_into()?;
+                debug!(checkpoint=?checkpoint, "Fetched weighted multisig checkpoint");
+                return Ok(Some(checkpoint));
+            }
+        }
+        debug!("No weighted quorum checkpoint found for message");
+        Ok(None)
+    }
+
+    /// Fetches the checkpoint signed by `validator` at `index`, if the
+    /// validator signed it.
+    async fn fetch_signed_checkpoint(
+        &self,
+        validator: &H256,
+        index: u32,
+    ) -> Result<Option<SignedCheckpointWithMessageId>> {
+        let addr = H160::from(*validator);
+        let Some(checkpoint_syncer) = self.checkpoint_syncers.get(&addr) else {
+            debug!(%validator, "Unable to find checkpoint syncer");
+            return Ok(None);
+        };
+        // Gracefully ignore an error fetching the checkpoint from a validator's
+        // checkpoint syncer, which can happen if the validator has not
+        // signed the checkpoint at `index`.
+        let Ok(Some(signed_checkpoint)) = checkpoint_syncer.fetch_checkpoint(index).await else {
+            debug!(
+                validator = format!("{:#x}", validator),
+                index = index,
+                "Unable to find signed checkpoint"
+            );
+            return Ok(None);
+        };
+
+        // If the signed checkpoint is for a different index, ignore it
+        if signed_checkpoint.value.index != index {
+            debug!(
+                validator = format!("{:#x}", validator),
+                index = index,
+                checkpoint_index = signed_checkpoint.value.index,
+                "Checkpoint index mismatch"
+            );
+            return Ok(None);
+        }
+
+        // Ensure that the signature is actually by the validator
+        let signer = signed_checkpoint.recover()?;
+        if H256::from(signer) != *validator {
+            debug!(
+                validator = format!("{:#x}", validator),
+                index = index,
+                "Checkpoint signature mismatch"
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(signed_checkpoint))
+    }
+
+    /// Fetches BLS signed checkpoints and aggregates them if there is a
+    /// quorum. The non-signer bitmap of the aggregate is relative to
+    /// `validators`, which must reflect the onchain ordering of the set.
+    /// `public_keys` are the keys registered for the validators, in the same
+    /// order. Returns Ok(None) if there is no quorum.
+    #[instrument(err, skip(self, public_keys))]
+    pub async fn fetch_bls_checkpoint(
+        &self,
+        validators: &[H256],
+        public_keys: &[BlsPublicKey],
+        threshold: usize,
+        index: u32,
+    ) -> Result<Option<BlsAggregatedCheckpoint>> {
+        ensure!(
+            validators.len() == public_keys.len(),
+            "Expected a public key for each of the {} validators, got {}",
+            validators.len(),
+            public_keys.len()
+        );
+
+        // Signed checkpoints with the position of their signer in the
+        // validator set, for a particular root
+        let mut signed_checkpoints_per_root: HashMap<
+            H256,
+            Vec<(usize, BlsSignedCheckpointWithMessageId)>,
+        > = HashMap::new();
+
+        for (validator_index, validator) in validators.iter().enumerate() {
+            let addr = H160::from(*validator);
+            let Some(checkpoint_syncer) = self.checkpoint_syncers.get(&addr) else {
                 debug!(%validator, "Unable to find checkpoint syncer");
                 continue;
+            };
+            // Gracefully ignore an error fetching the checkpoint, which can happen
+            // if the validator has not signed the checkpoint at `index` or does
+            // not sign with a BLS key.
+            let Ok(Some(signed_checkpoint)) = checkpoint_syncer.fetch_bls_checkpoint(index).await
+            else {
+                debug!(
+                    validator = format!("{:#x}", validator),
+                    index = index,
+                    "Unable to find BLS signed checkpoint"
+                );
+                continue;
+            };
+
+            if signed_checkpoint.value.index != index {
+                debug!(
+                    validator = format!("{:#x}", validator),
+                    index = index,
+                    checkpoint_index = signed_checkpoint.value.index,
+                    "BLS checkpoint index mismatch"
+                );
+                continue;
+            }
+
+            // A signature which isn't by the validator's registered key would
+            // invalidate the whole aggregate, whichever key the validator
+            // published it with.
+            let public_key = &public_keys[validator_index];
+            if !signed_checkpoint.verify_with(public_key) {
+                debug!(
+                    validator = format!("{:#x}", validator),
+                    index = index,
+                    published_public_key = ?signed_checkpoint.public_key,
+                    registered_public_key = ?public_key,
+                    "BLS checkpoint signature is not by the registered public key"
+                );
+                continue;
+            }
+
+            let root = signed_checkpoint.value.root;
+            let signed_checkpoints = signed_checkpoints_per_root.entry(root).or_default();
+            signed_checkpoints.push((validator_index, signed_checkpoint));
+
+            let signature_count = signed_checkpoints.len();
+            debug!(
+                validator = format!("{:#x}", validator),
+                index = index,
+                root = format!("{:#x}", root),
+                signature_count = signature_count,
+                "Found BLS signed checkpoint"
+            );
+
+            if signature_count >= threshold {
+                let checkpoint =
+                    BlsAggregatedCheckpoint::aggregate(validators.len(), signed_checkpoints)?;
+                debug!(checkpoint=?checkpoint, "Aggregated BLS checkpoint");
+                return Ok(Some(checkpoint));
             }
         }
-        debug!("No quorum checkpoint found for message");
+        debug!("No BLS quorum checkpoint found for message");
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use async_trait::async_trait;
+    use ethers::signers::LocalWallet;
+    use hyperlane_core::{
+        BlsSecretKey, Checkpoint, CheckpointWithMessageId, HyperlaneSigner, HyperlaneSignerExt,
+        SignedAnnouncement,
+    };
+    use hyperlane_ethereum::Signers;
+    use prometheus::Registry;
+
+    use super::*;
+
+    /// A checkpoint syncer serving fixed signed checkpoints
+    #[derive(Debug, Default)]
+    struct MockCheckpointSyncer {
+        checkpoints: HashMap<u32, SignedCheckpointWithMessageId>,
+        bls_checkpoints: HashMap<u32, BlsSignedCheckpointWithMessageId>,
+    }
+
+    #[async_trait]
+    impl CheckpointSyncer for MockCheckpointSyncer {
+        async fn latest_index(&self) -> Result<Option<u32>> {
+            Ok(self.checkpoints.keys().max().copied())
+        }
+        async fn write_latest_index(&self, _index: u32) -> Result<()> {
+            unimplemented!()
+        }
+        async fn fetch_checkpoint(
+            &self,
+            index: u32,
+        ) -> Result<Option<SignedCheckpointWithMessageId>> {
+            Ok(self.checkpoints.get(&index).cloned())
+        }
+        async fn write_checkpoint(
+            &self,
+            _signed_checkpoint: &SignedCheckpointWithMessageId,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+        async fn fetch_bls_checkpoint(
+            &self,
+            index: u32,
+        ) -> Result<Option<BlsSignedCheckpointWithMessageId>> {
+            Ok(self.bls_checkpoints.get(&index).cloned())
+        }
+        async fn write_bls_checkpoint(
+            &self,
+            _signed_checkpoint: &BlsSignedCheckpointWithMessageId,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+        async fn write_announcement(
+            &self,
+            _signed_announcement: &SignedAnnouncement,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+        fn announcement_location(&self) -> String {
+            "mock://".into()
+        }
+    }
+
+    const INDEX: u32 = 7;
+
+    fn checkpoint() -> CheckpointWithMessageId {
+        CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: H256::repeat_byte(1),
+                mailbox_domain: 1,
+                root: H256::repeat_byte(2),
+                index: INDEX,
+            },
+            message_id: H256::repeat_byte(3),
+        }
+    }
+
+    fn signer(byte: u8) -> Signers {
+        hex::encode([byte; 32])
+            .parse::<LocalWallet>()
+            .unwrap()
+            .into()
+    }
+
+    /// Builds a validator set with the given stake weights, of which the
+    /// validators at `signing` signed the checkpoint at `INDEX`.
+    async fn weighted_set(
+        weights: &[u64],
+        signing: &[usize],
+    ) -> (
+        MultisigCheckpointSyncer,
+        Vec<WeightedValidator>,
+        Vec<SignedCheckpointWithMessageId>,
+    ) {
+        let mut syncers: HashMap<H160, Arc<dyn CheckpointSyncer>> = HashMap::new();
+        let mut validators = vec![];
+        let mut signed = vec![];
+        for (i, weight) in weights.iter().enumerate() {
+            let signer = signer(i as u8 + 1);
+            let mut syncer = MockCheckpointSyncer::default();
+            if signing.contains(&i) {
+                let signed_checkpoint = signer.sign(checkpoint()).await.unwrap();
+                syncer.checkpoints.insert(INDEX, signed_checkpoint.clone());
+                signed.push(signed_checkpoint);
+            }
+            syncers.insert(signer.eth_address(), Arc::new(syncer));
+            validators.push(WeightedValidator {
+                address: signer.eth_address().into(),
+                weight: U256::from(*weight),
+            });
+        }
+        let metrics = CoreMetrics::new("test", 9090, Registry::new()).unwrap();
+        (
+            MultisigCheckpointSyncer::new(syncers, Arc::new(metrics), None),
+            validators,
+            signed,
+        )
+    }
+
+    #[tokio::test]
+    async fn selects_fewest_signatures_reaching_threshold_weight() {
+        let (syncer, validators, signed) = weighted_set(&[10, 50, 30, 20], &[0, 1, 2, 3]).await;
+
+        let checkpoint = syncer
+            .fetch_weighted_checkpoint(&validators, U256::from(60), INDEX)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(checkpoint.checkpoint, checkpoint());
+        // The two heaviest validators, in the onchain order of the set
+        assert_eq!(
+            checkpoint.signatures,
+            vec![signed[1].signature, signed[2].signature]
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_non_signers() {
+        let (syncer, validators, signed) = weighted_set(&[10, 50, 30, 20], &[0, 2, 3]).await;
+
+        let checkpoint = syncer
+            .fetch_weighted_checkpoint(&validators, U256::from(60), INDEX)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            checkpoint.signatures,
+            signed.iter().map(|s| s.signature).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn no_checkpoint_below_threshold_weight() {
+        let (syncer, validators, _) = weighted_set(&[10, 50, 30, 20], &[0, 2, 3]).await;
+
+        let checkpoint = syncer
+            .fetch_weighted_checkpoint(&validators, U256::from(61), INDEX)
+            .await
+            .unwrap();
+
+        assert!(checkpoint.is_none());
+    }
+
+    fn bls_secret_key(n: u64) -> BlsSecretKey {
+        BlsSecretKey::from_bytes(H256::from_low_u64_be(n)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn skips_bls_signatures_not_by_the_registered_key() {
+        let validators: Vec<H256> = (1..=3)
+            .map(|i| H256::from(signer(i).eth_address()))
+            .collect();
+        let registered: Vec<BlsSecretKey> = (1..=3).map(bls_secret_key).collect();
+        // The second validator publishes a checkpoint signed with, and
+        // claiming, a key other than the one registered for it.
+        let signing_keys = [
+            registered[0].clone(),
+            bls_secret_key(42),
+            registered[2].clone(),
+        ];
+
+        let mut syncers: HashMap<H160, Arc<dyn CheckpointSyncer>> = HashMap::new();
+        for (validator, key) in validators.iter().zip(&signing_keys) {
+            let mut syncer = MockCheckpointSyncer::default();
+            syncer
+                .bls_checkpoints
+                .insert(INDEX, key.sign_checkpoint(checkpoint()));
+            syncers.insert(H160::from(*validator), Arc::new(syncer));
+        }
+        let metrics = CoreMetrics::new("test", 9090, Registry::new()).unwrap();
+        let syncer = MultisigCheckpointSyncer::new(syncers, Arc::new(metrics), None);
+        let public_keys: Vec<BlsPublicKey> = registered.iter().map(|k| k.public_key()).collect();
+
+        let checkpoint = syncer
+            .fetch_bls_checkpoint(&validators, &public_keys, 2, INDEX)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(checkpoint.non_signer_bitmap, U256::from(0b010));
+        let signers = BlsPublicKey::aggregate([&public_keys[0], &public_keys[2]]);
+        assert!(checkpoint
+            .signature
+            .verify(&signers, checkpoint.checkpoint.signing_hash()));
+
+        // Without the bad signature there is no quorum of three
+        let checkpoint = syncer
+            .fetch_bls_checkpoint(&validators, &public_keys, 3, INDEX)
+            .await
+            .unwrap();
+        assert!(checkpoint.is_none());
+    }
+}
@@ -4,7 +4,9 @@ use async_trait::async_trait;
 use derive_new::new;
 use eyre::{bail, Result};
 use futures_util::TryStreamExt;
-use hyperlane_core::{SignedAnnouncement, SignedCheckpointWithMessageId};
+use hyperlane_core::{
+    BlsSignedCheckpointWithMessageId, SignedAnnouncement, SignedCheckpointWithMessageId,
+};
 use prometheus::IntGauge;
 use rusoto_core::{
     credential::{Anonymous, AwsCredentials, StaticProvider},
@@ -132,6 +134,10 @@ impl S3Storage {
         format!("checkpoint_{index}_with_id.json")
     }
 
+    fn bls_checkpoint_key(index: u32) -> String {
+        format!("bls_checkpoint_{index}_with_id.json")
+    }
+
     fn latest_index_key() -> String {
         "checkpoint_latest_index.json".to_owned()
     }
@@ -188,6 +194,30 @@ impl CheckpointSyncer for S3Storage {
         Ok(())
     }
 
+    async fn fetch_bls_checkpoint(
+        &self,
+        index: u32,
+    ) -> Result<Option<BlsSignedCheckpointWithMessageId>> {
+        self.anonymously_read_from_bucket(S3Storage::bls_checkpoint_key(index))
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    async fn write_bls_checkpoint(
+        &self,
+        signed_checkpoint: &BlsSignedCheckpointWithMessageId,
+    ) -> Result<()> {
+        let serialized_checkpoint = serde_json::to_string_pretty(signed_checkpoint)?;
+        self.write_to_bucket(
+            S3Storage::bls_checkpoint_key(signed_checkpoint.value.index),
+            &serialized_checkpoint,
+        )
+        .await?;
+        Ok(())
+    }
+
     async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
         let serialized_announcement = serde_json::to_string_pretty(signed_announcement)?;
         self.write_to_bucket(S3Storage::announcement_key(), &serialized_announcement)
@@ -1,10 +1,13 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use derive_new::new;
 
-use crate::accumulator::{
-    hash_concat,
-    merkle::{merkle_root_from_branch, Proof},
-    H256, TREE_DEPTH, ZERO_HASHES,
+use crate::{
+    accumulator::{
+        hash_concat,
+        merkle::{merkle_root_from_branch, Proof},
+        H256, TREE_DEPTH, ZERO_HASHES,
+    },
+    Decode, Encode, HyperlaneProtocolError,
 };
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, new, PartialEq, Eq)]
@@ -89,6 +92,39 @@ impl IncrementalMerkle {
     }
 }
 
+impl Encode for IncrementalMerkle {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: std::io::Write,
+    {
+        for hash in self.branch.iter() {
+            writer.write_all(hash.as_bytes())?;
+        }
+        writer.write_all(&(self.count as u64).to_be_bytes())?;
+        Ok(TREE_DEPTH * 32 + 8)
+    }
+}
+
+impl Decode for IncrementalMerkle {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        let mut branch = [H256::default(); TREE_DEPTH];
+        let mut count_bytes = [0u8; 8];
+
+        for item in &mut branch {
+            reader.read_exact(item.as_bytes_mut())?;
+        }
+        reader.read_exact(&mut count_bytes)?;
+
+        let count = u64::from_be_bytes(count_bytes) as usize;
+
+        Ok(Self { branch, count })
+    }
+}
+
 #[cfg(all(test, feature = "ethers"))]
 mod test {
     use ethers_core::utils::hash_message;
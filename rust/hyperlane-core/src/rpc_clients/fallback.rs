@@ -15,7 +15,9 @@ use tracing::{info, trace, warn_span};
 
 use crate::ChainCommunicationError;
 
-use super::RpcClientError;
+use super::{
+    HealthConf, ProviderHealth, ProviderHealthObserver, ProviderHealthSnapshot, RpcClientError,
+};
 
 /// Read the current block number from a chain.
 #[async_trait]
@@ -24,38 +26,87 @@ pub trait BlockNumberGetter: Send + Sync + Debug {
     async fn get_block_number(&self) -> Result<u64, ChainCommunicationError>;
 }
 
-const MAX_BLOCK_TIME: Duration = Duration::from_secs(2 * 60);
+/// Substrings of error messages, in lower case, which indicate a rate limit.
+/// These are the provider's fault even if they also match
+/// [`REQUEST_ERROR_MESSAGES`].
+const RATE_LIMIT_ERROR_MESSAGES: &[&str] = &["429", "rate limit", "too many requests"];
+
+/// Substrings of error messages, in lower case, which indicate the request
+/// was invalid or reverted rather than that the provider failed. Covers the
+/// gRPC status codes, which are formatted as e.g. `InvalidArgument`.
+const REQUEST_ERROR_MESSAGES: &[&str] = &[
+    "revert",
+    "invalidargument",
+    "invalid argument",
+    "failedprecondition",
+    "failed precondition",
+    "outofrange",
+    "out of gas",
+    "insufficient funds",
+    "insufficient balance",
+    "nonce",
+    "sequence mismatch",
+    "underpriced",
+    "already known",
+];
+
+/// Whether an error returned by an inner provider is the provider's fault,
+/// e.g. a timeout or connection error, and should count against its health.
+/// Invalid requests, reverts and errors decoding the request are not, since
+/// every provider would return them.
+pub fn is_provider_error(err: &ChainCommunicationError) -> bool {
+    use ChainCommunicationError::*;
+    match err {
+        HyperlaneProtocolError(_)
+        | ContractError(_)
+        | SignerUnavailable
+        | StrOrIntParseError(_)
+        | Utf8(_)
+        | HexParseError(_)
+        | UintParseError(_)
+        | FromDecStrError(_)
+        | ParseIntError(_)
+        | HashParsingError(_)
+        | InvalidRequest { .. }
+        | ParseError { .. }
+        | InsufficientFunds { .. }
+        | PrimitiveTypeError(_)
+        | ParseBigDecimalError(_)
+        | HyperlaneSignerError(_) => false,
+        Other(_) | CustomError(_) | EyreError(_) => {
+            let msg = err.to_string().to_ascii_lowercase().replace('_', " ");
+            RATE_LIMIT_ERROR_MESSAGES.iter().any(|m| msg.contains(m))
+                || !REQUEST_ERROR_MESSAGES.iter().any(|m| msg.contains(m))
+        }
+        _ => true,
+    }
+}
 
 /// Information about a provider in `PrioritizedProviders`
-
-#[derive(Clone, Copy, new)]
+#[derive(Clone, Debug, new)]
 pub struct PrioritizedProviderInner {
     /// Index into the `providers` field of `PrioritizedProviders`
     pub index: usize,
-    /// Tuple of the block number and the time when it was queried
-    #[new(value = "(0, Instant::now())")]
-    last_block_height: (u64, Instant),
+    /// Rolling health statistics of the provider
+    #[new(default)]
+    pub health: ProviderHealth,
 }
 
-impl PrioritizedProviderInner {
-    fn from_block_height(index: usize, block_height: u64) -> Self {
-        Self {
-            index,
-            last_block_height: (block_height, Instant::now()),
-        }
-    }
-}
 /// Sub-providers and priority information
 pub struct PrioritizedProviders<T> {
     /// Unsorted list of providers this provider calls
     pub providers: Vec<T>,
-    /// Sorted list of providers this provider calls, in descending order or reliability
+    /// Sorted list of providers this provider calls, in descending order of health score
     pub priorities: RwLock<Vec<PrioritizedProviderInner>>,
 }
 
 /// A provider that bundles multiple providers and attempts to call the first,
 /// then the second, and so on until a response is received.
 ///
+/// Providers are ordered by a health score combining their error rate,
+/// latency and block height staleness. Providers that fail repeatedly have
+/// their circuit opened and are skipped until a cooldown has elapsed.
+///
 /// Although no trait bounds are used in the struct definition, the intended purpose of `B`
 /// is to be bound by `BlockNumberGetter` and have `T` be convertible to `B`. That is,
 /// inner providers should be able to get the current block number, or be convertible into
@@ -63,7 +114,8 @@ pub struct PrioritizedProviders<T> {
 pub struct FallbackProvider<T, B> {
     /// The sub-providers called by this provider
     pub inner: Arc<PrioritizedProviders<T>>,
-    max_block_time: Duration,
+    health_conf: HealthConf,
+    health_observer: Option<Arc<dyn ProviderHealthObserver>>,
     _phantom: PhantomData<B>,
 }
 
@@ -71,7 +123,8 @@ impl<T, B> Clone for FallbackProvider<T, B> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
-            max_block_time: self.max_block_time,
+            health_conf: self.health_conf,
+            health_observer: self.health_observer.clone(),
             _phantom: PhantomData,
         }
     }
@@ -113,19 +166,26 @@ where
         Self::builder().add_providers(providers).build()
     }
 
-    async fn deprioritize_provider(&self, priority: PrioritizedProviderInner) {
-        // De-prioritize the current provider by moving it to the end of the queue
-        let mut priorities = self.inner.priorities.write().await;
-        priorities.retain(|&p| p.index != priority.index);
-        priorities.push(priority);
-    }
-
-    async fn update_last_seen_block(&self, provider_index: usize, current_block_height: u64) {
-        let mut priorities = self.inner.priorities.write().await;
-        // Get provider position in the up-to-date priorities vec
-        if let Some(position) = priorities.iter().position(|p| p.index == provider_index) {
-            priorities[position] =
-                PrioritizedProviderInner::from_block_height(provider_index, current_block_height);
+    /// Apply `update` to the health of a provider, then re-sort the
+    /// priorities by score and notify the observer.
+    async fn update_health(&self, provider_index: usize, update: impl FnOnce(&mut ProviderHealth)) {
+        let snapshot = {
+            let mut priorities = self.inner.priorities.write().await;
+            let Some(priority) = priorities.iter_mut().find(|p| p.index == provider_index) else {
+                return;
+            };
+            update(&mut priority.health);
+            let snapshot = priority.health.snapshot(&self.health_conf);
+            // Stable sort, so providers with equal scores keep their relative order
+            priorities.sort_by(|a, b| {
+                b.health
+                    .score(&self.health_conf)
+                    .total_cmp(&a.health.score(&self.health_conf))
+            });
+            snapshot
+        };
+        if let Some(observer) = &self.health_observer {
+            observer.observe(provider_index, &snapshot);
         }
     }
 
@@ -135,12 +195,56 @@ where
         (*read_lock).clone()
     }
 
-    /// De-prioritize a provider that has either timed out or returned a bad response
+    /// Providers to attempt, in order of priority. Providers with an open circuit,
+    /// or a half-open one whose trial request is in flight, are skipped, unless
+    /// no provider is callable, in which case all providers are attempted
+    /// rather than failing outright.
+    pub async fn take_callable_priorities_snapshot(&self) -> Vec<PrioritizedProviderInner> {
+        let now = Instant::now();
+        let mut priorities = self.inner.priorities.write().await;
+        let callable = priorities
+            .iter_mut()
+            .filter_map(|p| {
+                p.health
+                    .is_callable(&self.health_conf, now)
+                    .then(|| p.clone())
+            })
+            .collect_vec();
+        if callable.is_empty() {
+            (*priorities).clone()
+        } else {
+            callable
+        }
+    }
+
+    /// Record how long a request to a provider took and whether it failed
+    /// because of the provider.
+    pub async fn handle_provider_response(
+        &self,
+        priority: &PrioritizedProviderInner,
+        latency: Duration,
+        success: bool,
+    ) {
+        if !success {
+            trace!(
+                provider_index=%priority.index,
+                provider=?self.inner.providers[priority.index],
+                "Recording failed request of an inner provider in FallbackProvider",
+            );
+        }
+        let conf = self.health_conf;
+        self.update_health(priority.index, |health| {
+            health.record(latency, success, &conf, Instant::now())
+        })
+        .await;
+    }
+
+    /// De-prioritize a provider whose block height has not increased within `max_block_time`
     pub async fn handle_stalled_provider(&self, priority: &PrioritizedProviderInner, provider: &T) {
         let now = Instant::now();
-        if now
-            .duration_since(priority.last_block_height.1)
-            .le(&self.max_block_time)
+        if !priority
+            .health
+            .block_height_check_due(&self.health_conf, now)
         {
             // Do nothing, it's too early to tell if the provider has stalled
             return;
@@ -150,19 +254,30 @@ where
         let current_block_height = block_getter
             .get_block_number()
             .await
-            .unwrap_or(priority.last_block_height.0);
-        if current_block_height <= priority.last_block_height.0 {
+            .unwrap_or(priority.health.last_block_height());
+        if current_block_height <= priority.health.last_block_height() {
             // The `max_block_time` elapsed but the block number returned by the provider has not increased
-            self.deprioritize_provider(*priority).await;
             info!(
                 provider_index=%priority.index,
                 provider=?self.inner.providers[priority.index],
-                "Deprioritizing an inner provider in FallbackProvider",
+                "Deprioritizing a stalled inner provider in FallbackProvider",
             );
-        } else {
-            self.update_last_seen_block(priority.index, current_block_height)
-                .await;
         }
+        self.update_health(priority.index, |health| {
+            health.record_block_height(current_block_height, Instant::now())
+        })
+        .await;
+    }
+
+    /// The current health of every inner provider, indexed by the order the
+    /// providers were added to the builder.
+    pub async fn health_snapshots(&self) -> Vec<ProviderHealthSnapshot> {
+        let priorities = self.inner.priorities.read().await;
+        priorities
+            .iter()
+            .sorted_by_key(|p| p.index)
+            .map(|p| p.health.snapshot(&self.health_conf))
+            .collect()
     }
 
     /// Call the first provider, then the second, and so on (in order of priority) until a response is received.
@@ -177,10 +292,14 @@ where
             if !errors.is_empty() {
                 tokio::time::sleep(Duration::from_millis(100)).await;
             }
-            let priorities_snapshot = self.take_priorities_snapshot().await;
+            let priorities_snapshot = self.take_callable_priorities_snapshot().await;
             for (idx, priority) in priorities_snapshot.iter().enumerate() {
                 let provider = &self.inner.providers[priority.index];
+                let start = Instant::now();
                 let resp = f(provider.clone()).await;
+                let provider_failed = matches!(&resp, Err(e) if is_provider_error(e));
+                self.handle_provider_response(priority, start.elapsed(), !provider_failed)
+                    .await;
                 self.handle_stalled_provider(priority, provider).await;
                 let _span =
                     warn_span!("FallbackProvider::call", fallback_count=%idx, provider_index=%priority.index, ?provider).entered();
@@ -205,7 +324,8 @@ where
 #[derive(Debug, Clone)]
 pub struct FallbackProviderBuilder<T, B> {
     providers: Vec<T>,
-    max_block_time: Duration,
+    health_conf: HealthConf,
+    health_observer: Option<Arc<dyn ProviderHealthObserver>>,
     _phantom: PhantomData<B>,
 }
 
@@ -213,7 +333,8 @@ impl<T, B> Default for FallbackProviderBuilder<T, B> {
     fn default() -> Self {
         Self {
             providers: Vec::new(),
-            max_block_time: MAX_BLOCK_TIME,
+            health_conf: HealthConf::default(),
+            health_observer: None,
             _phantom: PhantomData,
         }
     }
@@ -236,7 +357,19 @@ impl<T, B> FallbackProviderBuilder<T, B> {
     /// Only used for testing purposes.
     /// TODO: Move tests into this crate to control the visibility with conditional compilation.
     pub fn with_max_block_time(mut self, max_block_time: Duration) -> Self {
-        self.max_block_time = max_block_time;
+        self.health_conf.max_block_time = max_block_time;
+        self
+    }
+
+    /// Override how providers are scored and when their circuit opens.
+    pub fn with_health_conf(mut self, health_conf: HealthConf) -> Self {
+        self.health_conf = health_conf;
+        self
+    }
+
+    /// Notify `observer` whenever the health of an inner provider changes.
+    pub fn with_health_observer(mut self, observer: Arc<dyn ProviderHealthObserver>) -> Self {
+        self.health_observer = Some(observer);
         self
     }
 
@@ -254,7 +387,8 @@ impl<T, B> FallbackProviderBuilder<T, B> {
         };
         FallbackProvider {
             inner: Arc::new(prioritized_providers),
-            max_block_time: self.max_block_time,
+            health_conf: self.health_conf,
+            health_observer: self.health_observer,
             _phantom: PhantomData,
         }
     }
@@ -331,4 +465,76 @@ pub mod test {
                 .collect()
         }
     }
+
+    #[cfg(test)]
+    #[derive(Debug, Clone)]
+    struct BlockGetterMock;
+
+    #[cfg(test)]
+    #[async_trait]
+    impl BlockNumberGetter for BlockGetterMock {
+        async fn get_block_number(&self) -> Result<u64, ChainCommunicationError> {
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn classifies_provider_errors() {
+        assert!(is_provider_error(
+            &ChainCommunicationError::TransactionTimeout()
+        ));
+        assert!(is_provider_error(&ChainCommunicationError::from_other_str(
+            "status: Unavailable, message: \"connection refused\""
+        )));
+        assert!(is_provider_error(&ChainCommunicationError::from_other_str(
+            "status: ResourceExhausted, message: \"rate limit exceeded\""
+        )));
+
+        assert!(!is_provider_error(
+            &ChainCommunicationError::InvalidRequest {
+                msg: "bad".to_owned()
+            }
+        ));
+        assert!(!is_provider_error(
+            &ChainCommunicationError::from_other_str(
+                "status: InvalidArgument, message: \"unknown address\""
+            )
+        ));
+        assert!(!is_provider_error(&ChainCommunicationError::CustomError(
+            "execution reverted".to_owned()
+        )));
+    }
+
+    #[test]
+    fn only_provider_errors_count_against_health() {
+        // The `macros` feature of tokio isn't enabled for this crate
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let provider: FallbackProvider<BlockGetterMock, BlockGetterMock> =
+                FallbackProvider::new([BlockGetterMock]);
+
+            let reverted = provider
+                .call::<()>(|_| {
+                    Box::pin(async {
+                        Err(ChainCommunicationError::CustomError(
+                            "execution reverted".to_owned(),
+                        ))
+                    })
+                })
+                .await;
+            assert!(reverted.is_err());
+            assert_eq!(provider.health_snapshots().await[0].error_rate, 0.0);
+
+            let timed_out = provider
+                .call::<()>(|_| {
+                    Box::pin(async { Err(ChainCommunicationError::TransactionTimeout()) })
+                })
+                .await;
+            assert!(timed_out.is_err());
+            assert!(provider.health_snapshots().await[0].error_rate > 0.0);
+        });
+    }
 }
@@ -0,0 +1,384 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// The default time after which a provider whose block height hasn't
+/// increased is considered stale.
+pub const MAX_BLOCK_TIME: Duration = Duration::from_secs(2 * 60);
+
+/// Multiplier applied to the score of a provider whose block height is stale.
+const STALE_SCORE_MULTIPLIER: f64 = 0.5;
+
+/// Configuration of how the health of the inner providers of a
+/// `FallbackProvider` is scored and when their circuit breaker trips.
+#[derive(Debug, Clone, Copy)]
+pub struct HealthConf {
+    /// Number of most recent requests used to compute the error rate and the
+    /// latency percentiles.
+    pub window_size: usize,
+    /// Number of consecutive failed requests after which the circuit opens.
+    pub consecutive_failures_threshold: u32,
+    /// Error rate over a full window above which the circuit opens.
+    pub error_rate_threshold: f64,
+    /// How long the circuit stays open before a trial request is let through.
+    pub open_duration: Duration,
+    /// p90 latency up to which a provider is not penalized.
+    pub latency_tolerance: Duration,
+    /// Time after which a provider whose block height hasn't increased is
+    /// considered stale.
+    pub max_block_time: Duration,
+}
+
+impl Default for HealthConf {
+    fn default() -> Self {
+        Self {
+            window_size: 100,
+            consecutive_failures_threshold: 5,
+            error_rate_threshold: 0.5,
+            open_duration: Duration::from_secs(30),
+            latency_tolerance: Duration::from_secs(1),
+            max_block_time: MAX_BLOCK_TIME,
+        }
+    }
+}
+
+/// State of the circuit breaker of an inner provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CircuitState {
+    /// Requests flow through normally.
+    Closed,
+    /// The provider failed too often and is skipped until `open_duration`
+    /// has elapsed.
+    Open,
+    /// The `open_duration` elapsed; the next request is a trial which either
+    /// closes or re-opens the circuit. Other requests skip the provider while
+    /// the trial is in flight.
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Numeric representation used when exporting the state as a metric.
+    pub fn as_metric_value(&self) -> i64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen => 1,
+            CircuitState::Open => 2,
+        }
+    }
+}
+
+/// Rolling health statistics of a single inner provider.
+#[derive(Debug, Clone)]
+pub struct ProviderHealth {
+    /// Most recent requests as tuples of (latency, success)
+    samples: VecDeque<(Duration, bool)>,
+    consecutive_failures: u32,
+    circuit: CircuitState,
+    opened_at: Option<Instant>,
+    /// When the trial request of a half-open circuit was let through
+    trial_started_at: Option<Instant>,
+    /// Tuple of the block number and the time when it was queried
+    last_block_height: (u64, Instant),
+    stale: bool,
+}
+
+impl Default for ProviderHealth {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            consecutive_failures: 0,
+            circuit: CircuitState::Closed,
+            opened_at: None,
+            trial_started_at: None,
+            last_block_height: (0, Instant::now()),
+            stale: false,
+        }
+    }
+}
+
+/// A point-in-time view of a provider's health, e.g. for metrics.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderHealthSnapshot {
+    /// Score in `[0, 1]`, higher is healthier
+    pub score: f64,
+    /// Ratio of failed requests in the window
+    pub error_rate: f64,
+    /// Median request latency in the window
+    pub latency_p50: Duration,
+    /// 90th percentile request latency in the window
+    pub latency_p90: Duration,
+    /// State of the circuit breaker
+    pub circuit: CircuitState,
+    /// Whether the block height returned by the provider has stopped increasing
+    pub stale: bool,
+}
+
+impl ProviderHealth {
+    /// Record the outcome of a request. `success` should be false only for
+    /// errors that are the provider's fault (e.g. timeouts or connection
+    /// errors), not for requests that are invalid or revert.
+    pub fn record(&mut self, latency: Duration, success: bool, conf: &HealthConf, now: Instant) {
+        self.samples.push_back((latency, success));
+        while self.samples.len() > conf.window_size.max(1) {
+            self.samples.pop_front();
+        }
+
+        if success {
+            self.consecutive_failures = 0;
+            if self.circuit == CircuitState::HalfOpen {
+                self.close();
+            }
+            return;
+        }
+
+        self.consecutive_failures += 1;
+        match self.circuit {
+            // The trial request failed, so wait for another `open_duration`
+            CircuitState::HalfOpen => self.open(now),
+            CircuitState::Closed => {
+                let window_full = self.samples.len() >= conf.window_size;
+                if self.consecutive_failures >= conf.consecutive_failures_threshold
+                    || (window_full && self.error_rate() > conf.error_rate_threshold)
+                {
+                    self.open(now);
+                }
+            }
+            CircuitState::Open => {}
+        }
+    }
+
+    /// Record the latest block height returned by the provider. The provider
+    /// becomes stale if its block height didn't increase within
+    /// `max_block_time`.
+    pub fn record_block_height(&mut self, block_height: u64, now: Instant) {
+        if block_height > self.last_block_height.0 {
+            self.last_block_height = (block_height, now);
+            self.stale = false;
+        } else {
+            self.stale = true;
+        }
+    }
+
+    /// Whether enough time has passed since the block height was last
+    /// recorded to check whether the provider has stalled.
+    pub fn block_height_check_due(&self, conf: &HealthConf, now: Instant) -> bool {
+        now.duration_since(self.last_block_height.1) > conf.max_block_time
+    }
+
+    /// The last recorded block height
+    pub fn last_block_height(&self) -> u64 {
+        self.last_block_height.0
+    }
+
+    /// Whether a request may be sent to this provider. Transitions an open
+    /// circuit to half-open once `open_duration` has elapsed, and lets a
+    /// single trial request through a half-open circuit. If the trial isn't
+    /// recorded within `open_duration`, e.g. because the request went to
+    /// another provider instead, another one is let through.
+    pub fn is_callable(&mut self, conf: &HealthConf, now: Instant) -> bool {
+        let elapsed = |since: Option<Instant>| {
+            since
+                .map(|at| now.duration_since(at) >= conf.open_duration)
+                .unwrap_or(true)
+        };
+        let callable = match self.circuit {
+            CircuitState::Closed => return true,
+            CircuitState::HalfOpen => elapsed(self.trial_started_at),
+            CircuitState::Open => elapsed(self.opened_at),
+        };
+        if callable {
+            self.circuit = CircuitState::HalfOpen;
+            self.trial_started_at = Some(now);
+        }
+        callable
+    }
+
+    /// The state of the circuit breaker
+    pub fn circuit(&self) -> CircuitState {
+        self.circuit
+    }
+
+    /// Ratio of failed requests in the window, `0` if there were no requests.
+    pub fn error_rate(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.;
+        }
+        let failures = self.samples.iter().filter(|(_, success)| !success).count();
+        failures as f64 / self.samples.len() as f64
+    }
+
+    /// Latency at the given percentile (in `[0, 1]`) of the window, zero if
+    /// there were no requests.
+    pub fn latency_percentile(&self, percentile: f64) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut latencies: Vec<_> = self.samples.iter().map(|(latency, _)| *latency).collect();
+        latencies.sort_unstable();
+        let rank = (percentile.clamp(0., 1.) * (latencies.len() - 1) as f64).round() as usize;
+        latencies[rank]
+    }
+
+    /// Score in `[0, 1]`, higher is healthier. Combines the success rate, a
+    /// penalty for p90 latencies above `latency_tolerance` and a penalty for
+    /// stale block heights. Providers with an open circuit score zero.
+    pub fn score(&self, conf: &HealthConf) -> f64 {
+        if self.circuit == CircuitState::Open {
+            return 0.;
+        }
+        let success_rate = 1. - self.error_rate();
+        let p90 = self.latency_percentile(0.9);
+        let latency_factor = if p90 <= conf.latency_tolerance {
+            1.
+        } else {
+            conf.latency_tolerance.as_secs_f64() / p90.as_secs_f64()
+        };
+        let staleness_factor = if self.stale {
+            STALE_SCORE_MULTIPLIER
+        } else {
+            1.
+        };
+        success_rate * latency_factor * staleness_factor
+    }
+
+    /// A point-in-time view of this provider's health
+    pub fn snapshot(&self, conf: &HealthConf) -> ProviderHealthSnapshot {
+        ProviderHealthSnapshot {
+            score: self.score(conf),
+            error_rate: self.error_rate(),
+            latency_p50: self.latency_percentile(0.5),
+            latency_p90: self.latency_percentile(0.9),
+            circuit: self.circuit,
+            stale: self.stale,
+        }
+    }
+
+    fn open(&mut self, now: Instant) {
+        self.circuit = CircuitState::Open;
+        self.opened_at = Some(now);
+        self.trial_started_at = None;
+    }
+
+    fn close(&mut self) {
+        self.circuit = CircuitState::Closed;
+        self.opened_at = None;
+        self.trial_started_at = None;
+        self.consecutive_failures = 0;
+    }
+}
+
+/// Receives the health of the inner providers of a `FallbackProvider`
+/// whenever it changes, e.g. to export it as metrics.
+pub trait ProviderHealthObserver: Send + Sync + Debug {
+    /// `provider_index` is the position of the provider in the order it was
+    /// added to the `FallbackProviderBuilder`.
+    fn observe(&self, provider_index: usize, health: &ProviderHealthSnapshot);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn conf() -> HealthConf {
+        HealthConf {
+            window_size: 4,
+            consecutive_failures_threshold: 2,
+            error_rate_threshold: 0.5,
+            open_duration: Duration::from_secs(10),
+            latency_tolerance: Duration::from_millis(100),
+            max_block_time: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures() {
+        let conf = conf();
+        let now = Instant::now();
+        let mut health = ProviderHealth::default();
+        health.record(Duration::ZERO, false, &conf, now);
+        assert_eq!(health.circuit(), CircuitState::Closed);
+        health.record(Duration::ZERO, false, &conf, now);
+        assert_eq!(health.circuit(), CircuitState::Open);
+        assert_eq!(health.score(&conf), 0.);
+        assert!(!health.is_callable(&conf, now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn circuit_opens_on_high_error_rate() {
+        let conf = conf();
+        let now = Instant::now();
+        let mut health = ProviderHealth::default();
+        for success in [false, true, false, true, false] {
+            health.record(Duration::ZERO, success, &conf, now);
+        }
+        assert_eq!(health.circuit(), CircuitState::Open);
+    }
+
+    #[test]
+    fn half_open_trial_closes_or_reopens_circuit() {
+        let conf = conf();
+        let now = Instant::now();
+        let mut health = ProviderHealth::default();
+        health.record(Duration::ZERO, false, &conf, now);
+        health.record(Duration::ZERO, false, &conf, now);
+
+        let later = now + conf.open_duration;
+        assert!(health.is_callable(&conf, later));
+        assert_eq!(health.circuit(), CircuitState::HalfOpen);
+        health.record(Duration::ZERO, false, &conf, later);
+        assert_eq!(health.circuit(), CircuitState::Open);
+
+        let even_later = later + conf.open_duration;
+        assert!(health.is_callable(&conf, even_later));
+        health.record(Duration::ZERO, true, &conf, even_later);
+        assert_eq!(health.circuit(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn half_open_circuit_lets_a_single_trial_through() {
+        let conf = conf();
+        let now = Instant::now();
+        let mut health = ProviderHealth::default();
+        health.record(Duration::ZERO, false, &conf, now);
+        health.record(Duration::ZERO, false, &conf, now);
+
+        let later = now + conf.open_duration;
+        assert!(health.is_callable(&conf, later));
+        assert!(!health.is_callable(&conf, later));
+        assert!(!health.is_callable(&conf, later + Duration::from_secs(1)));
+        assert_eq!(health.circuit(), CircuitState::HalfOpen);
+
+        // The trial was never made, so another one is let through
+        let even_later = later + conf.open_duration;
+        assert!(health.is_callable(&conf, even_later));
+        assert!(!health.is_callable(&conf, even_later));
+        health.record(Duration::ZERO, true, &conf, even_later);
+        assert_eq!(health.circuit(), CircuitState::Closed);
+        assert!(health.is_callable(&conf, even_later));
+        assert!(health.is_callable(&conf, even_later));
+    }
+
+    #[test]
+    fn score_penalizes_latency_and_staleness() {
+        let conf = conf();
+        let now = Instant::now();
+        let mut health = ProviderHealth::default();
+        health.record(Duration::from_millis(50), true, &conf, now);
+        assert_eq!(health.score(&conf), 1.);
+
+        health.record(Duration::from_millis(200), true, &conf, now);
+        assert_eq!(health.latency_percentile(0.9), Duration::from_millis(200));
+        assert_eq!(health.score(&conf), 0.5);
+
+        health.record_block_height(0, now);
+        assert_eq!(health.score(&conf), 0.25);
+        health.record_block_height(1, now);
+        assert_eq!(health.score(&conf), 0.5);
+    }
+}
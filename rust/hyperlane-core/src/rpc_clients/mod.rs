@@ -3,12 +3,15 @@ pub use self::error::*;
 #[cfg(feature = "async")]
 pub use self::fallback::*;
 
+pub use self::health::*;
+
 #[cfg(feature = "async")]
 pub use self::retry::*;
 
 mod error;
 #[cfg(feature = "async")]
 mod fallback;
+mod health;
 
 #[cfg(feature = "async")]
 mod retry;
@@ -0,0 +1,18 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::{BlsPublicKey, ChainResult, HyperlaneContract, H256};
+
+/// Interface for the BlsMultisigIsm chain contract, which verifies an
+/// aggregated BLS signature of a subset of its validators against their
+/// registered public keys. Its validators and threshold are read like those
+/// of a `MultisigIsm`. Allows abstraction over different chains
+#[async_trait]
+#[auto_impl(&, Box, Arc)]
+pub trait BlsMultisigIsm: HyperlaneContract + Send + Sync + Debug {
+    /// Returns the BLS public keys registered for the validators, in the
+    /// same order
+    async fn registered_public_keys(&self, validators: &[H256]) -> ChainResult<Vec<BlsPublicKey>>;
+}
@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use auto_impl::auto_impl;
 use eyre::Result;
 
-use crate::LogMeta;
+use crate::{ChainCommunicationError, LogMeta};
 
 /// A cursor governs event indexing for a contract.
 #[async_trait]
@@ -25,6 +25,15 @@ pub trait ContractSyncCursor<T>: Send + Sync + 'static {
     /// however may require logs to meet certain criteria (e.g. no gaps), that if
     /// not met, should result in internal state changes (e.g. rewinding) and not an Err.
     async fn update(&mut self, logs: Vec<(T, LogMeta)>, range: RangeInclusive<u32>) -> Result<()>;
+
+    /// Notifies the cursor that querying the range returned by `next_action` failed,
+    /// so it can retry the range and adapt the size of the ranges that follow.
+    fn handle_query_error(&mut self, _range: RangeInclusive<u32>, _err: &ChainCommunicationError) {}
+
+    /// The max number of blocks or sequences currently queried at once, if known.
+    fn chunk_size(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// The action that should be taken by the contract sync loop
@@ -35,6 +35,31 @@ pub trait Indexer<T: Sized>: Send + Sync + Debug {
     async fn get_finalized_block_number(&self) -> ChainResult<u32>;
 }
 
+/// A log pushed by a `LogSubscriber`.
+#[derive(Debug, Clone)]
+pub enum SubscribedLog<T> {
+    /// The log was emitted in a new block.
+    Emitted(T, LogMeta),
+    /// A previously emitted log was removed because its block was reorged out.
+    Removed(T, LogMeta),
+}
+
+/// Interface for pushing logs as soon as they are emitted, as opposed to
+/// `Indexer::fetch_logs` which polls for them by range.
+#[cfg(feature = "async")]
+#[async_trait]
+#[auto_impl(&, Box, Arc)]
+pub trait LogSubscriber<T>: Send + Sync + Debug {
+    /// Subscribe to new logs and send them to `sender` as they arrive.
+    /// Returns once the subscription ends, e.g. because the connection was
+    /// lost. Logs emitted while not subscribed are not replayed, so callers
+    /// are responsible for reconciling any gap using `Indexer::fetch_logs`.
+    async fn subscribe(
+        &self,
+        sender: tokio::sync::mpsc::UnboundedSender<SubscribedLog<T>>,
+    ) -> ChainResult<()>;
+}
+
 /// Interface for indexing data in sequence.
 #[async_trait]
 #[auto_impl(&, Box, Arc)]
@@ -41,6 +41,12 @@ pub enum ModuleType {
     Null,
     /// Ccip Read ISM (accepts offchain signature information)
     CcipRead,
+    /// BLS Multisig ISM (aggregated BLS signature of an operator set)
+    BlsMultisig,
+    /// Weighted Message ID Multisig ISM (signatures reaching a threshold weight)
+    WeightedMessageIdMultisig,
+    /// Optimistic ISM (pre-verified by a submodule, verified after a fraud window)
+    Optimistic,
 }
 
 /// Interface for the InterchainSecurityModule chain contract. Allows abstraction over
@@ -1,4 +1,6 @@
 pub use aggregation_ism::*;
+#[cfg(feature = "bls")]
+pub use bls_multisig_ism::*;
 pub use ccip_read_ism::*;
 pub use cursor::*;
 pub use db::*;
@@ -10,14 +12,18 @@ pub use interchain_security_module::*;
 pub use mailbox::*;
 pub use merkle_tree_hook::*;
 pub use multisig_ism::*;
+pub use optimistic_ism::*;
 pub use provider::*;
 pub use routing_ism::*;
 pub use signing::*;
 pub use validator_announce::*;
+pub use weighted_multisig_ism::*;
 
 use crate::{FixedPointNumber, H512, U256};
 
 mod aggregation_ism;
+#[cfg(feature = "bls")]
+mod bls_multisig_ism;
 mod ccip_read_ism;
 mod cursor;
 mod db;
@@ -29,10 +35,12 @@ mod interchain_security_module;
 mod mailbox;
 mod merkle_tree_hook;
 mod multisig_ism;
+mod optimistic_ism;
 mod provider;
 mod routing_ism;
 mod signing;
 mod validator_announce;
+mod weighted_multisig_ism;
 
 /// The result of a transaction
 #[derive(Debug, Clone)]
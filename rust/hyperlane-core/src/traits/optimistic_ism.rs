@@ -0,0 +1,69 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::{
+    ChainResult, HyperlaneContract, HyperlaneMessage, TxCostEstimate, TxOutcome, H256, U256,
+};
+
+/// A message pre-verified by an optimistic ISM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreVerification {
+    /// Id of the pre-verified message
+    pub message_id: H256,
+    /// Origin domain of the pre-verified message
+    pub origin: u32,
+    /// The submodule which pre-verified the message
+    pub submodule: H256,
+    /// The unix timestamp at which the message was pre-verified
+    pub pre_verified_at: u64,
+}
+
+/// Interface for the OptimisticIsm chain contract. Messages are pre-verified
+/// by a submodule and only verified once a fraud window has passed without
+/// a watcher flagging the submodule as fraudulent.
+/// Allows abstraction over different chains
+#[async_trait]
+#[auto_impl(&, Box, Arc)]
+pub trait OptimisticIsm: HyperlaneContract + Send + Sync + Debug {
+    /// Returns the ISM which pre-verifies the message
+    async fn submodule(&self, message: &HyperlaneMessage) -> ChainResult<H256>;
+
+    /// Returns the length of the fraud window in seconds
+    async fn fraud_window(&self) -> ChainResult<u64>;
+
+    /// Returns the unix timestamp at which the message was pre-verified, if
+    /// it was
+    async fn pre_verified_at(&self, message: &HyperlaneMessage) -> ChainResult<Option<u64>>;
+
+    /// Pre-verify a message with the metadata for its submodule
+    async fn pre_verify(
+        &self,
+        metadata: &[u8],
+        message: &HyperlaneMessage,
+        tx_gas_limit: Option<U256>,
+    ) -> ChainResult<TxOutcome>;
+
+    /// Estimate transaction costs to pre-verify a message
+    async fn pre_verify_estimate_costs(
+        &self,
+        metadata: &[u8],
+        message: &HyperlaneMessage,
+    ) -> ChainResult<TxCostEstimate>;
+
+    /// Returns whether the submodule was flagged as fraudulent
+    async fn is_fraudulent(&self, submodule: H256) -> ChainResult<bool>;
+
+    /// Flag a submodule as fraudulent, which stops the messages it
+    /// pre-verified from being verified
+    async fn mark_fraudulent(&self, submodule: H256) -> ChainResult<TxOutcome>;
+
+    /// Returns the pre-verifications from `from_block`, or from the latest
+    /// block if not set, up to the latest block, and the block to continue
+    /// from.
+    async fn pre_verifications(
+        &self,
+        from_block: Option<u64>,
+    ) -> ChainResult<(Vec<PreVerification>, u64)>;
+}
@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use auto_impl::auto_impl;
 use thiserror::Error;
 
-use crate::{BlockInfo, ChainInfo, ChainResult, HyperlaneChain, TxnInfo, H256, U256};
+use crate::{BlockInfo, ChainInfo, ChainResult, HyperlaneChain, TxnInfo, H256, H512, U256};
 
 /// Interface for a provider. Allows abstraction over different provider types
 /// for different chains.
@@ -19,8 +19,12 @@ pub trait HyperlaneProvider: HyperlaneChain + Send + Sync + Debug {
     /// Get block info for a given block hash
     async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo>;
 
-    /// Get txn info for a given txn hash
-    async fn get_txn_by_hash(&self, hash: &H256) -> ChainResult<TxnInfo>;
+    /// Get block info for the block at a given height
+    async fn get_block_by_height(&self, height: u64) -> ChainResult<BlockInfo>;
+
+    /// Get txn info for a given txn hash. Chains with 256-bit txn hashes use
+    /// the lower 32 bytes.
+    async fn get_txn_by_hash(&self, hash: &H512) -> ChainResult<TxnInfo>;
 
     /// Returns whether a contract exists at the provided address
     async fn is_contract(&self, address: &H256) -> ChainResult<bool>;
@@ -30,6 +34,10 @@ pub trait HyperlaneProvider: HyperlaneChain + Send + Sync + Debug {
 
     /// Fetch metrics related to this chain
     async fn get_chain_metrics(&self) -> ChainResult<Option<ChainInfo>>;
+
+    /// Fetch the chain id reported by the provider, if the chain has a
+    /// numeric chain id.
+    async fn get_chain_id(&self) -> ChainResult<Option<U256>>;
 }
 
 /// Errors when querying for provider information.
@@ -44,4 +52,10 @@ pub enum HyperlaneProviderError {
     /// Could not find a transaction, block, or other object
     #[error("Could not find object from provider with hash {0:?}")]
     CouldNotFindObjectByHash(H256),
+    /// Could not find a block at the given height
+    #[error("Could not find block from provider at height {0}")]
+    CouldNotFindBlockByHeight(u64),
+    /// Could not find a transaction with the given hash
+    #[error("Could not find transaction from provider with hash {0:?}")]
+    CouldNotFindTransactionByHash(H512),
 }
@@ -0,0 +1,29 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use auto_impl::auto_impl;
+
+use crate::{ChainResult, HyperlaneContract, HyperlaneMessage, H256, U256};
+
+/// A validator of a weighted multisig ISM, e.g. an operator with its stake
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeightedValidator {
+    /// The validator's signing address
+    pub address: H256,
+    /// The validator's weight
+    pub weight: U256,
+}
+
+/// Interface for the WeightedMultisigIsm chain contract, which requires the
+/// signers of a checkpoint to reach a total weight instead of a count.
+/// Allows abstraction over different chains
+#[async_trait]
+#[auto_impl(&, Box, Arc)]
+pub trait WeightedMultisigIsm: HyperlaneContract + Send + Sync + Debug {
+    /// Returns the weighted validators and the total weight of signatures
+    /// needed to verify message
+    async fn validators_and_threshold_weight(
+        &self,
+        message: &HyperlaneMessage,
+    ) -> ChainResult<(Vec<WeightedValidator>, U256)>;
+}
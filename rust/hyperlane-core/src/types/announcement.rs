@@ -50,3 +50,13 @@ impl Signable for Announcement {
 
 /// An announcement that has been signed.
 pub type SignedAnnouncement = SignedType<Announcement>;
+
+/// A storage location announced on the ValidatorAnnounce contract, as emitted
+/// when an announcement is made.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ValidatorAnnouncement {
+    /// The validator address
+    pub validator: H256,
+    /// The location of signed checkpoints
+    pub storage_location: String,
+}
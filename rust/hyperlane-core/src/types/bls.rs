@@ -0,0 +1,453 @@
+use std::fmt::{Debug, Formatter};
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{CheckpointWithMessageId, Signable, H256, U256};
+
+/// Error types for BLS keys, signatures and aggregation
+#[derive(Debug, thiserror::Error)]
+pub enum BlsError {
+    /// The encoding has the wrong number of bytes
+    #[error("Expected {expected} bytes, got {actual}")]
+    InvalidLength {
+        /// Expected number of bytes
+        expected: usize,
+        /// Actual number of bytes
+        actual: usize,
+    },
+    /// A coordinate is not a canonical field element, or the point is not on
+    /// the curve
+    #[error("Invalid curve point")]
+    InvalidPoint,
+    /// The secret key reduces to zero
+    #[error("Invalid BLS secret key")]
+    InvalidSecretKey,
+    /// The signed checkpoints are over inconsistent checkpoints
+    #[error("BLS signed checkpoints are for inconsistent checkpoints")]
+    InconsistentCheckpoints,
+    /// There are no signed checkpoints to aggregate
+    #[error("No BLS signed checkpoints to aggregate")]
+    EmptySignatures,
+    /// A signer's position in the validator set does not fit the bitmap
+    #[error("Validator index {0} does not fit the non-signer bitmap")]
+    ValidatorIndexOutOfRange(usize),
+}
+
+/// A BLS secret key, which is a scalar of the BN254 curve.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BlsSecretKey(Fr);
+
+/// A BLS public key, which is a point on the G2 subgroup of the BN254 curve.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BlsPublicKey(G2Affine);
+
+/// A BLS signature, which is a point on the G1 subgroup of the BN254 curve.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BlsSignature(G1Affine);
+
+impl BlsSecretKey {
+    /// Interpret big-endian bytes as a secret key, reduced modulo the group
+    /// order.
+    pub fn from_bytes(bytes: H256) -> Result<Self, BlsError> {
+        let scalar = Fr::from_be_bytes_mod_order(bytes.as_bytes());
+        if scalar.is_zero() {
+            return Err(BlsError::InvalidSecretKey);
+        }
+        Ok(Self(scalar))
+    }
+
+    /// The public key of this secret key.
+    pub fn public_key(&self) -> BlsPublicKey {
+        BlsPublicKey((G2Affine::generator() * self.0).into_affine())
+    }
+
+    /// Sign a 32 byte hash.
+    pub fn sign(&self, hash: H256) -> BlsSignature {
+        BlsSignature((hash_to_g1(hash) * self.0).into_affine())
+    }
+
+    /// Sign the signing hash of a (checkpoint, messageId) tuple.
+    pub fn sign_checkpoint(
+        &self,
+        checkpoint: CheckpointWithMessageId,
+    ) -> BlsSignedCheckpointWithMessageId {
+        BlsSignedCheckpointWithMessageId {
+            value: checkpoint,
+            public_key: self.public_key(),
+            signature: self.sign(checkpoint.signing_hash()),
+        }
+    }
+}
+
+impl Debug for BlsSecretKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // never print the key itself
+        write!(f, "BlsSecretKey({:?})", self.public_key())
+    }
+}
+
+impl BlsPublicKey {
+    /// Encoded length, in the order the EVM pairing precompile expects:
+    /// `x.c1 || x.c0 || y.c1 || y.c0`
+    pub const LENGTH: usize = 128;
+
+    /// Sum public keys, to verify an aggregate signature of the same signers.
+    pub fn aggregate<'a>(keys: impl IntoIterator<Item = &'a BlsPublicKey>) -> BlsPublicKey {
+        let sum = keys
+            .into_iter()
+            .fold(G2Projective::zero(), |sum, key| sum + key.0);
+        BlsPublicKey(sum.into_affine())
+    }
+
+    /// Encode the public key.
+    pub fn to_bytes(&self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0u8; Self::LENGTH];
+        // the identity, e.g. an aggregate of no keys, is encoded as zeros like
+        // the EVM precompiles do
+        if let Some((x, y)) = self.0.xy() {
+            bytes[..32].copy_from_slice(&fq_to_bytes(&x.c1));
+            bytes[32..64].copy_from_slice(&fq_to_bytes(&x.c0));
+            bytes[64..96].copy_from_slice(&fq_to_bytes(&y.c1));
+            bytes[96..].copy_from_slice(&fq_to_bytes(&y.c0));
+        }
+        bytes
+    }
+
+    /// Decode a public key, checking that it is a valid point.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BlsError> {
+        check_length(bytes, Self::LENGTH)?;
+        let x = Fq2::new(fq_from_bytes(&bytes[32..64])?, fq_from_bytes(&bytes[..32])?);
+        let y = Fq2::new(fq_from_bytes(&bytes[96..])?, fq_from_bytes(&bytes[64..96])?);
+        if x.is_zero() && y.is_zero() {
+            return Ok(Self(G2Affine::identity()));
+        }
+        let point = G2Affine::new_unchecked(x, y);
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(BlsError::InvalidPoint);
+        }
+        Ok(Self(point))
+    }
+}
+
+impl BlsSignature {
+    /// Encoded length: `x || y`
+    pub const LENGTH: usize = 64;
+
+    /// Sum signatures over the same hash into a single signature.
+    pub fn aggregate<'a>(signatures: impl IntoIterator<Item = &'a BlsSignature>) -> BlsSignature {
+        let sum = signatures
+            .into_iter()
+            .fold(G1Projective::zero(), |sum, signature| sum + signature.0);
+        BlsSignature(sum.into_affine())
+    }
+
+    /// Check the signature over `hash` against a (possibly aggregated) public
+    /// key.
+    pub fn verify(&self, public_key: &BlsPublicKey, hash: H256) -> bool {
+        Bn254::pairing(self.0, G2Affine::generator())
+            == Bn254::pairing(hash_to_g1(hash), public_key.0)
+    }
+
+    /// Encode the signature.
+    pub fn to_bytes(&self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0u8; Self::LENGTH];
+        // the identity is encoded as (0, 0), like the EVM precompiles do
+        if let Some((x, y)) = self.0.xy() {
+            bytes[..32].copy_from_slice(&fq_to_bytes(x));
+            bytes[32..].copy_from_slice(&fq_to_bytes(y));
+        }
+        bytes
+    }
+
+    /// Decode a signature, checking that it is a valid point.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BlsError> {
+        check_length(bytes, Self::LENGTH)?;
+        let x = fq_from_bytes(&bytes[..32])?;
+        let y = fq_from_bytes(&bytes[32..])?;
+        if x.is_zero() && y.is_zero() {
+            return Ok(Self(G1Affine::identity()));
+        }
+        let point = G1Affine::new_unchecked(x, y);
+        // G1 has a cofactor of 1, so every point on the curve is in the subgroup
+        if !point.is_on_curve() {
+            return Err(BlsError::InvalidPoint);
+        }
+        Ok(Self(point))
+    }
+}
+
+/// Map a hash to a point on G1 by try-and-increment, the same way as the
+/// `BN254.hashToG1` solidity library used by EigenLayer.
+fn hash_to_g1(hash: H256) -> G1Affine {
+    // p = 3 mod 4, so beta^((p + 1) / 4) is a square root of beta if one exists
+    let mut exponent = Fq::MODULUS;
+    exponent.add_with_carry(&1u64.into());
+    exponent.div2();
+    exponent.div2();
+
+    let mut x = Fq::from_be_bytes_mod_order(hash.as_bytes());
+    loop {
+        let beta = x * x * x + Fq::from(3u64);
+        let y = beta.pow(exponent);
+        if y * y == beta {
+            return G1Affine::new_unchecked(x, y);
+        }
+        x += Fq::one();
+    }
+}
+
+fn check_length(bytes: &[u8], expected: usize) -> Result<(), BlsError> {
+    if bytes.len() != expected {
+        return Err(BlsError::InvalidLength {
+            expected,
+            actual: bytes.len(),
+        });
+    }
+    Ok(())
+}
+
+fn fq_to_bytes(element: &Fq) -> Vec<u8> {
+    element.into_bigint().to_bytes_be()
+}
+
+fn fq_from_bytes(bytes: &[u8]) -> Result<Fq, BlsError> {
+    let element = Fq::from_be_bytes_mod_order(bytes);
+    // reject encodings which are not reduced
+    if fq_to_bytes(&element) != bytes {
+        return Err(BlsError::InvalidPoint);
+    }
+    Ok(element)
+}
+
+macro_rules! impl_bls_point_traits {
+    ($name:ident) => {
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "{}(0x{})",
+                    stringify!($name),
+                    hex::encode(self.to_bytes())
+                )
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&format!("0x{}", hex::encode(self.to_bytes())))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                let bytes =
+                    hex::decode(s.trim_start_matches("0x")).map_err(serde::de::Error::custom)?;
+                Self::from_bytes(&bytes).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+impl_bls_point_traits!(BlsPublicKey);
+impl_bls_point_traits!(BlsSignature);
+
+/// A (checkpoint, messageId) tuple signed with a BLS key
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlsSignedCheckpointWithMessageId {
+    /// The checkpoint
+    pub value: CheckpointWithMessageId,
+    /// The public key of the signer
+    pub public_key: BlsPublicKey,
+    /// Signature over the checkpoint's signing hash
+    pub signature: BlsSignature,
+}
+
+impl BlsSignedCheckpointWithMessageId {
+    /// Check that the signature is by the included public key.
+    pub fn verify(&self) -> bool {
+        self.verify_with(&self.public_key)
+    }
+
+    /// Check that the signature is by `public_key`, e.g. the key registered
+    /// for the signer rather than the included one.
+    pub fn verify_with(&self, public_key: &BlsPublicKey) -> bool {
+        self.signature.verify(public_key, self.value.signing_hash())
+    }
+}
+
+/// A checkpoint with the aggregated BLS signature of a subset of a validator
+/// set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlsAggregatedCheckpoint {
+    /// The checkpoint
+    pub checkpoint: CheckpointWithMessageId,
+    /// Sum of the signers' signatures
+    pub signature: BlsSignature,
+    /// Bit `i` is set if the validator at index `i` of the validator set did
+    /// not sign
+    pub non_signer_bitmap: U256,
+}
+
+impl BlsAggregatedCheckpoint {
+    /// Aggregate signed checkpoints, given with the index of their signer in
+    /// a validator set of `validator_count` validators.
+    pub fn aggregate(
+        validator_count: usize,
+        signed_checkpoints: &[(usize, BlsSignedCheckpointWithMessageId)],
+    ) -> Result<Self, BlsError> {
+        let Some((_, first)) = signed_checkpoints.first() else {
+            return Err(BlsError::EmptySignatures);
+        };
+        let checkpoint = first.value;
+        if !signed_checkpoints
+            .iter()
+            .all(|(_, signed)| signed.value == checkpoint)
+        {
+            return Err(BlsError::InconsistentCheckpoints);
+        }
+        if validator_count > 256 {
+            return Err(BlsError::ValidatorIndexOutOfRange(validator_count - 1));
+        }
+
+        if let Some((index, _)) = signed_checkpoints
+            .iter()
+            .find(|(index, _)| *index >= validator_count)
+        {
+            return Err(BlsError::ValidatorIndexOutOfRange(*index));
+        }
+
+        let non_signer_bitmap = (0..validator_count)
+            .filter(|index| !signed_checkpoints.iter().any(|(i, _)| i == index))
+            .fold(U256::zero(), |bitmap, index| {
+                bitmap | (U256::one() << index)
+            });
+
+        Ok(Self {
+            checkpoint,
+            signature: BlsSignature::aggregate(
+                signed_checkpoints
+                    .iter()
+                    .map(|(_, signed)| &signed.signature),
+            ),
+            non_signer_bitmap,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Checkpoint;
+
+    fn key(n: u64) -> BlsSecretKey {
+        BlsSecretKey::from_bytes(H256::from_low_u64_be(n)).unwrap()
+    }
+
+    fn checkpoint(index: u32) -> CheckpointWithMessageId {
+        CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: H256::repeat_byte(1),
+                mailbox_domain: 1,
+                root: H256::repeat_byte(2),
+                index,
+            },
+            message_id: H256::repeat_byte(3),
+        }
+    }
+
+    #[test]
+    fn hash_to_g1_is_on_curve() {
+        for n in 0..10 {
+            assert!(hash_to_g1(H256::from_low_u64_be(n)).is_on_curve());
+        }
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let signed = key(1).sign_checkpoint(checkpoint(7));
+        assert!(signed.verify());
+
+        let mut tampered = signed.clone();
+        tampered.value.checkpoint.index = 8;
+        assert!(!tampered.verify());
+
+        let mut wrong_key = signed;
+        wrong_key.public_key = key(2).public_key();
+        assert!(!wrong_key.verify());
+    }
+
+    #[test]
+    fn aggregate_signature_verifies_against_aggregate_key() {
+        let keys = [key(1), key(2), key(3)];
+        let signed: Vec<_> = [0, 2]
+            .into_iter()
+            .map(|i| (i, keys[i].sign_checkpoint(checkpoint(7))))
+            .collect();
+
+        let aggregated = BlsAggregatedCheckpoint::aggregate(keys.len(), &signed).unwrap();
+        assert_eq!(aggregated.non_signer_bitmap, U256::from(0b010u64));
+
+        let signers = BlsPublicKey::aggregate(signed.iter().map(|(_, s)| &s.public_key));
+        assert!(aggregated
+            .signature
+            .verify(&signers, checkpoint(7).signing_hash()));
+        assert!(!aggregated
+            .signature
+            .verify(&keys[0].public_key(), checkpoint(7).signing_hash()));
+    }
+
+    #[test]
+    fn aggregate_rejects_inconsistent_checkpoints() {
+        let signed = vec![
+            (0, key(1).sign_checkpoint(checkpoint(7))),
+            (1, key(2).sign_checkpoint(checkpoint(8))),
+        ];
+        assert!(matches!(
+            BlsAggregatedCheckpoint::aggregate(2, &signed),
+            Err(BlsError::InconsistentCheckpoints)
+        ));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let signed = key(5).sign_checkpoint(checkpoint(1));
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(
+            serde_json::from_str::<BlsSignedCheckpointWithMessageId>(&json).unwrap(),
+            signed
+        );
+    }
+
+    #[test]
+    fn encodes_the_identity() {
+        let identity = BlsPublicKey::aggregate([]);
+        assert_eq!(identity.to_bytes(), [0; BlsPublicKey::LENGTH]);
+        assert_eq!(
+            format!("{identity:?}"),
+            format!("BlsPublicKey(0x{})", "00".repeat(BlsPublicKey::LENGTH))
+        );
+        assert_eq!(
+            BlsPublicKey::from_bytes(&identity.to_bytes()).unwrap(),
+            identity
+        );
+
+        // Keys which cancel out sum to the identity too
+        let key = key(1).public_key();
+        let negated = BlsPublicKey(-key.0);
+        assert_eq!(BlsPublicKey::aggregate([&key, &negated]), identity);
+    }
+
+    #[test]
+    fn rejects_points_off_the_curve() {
+        let mut bytes = key(1).sign(H256::zero()).to_bytes();
+        bytes[63] ^= 1;
+        assert!(matches!(
+            BlsSignature::from_bytes(&bytes),
+            Err(BlsError::InvalidPoint)
+        ));
+    }
+}
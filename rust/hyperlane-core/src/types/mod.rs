@@ -7,6 +7,8 @@ pub use self::primitive_types::*;
 #[cfg(feature = "ethers")]
 pub use ::primitive_types as ethers_core_types;
 pub use announcement::*;
+#[cfg(feature = "bls")]
+pub use bls::*;
 pub use chain_data::*;
 pub use checkpoint::*;
 pub use log_metadata::*;
@@ -16,6 +18,8 @@ pub use message::*;
 use crate::{Decode, Encode, HyperlaneProtocolError};
 
 mod announcement;
+#[cfg(feature = "bls")]
+mod bls;
 mod chain_data;
 mod checkpoint;
 mod log_metadata;
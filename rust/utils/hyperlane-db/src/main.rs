@@ -0,0 +1,460 @@
+//! Offline tooling for inspecting and repairing the database of a Hyperlane
+//! agent. The agent using the database must be stopped while running this.
+
+#![forbid(unsafe_code)]
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use clap::{Args, Parser, Subcommand};
+use eyre::{bail, eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use hyperlane_base::db::{HyperlaneRocksDB, DB, TABLE_KEY_PREFIXES};
+use hyperlane_core::{
+    GasPaymentKey, HyperlaneDomain, HyperlaneDomainProtocol, HyperlaneDomainTechnicalStack,
+    HyperlaneDomainType, KnownHyperlaneDomain, H256,
+};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Path to the agent database
+    #[arg(long)]
+    db: PathBuf,
+    #[command(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    /// Print the messages in a nonce range as JSON lines
+    Messages(MessagesArgs),
+    /// Print the gas payment and expenditure of a message
+    Gas(GasArgs),
+    /// Print whether the message with a nonce was processed
+    Processed(NonceArgs),
+    /// Mark the message with a nonce as processed
+    MarkProcessed(NonceArgs),
+    /// Mark the message with a nonce as not processed
+    MarkUnprocessed(NonceArgs),
+    /// Reset the number of times delivery of a message was retried
+    ResetRetries(MessageIdArgs),
+    /// Delete the message with a nonce
+    DeleteMessage(NonceArgs),
+    /// Delete the merkle tree insertion at a leaf index
+    DeleteMerkleInsertion(LeafIndexArgs),
+    /// Verify that the stored merkle tree insertions are contiguous
+    VerifyMerkle(ChainArgs),
+    /// Export all entries, or those of a chain, as JSON lines
+    Export(ExportArgs),
+    /// Import entries exported as JSON lines
+    Import(ImportArgs),
+}
+
+#[derive(Args)]
+struct ChainArgs {
+    /// Name of the chain the entries belong to
+    #[arg(long)]
+    chain: String,
+}
+
+#[derive(Args)]
+struct MessagesArgs {
+    #[command(flatten)]
+    chain: ChainArgs,
+    /// First nonce to print
+    #[arg(long, default_value_t = 0)]
+    from: u32,
+    /// Last nonce to print, defaults to the last contiguously stored nonce
+    #[arg(long)]
+    to: Option<u32>,
+}
+
+#[derive(Args)]
+struct GasArgs {
+    #[command(flatten)]
+    chain: ChainArgs,
+    #[arg(long)]
+    message_id: H256,
+    /// Destination of the message, required if the message isn't stored
+    #[arg(long)]
+    destination: Option<u32>,
+}
+
+#[derive(Args)]
+struct NonceArgs {
+    #[command(flatten)]
+    chain: ChainArgs,
+    #[arg(long)]
+    nonce: u32,
+}
+
+#[derive(Args)]
+struct MessageIdArgs {
+    #[command(flatten)]
+    chain: ChainArgs,
+    #[arg(long)]
+    message_id: H256,
+}
+
+#[derive(Args)]
+struct LeafIndexArgs {
+    #[command(flatten)]
+    chain: ChainArgs,
+    #[arg(long)]
+    leaf_index: u32,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// Only export the entries of this chain
+    #[arg(long)]
+    chain: Option<String>,
+    /// File to export to, defaults to stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    /// File to import from, defaults to stdin
+    #[arg(long)]
+    input: Option<PathBuf>,
+}
+
+/// A raw database entry, hex encoded.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    value: String,
+}
+
+impl ChainArgs {
+    fn open(&self, db: &DB) -> HyperlaneRocksDB {
+        HyperlaneRocksDB::new(&domain(&self.chain), db.clone())
+    }
+}
+
+/// Entries are keyed by the name of the chain only, so chains unknown to this
+/// binary are supported by name as well.
+fn domain(name: &str) -> HyperlaneDomain {
+    KnownHyperlaneDomain::from_str(name)
+        .map(Into::into)
+        .unwrap_or_else(|_| HyperlaneDomain::Unknown {
+            domain_id: 0,
+            domain_name: name.to_owned(),
+            domain_type: HyperlaneDomainType::Unknown,
+            domain_protocol: HyperlaneDomainProtocol::Ethereum,
+            domain_technical_stack: HyperlaneDomainTechnicalStack::Other,
+        })
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if !matches!(cli.cmd, Cmd::Import(_)) && !cli.db.is_dir() {
+        bail!("No database found at {}", cli.db.display());
+    }
+    let db = DB::from_path(&cli.db)?;
+    let mut out = io::stdout().lock();
+
+    match cli.cmd {
+        Cmd::Messages(args) => print_messages(&args.chain.open(&db), args.from, args.to, &mut out),
+        Cmd::Gas(args) => print_gas(
+            &args.chain.open(&db),
+            args.message_id,
+            args.destination,
+            &mut out,
+        ),
+        Cmd::Processed(args) => {
+            let db = args.chain.open(&db);
+            let processed = db.retrieve_processed_by_nonce(&args.nonce)?;
+            writeln!(
+                out,
+                "{}",
+                json!({ "nonce": args.nonce, "processed": processed.unwrap_or(false) })
+            )?;
+            Ok(())
+        }
+        Cmd::MarkProcessed(args) => Ok(args
+            .chain
+            .open(&db)
+            .store_processed_by_nonce(&args.nonce, &true)?),
+        Cmd::MarkUnprocessed(args) => Ok(args
+            .chain
+            .open(&db)
+            .store_processed_by_nonce(&args.nonce, &false)?),
+        Cmd::ResetRetries(args) => Ok(args
+            .chain
+            .open(&db)
+            .store_pending_message_retry_count_by_message_id(&args.message_id, &0)?),
+        Cmd::DeleteMessage(args) => {
+            if !args.chain.open(&db).delete_message_by_nonce(args.nonce)? {
+                bail!("No message stored with nonce {}", args.nonce);
+            }
+            Ok(())
+        }
+        Cmd::DeleteMerkleInsertion(args) => {
+            if !args
+                .chain
+                .open(&db)
+                .delete_merkle_tree_insertion_by_leaf_index(args.leaf_index)?
+            {
+                bail!(
+                    "No merkle tree insertion stored at index {}",
+                    args.leaf_index
+                );
+            }
+            Ok(())
+        }
+        Cmd::VerifyMerkle(args) => verify_merkle(&args.open(&db), &mut out),
+        Cmd::Export(args) => match args.output {
+            Some(path) => export(
+                &db,
+                args.chain.as_deref(),
+                BufWriter::new(File::create(path)?),
+            ),
+            None => export(&db, args.chain.as_deref(), out),
+        },
+        Cmd::Import(args) => {
+            let count = match args.input {
+                Some(path) => import(&db, BufReader::new(File::open(path)?))?,
+                None => import(&db, io::stdin().lock())?,
+            };
+            eprintln!("Imported {count} entries");
+            Ok(())
+        }
+    }
+}
+
+fn print_messages(
+    db: &HyperlaneRocksDB,
+    from: u32,
+    to: Option<u32>,
+    mut out: impl Write,
+) -> Result<()> {
+    for nonce in from..=to.unwrap_or(u32::MAX) {
+        let Some(message) = db.retrieve_message_by_nonce(nonce)? else {
+            if to.is_some() {
+                continue;
+            }
+            break;
+        };
+        let id = message.id();
+        let line = json!({
+            "nonce": nonce,
+            "id": format!("{id:?}"),
+            "blockNumber": db.retrieve_dispatched_block_number_by_nonce(&nonce)?,
+            "processed": db.retrieve_processed_by_nonce(&nonce)?.unwrap_or(false),
+            "retryCount": db.retrieve_pending_message_retry_count_by_message_id(&id)?,
+            "version": message.version,
+            "origin": message.origin,
+            "sender": format!("{:?}", message.sender),
+            "destination": message.destination,
+            "recipient": format!("{:?}", message.recipient),
+            "body": format!("0x{}", hex::encode(&message.body)),
+        });
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
+
+fn print_gas(
+    db: &HyperlaneRocksDB,
+    message_id: H256,
+    destination: Option<u32>,
+    mut out: impl Write,
+) -> Result<()> {
+    let destination = match destination {
+        Some(destination) => destination,
+        None => {
+            db.retrieve_message_by_id(&message_id)?
+                .ok_or_else(|| eyre!("Message {message_id:?} isn't stored, pass --destination"))?
+                .destination
+        }
+    };
+    let payment = db.retrieve_gas_payment_by_gas_payment_key(GasPaymentKey {
+        message_id,
+        destination,
+    })?;
+    let expenditure = db.retrieve_gas_expenditure_by_message_id(message_id)?;
+    let line = json!({
+        "messageId": format!("{message_id:?}"),
+        "destination": destination,
+        "payment": payment.payment.to_string(),
+        "gasAmount": payment.gas_amount.to_string(),
+        "tokensUsed": expenditure.tokens_used.to_string(),
+        "gasUsed": expenditure.gas_used.to_string(),
+    });
+    writeln!(out, "{line}")?;
+    Ok(())
+}
+
+/// Checks that merkle tree insertions are stored for every leaf index up to
+/// the highest one, along with their block number and message id mapping.
+/// Prints the problems found and fails if there are any.
+fn verify_merkle(db: &HyperlaneRocksDB, mut out: impl Write) -> Result<()> {
+    let indices = db.retrieve_merkle_tree_insertion_leaf_indices()?;
+    let mut problems = 0;
+    let mut expected = 0;
+    for &index in &indices {
+        if index != expected {
+            writeln!(
+                out,
+                "Missing insertions for leaf indices {expected}..={}",
+                index - 1
+            )?;
+            problems += 1;
+        }
+        expected = index + 1;
+
+        let Some(insertion) = db.retrieve_merkle_tree_insertion_by_leaf_index(&index)? else {
+            continue;
+        };
+        if insertion.index() != index {
+            writeln!(
+                out,
+                "Insertion stored at leaf index {index} has index {}",
+                insertion.index()
+            )?;
+            problems += 1;
+        }
+        if db
+            .retrieve_merkle_tree_insertion_block_number_by_leaf_index(&index)?
+            .is_none()
+        {
+            writeln!(out, "Missing block number for leaf index {index}")?;
+            problems += 1;
+        }
+        if db.retrieve_merkle_leaf_index_by_message_id(&insertion.message_id())? != Some(index) {
+            writeln!(
+                out,
+                "Message id {:?} doesn't map to leaf index {index}",
+                insertion.message_id()
+            )?;
+            problems += 1;
+        }
+    }
+    if problems > 0 {
+        bail!(
+            "Found {problems} problems in {} merkle tree insertions",
+            indices.len()
+        );
+    }
+    writeln!(out, "Verified {} merkle tree insertions", indices.len())?;
+    Ok(())
+}
+
+/// Writes every entry, or those whose key belongs to `chain`, as a JSON line.
+fn export(db: &DB, chain: Option<&str>, mut out: impl Write) -> Result<()> {
+    let prefix = chain
+        .map(|chain| format!("{}_", domain(chain).name()))
+        .unwrap_or_default();
+    for entry in db.iter_prefix(prefix.as_bytes()) {
+        let (key, value) = entry?;
+        // Keys of chains whose name starts with `{chain}_` share the prefix,
+        // so only keep those continuing with the name of a table
+        if chain.is_some()
+            && !TABLE_KEY_PREFIXES
+                .iter()
+                .any(|table| key[prefix.len()..].starts_with(table.as_bytes()))
+        {
+            continue;
+        }
+        let entry = Entry {
+            key: hex::encode(key),
+            value: hex::encode(value),
+        };
+        writeln!(out, "{}", serde_json::to_string(&entry)?)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Stores every entry read as a JSON line, returning the number of entries.
+fn import(db: &DB, input: impl BufRead) -> Result<usize> {
+    let mut count = 0;
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(&line)
+            .map_err(|e| eyre!("Invalid entry on line {}: {e}", i + 1))?;
+        db.store(&hex::decode(entry.key)?, &hex::decode(entry.value)?)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::test_utils::run_test_db;
+    use hyperlane_core::{HyperlaneMessage, MerkleTreeInsertion};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        run_test_db(|db| async move {
+            let chain = domain("export_import_roundtrip");
+            let hyperlane_db = HyperlaneRocksDB::new(&chain, db.clone());
+            hyperlane_db
+                .store_message(&HyperlaneMessage::default(), 10)
+                .unwrap();
+            HyperlaneRocksDB::new(&domain("other"), db.clone())
+                .store_processed_by_nonce(&0, &true)
+                .unwrap();
+            HyperlaneRocksDB::new(&domain(&format!("{}_other", chain.name())), db.clone())
+                .store_processed_by_nonce(&0, &true)
+                .unwrap();
+
+            let mut exported = vec![];
+            export(&db, Some(chain.name()), &mut exported).unwrap();
+            assert_eq!(String::from_utf8_lossy(&exported).lines().count(), 3);
+
+            run_test_db(|imported| async move {
+                assert_eq!(import(&imported, exported.as_slice()).unwrap(), 3);
+                let imported = HyperlaneRocksDB::new(&chain, imported);
+                assert!(imported.retrieve_message_by_nonce(0).unwrap().is_some());
+                assert_eq!(
+                    imported
+                        .retrieve_dispatched_block_number_by_nonce(&0)
+                        .unwrap(),
+                    Some(10)
+                );
+            })
+            .await;
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_verify_merkle_finds_gaps() {
+        run_test_db(|db| async move {
+            let db = HyperlaneRocksDB::new(&domain("verify_merkle_finds_gaps"), db);
+            for index in [0, 1, 3] {
+                let insertion =
+                    MerkleTreeInsertion::new(index, H256::from_low_u64_be(index as u64));
+                db.process_tree_insertion(&insertion, 1).unwrap();
+            }
+
+            let mut out = vec![];
+            assert!(verify_merkle(&db, &mut out).is_err());
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                "Missing insertions for leaf indices 2..=2\n"
+            );
+
+            let insertion = MerkleTreeInsertion::new(2, H256::from_low_u64_be(2));
+            db.process_tree_insertion(&insertion, 1).unwrap();
+            assert!(verify_merkle(&db, vec![]).is_ok());
+        })
+        .await;
+    }
+}
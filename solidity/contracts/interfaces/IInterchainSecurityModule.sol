@@ -10,7 +10,10 @@ interface IInterchainSecurityModule {
         MERKLE_ROOT_MULTISIG,
         MESSAGE_ID_MULTISIG,
         NULL, // used with relayer carrying no metadata
-        CCIP_READ
+        CCIP_READ,
+        BLS_MULTISIG, // aggregated BLS signature and non-signer bitmap
+        WEIGHTED_MESSAGE_ID_MULTISIG, // signatures reaching a threshold weight
+        OPTIMISTIC // pre-verified by a submodule, verified after a fraud window
     }
 
     /**
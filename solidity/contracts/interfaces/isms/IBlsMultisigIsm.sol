@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity >=0.8.0;
+
+import {IMultisigIsm} from "./IMultisigIsm.sol";
+
+interface IBlsMultisigIsm is IMultisigIsm {
+    /**
+     * @notice Returns the BLS public keys registered for _validators, against
+     * which their aggregated signature is verified
+     * @param _validators The validators, as returned by validatorsAndThreshold
+     * @return publicKeys The G2 public key of each validator, encoded as
+     * x.c1 || x.c0 || y.c1 || y.c0
+     */
+    function registeredPublicKeys(
+        address[] calldata _validators
+    ) external view returns (bytes[] memory publicKeys);
+}
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity >=0.8.0;
+
+import {IInterchainSecurityModule} from "../IInterchainSecurityModule.sol";
+
+interface IOptimisticIsm is IInterchainSecurityModule {
+    /**
+     * @notice Emitted when a message is pre-verified
+     * @param messageId The id of the pre-verified message
+     * @param submodule The ISM which pre-verified the message
+     * @param origin The origin domain of the message
+     */
+    event PreVerified(
+        bytes32 indexed messageId,
+        address indexed submodule,
+        uint32 origin
+    );
+
+    /**
+     * @notice Emitted when a watcher flags a submodule as fraudulent
+     * @param submodule The fraudulent submodule
+     * @param watcher The watcher which flagged the submodule
+     */
+    event FraudulentSubmoduleMarked(
+        address indexed submodule,
+        address indexed watcher
+    );
+
+    /**
+     * @notice Returns the ISM which pre-verifies _message
+     * @param _message Hyperlane formatted interchain message
+     */
+    function submodule(
+        bytes calldata _message
+    ) external view returns (IInterchainSecurityModule);
+
+    /**
+     * @notice Returns the number of seconds after pre-verification during
+     * which watchers can flag the submodule as fraudulent
+     */
+    function fraudWindow() external view returns (uint256);
+
+    /**
+     * @notice Returns the timestamp at which a message was pre-verified, or
+     * zero if it was not
+     * @param _id The id of the message
+     */
+    function preVerifiedAt(bytes32 _id) external view returns (uint256);
+
+    /**
+     * @notice Returns whether a submodule was flagged as fraudulent
+     * @param _submodule The submodule
+     */
+    function isFraudulent(address _submodule) external view returns (bool);
+
+    /**
+     * @notice Pre-verifies _message with the submodule for _message
+     * @param _metadata Metadata for the submodule
+     * @param _message Hyperlane formatted interchain message
+     * @return True if the message was pre-verified
+     */
+    function preVerify(
+        bytes calldata _metadata,
+        bytes calldata _message
+    ) external returns (bool);
+
+    /**
+     * @notice Flags a submodule as fraudulent. Only callable by watchers.
+     * @param _submodule The fraudulent submodule
+     */
+    function markFraudulent(address _submodule) external;
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity >=0.8.0;
+
+import {IInterchainSecurityModule} from "../IInterchainSecurityModule.sol";
+
+interface IWeightedMultisigIsm is IInterchainSecurityModule {
+    struct ValidatorInfo {
+        address signingAddress;
+        uint96 weight;
+    }
+
+    /**
+     * @notice Returns the set of validators responsible for verifying _message,
+     * with their weights, and the total weight of signatures required
+     * @dev Can change based on the content of _message, e.g. with stake
+     * @param _message Hyperlane formatted interchain message
+     * @return validators The array of validators with their weights
+     * @return thresholdWeight The total weight of validator signatures needed
+     */
+    function validatorsAndThresholdWeight(
+        bytes calldata _message
+    )
+        external
+        view
+        returns (ValidatorInfo[] memory validators, uint96 thresholdWeight);
+}
@@ -165,6 +165,7 @@ export {
   AgentConfigSchema,
   AgentLogFormat,
   AgentLogLevel,
+  AgentNotificationSinkType,
   AgentSigner,
   AgentSignerAwsKey,
   AgentSignerHexKey,